@@ -57,10 +57,7 @@ async fn run() -> anyhow::Result<()> {
     }
 
     // Select the first one with GBP currency
-    let merchant_account = merchant_accounts
-        .into_iter()
-        .find(|m| m.currency == Currency::Gbp)
-        .context("Cannot find a GBP merchant account")?;
+    let merchant_account = tl.merchant_accounts.find_by_currency(Currency::Gbp).await?;
 
     // Create a new outgoing payment
     let res = tl
@@ -74,18 +71,21 @@ async fn run() -> anyhow::Result<()> {
                     scheme_selection: None,
                 },
                 beneficiary: Beneficiary::MerchantAccount {
-                    merchant_account_id: merchant_account.id,
+                    merchant_account_id: merchant_account.id.into(),
                     account_holder_name: None,
                     reference: None,
                     statement_reference: None,
+                    verification: None,
                 },
             },
-            user: CreatePaymentUserRequest::NewUser {
-                name: Some("Some One".to_string()),
-                email: Some("some.one@email.com".to_string()),
-                phone: None,
-            },
+            user: CreatePaymentUserRequest::new_user()
+                .with_name("Some One")
+                .with_email("some.one@email.com")
+                .build(),
             metadata: None,
+            schedule: None,
+            related_products: None,
+            risk_assessment: None,
         })
         .await?;
 
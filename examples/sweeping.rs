@@ -107,6 +107,7 @@ async fn run() -> anyhow::Result<()> {
                     max_amount_in_minor: amount,
                     currency: merchant_account.currency.clone(),
                     frequency: frequency.clone(),
+                    destination: None,
                 },
             )
             .await?;
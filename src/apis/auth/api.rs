@@ -1,5 +1,8 @@
 use crate::{
-    apis::{auth::AuthenticationResult, TrueLayerClientInner},
+    apis::{
+        auth::{AuthenticationResult, Credentials, TokenStatus},
+        TrueLayerClientInner,
+    },
     Error,
 };
 use std::sync::Arc;
@@ -22,4 +25,26 @@ impl AuthApi {
         // Just delegate to the authenticator
         self.inner.authenticator.get_access_token().await
     }
+
+    /// Replaces the credentials of the client, e.g. after the client secret has been rotated,
+    /// without rebuilding the client.
+    ///
+    /// The cached access token is discarded and a new one is requested with the new credentials
+    /// on the next request. Requests already in flight are not affected.
+    ///
+    /// Returns an error if the new credentials belong to a different client id.
+    pub async fn update_credentials(&self, credentials: Credentials) -> Result<(), Error> {
+        self.inner
+            .authenticator
+            .update_credentials(credentials)
+            .await
+    }
+
+    /// Returns the state of the access token currently cached by the client.
+    ///
+    /// Unlike [`get_access_token`](AuthApi::get_access_token), this never sends a request
+    /// to the authentication server, so it is suitable for health checks.
+    pub async fn token_status(&self) -> TokenStatus {
+        self.inner.authenticator.token_status().await
+    }
 }
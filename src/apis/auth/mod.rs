@@ -2,6 +2,8 @@
 
 mod api;
 mod model;
+mod provider;
 
 pub use api::AuthApi;
 pub use model::*;
+pub use provider::AccessTokenProvider;
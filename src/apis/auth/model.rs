@@ -1,16 +1,21 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
-use std::ops::Deref;
+use std::{
+    fmt::{Debug, Formatter},
+    ops::Deref,
+};
 
 /// Credentials used to authenticate against TrueLayer's APIs.
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "grant_type", rename_all = "snake_case")]
 pub enum Credentials {
     AuthorizationCode {
         client_id: String,
         client_secret: Token,
-        code: String,
+        code: Token,
         redirect_uri: String,
     },
     RefreshToken {
@@ -60,6 +65,7 @@ impl Credentials {
 pub struct AuthenticationResult {
     pub(crate) access_token: AccessToken,
     pub(crate) refresh_token: Option<Token>,
+    pub(crate) source: TokenSource,
 }
 
 impl AuthenticationResult {
@@ -72,6 +78,52 @@ impl AuthenticationResult {
     pub fn refresh_token(&self) -> Option<&Token> {
         self.refresh_token.as_ref()
     }
+
+    /// Expiration date of the access token. Shorthand for [`AccessToken::expires_at`].
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.access_token.expires_at
+    }
+
+    /// Scopes granted to the access token. Shorthand for [`AccessToken::scopes`].
+    pub fn scopes(&self) -> &[String] {
+        &self.access_token.scopes
+    }
+
+    /// Whether the access token was reused from the cache or obtained with a new request.
+    pub fn source(&self) -> TokenSource {
+        self.source
+    }
+}
+
+/// Where the access token of an [`AuthenticationResult`] comes from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum TokenSource {
+    /// The token was already cached by the client and still valid.
+    Cache,
+    /// The token was just obtained from the authentication server.
+    Exchange,
+    /// The token was just obtained from the
+    /// [`AccessTokenProvider`](crate::apis::auth::AccessTokenProvider) of the client.
+    Provider,
+}
+
+/// State of the access token cached by the client, as returned by
+/// [`AuthApi::token_status`](crate::apis::auth::AuthApi::token_status).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TokenStatus {
+    /// No access token has been obtained yet.
+    Missing,
+    /// An access token is cached.
+    Cached {
+        /// When the token was obtained from the authentication server.
+        obtained_at: DateTime<Utc>,
+        /// When the token expires, if it does.
+        expires_at: Option<DateTime<Utc>>,
+        /// Scopes granted to the token.
+        scopes: Vec<String>,
+        /// Whether the token is close enough to its expiration to be refreshed on next use.
+        refresh_due: bool,
+    },
 }
 
 /// Opaque access token used to authenticate to TrueLayer APIs.
@@ -79,9 +131,28 @@ impl AuthenticationResult {
 pub struct AccessToken {
     pub(crate) token: Token,
     pub(crate) expires_at: Option<DateTime<Utc>>,
+    pub(crate) obtained_at: DateTime<Utc>,
+    pub(crate) scopes: Vec<String>,
 }
 
 impl AccessToken {
+    /// Wraps an access token obtained outside of the client, e.g. to inject it
+    /// with an [`AccessTokenProvider`](crate::apis::auth::AccessTokenProvider).
+    pub fn new(token: impl Into<Token>, expires_at: Option<DateTime<Utc>>) -> Self {
+        Self {
+            token: token.into(),
+            expires_at,
+            obtained_at: Utc::now(),
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Sets the scopes granted to the token.
+    pub fn with_scopes(mut self, scopes: Vec<String>) -> Self {
+        self.scopes = scopes;
+        self
+    }
+
     /// Actual token contents held by this `AccessToken` instance.
     pub fn token(&self) -> &Token {
         &self.token
@@ -93,6 +164,16 @@ impl AccessToken {
     pub fn expires_at(&self) -> Option<DateTime<Utc>> {
         self.expires_at
     }
+
+    /// When the token was obtained from the authentication server.
+    pub fn obtained_at(&self) -> DateTime<Utc> {
+        self.obtained_at
+    }
+
+    /// Scopes granted to the token by the authentication server.
+    pub fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
 }
 
 impl Deref for AccessToken {
@@ -103,6 +184,104 @@ impl Deref for AccessToken {
     }
 }
 
+/// Short-lived token granting access to a single resource (e.g., a payment),
+/// meant to be handed over to front-end components like the Hosted Payments Page.
+///
+/// The token records when it was received, so that callers can tell whether it is likely expired
+/// before using it. Like [`Token`], its contents are redacted when printed with `Debug`.
+///
+/// Resource tokens are (de)serialized as their bare token, which is how they appear in API responses,
+/// so the issue time is not serialized: a deserialized token is considered issued when it was deserialized.
+/// Callers storing a token for later use must keep track of its [`issued_at()`](ResourceToken::issued_at) themselves.
+///
+/// ```rust
+/// # use truelayer_rust::apis::auth::{ResourceToken, Token};
+/// let token = ResourceToken::from(Token::new("supersecret"));
+///
+/// assert!(!format!("{:?}", token).contains("supersecret"));
+/// assert!(!token.is_probably_expired());
+///
+/// // Only the bare token is serialized
+/// assert_eq!(serde_json::to_string(&token).unwrap(), r#""supersecret""#);
+/// ```
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(from = "Token", into = "Token")]
+pub struct ResourceToken {
+    token: Token,
+    issued_at: DateTime<Utc>,
+}
+
+impl ResourceToken {
+    /// How long resource tokens are known to be valid for after being issued.
+    pub const TTL_MINUTES: i64 = 15;
+
+    /// Actual token contents held by this `ResourceToken` instance.
+    pub fn token(&self) -> &Token {
+        &self.token
+    }
+
+    /// When the token was received from TrueLayer, or when it was deserialized.
+    pub fn issued_at(&self) -> DateTime<Utc> {
+        self.issued_at
+    }
+
+    /// Estimated expiration date of the token, based on its known TTL.
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.issued_at + Duration::minutes(Self::TTL_MINUTES)
+    }
+
+    /// Returns `true` if the token has been issued more than its known TTL ago.
+    ///
+    /// The actual expiration is decided by TrueLayer, so this is only a best effort estimate.
+    pub fn is_probably_expired(&self) -> bool {
+        Utc::now() >= self.expires_at()
+    }
+}
+
+impl From<Token> for ResourceToken {
+    fn from(token: Token) -> Self {
+        Self {
+            token,
+            issued_at: Utc::now(),
+        }
+    }
+}
+
+impl From<ResourceToken> for Token {
+    fn from(resource_token: ResourceToken) -> Self {
+        resource_token.token
+    }
+}
+
+impl Deref for ResourceToken {
+    type Target = Token;
+
+    fn deref(&self) -> &Self::Target {
+        self.token()
+    }
+}
+
+/// Resource tokens are (de)serialized as their bare token.
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for ResourceToken {
+    fn schema_name() -> String {
+        Token::schema_name()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        Token::json_schema(gen)
+    }
+}
+
+impl Debug for ResourceToken {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResourceToken")
+            .field("token", &"[REDACTED]")
+            .field("issued_at", &self.issued_at)
+            .finish()
+    }
+}
+
 /// Wrapper for a secret string that makes it harder to accidentally expose secrets
 /// and ensures the backing memory is wiped on drop.
 ///
@@ -123,7 +302,12 @@ impl Deref for AccessToken {
 /// assert!(serialized.contains("supersecret"));
 /// ```
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct Token(#[serde(serialize_with = "serialize_secret")] Secret<String>);
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Token(
+    #[serde(serialize_with = "serialize_secret")]
+    #[cfg_attr(feature = "json-schema", schemars(with = "String"))]
+    Secret<String>,
+);
 
 impl Token {
     /// Wraps a secret string in a new `Token`.
@@ -0,0 +1,26 @@
+use crate::{apis::auth::AccessToken, Error};
+use async_trait::async_trait;
+use std::fmt::Debug;
+
+/// Source of access tokens obtained outside of the client, e.g. from a central token broker.
+///
+/// Set with [`TrueLayerClientBuilder::with_access_token_provider()`](crate::client::TrueLayerClientBuilder::with_access_token_provider),
+/// the provider replaces the authentication server: it's called whenever the client has no access token
+/// or the cached one is about to expire. Concurrent requests wait for the same call.
+///
+/// A pre-fetched [`AccessToken`] is itself a provider, which always returns the same token.
+#[async_trait]
+pub trait AccessTokenProvider: Debug + Send + Sync + 'static {
+    /// Returns a valid access token.
+    ///
+    /// Tokens without an expiration date are cached until the credentials are updated
+    /// with [`AuthApi::update_credentials()`](crate::apis::auth::AuthApi::update_credentials).
+    async fn get_access_token(&self) -> Result<AccessToken, Error>;
+}
+
+#[async_trait]
+impl AccessTokenProvider for AccessToken {
+    async fn get_access_token(&self) -> Result<AccessToken, Error> {
+        Ok(self.clone())
+    }
+}
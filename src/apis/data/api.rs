@@ -0,0 +1,373 @@
+use crate::{
+    apis::{
+        data::{Account, AccountBalance, DataResults, ListAccountTransactionsRequest, Transaction},
+        TrueLayerClientInner, NO_QUERY,
+    },
+    common::DecodeJson,
+    Error, RequestOptions,
+};
+use reqwest::Url;
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::Arc;
+use urlencoding::encode;
+
+/// TrueLayer Data APIs client.
+///
+/// The Data API reads the accounts of a user who granted access to them, therefore it requires
+/// a client authenticated on behalf of that user, i.e. built with
+/// [`Credentials::AuthorizationCode`](crate::apis::auth::Credentials::AuthorizationCode)
+/// or [`Credentials::RefreshToken`](crate::apis::auth::Credentials::RefreshToken).
+#[derive(Clone, Debug)]
+pub struct DataApi {
+    inner: Arc<TrueLayerClientInner>,
+    options: RequestOptions,
+}
+
+impl DataApi {
+    pub(crate) fn new(inner: Arc<TrueLayerClientInner>) -> Self {
+        Self {
+            inner,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Returns a copy of this client which applies the given [`RequestOptions`] to all its requests.
+    pub fn with_options(&self, options: RequestOptions) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            options,
+        }
+    }
+
+    /// Lists all the accounts of the user.
+    #[tracing::instrument(target = "truelayer_rust::data", name = "List Accounts", skip(self))]
+    pub async fn list_accounts(&self) -> Result<Vec<Account>, Error> {
+        self.get_results(self.endpoint("/accounts"), NO_QUERY).await
+    }
+
+    /// Gets the details of an account of the user.
+    ///
+    /// If there's no account with the given id, `None` is returned.
+    #[tracing::instrument(target = "truelayer_rust::data", name = "Get Account", skip(self))]
+    pub async fn get_account(&self, account_id: &str) -> Result<Option<Account>, Error> {
+        self.get_first_result(self.endpoint(&format!("/accounts/{}", encode(account_id))))
+            .await
+    }
+
+    /// Gets the balance of an account of the user.
+    ///
+    /// If there's no account with the given id, `None` is returned.
+    #[tracing::instrument(
+        target = "truelayer_rust::data",
+        name = "Get Account Balance",
+        skip(self)
+    )]
+    pub async fn get_account_balance(
+        &self,
+        account_id: &str,
+    ) -> Result<Option<AccountBalance>, Error> {
+        self.get_first_result(self.endpoint(&format!("/accounts/{}/balance", encode(account_id))))
+            .await
+    }
+
+    /// Lists the transactions settled on an account of the user in the given time range.
+    #[tracing::instrument(
+        target = "truelayer_rust::data",
+        name = "List Account Transactions",
+        skip(self, request)
+    )]
+    pub async fn list_account_transactions(
+        &self,
+        account_id: &str,
+        request: &ListAccountTransactionsRequest,
+    ) -> Result<Vec<Transaction>, Error> {
+        self.get_results(
+            self.endpoint(&format!("/accounts/{}/transactions", encode(account_id))),
+            request,
+        )
+        .await
+    }
+
+    /// URL of an endpoint of the Data API, which is served next to the payments APIs.
+    fn endpoint(&self, path: &str) -> Url {
+        self.inner
+            .environment
+            .payments_endpoint(&format!("/data/v1{}", path))
+    }
+
+    /// Gets the results of a request, unwrapping them from the envelope of the Data API.
+    async fn get_results<T, Q>(&self, url: Url, query: &Q) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let res: DataResults<T> = self
+            .inner
+            .client
+            .get(url)
+            .query(query)
+            .with_extension(self.options.clone())
+            .send()
+            .await?
+            .decode_json()
+            .await?;
+
+        Ok(res.results)
+    }
+
+    /// Gets the only result of a request for a single resource, or `None` if the server returned 404.
+    async fn get_first_result<T>(&self, url: Url) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned,
+    {
+        let res: Option<DataResults<T>> = self
+            .inner
+            .get_optional(url, NO_QUERY, &self.options)
+            .await?;
+
+        Ok(res.and_then(|res| res.results.into_iter().next()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        apis::{
+            auth::Credentials,
+            data::{AccountNumber, AccountProvider, AccountType, RunningBalance, TransactionType},
+        },
+        authenticator::Authenticator,
+        client::Environment,
+        middlewares::error_handling::ErrorHandlingMiddleware,
+        validation::RequestValidation,
+    };
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    async fn mock_client_and_server() -> (DataApi, MockServer) {
+        let mock_server = MockServer::start().await;
+
+        let credentials = Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        };
+
+        let authenticator = Authenticator::new(
+            reqwest::Client::new().into(),
+            Url::parse(&mock_server.uri()).unwrap(),
+            credentials,
+        );
+
+        let inner = TrueLayerClientInner {
+            client: reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+                .with(ErrorHandlingMiddleware)
+                .build(),
+            authenticator,
+            environment: Environment::from_single_url(&Url::parse(&mock_server.uri()).unwrap()),
+            runtime: None,
+            #[cfg(feature = "payments")]
+            provider_cache: None,
+            request_validation: RequestValidation::default(),
+        };
+
+        (DataApi::new(Arc::new(inner)), mock_server)
+    }
+
+    fn account_json() -> serde_json::Value {
+        json!({
+            "update_timestamp": "2022-01-01T00:00:00Z",
+            "account_id": "account-id",
+            "account_type": "TRANSACTION",
+            "display_name": "Current Account",
+            "currency": "GBP",
+            "account_number": {
+                "number": "12345678",
+                "sort_code": "01-02-03",
+                "swift_bic": "CPBKGB00"
+            },
+            "provider": {
+                "display_name": "Mock Bank",
+                "provider_id": "mock",
+                "logo_uri": "https://truelayer-provider-assets.s3.amazonaws.com/global/logos/mock.svg"
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn list_accounts() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path("/data/v1/accounts"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [account_json()],
+                "status": "Succeeded"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let accounts = api.list_accounts().await.unwrap();
+
+        assert_eq!(
+            accounts,
+            vec![Account {
+                account_id: "account-id".into(),
+                account_type: AccountType::Transaction,
+                display_name: "Current Account".into(),
+                currency: "GBP".into(),
+                account_number: AccountNumber {
+                    iban: None,
+                    swift_bic: Some("CPBKGB00".into()),
+                    number: Some("12345678".into()),
+                    sort_code: Some("01-02-03".into()),
+                },
+                provider: AccountProvider {
+                    provider_id: "mock".into(),
+                    display_name: Some("Mock Bank".into()),
+                    logo_uri: Some(
+                        "https://truelayer-provider-assets.s3.amazonaws.com/global/logos/mock.svg"
+                            .into()
+                    ),
+                },
+                update_timestamp: Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn get_account() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path("/data/v1/accounts/account-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [account_json()],
+                "status": "Succeeded"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let account = api.get_account("account-id").await.unwrap().unwrap();
+
+        assert_eq!(account.account_id, "account-id");
+    }
+
+    #[tokio::test]
+    async fn get_account_not_found() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path("/data/v1/accounts/account-id"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let account = api.get_account("account-id").await.unwrap();
+
+        assert!(account.is_none());
+    }
+
+    #[tokio::test]
+    async fn get_account_balance() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path("/data/v1/accounts/account%2Fid/balance"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [{
+                    "currency": "GBP",
+                    "available": 1161.2,
+                    "current": 1161.2,
+                    "overdraft": 1000,
+                    "update_timestamp": "2022-01-01T00:00:00Z"
+                }],
+                "status": "Succeeded"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let balance = api
+            .get_account_balance("account/id")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            balance,
+            AccountBalance {
+                currency: "GBP".into(),
+                available: 1161.2,
+                current: 1161.2,
+                overdraft: Some(1000.0),
+                update_timestamp: Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn list_account_transactions() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path("/data/v1/accounts/account-id/transactions"))
+            .and(query_param("from", "2022-01-01T00:00:00Z"))
+            .and(query_param("to", "2022-02-01T00:00:00Z"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "results": [{
+                    "timestamp": "2022-01-15T00:00:00Z",
+                    "description": "GOOGLE PLAY STORE",
+                    "transaction_type": "DEBIT",
+                    "transaction_category": "PURCHASE",
+                    "transaction_classification": ["Entertainment", "Games"],
+                    "merchant_name": "Google play",
+                    "amount": -2.99,
+                    "currency": "GBP",
+                    "transaction_id": "transaction-id",
+                    "running_balance": {
+                        "currency": "GBP",
+                        "amount": 1158.21
+                    },
+                    "meta": {
+                        "provider_transaction_category": "DEB"
+                    }
+                }],
+                "status": "Succeeded"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let transactions = api
+            .list_account_transactions(
+                "account-id",
+                &ListAccountTransactionsRequest {
+                    from: Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap(),
+                    to: Utc.with_ymd_and_hms(2022, 2, 1, 0, 0, 0).unwrap(),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].transaction_id, "transaction-id");
+        assert_eq!(transactions[0].amount, -2.99);
+        assert_eq!(transactions[0].transaction_type, TransactionType::Debit);
+        assert_eq!(
+            transactions[0].running_balance,
+            Some(RunningBalance {
+                amount: 1158.21,
+                currency: "GBP".into()
+            })
+        );
+    }
+}
@@ -0,0 +1,7 @@
+//! APIs and models related to the Data API, which reads the bank accounts of a user.
+
+mod api;
+mod model;
+
+pub use api::DataApi;
+pub use model::*;
@@ -0,0 +1,115 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Bank account of the user.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct Account {
+    pub account_id: String,
+    pub account_type: AccountType,
+    pub display_name: String,
+    /// ISO 4217 code of the currency of the account.
+    pub currency: String,
+    pub account_number: AccountNumber,
+    pub provider: AccountProvider,
+    pub update_timestamp: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AccountType {
+    Transaction,
+    Savings,
+    BusinessTransaction,
+    BusinessSavings,
+}
+
+/// Identifiers of an account. Which ones are present depends on the bank.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct AccountNumber {
+    pub iban: Option<String>,
+    pub swift_bic: Option<String>,
+    pub number: Option<String>,
+    pub sort_code: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct AccountProvider {
+    pub provider_id: String,
+    pub display_name: Option<String>,
+    pub logo_uri: Option<String>,
+}
+
+/// Balance of an account, in major units of its currency.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct AccountBalance {
+    /// ISO 4217 code of the currency of the balance.
+    pub currency: String,
+    pub available: f64,
+    pub current: f64,
+    pub overdraft: Option<f64>,
+    pub update_timestamp: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct ListAccountTransactionsRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// Transaction settled on an account.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct Transaction {
+    pub transaction_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub description: String,
+    /// Amount in major units of the currency, negative for debits.
+    pub amount: f64,
+    /// ISO 4217 code of the currency of the transaction.
+    pub currency: String,
+    pub transaction_type: TransactionType,
+    pub transaction_category: String,
+    #[serde(default)]
+    pub transaction_classification: Vec<String>,
+    pub merchant_name: Option<String>,
+    pub running_balance: Option<RunningBalance>,
+    pub provider_transaction_id: Option<String>,
+    pub normalised_provider_transaction_id: Option<String>,
+    /// Additional details reported by the bank, which vary between banks.
+    pub meta: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionType {
+    Debit,
+    Credit,
+}
+
+/// Balance of the account after a transaction, in major units of its currency.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct RunningBalance {
+    pub amount: f64,
+    pub currency: String,
+}
+
+/// Envelope of all the responses of the Data API.
+#[derive(Deserialize)]
+pub(crate) struct DataResults<T> {
+    pub(crate) results: Vec<T>,
+}
@@ -0,0 +1,178 @@
+//! Strongly typed identifiers of TrueLayer resources.
+//!
+//! Each resource has its own identifier type, so that passing e.g. a payout id where a payment id
+//! is expected is caught at compile time. API clients accept anything that converts into the
+//! expected identifier, including plain strings.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::Infallible,
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+use uuid::Uuid;
+
+macro_rules! resource_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+        #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+        #[serde(transparent)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new(id: impl Into<String>) -> Self {
+                Self(id.into())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+
+            pub fn into_inner(self) -> String {
+                self.0
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Infallible;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Ok(Self::new(s))
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(id: String) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<&String> for $name {
+            fn from(id: &String) -> Self {
+                Self(id.clone())
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(id: &str) -> Self {
+                Self::new(id)
+            }
+        }
+
+        impl From<&$name> for $name {
+            fn from(id: &$name) -> Self {
+                id.clone()
+            }
+        }
+
+        impl From<Uuid> for $name {
+            fn from(id: Uuid) -> Self {
+                Self(id.to_string())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                &self.0 == other
+            }
+        }
+
+        impl PartialEq<$name> for str {
+            fn eq(&self, other: &$name) -> bool {
+                self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for &str {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for String {
+            fn eq(&self, other: &$name) -> bool {
+                self == &other.0
+            }
+        }
+    };
+}
+
+resource_id!(
+    /// Identifier of a payment.
+    PaymentId
+);
+resource_id!(
+    /// Identifier of a payout.
+    PayoutId
+);
+resource_id!(
+    /// Identifier of a merchant account.
+    MerchantAccountId
+);
+resource_id!(
+    /// Identifier of a mandate.
+    MandateId
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn serde_round_trip_as_plain_string() {
+        let id = PaymentId::new("some-payment-id");
+
+        assert_eq!(serde_json::to_value(&id).unwrap(), json!("some-payment-id"));
+        assert_eq!(
+            serde_json::from_value::<PaymentId>(json!("some-payment-id")).unwrap(),
+            id
+        );
+    }
+
+    #[test]
+    fn display_and_from_str_round_trip() {
+        let id: PayoutId = "some-payout-id".parse().unwrap();
+
+        assert_eq!(id.to_string(), "some-payout-id");
+        assert_eq!(id.to_string().parse::<PayoutId>().unwrap(), id);
+    }
+
+    #[test]
+    fn from_uuid() {
+        let uuid = Uuid::new_v4();
+
+        assert_eq!(MandateId::from(uuid), uuid.to_string());
+    }
+}
@@ -0,0 +1,580 @@
+use crate::{
+    apis::{
+        mandates::{
+            CreateMandateRequest, CreateMandateResponse, Mandate, MandateConstraintsUsage,
+            MandateId,
+        },
+        payments::{
+            Currency, PsuContext, StartAuthorizationFlowRequest, StartAuthorizationFlowResponse,
+        },
+        TrueLayerClientInner, NO_QUERY,
+    },
+    common::{DecodeJson, IDEMPOTENCY_KEY_HEADER},
+    Error, IdempotentRequest, RequestOptions,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use urlencoding::encode;
+use uuid::Uuid;
+
+/// TrueLayer mandates APIs client.
+///
+/// Creating and managing mandates requires the client credentials to include
+/// the scope returned by [`MandateDetail::required_scope()`](crate::apis::mandates::MandateDetail::required_scope).
+#[derive(Clone, Debug)]
+pub struct MandatesApi {
+    inner: Arc<TrueLayerClientInner>,
+    options: RequestOptions,
+}
+
+impl MandatesApi {
+    pub(crate) fn new(inner: Arc<TrueLayerClientInner>) -> Self {
+        Self {
+            inner,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Returns a copy of this client which applies the given [`RequestOptions`] to all its requests.
+    pub fn with_options(&self, options: RequestOptions) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            options,
+        }
+    }
+
+    /// Creates a new mandate.
+    #[tracing::instrument(
+        target = "truelayer_rust::mandates",
+        name = "Create Mandate",
+        skip(self, create_mandate_request),
+        fields(currency = %create_mandate_request.currency)
+    )]
+    pub async fn create(
+        &self,
+        create_mandate_request: &CreateMandateRequest,
+    ) -> Result<CreateMandateResponse, Error> {
+        self.prepare_create(create_mandate_request)?
+            .send(&self.inner, &self.options)
+            .await
+    }
+
+    /// Prepares the request to create a new mandate without sending it.
+    ///
+    /// The returned [`IdempotentRequest`] can be persisted and submitted (even multiple times)
+    /// with [`TrueLayerClient::resubmit()`](crate::TrueLayerClient::resubmit):
+    /// the mandate is created only once.
+    pub fn prepare_create(
+        &self,
+        create_mandate_request: &CreateMandateRequest,
+    ) -> Result<IdempotentRequest<CreateMandateResponse>, Error> {
+        self.inner.validate(create_mandate_request)?;
+
+        // Generate a new random idempotency-key for this request
+        IdempotentRequest::new("/mandates", create_mandate_request, Uuid::new_v4())
+    }
+
+    /// Starts the authorization flow for a mandate.
+    ///
+    /// If the provider requires details about the user for embedded flows, set a [`PsuContext`]
+    /// with [`RequestOptions::with_psu_context()`] on the client returned by [`with_options()`](Self::with_options).
+    #[tracing::instrument(
+        target = "truelayer_rust::mandates",
+        name = "Start Mandate Authorization Flow",
+        skip(self, mandate_id, req),
+        fields(mandate_id = tracing::field::Empty)
+    )]
+    pub async fn start_authorization_flow(
+        &self,
+        mandate_id: impl Into<MandateId>,
+        req: &StartAuthorizationFlowRequest,
+    ) -> Result<StartAuthorizationFlowResponse, Error> {
+        let mandate_id = mandate_id.into();
+        tracing::Span::current().record("mandate_id", mandate_id.as_str());
+
+        // Generate a new random idempotency-key for this request
+        let idempotency_key = Uuid::new_v4();
+
+        let res = self
+            .inner
+            .client
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/mandates/{}/authorization-flow",
+                encode(mandate_id.as_str())
+            )))
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .headers(PsuContext::headers(self.options.psu_context.as_ref())?)
+            .json(req)
+            .with_extension(self.options.clone())
+            .send()
+            .await?
+            .decode_json()
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Gets the details of an existing mandate.
+    ///
+    /// If there's no mandate with the given id, `None` is returned.
+    #[tracing::instrument(
+        target = "truelayer_rust::mandates",
+        name = "Get Mandate by ID",
+        skip(self, id),
+        fields(id = tracing::field::Empty)
+    )]
+    pub async fn get_by_id(&self, id: impl Into<MandateId>) -> Result<Option<Mandate>, Error> {
+        let id = id.into();
+        tracing::Span::current().record("id", id.as_str());
+
+        self.inner
+            .get_optional(
+                self.inner
+                    .environment
+                    .payments_endpoint(&format!("/mandates/{}", encode(id.as_str()))),
+                NO_QUERY,
+                &self.options,
+            )
+            .await
+    }
+
+    /// Gets how much can still be paid against a mandate, within its constraints.
+    ///
+    /// Use [`MandateConstraintsUsage::maximum_available_amount()`] to know how much
+    /// the next payment on the mandate can move.
+    ///
+    /// If there's no mandate with the given id, `None` is returned.
+    #[tracing::instrument(
+        target = "truelayer_rust::mandates",
+        name = "Get Mandate Constraints",
+        skip(self, mandate_id),
+        fields(mandate_id = tracing::field::Empty)
+    )]
+    pub async fn get_constraints(
+        &self,
+        mandate_id: impl Into<MandateId>,
+    ) -> Result<Option<MandateConstraintsUsage>, Error> {
+        let mandate_id = mandate_id.into();
+        tracing::Span::current().record("mandate_id", mandate_id.as_str());
+
+        self.inner
+            .get_optional(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/mandates/{}/constraints",
+                    encode(mandate_id.as_str())
+                )),
+                NO_QUERY,
+                &self.options,
+            )
+            .await
+    }
+
+    /// Asks the provider whether the remitter account of a mandate
+    /// has enough funds for a payment of the given amount.
+    #[tracing::instrument(
+        target = "truelayer_rust::mandates",
+        name = "Confirm Mandate Funds",
+        skip(self, mandate_id),
+        fields(mandate_id = tracing::field::Empty)
+    )]
+    pub async fn confirm_funds(
+        &self,
+        mandate_id: impl Into<MandateId>,
+        amount_in_minor: u64,
+        currency: Currency,
+    ) -> Result<bool, Error> {
+        #[derive(Deserialize)]
+        struct ConfirmFundsResponse {
+            confirmed: bool,
+        }
+
+        let mandate_id = mandate_id.into();
+        tracing::Span::current().record("mandate_id", mandate_id.as_str());
+
+        let res: ConfirmFundsResponse = self
+            .inner
+            .client
+            .get(
+                self.inner
+                    .environment
+                    .payments_endpoint(&format!("/mandates/{}/funds", encode(mandate_id.as_str()))),
+            )
+            .query(&[
+                ("amount_in_minor", amount_in_minor.to_string()),
+                ("currency", currency.to_string()),
+            ])
+            .with_extension(self.options.clone())
+            .send()
+            .await?
+            .decode_json()
+            .await?;
+
+        Ok(res.confirmed)
+    }
+
+    /// Revokes a mandate. No further payments can be made against a revoked mandate.
+    #[tracing::instrument(
+        target = "truelayer_rust::mandates",
+        name = "Revoke Mandate",
+        skip(self, mandate_id),
+        fields(mandate_id = tracing::field::Empty)
+    )]
+    pub async fn revoke(&self, mandate_id: impl Into<MandateId>) -> Result<(), Error> {
+        let mandate_id = mandate_id.into();
+        tracing::Span::current().record("mandate_id", mandate_id.as_str());
+
+        // Generate a new random idempotency-key for this request
+        let idempotency_key = Uuid::new_v4();
+
+        self.inner
+            .client
+            .post(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/mandates/{}/revoke",
+                    encode(mandate_id.as_str())
+                )),
+            )
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .json(&json!({}))
+            .with_extension(self.options.clone())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        apis::{
+            auth::Credentials,
+            mandates::{
+                Constraints, MandateBeneficiary, MandateDetail, MandateProviderSelection,
+                MandateStatus, PeriodAlignment, PeriodicLimit, PeriodicLimits,
+                RECURRING_PAYMENTS_SWEEPING_SCOPE,
+            },
+            payments::{CreatePaymentUserRequest, Currency, FailureReason},
+        },
+        authenticator::Authenticator,
+        client::Environment,
+        middlewares::error_handling::ErrorHandlingMiddleware,
+        validation::RequestValidation,
+    };
+    use chrono::{TimeZone, Utc};
+    use url::Url;
+    use wiremock::{
+        matchers::{body_partial_json, header_exists, method, path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    async fn mock_client_and_server() -> (TrueLayerClientInner, MockServer) {
+        let mock_server = MockServer::start().await;
+
+        let credentials = Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        };
+
+        let authenticator = Authenticator::new(
+            reqwest::Client::new().into(),
+            Url::parse(&mock_server.uri()).unwrap(),
+            credentials,
+        );
+
+        let inner = TrueLayerClientInner {
+            client: reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+                .with(ErrorHandlingMiddleware)
+                .build(),
+            authenticator,
+            environment: Environment::from_single_url(&Url::parse(&mock_server.uri()).unwrap()),
+            runtime: None,
+            #[cfg(feature = "payments")]
+            provider_cache: None,
+            request_validation: RequestValidation::default(),
+        };
+
+        (inner, mock_server)
+    }
+
+    fn sweeping_mandate() -> MandateDetail {
+        MandateDetail::Sweeping {
+            provider_selection: MandateProviderSelection::Preselected {
+                provider_id: "ob-bank-name".to_string(),
+                remitter: None,
+            },
+            beneficiary: MandateBeneficiary::MerchantAccount {
+                merchant_account_id: "merchant-account-id".to_string(),
+                account_holder_name: None,
+            },
+            reference: Some("some-reference".to_string()),
+        }
+    }
+
+    fn constraints() -> Constraints {
+        Constraints {
+            valid_from: None,
+            valid_to: None,
+            maximum_individual_amount: 1000,
+            periodic_limits: Some(PeriodicLimits {
+                month: Some(PeriodicLimit {
+                    maximum_amount: 5000,
+                    period_alignment: PeriodAlignment::Calendar,
+                }),
+                ..PeriodicLimits::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn sweeping_mandates_require_sweeping_scope() {
+        assert_eq!(
+            sweeping_mandate().required_scope(),
+            RECURRING_PAYMENTS_SWEEPING_SCOPE
+        );
+    }
+
+    #[tokio::test]
+    async fn create() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = MandatesApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/mandates"))
+            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .and(body_partial_json(json!({
+                "mandate": {
+                    "type": "sweeping",
+                    "provider_selection": {
+                        "type": "preselected",
+                        "provider_id": "ob-bank-name"
+                    },
+                    "beneficiary": {
+                        "type": "merchant_account",
+                        "merchant_account_id": "merchant-account-id"
+                    },
+                    "reference": "some-reference"
+                },
+                "currency": "GBP",
+                "user": {
+                    "id": "user-id"
+                },
+                "constraints": {
+                    "maximum_individual_amount": 1000,
+                    "periodic_limits": {
+                        "month": {
+                            "maximum_amount": 5000,
+                            "period_alignment": "calendar"
+                        }
+                    }
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "mandate-id",
+                "resource_token": "resource-token",
+                "user": {
+                    "id": "user-id"
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .create(&CreateMandateRequest {
+                mandate: sweeping_mandate(),
+                currency: Currency::Gbp,
+                user: CreatePaymentUserRequest::ExistingUser {
+                    id: "user-id".to_string(),
+                },
+                constraints: constraints(),
+                metadata: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(res.id, "mandate-id");
+        assert_eq!(res.user.id, "user-id");
+    }
+
+    #[tokio::test]
+    async fn get_by_id() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = MandatesApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/mandates/mandate-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "mandate-id",
+                "currency": "GBP",
+                "mandate": {
+                    "type": "sweeping",
+                    "provider_selection": {
+                        "type": "preselected",
+                        "provider_id": "ob-bank-name"
+                    },
+                    "beneficiary": {
+                        "type": "merchant_account",
+                        "merchant_account_id": "merchant-account-id"
+                    },
+                    "reference": "some-reference"
+                },
+                "constraints": {
+                    "maximum_individual_amount": 1000,
+                    "periodic_limits": {
+                        "month": {
+                            "maximum_amount": 5000,
+                            "period_alignment": "calendar"
+                        }
+                    }
+                },
+                "created_at": "2022-01-01T00:00:00Z",
+                "status": "authorized",
+                "authorized_at": "2022-01-01T00:01:00Z"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mandate = api.get_by_id("mandate-id").await.unwrap().unwrap();
+
+        assert_eq!(mandate.id, "mandate-id");
+        assert_eq!(mandate.mandate, sweeping_mandate());
+        assert_eq!(mandate.constraints, constraints());
+        assert_eq!(
+            mandate.status,
+            MandateStatus::Authorized {
+                authorized_at: Utc.with_ymd_and_hms(2022, 1, 1, 0, 1, 0).unwrap()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_by_id_failed() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = MandatesApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/mandates/mandate-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "mandate-id",
+                "currency": "GBP",
+                "mandate": {
+                    "type": "sweeping",
+                    "provider_selection": {
+                        "type": "preselected",
+                        "provider_id": "ob-bank-name"
+                    },
+                    "beneficiary": {
+                        "type": "merchant_account",
+                        "merchant_account_id": "merchant-account-id"
+                    },
+                    "reference": "some-reference"
+                },
+                "constraints": {
+                    "maximum_individual_amount": 1000
+                },
+                "created_at": "2022-01-01T00:00:00Z",
+                "status": "failed",
+                "failed_at": "2022-01-01T00:01:00Z",
+                "failure_stage": "authorizing",
+                "failure_reason": "authorization_failed"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mandate = api.get_by_id("mandate-id").await.unwrap().unwrap();
+
+        assert_eq!(
+            mandate.status,
+            MandateStatus::Failed {
+                failed_at: Utc.with_ymd_and_hms(2022, 1, 1, 0, 1, 0).unwrap(),
+                failure_stage: "authorizing".to_string(),
+                failure_reason: FailureReason::AuthorizationFailed,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_by_id_not_found() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = MandatesApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/mandates/non-existent"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert!(api.get_by_id("non-existent").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn revoke() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = MandatesApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/mandates/mandate-id/revoke"))
+            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        api.revoke("mandate-id").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn get_constraints() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = MandatesApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/mandates/mandate-id/constraints"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "maximum_individual_amount": 1000,
+                "current_month": {
+                    "start_date": "2022-01-01T00:00:00Z",
+                    "end_date": "2022-02-01T00:00:00Z",
+                    "maximum_available_amount": 400
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let usage = api.get_constraints("mandate-id").await.unwrap().unwrap();
+
+        assert_eq!(usage.maximum_individual_amount, 1000);
+        assert_eq!(usage.current_day, None);
+        assert_eq!(usage.maximum_available_amount(), 400);
+    }
+
+    #[tokio::test]
+    async fn confirm_funds() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = MandatesApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/mandates/mandate-id/funds"))
+            .and(query_param("amount_in_minor", "100"))
+            .and(query_param("currency", "GBP"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "confirmed": true
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert!(api
+            .confirm_funds("mandate-id", 100, Currency::Gbp)
+            .await
+            .unwrap());
+    }
+}
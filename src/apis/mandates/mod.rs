@@ -0,0 +1,8 @@
+//! APIs and models related to mandates (variable recurring payments).
+
+mod api;
+mod model;
+
+pub use crate::apis::ids::MandateId;
+pub use api::MandatesApi;
+pub use model::*;
@@ -0,0 +1,389 @@
+use crate::{
+    apis::{
+        auth::ResourceToken,
+        ids::MandateId,
+        payments::{
+            AccountIdentifier, CreatePaymentUserRequest, CreatePaymentUserResponse, Currency,
+            FailureReason, Metadata, ProviderFilter, Remitter, User,
+        },
+        Missing,
+    },
+    pollable::IsInTerminalState,
+    Error, Pollable, TrueLayerClient,
+};
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Scope required to create and manage sweeping mandates.
+pub const RECURRING_PAYMENTS_SWEEPING_SCOPE: &str = "recurring_payments:sweeping";
+
+/// Scope required to create and manage commercial mandates.
+pub const RECURRING_PAYMENTS_COMMERCIAL_SCOPE: &str = "recurring_payments:commercial";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct CreateMandateRequest {
+    pub mandate: MandateDetail,
+    pub currency: Currency,
+    pub user: CreatePaymentUserRequest,
+    pub constraints: Constraints,
+    pub metadata: Option<Metadata>,
+}
+
+impl CreateMandateRequest {
+    /// Starts building a request. `build()` becomes available once the mandate details,
+    /// the currency, the user and the constraints have all been set.
+    pub fn builder() -> CreateMandateRequestBuilder {
+        CreateMandateRequestBuilder {
+            mandate: Missing,
+            currency: Missing,
+            user: Missing,
+            constraints: Missing,
+            metadata: None,
+        }
+    }
+}
+
+/// Typestate builder for [`CreateMandateRequest`], created with [`CreateMandateRequest::builder()`].
+///
+/// Each type parameter is [`Missing`] until the corresponding required field is set.
+#[derive(Debug, Clone)]
+pub struct CreateMandateRequestBuilder<M = Missing, C = Missing, U = Missing, K = Missing> {
+    mandate: M,
+    currency: C,
+    user: U,
+    constraints: K,
+    metadata: Option<Metadata>,
+}
+
+impl<M, C, U, K> CreateMandateRequestBuilder<M, C, U, K> {
+    pub fn mandate(
+        self,
+        mandate: MandateDetail,
+    ) -> CreateMandateRequestBuilder<MandateDetail, C, U, K> {
+        CreateMandateRequestBuilder {
+            mandate,
+            currency: self.currency,
+            user: self.user,
+            constraints: self.constraints,
+            metadata: self.metadata,
+        }
+    }
+
+    pub fn currency(self, currency: Currency) -> CreateMandateRequestBuilder<M, Currency, U, K> {
+        CreateMandateRequestBuilder {
+            mandate: self.mandate,
+            currency,
+            user: self.user,
+            constraints: self.constraints,
+            metadata: self.metadata,
+        }
+    }
+
+    pub fn user(
+        self,
+        user: CreatePaymentUserRequest,
+    ) -> CreateMandateRequestBuilder<M, C, CreatePaymentUserRequest, K> {
+        CreateMandateRequestBuilder {
+            mandate: self.mandate,
+            currency: self.currency,
+            user,
+            constraints: self.constraints,
+            metadata: self.metadata,
+        }
+    }
+
+    pub fn constraints(
+        self,
+        constraints: Constraints,
+    ) -> CreateMandateRequestBuilder<M, C, U, Constraints> {
+        CreateMandateRequestBuilder {
+            mandate: self.mandate,
+            currency: self.currency,
+            user: self.user,
+            constraints,
+            metadata: self.metadata,
+        }
+    }
+
+    pub fn metadata(mut self, metadata: impl Into<Metadata>) -> Self {
+        self.metadata = Some(metadata.into());
+        self
+    }
+}
+
+impl CreateMandateRequestBuilder<MandateDetail, Currency, CreatePaymentUserRequest, Constraints> {
+    /// Builds the request.
+    pub fn build(self) -> CreateMandateRequest {
+        CreateMandateRequest {
+            mandate: self.mandate,
+            currency: self.currency,
+            user: self.user,
+            constraints: self.constraints,
+            metadata: self.metadata,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MandateDetail {
+    /// Sweeping of funds between accounts owned by the same user.
+    Sweeping {
+        provider_selection: MandateProviderSelection,
+        beneficiary: MandateBeneficiary,
+        reference: Option<String>,
+    },
+    /// Recurring payments to a merchant.
+    Commercial {
+        provider_selection: MandateProviderSelection,
+        beneficiary: MandateBeneficiary,
+        reference: Option<String>,
+    },
+}
+
+impl MandateDetail {
+    /// Scope the client credentials must include to operate on this kind of mandate.
+    pub fn required_scope(&self) -> &'static str {
+        match self {
+            MandateDetail::Sweeping { .. } => RECURRING_PAYMENTS_SWEEPING_SCOPE,
+            MandateDetail::Commercial { .. } => RECURRING_PAYMENTS_COMMERCIAL_SCOPE,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MandateProviderSelection {
+    UserSelected {
+        filter: Option<ProviderFilter>,
+    },
+    Preselected {
+        provider_id: String,
+        remitter: Option<Remitter>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MandateBeneficiary {
+    MerchantAccount {
+        merchant_account_id: String,
+        account_holder_name: Option<String>,
+    },
+    ExternalAccount {
+        account_holder_name: String,
+        account_identifier: AccountIdentifier,
+    },
+}
+
+/// Limits to the payments which can be made against a mandate.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct Constraints {
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_to: Option<DateTime<Utc>>,
+    pub maximum_individual_amount: u64,
+    pub periodic_limits: Option<PeriodicLimits>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct PeriodicLimits {
+    pub day: Option<PeriodicLimit>,
+    pub week: Option<PeriodicLimit>,
+    pub fortnight: Option<PeriodicLimit>,
+    pub month: Option<PeriodicLimit>,
+    pub half_year: Option<PeriodicLimit>,
+    pub year: Option<PeriodicLimit>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct PeriodicLimit {
+    pub maximum_amount: u64,
+    pub period_alignment: PeriodAlignment,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PeriodAlignment {
+    /// Periods start from the date the mandate was authorized.
+    Consent,
+    /// Periods are aligned to calendar days, weeks, months, etc.
+    Calendar,
+}
+
+/// Amounts which can still be paid against a mandate, within its constraints.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct MandateConstraintsUsage {
+    pub valid_from: Option<DateTime<Utc>>,
+    pub valid_to: Option<DateTime<Utc>>,
+    pub maximum_individual_amount: u64,
+    /// Usage of each periodic limit of the mandate in its current period,
+    /// or `None` for the periods the mandate has no limit for.
+    pub current_day: Option<PeriodUsage>,
+    pub current_week: Option<PeriodUsage>,
+    pub current_fortnight: Option<PeriodUsage>,
+    pub current_month: Option<PeriodUsage>,
+    pub current_half_year: Option<PeriodUsage>,
+    pub current_year: Option<PeriodUsage>,
+}
+
+impl MandateConstraintsUsage {
+    /// Largest amount a single payment on the mandate can move right now,
+    /// considering both the individual limit and what is left of each periodic limit.
+    pub fn maximum_available_amount(&self) -> u64 {
+        [
+            &self.current_day,
+            &self.current_week,
+            &self.current_fortnight,
+            &self.current_month,
+            &self.current_half_year,
+            &self.current_year,
+        ]
+        .into_iter()
+        .flatten()
+        .map(|period| period.maximum_available_amount)
+        .fold(self.maximum_individual_amount, u64::min)
+    }
+}
+
+/// Usage of a periodic limit of a mandate in its current period.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct PeriodUsage {
+    pub start_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    /// Amount which can still be paid in this period.
+    pub maximum_available_amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct CreateMandateResponse {
+    pub id: MandateId,
+    pub resource_token: ResourceToken,
+    pub user: CreatePaymentUserResponse,
+}
+
+#[async_trait]
+impl Pollable for CreateMandateResponse {
+    type Output = Mandate;
+
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
+        tl.mandates
+            .get_by_id(&self.id)
+            .await
+            .transpose()
+            .unwrap_or_else(|| Err(Error::Other(anyhow!("Mandate returned 404 while polling"))))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct Mandate {
+    pub id: MandateId,
+    pub currency: Currency,
+    pub mandate: MandateDetail,
+    pub constraints: Constraints,
+    pub user: Option<User>,
+    pub created_at: DateTime<Utc>,
+    pub metadata: Option<Metadata>,
+    #[serde(flatten)]
+    pub status: MandateStatus,
+}
+
+impl TryFrom<Mandate> for CreateMandateRequest {
+    type Error = Error;
+
+    /// Builds a request for a new mandate with the same details as an existing one,
+    /// for the same user.
+    ///
+    /// Fails if the user of the mandate is not known.
+    fn try_from(m: Mandate) -> Result<Self, Self::Error> {
+        let user = m
+            .user
+            .ok_or_else(|| Error::Other(anyhow!("Mandate {} has no user", m.id)))?;
+
+        Ok(CreateMandateRequest {
+            mandate: m.mandate,
+            currency: m.currency,
+            user: user.into(),
+            constraints: m.constraints,
+            metadata: m.metadata,
+        })
+    }
+}
+
+#[async_trait]
+impl Pollable for Mandate {
+    type Output = Mandate;
+
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
+        tl.mandates
+            .get_by_id(&self.id)
+            .await
+            .transpose()
+            .unwrap_or_else(|| Err(Error::Other(anyhow!("Mandate returned 404 while polling"))))
+    }
+}
+
+impl IsInTerminalState for Mandate {
+    /// A mandate is considered to be in a terminal state if it is `Authorized`, `Failed` or `Revoked`.
+    ///
+    /// An authorized mandate can still be revoked later on.
+    fn is_in_terminal_state(&self) -> bool {
+        matches!(
+            self.status,
+            MandateStatus::Authorized { .. }
+                | MandateStatus::Failed { .. }
+                | MandateStatus::Revoked { .. }
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MandateStatus {
+    AuthorizationRequired,
+    Authorizing,
+    Authorized {
+        authorized_at: DateTime<Utc>,
+    },
+    Failed {
+        failed_at: DateTime<Utc>,
+        failure_stage: String,
+        failure_reason: FailureReason,
+    },
+    Revoked {
+        revoked_at: DateTime<Utc>,
+        revoked_by: String,
+    },
+}
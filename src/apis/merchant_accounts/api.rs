@@ -1,17 +1,20 @@
 use crate::{
     apis::{
         merchant_accounts::{
-            ListPaymentSourcesRequest, ListTransactionsRequest, MerchantAccount,
-            SetupSweepingRequest, SweepingSettings, Transaction,
+            ListMerchantAccountsRequest, ListPaymentSourcesRequest, ListTransactionsRequest,
+            MerchantAccount, MerchantAccountBalance, MerchantAccountId, SetupSweepingRequest,
+            SweepingSettings, SweepingStatus, Transaction,
         },
-        payments::PaymentSource,
-        TrueLayerClientInner,
+        payments::{Currency, PaymentSource},
+        TrueLayerClientInner, NO_QUERY,
     },
-    common::IDEMPOTENCY_KEY_HEADER,
-    Error,
+    common::{DecodeJson, IDEMPOTENCY_KEY_HEADER},
+    Error, RequestOptions,
 };
-use serde::Deserialize;
-use std::sync::Arc;
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use futures::{stream, Stream};
+use std::{sync::Arc, time::Duration};
 use urlencoding::encode;
 use uuid::Uuid;
 
@@ -19,128 +22,250 @@ use uuid::Uuid;
 #[derive(Clone, Debug)]
 pub struct MerchantAccountsApi {
     inner: Arc<TrueLayerClientInner>,
+    options: RequestOptions,
 }
 
 impl MerchantAccountsApi {
     pub(crate) fn new(inner: Arc<TrueLayerClientInner>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Returns a copy of this client which applies the given [`RequestOptions`] to all its requests.
+    pub fn with_options(&self, options: RequestOptions) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            options,
+        }
     }
 
     /// Lists all merchant accounts.
-    #[tracing::instrument(name = "List Merchant Accounts", skip(self))]
     pub async fn list(&self) -> Result<Vec<MerchantAccount>, Error> {
-        let res: ListResponse<_> = self
-            .inner
-            .client
-            .get(
+        self.list_filtered(&ListMerchantAccountsRequest::default())
+            .await
+    }
+
+    /// Lists the merchant accounts matching the currency filter and the page of the request.
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "List Merchant Accounts",
+        skip(self, request),
+        fields(currency = ?request.currency)
+    )]
+    pub async fn list_filtered(
+        &self,
+        request: &ListMerchantAccountsRequest,
+    ) -> Result<Vec<MerchantAccount>, Error> {
+        self.inner
+            .paginated_get(
                 self.inner
                     .environment
-                    .payments_url()
-                    .join("/merchant-accounts")
-                    .unwrap(),
+                    .payments_endpoint("/merchant-accounts"),
+                request,
+                &self.options,
             )
-            .send()
-            .await?
-            .json()
-            .await?;
+            .await
+    }
 
-        Ok(res.items)
+    /// Gets the first merchant account in the given currency.
+    ///
+    /// The currency is sent as a filter, and checked again on the merchant accounts returned.
+    /// Returns [`Error::NoMerchantAccount`] if there's no merchant account in the given currency.
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "Find Merchant Account by Currency",
+        skip(self)
+    )]
+    pub async fn find_by_currency(&self, currency: Currency) -> Result<MerchantAccount, Error> {
+        self.list_filtered(&ListMerchantAccountsRequest {
+            currency: Some(currency.clone()),
+            ..ListMerchantAccountsRequest::default()
+        })
+        .await?
+        .into_iter()
+        .find(|merchant_account| merchant_account.currency == currency)
+        .ok_or(Error::NoMerchantAccount(currency))
     }
 
     /// Gets the details of an existing merchant account.
     ///
     /// If there's no merchant account with the given id, `None` is returned.
-    #[tracing::instrument(name = "Get Merchant Account by ID", skip(self))]
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "Get Merchant Account by ID",
+        skip(self, merchant_account_id),
+        fields(merchant_account_id = tracing::field::Empty)
+    )]
     pub async fn get_by_id(
         &self,
-        merchant_account_id: &str,
+        merchant_account_id: impl Into<MerchantAccountId>,
     ) -> Result<Option<MerchantAccount>, Error> {
-        let res = self
-            .inner
-            .client
-            .get(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/merchant-accounts/{}",
-                        encode(merchant_account_id)
-                    ))
-                    .unwrap(),
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
+
+        self.inner
+            .get_optional(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/merchant-accounts/{}",
+                    encode(merchant_account_id.as_str())
+                )),
+                NO_QUERY,
+                &self.options,
             )
-            .send()
             .await
-            .map_err(Error::from);
+    }
 
-        // Return `None` if the server returned 404
-        let merchant_account = match res {
-            Ok(body) => Some(body.json().await?),
-            Err(Error::ApiError(api_error)) if api_error.status == 404 => None,
-            Err(e) => return Err(e),
+    /// Gets the balance of an existing merchant account.
+    ///
+    /// The Merchant Accounts API exposes balances only as part of the account details,
+    /// so this fetches the merchant account and keeps just its balance.
+    ///
+    /// If there's no merchant account with the given id, `None` is returned.
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "Get Merchant Account Balance",
+        skip(self, merchant_account_id),
+        fields(merchant_account_id = tracing::field::Empty)
+    )]
+    pub async fn get_balance(
+        &self,
+        merchant_account_id: impl Into<MerchantAccountId>,
+    ) -> Result<Option<MerchantAccountBalance>, Error> {
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
+
+        Ok(self
+            .get_by_id(merchant_account_id)
+            .await?
+            .map(|merchant_account| merchant_account.balance()))
+    }
+
+    /// Polls the balance of a merchant account every `interval`, yielding the first balance
+    /// and then only the balances which differ from the previous one.
+    ///
+    /// Errors are yielded as they happen and polling carries on after them:
+    /// drop the stream to stop watching. The stream only ends after yielding
+    /// [`Error::NoRuntime`], as it cannot wait between polls without a runtime.
+    pub fn watch_balance(
+        &self,
+        merchant_account_id: impl Into<MerchantAccountId>,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<MerchantAccountBalance, Error>> {
+        let state = WatchBalanceState {
+            api: self.clone(),
+            merchant_account_id: merchant_account_id.into(),
+            last_balance: None,
+            first_poll: true,
+            stopped: false,
         };
 
-        Ok(merchant_account)
+        stream::unfold(state, move |mut state| async move {
+            if state.stopped {
+                return None;
+            }
+
+            loop {
+                if !state.first_poll {
+                    match state.api.inner.runtime() {
+                        Ok(runtime) => runtime.sleep(interval).await,
+                        Err(e) => {
+                            state.stopped = true;
+                            return Some((Err(e), state));
+                        }
+                    }
+                }
+                state.first_poll = false;
+
+                match state.api.get_balance(&state.merchant_account_id).await {
+                    Ok(Some(balance)) if state.last_balance.as_ref() == Some(&balance) => continue,
+                    Ok(Some(balance)) => {
+                        state.last_balance = Some(balance.clone());
+                        return Some((Ok(balance), state));
+                    }
+                    Ok(None) => {
+                        let error = Error::Other(anyhow!(
+                            "Merchant account {} not found",
+                            state.merchant_account_id
+                        ));
+                        return Some((Err(error), state));
+                    }
+                    Err(e) => return Some((Err(e), state)),
+                }
+            }
+        })
     }
 
     /// Set the automatic sweeping settings for a merchant account.
     /// At regular intervals, any available balance in excess of the configured
     /// `max_amount_in_minor` is withdrawn to a pre-configured IBAN.
+    ///
+    /// Returns the settings as stored by TrueLayer.
     #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
         name = "Setup Merchant Account Sweeping",
         skip(self, merchant_account_id, request),
         fields(
-            merchant_account_id = %merchant_account_id,
+            merchant_account_id = tracing::field::Empty,
             amount_in_minor = %request.max_amount_in_minor,
             currency = %request.currency
         )
     )]
     pub async fn setup_sweeping(
         &self,
-        merchant_account_id: &str,
+        merchant_account_id: impl Into<MerchantAccountId>,
         request: &SetupSweepingRequest,
-    ) -> Result<(), Error> {
+    ) -> Result<SweepingSettings, Error> {
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
+
         // Generate a new random idempotency-key for this request
         let idempotency_key = Uuid::new_v4();
 
-        self.inner
+        let res = self
+            .inner
             .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/merchant-accounts/{}/sweeping",
-                        merchant_account_id
-                    ))
-                    .unwrap(),
-            )
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/merchant-accounts/{}/sweeping",
+                encode(merchant_account_id.as_str())
+            )))
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
             .json(request)
+            .with_extension(self.options.clone())
             .send()
+            .await?
+            .decode_json()
             .await?;
 
-        Ok(())
+        Ok(res)
     }
 
     /// Disable automatic sweeping for a merchant account.
-    #[tracing::instrument(name = "Disable Merchant Account Sweeping", skip(self))]
-    pub async fn disable_sweeping(&self, merchant_account_id: &str) -> Result<(), Error> {
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "Disable Merchant Account Sweeping",
+        skip(self, merchant_account_id),
+        fields(merchant_account_id = tracing::field::Empty)
+    )]
+    pub async fn disable_sweeping(
+        &self,
+        merchant_account_id: impl Into<MerchantAccountId>,
+    ) -> Result<(), Error> {
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
+
         // Generate a new random idempotency-key for this request
         let idempotency_key = Uuid::new_v4();
 
         self.inner
             .client
-            .delete(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/merchant-accounts/{}/sweeping",
-                        merchant_account_id
-                    ))
-                    .unwrap(),
-            )
+            .delete(self.inner.environment.payments_endpoint(&format!(
+                "/merchant-accounts/{}/sweeping",
+                encode(merchant_account_id.as_str())
+            )))
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .with_extension(self.options.clone())
             .send()
             .await?;
 
@@ -151,110 +276,154 @@ impl MerchantAccountsApi {
     ///
     /// If there's no merchant account with the given id, or if it has not enabled sweeping,
     /// `None` is returned.
-    #[tracing::instrument(name = "Get Merchant Account Sweeping Settings", skip(self))]
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "Get Merchant Account Sweeping Settings",
+        skip(self, merchant_account_id),
+        fields(merchant_account_id = tracing::field::Empty)
+    )]
     pub async fn get_sweeping_settings(
         &self,
-        merchant_account_id: &str,
+        merchant_account_id: impl Into<MerchantAccountId>,
     ) -> Result<Option<SweepingSettings>, Error> {
-        let res = self
-            .inner
-            .client
-            .get(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/merchant-accounts/{}/sweeping",
-                        encode(merchant_account_id)
-                    ))
-                    .unwrap(),
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
+
+        self.inner
+            .get_optional(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/merchant-accounts/{}/sweeping",
+                    encode(merchant_account_id.as_str())
+                )),
+                NO_QUERY,
+                &self.options,
             )
-            .send()
             .await
-            .map_err(Error::from);
+    }
 
-        // Return `None` if the server returned 404
-        let settings = match res {
-            Ok(body) => Some(body.json().await?),
-            Err(Error::ApiError(api_error)) if api_error.status == 404 => None,
-            Err(e) => return Err(e),
-        };
+    /// Gets when the next automatic sweep of a merchant account is scheduled to run.
+    ///
+    /// If there's no merchant account with the given id, if it has not enabled sweeping
+    /// or if sweeping is not active, `None` is returned.
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "Get Next Merchant Account Sweeping Execution",
+        skip(self, merchant_account_id),
+        fields(merchant_account_id = tracing::field::Empty)
+    )]
+    pub async fn get_next_sweeping_execution(
+        &self,
+        merchant_account_id: impl Into<MerchantAccountId>,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
+
+        let settings = self.get_sweeping_settings(merchant_account_id).await?;
 
-        Ok(settings)
+        Ok(settings
+            .filter(|s| s.status == SweepingStatus::Active)
+            .and_then(|s| s.next_execution_at))
     }
 
     /// Gets the transactions of a single merchant account.
-    #[tracing::instrument(name = "List Transactions", skip(self, request))]
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "List Transactions",
+        skip(self, merchant_account_id, request),
+        fields(merchant_account_id = tracing::field::Empty)
+    )]
     pub async fn list_transactions(
         &self,
-        merchant_account_id: &str,
+        merchant_account_id: impl Into<MerchantAccountId>,
         request: &ListTransactionsRequest,
     ) -> Result<Vec<Transaction>, Error> {
-        let res: ListResponse<_> = self
-            .inner
-            .client
-            .get(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/merchant-accounts/{}/transactions",
-                        merchant_account_id
-                    ))
-                    .unwrap(),
-            )
-            .query(request)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
 
-        Ok(res.items)
+        self.inner
+            .paginated_get(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/merchant-accounts/{}/transactions",
+                    encode(merchant_account_id.as_str())
+                )),
+                request,
+                &self.options,
+            )
+            .await
     }
 
     /// Gets the payment sources from which the merchant account has received payment.
     #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
         name = "List Payment Sources",
-        skip(self, request),
+        skip(self, merchant_account_id, request),
         fields(
+            merchant_account_id = tracing::field::Empty,
             user_id = %request.user_id
         )
     )]
     pub async fn list_payment_sources(
         &self,
-        merchant_account_id: &str,
+        merchant_account_id: impl Into<MerchantAccountId>,
         request: &ListPaymentSourcesRequest,
     ) -> Result<Vec<PaymentSource>, Error> {
-        let res: ListResponse<_> = self
-            .inner
-            .client
-            .get(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/merchant-accounts/{}/payment-sources",
-                        merchant_account_id
-                    ))
-                    .unwrap(),
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
+
+        self.inner
+            .paginated_get(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/merchant-accounts/{}/payment-sources",
+                    encode(merchant_account_id.as_str())
+                )),
+                request,
+                &self.options,
             )
-            .query(request)
-            .send()
-            .await?
-            .json()
-            .await?;
+            .await
+    }
 
-        Ok(res.items)
+    /// Gets the details of a payment source from which the merchant account has received payment.
+    ///
+    /// If there's no payment source with the given id, `None` is returned.
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "Get Payment Source by ID",
+        skip(self, merchant_account_id),
+        fields(merchant_account_id = tracing::field::Empty)
+    )]
+    pub async fn get_payment_source_by_id(
+        &self,
+        merchant_account_id: impl Into<MerchantAccountId>,
+        payment_source_id: &str,
+    ) -> Result<Option<PaymentSource>, Error> {
+        let merchant_account_id = merchant_account_id.into();
+        tracing::Span::current().record("merchant_account_id", merchant_account_id.as_str());
+
+        self.inner
+            .get_optional(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/merchant-accounts/{}/payment-sources/{}",
+                    encode(merchant_account_id.as_str()),
+                    encode(payment_source_id)
+                )),
+                NO_QUERY,
+                &self.options,
+            )
+            .await
     }
 }
 
-#[derive(Deserialize)]
-struct ListResponse<T> {
-    pub items: Vec<T>,
+/// State of the stream returned by [`MerchantAccountsApi::watch_balance`].
+struct WatchBalanceState {
+    api: MerchantAccountsApi,
+    merchant_account_id: MerchantAccountId,
+    last_balance: Option<MerchantAccountBalance>,
+    first_poll: bool,
+    stopped: bool,
 }
 
 #[cfg(test)]
-mod tests {
+pub(super) mod tests {
     use super::*;
     use crate::{
         apis::{
@@ -269,8 +438,10 @@ mod tests {
         authenticator::Authenticator,
         client::Environment,
         middlewares::error_handling::ErrorHandlingMiddleware,
+        validation::RequestValidation,
     };
-    use chrono::{SecondsFormat, Utc};
+    use chrono::{SecondsFormat, TimeZone, Utc};
+    use futures::StreamExt;
     use reqwest::Url;
     use serde_json::json;
     use wiremock::{
@@ -278,7 +449,7 @@ mod tests {
         Mock, MockServer, ResponseTemplate,
     };
 
-    async fn mock_client_and_server() -> (MerchantAccountsApi, MockServer) {
+    pub(crate) async fn mock_client_and_server() -> (MerchantAccountsApi, MockServer) {
         let mock_server = MockServer::start().await;
 
         let credentials = Credentials::ClientCredentials {
@@ -299,6 +470,10 @@ mod tests {
                 .build(),
             authenticator,
             environment: Environment::from_single_url(&Url::parse(&mock_server.uri()).unwrap()),
+            runtime: None,
+            #[cfg(feature = "payments")]
+            provider_cache: None,
+            request_validation: RequestValidation::default(),
         };
 
         (MerchantAccountsApi::new(Arc::new(inner)), mock_server)
@@ -337,7 +512,7 @@ mod tests {
         assert_eq!(
             merchant_accounts,
             vec![MerchantAccount {
-                id: "merchant-account-id".to_string(),
+                id: "merchant-account-id".into(),
                 currency: Currency::Gbp,
                 account_identifiers: vec![AccountIdentifier::SortCodeAccountNumber {
                     sort_code: "sort-code".to_string(),
@@ -368,6 +543,77 @@ mod tests {
         assert_eq!(merchant_accounts, vec![]);
     }
 
+    #[tokio::test]
+    async fn list_filtered_sends_filters_and_pagination() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts"))
+            .and(query_param("currency", "EUR"))
+            .and(query_param("cursor", "next-page"))
+            .and(query_param("limit", "10"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": []
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let merchant_accounts = api
+            .list_filtered(&ListMerchantAccountsRequest {
+                currency: Some(Currency::Eur),
+                cursor: Some("next-page".into()),
+                limit: Some(10),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(merchant_accounts, vec![]);
+    }
+
+    #[tokio::test]
+    async fn find_by_currency() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        let merchant_account = |id: &str, currency: &str| {
+            json!({
+                "id": id,
+                "currency": currency,
+                "account_identifiers": [],
+                "available_balance_in_minor": 100,
+                "current_balance_in_minor": 200,
+                "account_holder_name": "Mr. Holder"
+            })
+        };
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts"))
+            .and(query_param("currency", "GBP"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [
+                    merchant_account("eur-account", "EUR"),
+                    merchant_account("gbp-account", "GBP"),
+                ]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts"))
+            .and(query_param("currency", "PLN"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": []
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let found = api.find_by_currency(Currency::Gbp).await.unwrap();
+        assert_eq!(found.id.as_str(), "gbp-account");
+
+        let res = api.find_by_currency(Currency::Pln).await;
+        assert!(matches!(res, Err(Error::NoMerchantAccount(Currency::Pln))));
+    }
+
     #[tokio::test]
     async fn get_by_id() {
         let (api, mock_server) = mock_client_and_server().await;
@@ -398,7 +644,7 @@ mod tests {
         assert_eq!(
             merchant_account,
             Some(MerchantAccount {
-                id: "merchant-account-id".to_string(),
+                id: "merchant-account-id".into(),
                 currency: Currency::Gbp,
                 account_identifiers: vec![AccountIdentifier::SortCodeAccountNumber {
                     sort_code: "sort-code".to_string(),
@@ -427,6 +673,89 @@ mod tests {
         assert_eq!(merchant_account, None);
     }
 
+    fn merchant_account_response(available_balance_in_minor: i64) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(json!({
+            "id": "merchant-account-id",
+            "currency": "GBP",
+            "account_identifiers": [],
+            "available_balance_in_minor": available_balance_in_minor,
+            "current_balance_in_minor": 200,
+            "account_holder_name": "Mr. Holder"
+        }))
+    }
+
+    #[tokio::test]
+    async fn get_balance() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts/merchant-account-id"))
+            .respond_with(merchant_account_response(100))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let balance = api.get_balance("merchant-account-id").await.unwrap();
+
+        assert_eq!(
+            balance,
+            Some(MerchantAccountBalance {
+                currency: Currency::Gbp,
+                available_balance_in_minor: 100,
+                current_balance_in_minor: 200
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn overdrawn_balance_is_not_payable() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts/merchant-account-id"))
+            .respond_with(merchant_account_response(-100))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let balance = api
+            .get_balance("merchant-account-id")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(balance.available_balance_in_minor, -100);
+        assert_eq!(balance.payable_amount_in_minor(), 0);
+    }
+
+    #[tokio::test]
+    async fn watch_balance_yields_only_changes() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        // The first two polls return the same balance, then it changes
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts/merchant-account-id"))
+            .respond_with(merchant_account_response(100))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts/merchant-account-id"))
+            .respond_with(merchant_account_response(300))
+            .mount(&mock_server)
+            .await;
+
+        let balances = api
+            .watch_balance("merchant-account-id", Duration::from_millis(1))
+            .take(2)
+            .map(|balance| balance.unwrap().available_balance_in_minor)
+            .collect::<Vec<_>>()
+            .await;
+
+        assert_eq!(balances, vec![100, 300]);
+    }
+
     #[tokio::test]
     async fn setup_sweeping() {
         let (api, mock_server) = mock_client_and_server().await;
@@ -440,23 +769,55 @@ mod tests {
             .and(body_partial_json(json!({
                 "max_amount_in_minor": 100,
                 "currency": "GBP",
-                "frequency": "daily"
+                "frequency": "daily",
+                "destination": {
+                    "type": "iban",
+                    "iban": "some-iban"
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "max_amount_in_minor": 100,
+                "currency": "GBP",
+                "frequency": "daily",
+                "destination": {
+                    "type": "iban",
+                    "iban": "some-iban"
+                },
+                "status": "active",
+                "next_execution_at": "2022-03-02T00:00:00.000Z"
             })))
-            .respond_with(ResponseTemplate::new(204))
             .expect(1)
             .mount(&mock_server)
             .await;
 
-        api.setup_sweeping(
-            &merchant_account_id,
-            &SetupSweepingRequest {
+        let settings = api
+            .setup_sweeping(
+                &merchant_account_id,
+                &SetupSweepingRequest {
+                    max_amount_in_minor: 100,
+                    currency: Currency::Gbp,
+                    frequency: SweepingFrequency::Daily,
+                    destination: Some(AccountIdentifier::Iban {
+                        iban: "some-iban".into(),
+                    }),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            settings,
+            SweepingSettings {
                 max_amount_in_minor: 100,
                 currency: Currency::Gbp,
                 frequency: SweepingFrequency::Daily,
-            },
-        )
-        .await
-        .unwrap();
+                destination: AccountIdentifier::Iban {
+                    iban: "some-iban".into()
+                },
+                status: SweepingStatus::Active,
+                next_execution_at: Some(Utc.with_ymd_and_hms(2022, 3, 2, 0, 0, 0).unwrap()),
+            }
+        );
     }
 
     #[tokio::test]
@@ -481,6 +842,7 @@ mod tests {
                     max_amount_in_minor: 100,
                     currency: Currency::Gbp,
                     frequency: SweepingFrequency::Daily,
+                    destination: None,
                 },
             )
             .await;
@@ -507,6 +869,20 @@ mod tests {
         api.disable_sweeping(&merchant_account_id).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn disable_sweeping_encodes_the_merchant_account_id() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("DELETE"))
+            .and(path("/merchant-accounts/some%2Fid/sweeping"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        api.disable_sweeping("some/id").await.unwrap();
+    }
+
     #[tokio::test]
     async fn disable_sweeping_account_not_found() {
         let (api, mock_server) = mock_client_and_server().await;
@@ -545,7 +921,8 @@ mod tests {
                 "destination": {
                     "type": "iban",
                     "iban": "some-iban"
-                }
+                },
+                "status": "suspended"
             })))
             .expect(1)
             .mount(&mock_server)
@@ -564,11 +941,49 @@ mod tests {
                 frequency: SweepingFrequency::Weekly,
                 destination: AccountIdentifier::Iban {
                     iban: "some-iban".into()
-                }
+                },
+                status: SweepingStatus::Suspended,
+                next_execution_at: None,
             })
         );
     }
 
+    #[tokio::test]
+    async fn get_next_sweeping_execution() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        let merchant_account_id = "merchant-account-id".to_string();
+        Mock::given(method("GET"))
+            .and(path(format!(
+                "/merchant-accounts/{}/sweeping",
+                merchant_account_id
+            )))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "max_amount_in_minor": 100,
+                "currency": "GBP",
+                "frequency": "weekly",
+                "destination": {
+                    "type": "iban",
+                    "iban": "some-iban"
+                },
+                "status": "active",
+                "next_execution_at": "2022-03-02T00:00:00.000Z"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let next_execution = api
+            .get_next_sweeping_execution(&merchant_account_id)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            next_execution,
+            Some(Utc.with_ymd_and_hms(2022, 3, 2, 0, 0, 0).unwrap())
+        );
+    }
+
     #[tokio::test]
     async fn get_sweeping_settings_not_found() {
         let (api, mock_server) = mock_client_and_server().await;
@@ -856,7 +1271,10 @@ mod tests {
                                 sort_code: "sort-code".to_string(),
                                 account_number: "account-number".to_string()
                             },
-                            reference: "payout-reference".to_string()
+                            reference: "payout-reference".to_string(),
+                            verification: None,
+                            date_of_birth: None,
+                            address: None,
                         },
                         context_code: TransactionPayoutContextCode::Withdrawal,
                         payout_id: "payout-id-3".into()
@@ -953,4 +1371,66 @@ mod tests {
         // Expect an error
         assert!(matches!(res, Err(Error::ApiError(e)) if e.status == 404));
     }
+
+    #[tokio::test]
+    async fn get_payment_source_by_id() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path(
+                "/merchant-accounts/merchant-account-id/payment-sources/payment-source-id",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "payment-source-id",
+                "user_id": "user-id",
+                "account_identifiers": [
+                    {
+                        "type": "iban",
+                        "iban": "GB33BUKB20201555555555"
+                    }
+                ],
+                "account_holder_name": "Mr. Holder"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let payment_source = api
+            .get_payment_source_by_id("merchant-account-id", "payment-source-id")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            payment_source,
+            Some(PaymentSource {
+                id: "payment-source-id".to_string(),
+                user_id: Some("user-id".to_string()),
+                account_identifiers: vec![AccountIdentifier::Iban {
+                    iban: "GB33BUKB20201555555555".to_string()
+                }],
+                account_holder_name: Some("Mr. Holder".to_string())
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn get_payment_source_by_id_not_found() {
+        let (api, mock_server) = mock_client_and_server().await;
+
+        Mock::given(method("GET"))
+            .and(path(
+                "/merchant-accounts/merchant-account-id/payment-sources/non-existent",
+            ))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let payment_source = api
+            .get_payment_source_by_id("merchant-account-id", "non-existent")
+            .await
+            .unwrap();
+
+        assert_eq!(payment_source, None);
+    }
 }
@@ -0,0 +1,508 @@
+use crate::{
+    apis::{
+        merchant_accounts::{
+            ListTransactionsRequest, MerchantAccountsApi, Transaction, TransactionPayoutStatus,
+            TransactionType, TransactionTypeFilter,
+        },
+        payments::Currency,
+        payouts::PayoutBeneficiary,
+    },
+    BuildError, Error,
+};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Output format of a [`TransactionExporter`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum ExportFormat {
+    /// Comma separated values, with a header row.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+/// Error returned while exporting transactions.
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    /// Fetching the transactions from TrueLayer failed.
+    #[error(transparent)]
+    Api(#[from] Error),
+    /// Writing to the output failed.
+    #[error("Failed to write export: {0}")]
+    Io(#[from] std::io::Error),
+    /// A transaction could not be serialized.
+    #[error("Failed to serialize transaction: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
+
+/// Streams the transactions of a merchant account into CSV or JSON lines.
+///
+/// The requested date range is fetched in consecutive windows (7 days by default),
+/// and each window is written out as soon as it is received, so large ranges never
+/// need to be held in memory at once.
+///
+/// Each exported row contains the following fields:
+///
+/// | Field          | Content                                                                |
+/// |----------------|------------------------------------------------------------------------|
+/// | `id`           | Id of the transaction.                                                 |
+/// | `timestamp`    | When the transaction was settled (payins) or created (payouts).        |
+/// | `type`         | `merchant_account_payment`, `external_payment` or `payout`.            |
+/// | `status`       | `settled`, `pending` or `executed`.                                    |
+/// | `currency`     | ISO 4217 currency code.                                                |
+/// | `amount`       | Amount in major units, e.g. `12.50`. Negative for payouts.             |
+/// | `counterparty` | Account holder name of the remitter or beneficiary, if known.          |
+/// | `reference`    | Reference of the external payment or payout, if any.                   |
+/// | `related_id`   | Id of the payment or payout behind the transaction, if any.            |
+#[derive(Debug, Clone)]
+pub struct TransactionExporter {
+    api: MerchantAccountsApi,
+    format: ExportFormat,
+    window: Duration,
+    r#type: Option<TransactionTypeFilter>,
+}
+
+impl TransactionExporter {
+    /// Creates a new exporter that fetches transactions through the given client.
+    pub fn new(api: MerchantAccountsApi, format: ExportFormat) -> Self {
+        Self {
+            api,
+            format,
+            window: Duration::days(7),
+            r#type: None,
+        }
+    }
+
+    /// Sets the size of the windows the date range is fetched in.
+    ///
+    /// Returns a [`BuildError`] if `window` is not positive.
+    pub fn with_window(mut self, window: Duration) -> Result<Self, BuildError> {
+        if window <= Duration::zero() {
+            return Err(BuildError::invalid_field(
+                "window",
+                "the export window must be positive",
+            ));
+        }
+
+        self.window = window;
+        Ok(self)
+    }
+
+    /// Only exports transactions of the given type.
+    pub fn with_type(mut self, r#type: TransactionTypeFilter) -> Self {
+        self.r#type = Some(r#type);
+        self
+    }
+
+    /// Writes all the transactions of a merchant account between `from` and `to` to `writer`,
+    /// oldest first.
+    ///
+    /// Returns the number of exported transactions.
+    #[tracing::instrument(
+        target = "truelayer_rust::merchant_accounts",
+        name = "Export Transactions",
+        skip(self, writer)
+    )]
+    pub async fn export<W>(
+        &self,
+        merchant_account_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        writer: &mut W,
+    ) -> Result<usize, ExportError>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        if self.format == ExportFormat::Csv {
+            writer.write_all(CSV_HEADER.as_bytes()).await?;
+        }
+
+        let mut exported = 0;
+        // Transactions falling exactly on the boundary between two windows
+        // may be returned for both of them.
+        let mut previous_window_ids = HashSet::new();
+        let mut window_start = from;
+
+        loop {
+            let window_end = std::cmp::min(window_start + self.window, to);
+
+            let mut transactions = self
+                .api
+                .list_transactions(
+                    merchant_account_id,
+                    &ListTransactionsRequest {
+                        from: window_start,
+                        to: window_end,
+                        r#type: self.r#type.clone(),
+                    },
+                )
+                .await?;
+            transactions.sort_by_key(|t| timestamp(&t.r#type));
+
+            let mut window_ids = HashSet::with_capacity(transactions.len());
+            for transaction in &transactions {
+                if previous_window_ids.contains(&transaction.id) {
+                    continue;
+                }
+
+                let row = ExportRow::from(transaction);
+                let line = match self.format {
+                    ExportFormat::Csv => row.to_csv_line(),
+                    ExportFormat::JsonLines => serde_json::to_string(&row)? + "\n",
+                };
+                writer.write_all(line.as_bytes()).await?;
+
+                window_ids.insert(transaction.id.clone());
+                exported += 1;
+            }
+            previous_window_ids = window_ids;
+
+            if window_end >= to {
+                break;
+            }
+            window_start = window_end;
+        }
+
+        writer.flush().await?;
+
+        Ok(exported)
+    }
+}
+
+const CSV_HEADER: &str =
+    "id,timestamp,type,status,currency,amount,counterparty,reference,related_id\n";
+
+#[derive(Serialize, Debug)]
+struct ExportRow<'a> {
+    id: &'a str,
+    timestamp: String,
+    r#type: &'static str,
+    status: &'static str,
+    currency: String,
+    amount: String,
+    counterparty: Option<&'a str>,
+    reference: Option<&'a str>,
+    related_id: Option<&'a str>,
+}
+
+impl<'a> From<&'a Transaction> for ExportRow<'a> {
+    fn from(transaction: &'a Transaction) -> Self {
+        let (r#type, status, counterparty, reference, related_id, negative) =
+            match &transaction.r#type {
+                TransactionType::MerchantAccountPayment {
+                    payment_source,
+                    payment_id,
+                    ..
+                } => (
+                    "merchant_account_payment",
+                    "settled",
+                    payment_source.account_holder_name.as_deref(),
+                    None,
+                    Some(payment_id.as_str()),
+                    false,
+                ),
+                TransactionType::ExternalPayment { remitter, .. } => (
+                    "external_payment",
+                    "settled",
+                    Some(remitter.account_holder_name.as_str()),
+                    Some(remitter.reference.as_str()),
+                    None,
+                    false,
+                ),
+                TransactionType::Payout {
+                    status,
+                    beneficiary,
+                    payout_id,
+                    ..
+                } => {
+                    let status = match status {
+                        TransactionPayoutStatus::Pending => "pending",
+                        TransactionPayoutStatus::Executed { .. } => "executed",
+                    };
+                    let (counterparty, reference) = match beneficiary {
+                        PayoutBeneficiary::ExternalAccount {
+                            account_holder_name,
+                            reference,
+                            ..
+                        } => (Some(account_holder_name.as_str()), reference.as_str()),
+                        PayoutBeneficiary::PaymentSource { reference, .. }
+                        | PayoutBeneficiary::BusinessAccount { reference } => {
+                            (None, reference.as_str())
+                        }
+                    };
+                    (
+                        "payout",
+                        status,
+                        counterparty,
+                        Some(reference),
+                        Some(payout_id.as_str()),
+                        true,
+                    )
+                }
+            };
+
+        Self {
+            id: &transaction.id,
+            timestamp: timestamp(&transaction.r#type).to_rfc3339_opts(SecondsFormat::Millis, true),
+            r#type,
+            status,
+            currency: transaction.currency.to_string(),
+            amount: format_minor_units(
+                transaction.amount_in_minor,
+                &transaction.currency,
+                negative,
+            ),
+            counterparty,
+            reference,
+            related_id,
+        }
+    }
+}
+
+impl ExportRow<'_> {
+    fn to_csv_line(&self) -> String {
+        let fields = [
+            self.id,
+            &self.timestamp,
+            self.r#type,
+            self.status,
+            &self.currency,
+            &self.amount,
+            self.counterparty.unwrap_or_default(),
+            self.reference.unwrap_or_default(),
+            self.related_id.unwrap_or_default(),
+        ];
+
+        let mut line = fields
+            .iter()
+            .map(|field| escape_csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        line.push('\n');
+        line
+    }
+}
+
+fn timestamp(r#type: &TransactionType) -> DateTime<Utc> {
+    match r#type {
+        TransactionType::MerchantAccountPayment { settled_at, .. }
+        | TransactionType::ExternalPayment { settled_at, .. } => *settled_at,
+        TransactionType::Payout { created_at, .. } => *created_at,
+    }
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Formats an amount in major units, negative if the amount is or `negative` is set
+/// (outgoing transactions may be reported with either sign).
+fn format_minor_units(amount_in_minor: i64, currency: &Currency, negative: bool) -> String {
+    let exponent = match currency {
+        Currency::Eur | Currency::Gbp | Currency::Nok | Currency::Pln => 2,
+    };
+    let divisor = 10u64.pow(exponent);
+    // `unsigned_abs` cannot overflow, unlike `abs` on `i64::MIN`
+    let magnitude = amount_in_minor.unsigned_abs();
+
+    format!(
+        "{}{}.{:0width$}",
+        if (negative || amount_in_minor < 0) && magnitude > 0 {
+            "-"
+        } else {
+            ""
+        },
+        magnitude / divisor,
+        magnitude % divisor,
+        width = exponent as usize
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::merchant_accounts::api::tests::mock_client_and_server;
+    use chrono::TimeZone;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path, query_param},
+        Mock, ResponseTemplate,
+    };
+
+    #[test]
+    fn minor_units_are_formatted_as_decimals() {
+        assert_eq!(format_minor_units(0, &Currency::Gbp, false), "0.00");
+        assert_eq!(format_minor_units(5, &Currency::Gbp, false), "0.05");
+        assert_eq!(format_minor_units(1250, &Currency::Eur, false), "12.50");
+        assert_eq!(
+            format_minor_units(123456789, &Currency::Pln, true),
+            "-1234567.89"
+        );
+        assert_eq!(format_minor_units(0, &Currency::Nok, true), "0.00");
+        assert_eq!(format_minor_units(-1250, &Currency::Gbp, false), "-12.50");
+        assert_eq!(format_minor_units(-1250, &Currency::Gbp, true), "-12.50");
+        assert_eq!(
+            format_minor_units(i64::MIN, &Currency::Eur, false),
+            "-92233720368547758.08"
+        );
+    }
+
+    #[test]
+    fn csv_fields_are_escaped() {
+        assert_eq!(escape_csv_field("plain"), "plain");
+        assert_eq!(escape_csv_field("Holder, Mr."), "\"Holder, Mr.\"");
+        assert_eq!(escape_csv_field("a \"quote\""), "\"a \"\"quote\"\"\"");
+    }
+
+    #[tokio::test]
+    async fn window_must_be_positive() {
+        let (api, _mock_server) = mock_client_and_server().await;
+        let exporter = TransactionExporter::new(api, ExportFormat::Csv);
+
+        for window in [Duration::zero(), Duration::days(-1)] {
+            assert!(matches!(
+                exporter.clone().with_window(window),
+                Err(BuildError::InvalidField {
+                    field: "window",
+                    ..
+                })
+            ));
+        }
+        assert!(exporter.with_window(Duration::days(1)).is_ok());
+    }
+
+    fn transactions_response(ids: &[(&str, &str)]) -> serde_json::Value {
+        json!({
+            "items": ids.iter().map(|(id, settled_at)| json!({
+                "id": id,
+                "currency": "GBP",
+                "amount_in_minor": 1050,
+                "type": "external_payment",
+                "status": "settled",
+                "settled_at": settled_at,
+                "remitter": {
+                    "account_identifier": {
+                        "type": "sort_code_account_number",
+                        "sort_code": "sort-code",
+                        "account_number": "account-number"
+                    },
+                    "account_holder_name": "Holder, Mr.",
+                    "reference": "ext-payment-ref"
+                }
+            })).collect::<Vec<_>>()
+        })
+    }
+
+    #[tokio::test]
+    async fn export_fetches_range_in_windows() {
+        let (api, mock_server) = mock_client_and_server().await;
+        let from = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2022, 1, 10, 0, 0, 0).unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts/merchant-account-id/transactions"))
+            .and(query_param("from", "2022-01-01T00:00:00.000Z"))
+            .and(query_param("to", "2022-01-08T00:00:00.000Z"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(transactions_response(&[
+                    ("transaction-id-2", "2022-01-08T00:00:00.000Z"),
+                    ("transaction-id-1", "2022-01-02T00:00:00.000Z"),
+                ])),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts/merchant-account-id/transactions"))
+            .and(query_param("from", "2022-01-08T00:00:00.000Z"))
+            .and(query_param("to", "2022-01-10T00:00:00.000Z"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(transactions_response(&[
+                    ("transaction-id-2", "2022-01-08T00:00:00.000Z"),
+                    ("transaction-id-3", "2022-01-09T00:00:00.000Z"),
+                ])),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut output = Vec::new();
+        let exported = TransactionExporter::new(api, ExportFormat::Csv)
+            .export("merchant-account-id", from, to, &mut output)
+            .await
+            .unwrap();
+
+        assert_eq!(exported, 3);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "id,timestamp,type,status,currency,amount,counterparty,reference,related_id\n\
+             transaction-id-1,2022-01-02T00:00:00.000Z,external_payment,settled,GBP,10.50,\"Holder, Mr.\",ext-payment-ref,\n\
+             transaction-id-2,2022-01-08T00:00:00.000Z,external_payment,settled,GBP,10.50,\"Holder, Mr.\",ext-payment-ref,\n\
+             transaction-id-3,2022-01-09T00:00:00.000Z,external_payment,settled,GBP,10.50,\"Holder, Mr.\",ext-payment-ref,\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn export_json_lines() {
+        let (api, mock_server) = mock_client_and_server().await;
+        let from = Utc.with_ymd_and_hms(2022, 1, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2022, 1, 2, 0, 0, 0).unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts/merchant-account-id/transactions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [
+                    {
+                        "id": "transaction-id-1",
+                        "currency": "EUR",
+                        "amount_in_minor": 7,
+                        "type": "payout",
+                        "status": "executed",
+                        "created_at": "2022-01-01T10:00:00.000Z",
+                        "executed_at": "2022-01-01T11:00:00.000Z",
+                        "beneficiary": {
+                            "type": "payment_source",
+                            "user_id": "payout-user-id",
+                            "payment_source_id": "payment-source-id",
+                            "reference": "payout-reference"
+                        },
+                        "context_code": "withdrawal",
+                        "payout_id": "payout-id"
+                    }
+                ]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut output = Vec::new();
+        let exported = TransactionExporter::new(api, ExportFormat::JsonLines)
+            .export("merchant-account-id", from, to, &mut output)
+            .await
+            .unwrap();
+
+        assert_eq!(exported, 1);
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.lines().count(), 1);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&output).unwrap(),
+            json!({
+                "id": "transaction-id-1",
+                "timestamp": "2022-01-01T10:00:00.000Z",
+                "type": "payout",
+                "status": "executed",
+                "currency": "EUR",
+                "amount": "-0.07",
+                "counterparty": null,
+                "reference": "payout-reference",
+                "related_id": "payout-id"
+            })
+        );
+    }
+}
@@ -1,7 +1,12 @@
 //! APIs and models related to merchant accounts.
 
 mod api;
+#[cfg(feature = "export")]
+mod export;
 mod model;
 
+pub use crate::apis::ids::MerchantAccountId;
 pub use api::MerchantAccountsApi;
+#[cfg(feature = "export")]
+pub use export::{ExportError, ExportFormat, TransactionExporter};
 pub use model::*;
@@ -1,28 +1,115 @@
-use crate::apis::{
-    payments::{AccountIdentifier, Currency, ExternalPaymentRemitter, PaymentSource},
-    payouts::PayoutBeneficiary,
+use crate::{
+    apis::{
+        ids::MerchantAccountId,
+        payments::{AccountIdentifier, Currency, ExternalPaymentRemitter, PaymentSource},
+        payouts::PayoutBeneficiary,
+    },
+    pollable::{PollError, PollOptions},
+    Error, Pollable, TrueLayerClient,
 };
+use anyhow::anyhow;
+use async_trait::async_trait;
 use chrono::{DateTime, SecondsFormat, Utc};
+use retry_policies::RetryPolicy;
 use serde::{Deserialize, Serialize, Serializer};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct MerchantAccount {
-    pub id: String,
+    pub id: MerchantAccountId,
     pub currency: Currency,
     pub account_identifiers: Vec<AccountIdentifier>,
-    pub available_balance_in_minor: u64,
-    pub current_balance_in_minor: u64,
+    /// Balance which can be paid out. Negative if the account is overdrawn.
+    pub available_balance_in_minor: i64,
+    /// Balance including the funds not yet settled. Negative if the account is overdrawn.
+    pub current_balance_in_minor: i64,
     pub account_holder_name: String,
 }
 
+#[async_trait]
+impl Pollable for MerchantAccount {
+    type Output = MerchantAccount;
+
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
+        tl.merchant_accounts
+            .get_by_id(&self.id)
+            .await
+            .transpose()
+            .unwrap_or_else(|| {
+                Err(Error::Other(anyhow!(
+                    "Merchant account returned 404 while polling"
+                )))
+            })
+    }
+}
+
+impl MerchantAccount {
+    /// Returns the balance of this merchant account.
+    pub fn balance(&self) -> MerchantAccountBalance {
+        MerchantAccountBalance {
+            currency: self.currency.clone(),
+            available_balance_in_minor: self.available_balance_in_minor,
+            current_balance_in_minor: self.current_balance_in_minor,
+        }
+    }
+
+    /// Continuously polls the server for updates on this merchant account until its available balance
+    /// is greater than or equal to `min_available_balance_in_minor`.
+    ///
+    /// Balances have no terminal state, so this is the balance counterpart of
+    /// [`poll_until_terminal_state`](crate::PollableUntilTerminalState::poll_until_terminal_state).
+    pub async fn poll_until_available_balance<R: RetryPolicy + Send + Sync>(
+        &self,
+        tl: &TrueLayerClient,
+        poll_options: PollOptions<R>,
+        min_available_balance_in_minor: i64,
+    ) -> Result<MerchantAccount, PollError> {
+        self.poll_until(tl, poll_options, |m| {
+            m.available_balance_in_minor >= min_available_balance_in_minor
+        })
+        .await
+    }
+}
+
+/// Balance of a merchant account.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct MerchantAccountBalance {
+    pub currency: Currency,
+    /// Balance which can be paid out. Negative if the account is overdrawn.
+    pub available_balance_in_minor: i64,
+    /// Balance including the funds not yet settled. Negative if the account is overdrawn.
+    pub current_balance_in_minor: i64,
+}
+
+impl MerchantAccountBalance {
+    /// Returns the amount which can be paid out, e.g. as the `amount_in_minor` of a
+    /// [`CreatePayoutRequest`](crate::apis::payouts::CreatePayoutRequest), or `0` if the account is overdrawn.
+    pub fn payable_amount_in_minor(&self) -> u64 {
+        u64::try_from(self.available_balance_in_minor).unwrap_or(0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct SetupSweepingRequest {
     pub max_amount_in_minor: u64,
     pub currency: Currency,
     pub frequency: SweepingFrequency,
+    /// Which of the approved accounts the excess balance is withdrawn to.
+    /// If `None`, the default approved account for the merchant account is used.
+    pub destination: Option<AccountIdentifier>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum SweepingFrequency {
     Daily,
@@ -31,19 +118,51 @@ pub enum SweepingFrequency {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct SweepingSettings {
     pub max_amount_in_minor: u64,
     pub currency: Currency,
     pub frequency: SweepingFrequency,
     pub destination: AccountIdentifier,
+    pub status: SweepingStatus,
+    /// When the next sweep is scheduled to run, if sweeping is active.
+    pub next_execution_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SweepingStatus {
+    Active,
+    Suspended,
+}
+
+/// Filters and pagination of the list of merchant accounts.
+///
+/// Parameters left to `None` are not sent.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct ListMerchantAccountsRequest {
+    /// Lists only the merchant accounts in this currency.
+    pub currency: Option<Currency>,
+    /// Cursor of the page to list, as returned by a previous request.
+    pub cursor: Option<String>,
+    /// Maximum number of merchant accounts to list.
+    pub limit: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct ListPaymentSourcesRequest {
     pub user_id: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct ListTransactionsRequest {
     #[serde(serialize_with = "serialize_timestamp")]
     pub from: DateTime<Utc>,
@@ -53,6 +172,7 @@ pub struct ListTransactionsRequest {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum TransactionTypeFilter {
     Payment,
@@ -60,15 +180,18 @@ pub enum TransactionTypeFilter {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Transaction {
     pub id: String,
     pub currency: Currency,
-    pub amount_in_minor: u64,
+    /// Amount of the transaction, which some views report as negative for outgoing transactions.
+    pub amount_in_minor: i64,
     #[serde(flatten)]
     pub r#type: TransactionType,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum TransactionType {
     MerchantAccountPayment {
@@ -93,12 +216,14 @@ pub enum TransactionType {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum TransactionPayinStatus {
     Settled,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum TransactionPayoutStatus {
     Pending,
@@ -106,6 +231,7 @@ pub enum TransactionPayoutStatus {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum TransactionPayoutContextCode {
     Withdrawal,
@@ -1,19 +1,136 @@
 //! Clients for the various TrueLayer APIs.
 
-use crate::{authenticator::Authenticator, client::Environment};
+use crate::{
+    authenticator::Authenticator,
+    client::Environment,
+    common::DecodeJson,
+    runtime::{resolve_runtime, Runtime},
+    validation::{RequestValidation, StrictValidation},
+    Error, RequestOptions,
+};
+use reqwest::Url;
 use reqwest_middleware::ClientWithMiddleware;
-use std::fmt::{Debug, Formatter};
+#[cfg(any(feature = "payments", feature = "merchant-accounts"))]
+use serde::Deserialize;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
 
 pub mod auth;
+#[cfg(feature = "data")]
+pub mod data;
+mod ids;
+#[cfg(feature = "mandates")]
+pub mod mandates;
+#[cfg(feature = "merchant-accounts")]
 pub mod merchant_accounts;
 pub mod payments;
 pub mod payments_providers;
+#[cfg(feature = "payouts")]
 pub mod payouts;
 
 pub(crate) struct TrueLayerClientInner {
     pub(crate) client: ClientWithMiddleware,
     pub(crate) authenticator: Authenticator,
     pub(crate) environment: Environment,
+    pub(crate) runtime: Option<Arc<dyn Runtime>>,
+    #[cfg(feature = "payments")]
+    pub(crate) provider_cache: Option<crate::apis::payments_providers::ProviderCache>,
+    pub(crate) request_validation: RequestValidation,
+}
+
+impl TrueLayerClientInner {
+    /// Returns the configured runtime, falling back to the one the caller is running on.
+    ///
+    /// The fallback is resolved lazily so that the client can be built outside of any runtime.
+    pub(crate) fn runtime(&self) -> Result<Arc<dyn Runtime>, Error> {
+        resolve_runtime(self.runtime.as_ref())
+    }
+
+    /// Checks a request locally if strict validation is enabled.
+    pub(crate) fn validate(&self, request: &impl StrictValidation) -> Result<(), Error> {
+        match self.request_validation {
+            RequestValidation::Disabled => Ok(()),
+            RequestValidation::Strict => Ok(request.validate_strict()?),
+        }
+    }
+
+    /// Gets a single resource, sending `query` as query string parameters.
+    ///
+    /// Returns `None` if the server returned 404.
+    pub(crate) async fn get_optional<T, Q>(
+        &self,
+        url: Url,
+        query: &Q,
+        options: &RequestOptions,
+    ) -> Result<Option<T>, Error>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let res = self
+            .client
+            .get(url)
+            .query(query)
+            .with_extension(options.clone())
+            .send()
+            .await
+            .map_err(Error::from);
+
+        // Return `None` if the server returned 404
+        match res {
+            Ok(body) => Ok(Some(body.decode_json().await?)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Gets the items of a collection, sending `query` as query string parameters.
+    ///
+    /// Unlike [`get_optional()`](Self::get_optional), a 404 is returned as an error,
+    /// as it means the collection itself (or its parent resource) does not exist.
+    /// See the [`error`](crate::error) module for the policy followed by all API clients.
+    #[cfg(any(feature = "payments", feature = "merchant-accounts"))]
+    pub(crate) async fn paginated_get<T, Q>(
+        &self,
+        url: Url,
+        query: &Q,
+        options: &RequestOptions,
+    ) -> Result<Vec<T>, Error>
+    where
+        T: DeserializeOwned,
+        Q: Serialize + ?Sized,
+    {
+        let res: ListResponse<T> = self
+            .client
+            .get(url)
+            .query(query)
+            .with_extension(options.clone())
+            .send()
+            .await?
+            .decode_json()
+            .await?;
+
+        Ok(res.items)
+    }
+}
+
+/// State of a required field which has not been set yet in a typestate builder,
+/// like [`CreatePaymentRequestBuilder`](payments::CreatePaymentRequestBuilder).
+///
+/// `build()` is only available once no field is in this state.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct Missing;
+
+/// Query string for requests without query parameters.
+pub(crate) const NO_QUERY: &[(&str, &str)] = &[];
+
+#[cfg(any(feature = "payments", feature = "merchant-accounts"))]
+#[derive(Deserialize)]
+struct ListResponse<T> {
+    items: Vec<T>,
 }
 
 impl Debug for TrueLayerClientInner {
@@ -3,37 +3,68 @@ use crate::{
         auth::Token,
         payments::{
             refunds::{CreateRefundRequest, CreateRefundResponse, Refund},
-            CreatePaymentRequest, CreatePaymentResponse, Payment, StartAuthorizationFlowRequest,
+            AuthorizationFlowNextAction, CreatePaymentRequest, CreatePaymentResponse, Payment,
+            PaymentId, ProviderReturnOutcome, PsuContext, RetryPaymentRequest,
+            RetryPaymentResponse, SignupPlusUserData, StartAuthorizationFlowRequest,
             StartAuthorizationFlowResponse, SubmitConsentActionResponse, SubmitFormActionRequest,
             SubmitFormActionResponse, SubmitProviderReturnParametersRequest,
-            SubmitProviderReturnParametersResponse, SubmitProviderSelectionActionRequest,
-            SubmitProviderSelectionActionResponse,
+            SubmitProviderReturnParametersResponse, SubmitProviderReturnParametersResponseResource,
+            SubmitProviderSelectionActionRequest, SubmitProviderSelectionActionResponse,
         },
-        TrueLayerClientInner,
+        TrueLayerClientInner, NO_QUERY,
     },
-    common::IDEMPOTENCY_KEY_HEADER,
-    Error,
+    common::{DecodeJson, IDEMPOTENCY_KEY_HEADER},
+    hpp::HppLink,
+    pollable::{PollError, PollOptions},
+    Error, IdempotentRequest, Pollable, PollableUntilTerminalState, RequestOptions,
+    TrueLayerClient,
 };
+use anyhow::anyhow;
+use futures::{stream, StreamExt};
 use reqwest::Url;
-use serde::Deserialize;
+use retry_policies::RetryPolicy;
 use serde_json::json;
-use std::sync::Arc;
+use std::{collections::HashMap, ops::Deref, sync::Arc};
+use url::form_urlencoded;
 use urlencoding::encode;
 use uuid::Uuid;
 
+/// Maximum number of requests in flight at the same time in [`PaymentsApi::get_many`].
+const GET_MANY_MAX_CONCURRENCY: usize = 10;
+
 /// TrueLayer payments APIs client.
 #[derive(Clone, Debug)]
 pub struct PaymentsApi {
     inner: Arc<TrueLayerClientInner>,
+    options: RequestOptions,
 }
 
 impl PaymentsApi {
     pub(crate) fn new(inner: Arc<TrueLayerClientInner>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Returns a copy of this client which applies the given [`RequestOptions`] to all its requests.
+    pub fn with_options(&self, options: RequestOptions) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            options,
+        }
     }
 
     /// Creates a new payment.
+    ///
+    /// With [`RequestValidation::Strict`](crate::validation::RequestValidation::Strict), the request
+    /// is checked with [`CreatePaymentRequest::validate`] first, and rejected without being sent
+    /// if its currency cannot be used with its accounts or countries.
+    ///
+    /// Use [`create_handle()`](Self::create_handle) to carry on the flow of the payment
+    /// without passing its id around.
     #[tracing::instrument(
+        target = "truelayer_rust::payments",
         name = "Create Payment",
         skip(self, create_payment_request),
         fields(
@@ -45,209 +76,399 @@ impl PaymentsApi {
         &self,
         create_payment_request: &CreatePaymentRequest,
     ) -> Result<CreatePaymentResponse, Error> {
-        // Generate a new random idempotency-key for this request
-        let idempotency_key = Uuid::new_v4();
+        self.prepare_create(create_payment_request)?
+            .send(&self.inner, &self.options)
+            .await
+    }
 
-        let res = self
-            .inner
-            .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join("/payments")
-                    .unwrap(),
-            )
-            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
-            .json(create_payment_request)
-            .send()
-            .await?
-            .json()
-            .await?;
+    /// Creates a new payment like [`create()`](Self::create), returning a [`PaymentHandle`]
+    /// which dereferences to the [`CreatePaymentResponse`] and carries on the flow of the payment.
+    pub async fn create_handle(
+        &self,
+        create_payment_request: &CreatePaymentRequest,
+    ) -> Result<PaymentHandle, Error> {
+        let response = self.create(create_payment_request).await?;
 
-        Ok(res)
+        Ok(PaymentHandle {
+            api: self.clone(),
+            response,
+        })
+    }
+
+    /// Prepares the request to create a new payment without sending it.
+    ///
+    /// The returned [`IdempotentRequest`] can be persisted and submitted (even multiple times)
+    /// with [`TrueLayerClient::resubmit()`](crate::TrueLayerClient::resubmit):
+    /// the payment is created only once.
+    pub fn prepare_create(
+        &self,
+        create_payment_request: &CreatePaymentRequest,
+    ) -> Result<IdempotentRequest<CreatePaymentResponse>, Error> {
+        self.inner.validate(create_payment_request)?;
+
+        // Generate a new random idempotency-key for this request
+        IdempotentRequest::new("/payments", create_payment_request, Uuid::new_v4())
     }
 
     /// Starts the authorization flow for a payment.
-    #[tracing::instrument(name = "Start Authorization Flow", skip(self, req))]
+    ///
+    /// If the provider requires details about the user for embedded flows, set a [`PsuContext`]
+    /// with [`RequestOptions::with_psu_context()`] on the client returned by [`with_options()`](Self::with_options).
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Start Authorization Flow",
+        skip(self, payment_id, req),
+        fields(payment_id = tracing::field::Empty)
+    )]
     pub async fn start_authorization_flow(
         &self,
-        payment_id: &str,
+        payment_id: impl Into<PaymentId>,
         req: &StartAuthorizationFlowRequest,
     ) -> Result<StartAuthorizationFlowResponse, Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
         // Generate a new random idempotency-key for this request
         let idempotency_key = Uuid::new_v4();
 
         let res = self
             .inner
             .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/payments/{}/authorization-flow",
-                        encode(payment_id)
-                    ))
-                    .unwrap(),
-            )
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/payments/{}/authorization-flow",
+                encode(payment_id.as_str())
+            )))
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .headers(PsuContext::headers(self.options.psu_context.as_ref())?)
             .json(req)
+            .with_extension(self.options.clone())
             .send()
             .await?
-            .json()
+            .decode_json()
             .await?;
 
         Ok(res)
     }
 
+    /// Restarts the authorization flow of a payment which has not been authorized yet,
+    /// for example to let the user try another bank after a failure.
+    ///
+    /// Returns an error without contacting the server if the payment is not in a resumable state.
+    /// See [`Payment::is_authorization_flow_resumable()`].
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Restart Authorization Flow",
+        skip(self, payment, req)
+    )]
+    pub async fn restart_authorization_flow(
+        &self,
+        payment: &Payment,
+        req: &StartAuthorizationFlowRequest,
+    ) -> Result<StartAuthorizationFlowResponse, Error> {
+        if !payment.is_authorization_flow_resumable() {
+            return Err(Error::Other(anyhow!(
+                "The authorization flow of payment {} cannot be restarted",
+                payment.id
+            )));
+        }
+
+        self.start_authorization_flow(&payment.id, req).await
+    }
+
     /// Submits the provider details selected by the PSU.
-    #[tracing::instrument(name = "Submit Provider Selection", skip(self, req))]
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Submit Provider Selection",
+        skip(self, payment_id, req),
+        fields(payment_id = tracing::field::Empty)
+    )]
     pub async fn submit_provider_selection(
         &self,
-        payment_id: &str,
+        payment_id: impl Into<PaymentId>,
         req: &SubmitProviderSelectionActionRequest,
     ) -> Result<SubmitProviderSelectionActionResponse, Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
         // Generate a new random idempotency-key for this request
         let idempotency_key = Uuid::new_v4();
 
         let res = self
             .inner
             .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/payments/{}/authorization-flow/actions/provider-selection",
-                        encode(payment_id)
-                    ))
-                    .unwrap(),
-            )
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/payments/{}/authorization-flow/actions/provider-selection",
+                encode(payment_id.as_str())
+            )))
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .headers(PsuContext::headers(self.options.psu_context.as_ref())?)
             .json(req)
+            .with_extension(self.options.clone())
             .send()
             .await?
-            .json()
+            .decode_json()
             .await?;
 
         Ok(res)
     }
 
     /// Formally submits the consent provided by the PSU
-    #[tracing::instrument(name = "Submit Consent", skip(self))]
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Submit Consent",
+        skip(self, payment_id),
+        fields(payment_id = tracing::field::Empty)
+    )]
     pub async fn submit_consent(
         &self,
-        payment_id: &str,
+        payment_id: impl Into<PaymentId>,
     ) -> Result<SubmitConsentActionResponse, Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
         let idempotency_key = Uuid::new_v4();
 
         let res = self
             .inner
             .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/payments/{}/authorization-flow/actions/consent",
-                        encode(payment_id)
-                    ))
-                    .unwrap(),
-            )
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/payments/{}/authorization-flow/actions/consent",
+                encode(payment_id.as_str())
+            )))
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .headers(PsuContext::headers(self.options.psu_context.as_ref())?)
             .json(&json!({}))
+            .with_extension(self.options.clone())
             .send()
             .await?
-            .json()
+            .decode_json()
             .await?;
 
         Ok(res)
     }
 
     /// Submits the form inputs entered by the PSU.
-    #[tracing::instrument(name = "Submit Form", skip(self, req))]
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Submit Form",
+        skip(self, payment_id, req),
+        fields(payment_id = tracing::field::Empty)
+    )]
     pub async fn submit_form_inputs(
         &self,
-        payment_id: &str,
+        payment_id: impl Into<PaymentId>,
         req: &SubmitFormActionRequest,
     ) -> Result<SubmitFormActionResponse, Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
         // Generate a new random idempotency-key for this request
         let idempotency_key = Uuid::new_v4();
 
         let res = self
             .inner
             .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/payments/{}/authorization-flow/actions/form",
-                        encode(payment_id)
-                    ))
-                    .unwrap(),
-            )
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/payments/{}/authorization-flow/actions/form",
+                encode(payment_id.as_str())
+            )))
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .headers(PsuContext::headers(self.options.psu_context.as_ref())?)
             .json(req)
+            .with_extension(self.options.clone())
             .send()
             .await?
-            .json()
+            .decode_json()
             .await?;
 
         Ok(res)
     }
 
     /// Attempts to cancel a payment.
-    #[tracing::instrument(name = "Cancel", skip(self))]
-    pub async fn cancel(&self, payment_id: &str) -> Result<(), Error> {
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Cancel",
+        skip(self, payment_id),
+        fields(payment_id = tracing::field::Empty)
+    )]
+    pub async fn cancel(&self, payment_id: impl Into<PaymentId>) -> Result<(), Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
         // Generate a new random idempotency-key for this request
         let idempotency_key = Uuid::new_v4();
 
         self.inner
             .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!("/payments/{}/actions/cancel", encode(payment_id)))
-                    .unwrap(),
-            )
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/payments/{}/actions/cancel",
+                encode(payment_id.as_str())
+            )))
             .json(&json!({}))
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .with_extension(self.options.clone())
             .send()
             .await?;
 
         Ok(())
     }
 
-    /// Gets the details of an existing payment.
+    /// Retries a failed payment, starting a new authorization attempt on the same payment
+    /// instead of creating a new one.
     ///
-    /// If there's no payment with the given id, `None` is returned.
-    #[tracing::instrument(name = "Get Payment by ID", skip(self))]
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<Payment>, Error> {
+    /// Only payments which failed for reasons the provider considers recoverable can be retried:
+    /// the server rejects the request otherwise.
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Retry Payment",
+        skip(self, payment_id, retry_payment_request),
+        fields(payment_id = tracing::field::Empty)
+    )]
+    pub async fn retry(
+        &self,
+        payment_id: impl Into<PaymentId>,
+        retry_payment_request: &RetryPaymentRequest,
+    ) -> Result<RetryPaymentResponse, Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
+        // Generate a new random idempotency-key for this request
+        let idempotency_key = Uuid::new_v4();
+
         let res = self
             .inner
             .client
-            .get(
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/payments/{}/actions/retry",
+                encode(payment_id.as_str())
+            )))
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .json(retry_payment_request)
+            .with_extension(self.options.clone())
+            .send()
+            .await?
+            .decode_json()
+            .await?;
+
+        Ok(res)
+    }
+
+    /// Gets the details of an existing payment.
+    ///
+    /// If there's no payment with the given id, `None` is returned.
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Get Payment by ID",
+        skip(self, id),
+        fields(id = tracing::field::Empty)
+    )]
+    pub async fn get_by_id(&self, id: impl Into<PaymentId>) -> Result<Option<Payment>, Error> {
+        let id = id.into();
+        tracing::Span::current().record("id", id.as_str());
+
+        self.inner
+            .get_optional(
                 self.inner
                     .environment
-                    .payments_url()
-                    .join(&format!("/payments/{}", encode(id)))
-                    .unwrap(),
+                    .payments_endpoint(&format!("/payments/{}", encode(id.as_str()))),
+                NO_QUERY,
+                &self.options,
             )
-            .send()
             .await
-            .map_err(Error::from);
+    }
+
+    /// Gets the details of many payments, keeping at most 10 requests in flight at the same time.
+    ///
+    /// Each id is mapped to the result of its own lookup, as [`get_by_id`](Self::get_by_id)
+    /// would return it: a failure only affects the payment it occurred for, so the lookups
+    /// which failed can be retried on their own.
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Get Payments by ID",
+        skip_all,
+        fields(count = ids.len())
+    )]
+    pub async fn get_many(
+        &self,
+        ids: &[PaymentId],
+    ) -> HashMap<PaymentId, Result<Option<Payment>, Error>> {
+        stream::iter(ids)
+            .map(|id| async move { (id.clone(), self.get_by_id(id).await) })
+            .buffer_unordered(GET_MANY_MAX_CONCURRENCY)
+            .collect()
+            .await
+    }
 
-        // Return `None` if the server returned 404
-        let payment = match res {
-            Ok(body) => Some(body.json().await?),
-            Err(Error::ApiError(api_error)) if api_error.status == 404 => None,
-            Err(e) => return Err(e),
+    /// Waits for the authorization of a payment to progress when its next action is
+    /// [`Wait`](AuthorizationFlowNextAction::Wait), e.g. while the user authorizes it in their bank's app.
+    ///
+    /// The payment is polled with the given [`PollOptions`] until its next action is not `Wait` anymore:
+    /// the returned payment either requires a new action from the user, has been authorized
+    /// or has reached a terminal state.
+    /// If the payment is not waiting in the first place, it is returned straight away.
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Await Payment Authorization",
+        skip(self, payment_id, poll_options),
+        fields(payment_id = tracing::field::Empty)
+    )]
+    pub async fn await_authorization<R>(
+        &self,
+        payment_id: impl Into<PaymentId>,
+        poll_options: PollOptions<R>,
+    ) -> Result<Payment, PollError>
+    where
+        R: RetryPolicy + Send + Sync,
+    {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
+        let payment = self
+            .get_by_id(&payment_id)
+            .await?
+            .ok_or_else(|| Error::Other(anyhow!("Payment {} not found", payment_id)))?;
+        let is_waiting =
+            |p: &Payment| matches!(p.next_action(), Some(AuthorizationFlowNextAction::Wait));
+        if !is_waiting(&payment) {
+            return Ok(payment);
+        }
+
+        // Poll through this client, so that its request options are applied
+        let tl = TrueLayerClient {
+            payments: self.clone(),
+            ..TrueLayerClient::from_inner(self.inner.clone())
         };
+        payment
+            .poll_until(&tl, poll_options, |p| !is_waiting(p))
+            .await
+    }
+
+    /// Gets the identity data of the payer of a payment created with Signup+ enabled
+    /// in its [`RelatedProducts`](crate::apis::payments::RelatedProducts).
+    ///
+    /// If the data is not available for the given payment, `None` is returned.
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Get Signup+ User Data",
+        skip(self, payment_id),
+        fields(payment_id = tracing::field::Empty)
+    )]
+    pub async fn get_signup_plus_user_data(
+        &self,
+        payment_id: impl Into<PaymentId>,
+    ) -> Result<Option<SignupPlusUserData>, Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
 
-        Ok(payment)
+        self.inner
+            .get_optional(
+                self.inner
+                    .environment
+                    .payments_endpoint("/signup-plus/payments"),
+                &[("payment_id", payment_id.as_str())],
+                &self.options,
+            )
+            .await
     }
 
     /// Creates a link to the TrueLayer Hosted Payments Page.
@@ -255,15 +476,15 @@ impl PaymentsApi {
     /// Note that the `return_uri` must be configured in your TrueLayer console.
     pub async fn get_hosted_payments_page_link(
         &self,
-        payment_id: &str,
+        payment_id: impl Into<PaymentId>,
         resource_token: &Token,
         return_uri: &str,
     ) -> Url {
-        let mut new_uri = self.inner.environment.hpp_url().join("/payments").unwrap();
+        let mut new_uri = self.inner.environment.hpp_endpoint("/payments");
 
         new_uri.set_fragment(Some(&format!(
             "payment_id={}&resource_token={}&return_uri={}",
-            payment_id,
+            payment_id.into(),
             resource_token.expose_secret(),
             return_uri
         )));
@@ -272,7 +493,11 @@ impl PaymentsApi {
     }
 
     /// Submit direct return query and fragment parameters returned from the provider.
-    #[tracing::instrument(name = "Submit Provider Return Parameters", skip_all)]
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Submit Provider Return Parameters",
+        skip_all
+    )]
     pub async fn submit_provider_return_parameters(
         &self,
         req: &SubmitProviderReturnParametersRequest,
@@ -286,117 +511,308 @@ impl PaymentsApi {
             .post(
                 self.inner
                     .environment
-                    .payments_url()
-                    .join("/payments-provider-return")
-                    .unwrap(),
+                    .payments_endpoint("/payments-provider-return"),
             )
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
             .json(req)
+            .with_extension(self.options.clone())
             .send()
             .await?
-            .json()
+            .decode_json()
             .await?;
 
         Ok(res)
     }
 
+    /// Handles the redirect back from the provider in a direct return flow.
+    ///
+    /// Splits the full `return_uri` the PSU landed on into its query and fragment,
+    /// submits them to TrueLayer with [`submit_provider_return_parameters()`](Self::submit_provider_return_parameters)
+    /// and returns whether the PSU came back for a payment or a mandate, or with an error
+    /// reported by the provider through the `error` and `error_description` parameters.
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Submit Provider Return URI",
+        skip_all
+    )]
+    pub async fn submit_provider_return_uri(
+        &self,
+        return_uri: &str,
+    ) -> Result<ProviderReturnOutcome, Error> {
+        let return_uri = Url::parse(return_uri)
+            .map_err(|e| Error::Other(anyhow!("Invalid provider return URI: {}", e)))?;
+        let query = return_uri.query().unwrap_or_default().to_string();
+        let fragment = return_uri.fragment().unwrap_or_default().to_string();
+
+        // Providers can report errors either in the query or in the fragment
+        let find_param = |name: &str| {
+            form_urlencoded::parse(query.as_bytes())
+                .chain(form_urlencoded::parse(fragment.as_bytes()))
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+        };
+        let error = find_param("error");
+        let error_description = find_param("error_description");
+
+        let res = self
+            .submit_provider_return_parameters(&SubmitProviderReturnParametersRequest {
+                query,
+                fragment,
+            })
+            .await?;
+
+        Ok(match (error, res.resource) {
+            (Some(error), resource) => ProviderReturnOutcome::Error {
+                resource,
+                error,
+                error_description,
+            },
+            (None, SubmitProviderReturnParametersResponseResource::Payment { payment_id }) => {
+                ProviderReturnOutcome::PaymentReturn { payment_id }
+            }
+            (None, SubmitProviderReturnParametersResponseResource::Mandate { mandate_id }) => {
+                ProviderReturnOutcome::MandateReturn { mandate_id }
+            }
+        })
+    }
+
     /// Creates a refund for a payment.
     #[tracing::instrument(
+        target = "truelayer_rust::payments",
         name = "Create Refund",
-        skip(self, create_refund_request),
+        skip(self, payment_id, create_refund_request),
         fields(
-            amount_in_minor = create_refund_request.amount_in_minor,
+            payment_id = tracing::field::Empty,
+            amount_in_minor = create_refund_request.amount_in_minor
         )
     )]
     pub async fn create_refund(
         &self,
-        payment_id: &str,
+        payment_id: impl Into<PaymentId>,
         create_refund_request: &CreateRefundRequest,
     ) -> Result<CreateRefundResponse, Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+        self.inner.validate(create_refund_request)?;
+
         let idempotency_key = Uuid::new_v4();
 
         let res = self
             .inner
             .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!("/payments/{}/refunds", encode(payment_id)))
-                    .unwrap(),
-            )
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/payments/{}/refunds",
+                encode(payment_id.as_str())
+            )))
             .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
             .json(create_refund_request)
+            .with_extension(self.options.clone())
             .send()
             .await?
-            .json()
+            .decode_json()
             .await?;
 
         Ok(res)
     }
 
+    /// Cancels a refund which is still `Pending`, so that it is never executed.
+    ///
+    /// Refunds which have already been authorized cannot be cancelled.
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Cancel Refund",
+        skip(self, payment_id),
+        fields(payment_id = tracing::field::Empty)
+    )]
+    pub async fn cancel_refund(
+        &self,
+        payment_id: impl Into<PaymentId>,
+        refund_id: &str,
+    ) -> Result<(), Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
+        let idempotency_key = Uuid::new_v4();
+
+        self.inner
+            .client
+            .post(self.inner.environment.payments_endpoint(&format!(
+                "/payments/{}/refunds/{}/actions/cancel",
+                encode(payment_id.as_str()),
+                encode(refund_id)
+            )))
+            .json(&json!({}))
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
+            .with_extension(self.options.clone())
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
     /// Gets the details of an existing refund.
     ///
     /// If there's no refund with the given id for the given payment id, `None` is returned.
-    #[tracing::instrument(name = "Get Refund by ID", skip(self))]
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "Get Refund by ID",
+        skip(self, payment_id),
+        fields(payment_id = tracing::field::Empty)
+    )]
     pub async fn get_refund_by_id(
         &self,
-        payment_id: &str,
+        payment_id: impl Into<PaymentId>,
         id: &str,
     ) -> Result<Option<Refund>, Error> {
-        let res = self
-            .inner
-            .client
-            .get(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!(
-                        "/payments/{}/refunds/{}",
-                        encode(payment_id),
-                        encode(id)
-                    ))
-                    .unwrap(),
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
+        self.inner
+            .get_optional(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/payments/{}/refunds/{}",
+                    encode(payment_id.as_str()),
+                    encode(id)
+                )),
+                NO_QUERY,
+                &self.options,
             )
-            .send()
             .await
-            .map_err(Error::from);
-
-        // Return `None` if the server returned 404
-        let refund = match res {
-            Ok(body) => Some(body.json().await?),
-            Err(Error::ApiError(api_error)) if api_error.status == 404 => None,
-            Err(e) => return Err(e),
-        };
-
-        Ok(refund)
     }
 
     /// Gets the refunds of a payment.
-    #[tracing::instrument(name = "List Refunds", skip(self))]
-    pub async fn list_refunds(&self, payment_id: &str) -> Result<Vec<Refund>, Error> {
-        let res: ListResponse<_> = self
-            .inner
-            .client
-            .get(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join(&format!("/payments/{}/refunds", encode(payment_id)))
-                    .unwrap(),
+    #[tracing::instrument(
+        target = "truelayer_rust::payments",
+        name = "List Refunds",
+        skip(self, payment_id),
+        fields(payment_id = tracing::field::Empty)
+    )]
+    pub async fn list_refunds(
+        &self,
+        payment_id: impl Into<PaymentId>,
+    ) -> Result<Vec<Refund>, Error> {
+        let payment_id = payment_id.into();
+        tracing::Span::current().record("payment_id", payment_id.as_str());
+
+        self.inner
+            .paginated_get(
+                self.inner.environment.payments_endpoint(&format!(
+                    "/payments/{}/refunds",
+                    encode(payment_id.as_str())
+                )),
+                NO_QUERY,
+                &self.options,
             )
-            .send()
-            .await?
-            .json()
-            .await?;
+            .await
+    }
+}
+
+/// A payment just created with [`PaymentsApi::create_handle()`], bound to the client which created it.
+///
+/// Follow-up operations are sent for this payment only, with the same [`RequestOptions`]
+/// as its creation. The functions of [`PaymentsApi`] taking a payment id remain available.
+///
+/// ```rust,no_run
+/// # use truelayer_rust::{TrueLayerClient, apis::payments::CreatePaymentRequest, pollable::PollOptions};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let tl: TrueLayerClient = unreachable!();
+/// # let req: CreatePaymentRequest = unreachable!();
+/// let payment = tl.payments.create_handle(&req).await?;
+/// println!("Redirect the user to {}", payment.hpp_link("https://my.return.uri").await.url());
+///
+/// let payment = payment.poll(PollOptions::default()).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "the handle holds the id and the resource token of the payment just created"]
+pub struct PaymentHandle {
+    api: PaymentsApi,
+    response: CreatePaymentResponse,
+}
+
+impl PaymentHandle {
+    /// Id of the payment.
+    pub fn id(&self) -> &PaymentId {
+        &self.response.id
+    }
+
+    /// Response returned when the payment was created, including the resource token.
+    pub fn response(&self) -> &CreatePaymentResponse {
+        &self.response
+    }
+
+    /// Consumes the handle, returning the response to the creation of the payment.
+    pub fn into_response(self) -> CreatePaymentResponse {
+        self.response
+    }
+
+    /// Starts the authorization flow of the payment.
+    ///
+    /// See [`PaymentsApi::start_authorization_flow()`].
+    pub async fn start_authorization_flow(
+        &self,
+        req: &StartAuthorizationFlowRequest,
+    ) -> Result<StartAuthorizationFlowResponse, Error> {
+        self.api.start_authorization_flow(self.id(), req).await
+    }
+
+    /// Attempts to cancel the payment.
+    pub async fn cancel(&self) -> Result<(), Error> {
+        self.api.cancel(self.id()).await
+    }
+
+    /// Creates a refund for the payment.
+    pub async fn refund(
+        &self,
+        create_refund_request: &CreateRefundRequest,
+    ) -> Result<CreateRefundResponse, Error> {
+        self.api
+            .create_refund(self.id(), create_refund_request)
+            .await
+    }
+
+    /// Builds the link to the Hosted Payments Page for the payment, valid as long as its resource token.
+    ///
+    /// Note that the `return_uri` must be configured in your TrueLayer console.
+    pub async fn hpp_link(&self, return_uri: &str) -> HppLink {
+        let url = self
+            .api
+            .get_hosted_payments_page_link(self.id(), &self.response.resource_token, return_uri)
+            .await;
+
+        HppLink::new(url, &self.response.resource_token)
+    }
+
+    /// Polls the payment until it reaches a terminal state.
+    pub async fn poll<R>(&self, poll_options: PollOptions<R>) -> Result<Payment, PollError>
+    where
+        R: RetryPolicy + Send + Sync,
+    {
+        // Poll through the client which created the payment, so that its request options are applied
+        let tl = TrueLayerClient {
+            payments: self.api.clone(),
+            ..TrueLayerClient::from_inner(self.api.inner.clone())
+        };
+        self.response
+            .poll_until_terminal_state(&tl, poll_options)
+            .await
+    }
+}
 
-        Ok(res.items)
+impl Deref for PaymentHandle {
+    type Target = CreatePaymentResponse;
+
+    fn deref(&self) -> &Self::Target {
+        &self.response
     }
 }
 
-#[derive(Deserialize)]
-struct ListResponse<T> {
-    pub items: Vec<T>,
+impl From<PaymentHandle> for CreatePaymentResponse {
+    fn from(handle: PaymentHandle) -> Self {
+        handle.response
+    }
 }
 
 #[cfg(test)]
@@ -406,25 +822,30 @@ mod tests {
         apis::{
             auth::Credentials,
             payments::{
-                refunds::RefundStatus, AdditionalInputType, AuthorizationFlowNextAction,
-                AuthorizationFlowResponseStatus, Beneficiary, ConsentSupported, CountryCode,
-                CreatePaymentStatus, CreatePaymentUserRequest, Currency, FailureStage,
-                FormSupported, PaymentMethod, PaymentMethodRequest, PaymentStatus, Provider,
-                ProviderSelection, ProviderSelectionRequest, ProviderSelectionSupported,
-                RedirectSupported, SchemeSelection, SubmitProviderReturnParametersResponseResource,
-                User,
+                refunds::RefundStatus, AccountIdentifier, AdditionalInputType,
+                AuthorizationFlowNextAction, AuthorizationFlowResponseStatus, Beneficiary,
+                ConsentSupported, CountryCode, CreatePaymentStatus, CreatePaymentUserRequest,
+                Currency, FailureReason, FailureStage, FormSupported, PaymentMethod,
+                PaymentMethodRequest, PaymentSchedule, PaymentStatus, Provider, ProviderSelection,
+                ProviderSelectionRequest, ProviderSelectionSupported, RedirectSupported,
+                RelatedProducts, RiskAssessment, RiskSegment, SchemeSelection, SignupPlus,
+                SignupPlusAddress, SubmitProviderReturnParametersResponseResource, User,
             },
         },
         authenticator::Authenticator,
         client::Environment,
+        common::{PSU_DEVICE_ID_HEADER, PSU_IP_ADDRESS_HEADER, PSU_USER_AGENT_HEADER},
         middlewares::error_handling::ErrorHandlingMiddleware,
+        validation::{RequestValidation, ValidationError, ValidationErrorKind},
     };
-    use chrono::Utc;
+    use chrono::{NaiveDate, Utc};
     use reqwest::Url;
+    use retry_policies::{policies::ExponentialBackoff, RetryPolicy};
     use serde_json::json;
-    use std::collections::HashMap;
+    use std::{collections::HashMap, str::FromStr, time::Duration};
     use wiremock::{
-        matchers::{body_partial_json, header_exists, method, path},
+        http::HeaderName,
+        matchers::{body_partial_json, header, header_exists, method, path, query_param},
         Mock, MockServer, ResponseTemplate,
     };
 
@@ -449,6 +870,9 @@ mod tests {
                 .build(),
             authenticator,
             environment: Environment::from_single_url(&Url::parse(&mock_server.uri()).unwrap()),
+            runtime: None,
+            provider_cache: None,
+            request_validation: RequestValidation::default(),
         };
 
         (inner, mock_server)
@@ -481,6 +905,12 @@ mod tests {
                 },
                 "user": {
                     "id": "user-id"
+                },
+                "related_products": {
+                    "signup_plus": {}
+                },
+                "risk_assessment": {
+                    "segment": "first_time_buyer"
                 }
             })))
             .respond_with(ResponseTemplate::new(200).set_body_json(json!({
@@ -511,37 +941,145 @@ mod tests {
                         account_holder_name: None,
                         reference: None,
                         statement_reference: None,
+                        verification: None,
                     },
                 },
                 user: CreatePaymentUserRequest::ExistingUser {
                     id: "user-id".to_string(),
                 },
                 metadata: None,
+                schedule: None,
+                related_products: Some(RelatedProducts {
+                    signup_plus: Some(SignupPlus {}),
+                }),
+                risk_assessment: Some(RiskAssessment {
+                    segment: Some(RiskSegment::FirstTimeBuyer),
+                }),
             })
             .await
             .unwrap();
 
         assert_eq!(res.id, "payment-id");
         assert_eq!(res.resource_token.expose_secret(), "resource-token");
+        assert!(!res.resource_token.is_probably_expired());
         assert_eq!(res.user.id, "user-id");
         assert_eq!(res.status, CreatePaymentStatus::AuthorizationRequired)
     }
 
     #[tokio::test]
-    async fn start_authorization_flow() {
-        let (inner, mock_server) = mock_client_and_server().await;
-        let api = PaymentsApi::new(Arc::new(inner));
-
-        let payment_id = "payment-id";
-
+    async fn create_retries_timeouts_with_the_same_idempotency_key() {
+        let mock_server = MockServer::start().await;
         Mock::given(method("POST"))
-            .and(path(format!("/payments/{}/authorization-flow", payment_id)))
-            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
-            .and(body_partial_json(json!({
-                "provider_selection": {},
-                "redirect": {
-                    "return_uri": "https://my.return.uri"
-                },
+            .and(path("/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .mount(&mock_server)
+            .await;
+        let response = ResponseTemplate::new(200).set_body_json(json!({
+            "id": "payment-id",
+            "resource_token": "resource-token",
+            "user": {
+                "id": "user-id"
+            },
+            "status": "authorization_required"
+        }));
+        // The first attempt times out, the second one succeeds
+        Mock::given(method("POST"))
+            .and(path("/payments"))
+            .respond_with(response.clone().set_delay(Duration::from_secs(5)))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/payments"))
+            .respond_with(response)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let retry_policy: Arc<dyn RetryPolicy + Send + Sync> = Arc::new(
+            ExponentialBackoff::builder()
+                .retry_bounds(Duration::from_millis(1), Duration::from_millis(10))
+                .build_with_max_retries(1),
+        );
+        let tl = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(retry_policy)
+        .with_environment(Environment::from_single_url(
+            &Url::parse(&mock_server.uri()).unwrap(),
+        ))
+        .build();
+
+        let res = tl
+            .payments
+            .with_options(RequestOptions::default().with_timeout(Duration::from_millis(200)))
+            .create(&CreatePaymentRequest {
+                amount_in_minor: 100,
+                currency: Currency::Gbp,
+                payment_method: PaymentMethodRequest::BankTransfer {
+                    provider_selection: ProviderSelectionRequest::UserSelected {
+                        filter: None,
+                        scheme_selection: None,
+                    },
+                    beneficiary: Beneficiary::MerchantAccount {
+                        merchant_account_id: "merchant-account-id".to_string(),
+                        account_holder_name: None,
+                        reference: None,
+                        statement_reference: None,
+                        verification: None,
+                    },
+                },
+                user: CreatePaymentUserRequest::ExistingUser {
+                    id: "user-id".to_string(),
+                },
+                metadata: None,
+                schedule: None,
+                related_products: None,
+                risk_assessment: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(res.id, "payment-id");
+
+        let idempotency_keys = mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|r| r.url.path() == "/payments")
+            .map(|r| {
+                r.headers
+                    .get(&HeaderName::from_str(IDEMPOTENCY_KEY_HEADER).unwrap())
+                    .map(|v| v.last().to_string())
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(idempotency_keys.len(), 2);
+        assert!(idempotency_keys[0].is_some());
+        assert_eq!(idempotency_keys[0], idempotency_keys[1]);
+    }
+
+    #[tokio::test]
+    async fn start_authorization_flow() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        let payment_id = "payment-id";
+
+        Mock::given(method("POST"))
+            .and(path(format!("/payments/{}/authorization-flow", payment_id)))
+            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .and(body_partial_json(json!({
+                "provider_selection": {},
+                "redirect": {
+                    "return_uri": "https://my.return.uri"
+                },
                 "consent": {},
             })))
             .respond_with(ResponseTemplate::new(200).set_body_json(json!({
@@ -606,7 +1144,8 @@ mod tests {
                     icon_uri: Some("https://truelayer-provider-assets.s3.amazonaws.com/global/icon/generic.svg".to_string()),
                     logo_uri: Some("https://truelayer-provider-assets.s3.amazonaws.com/global/logos/generic.svg".to_string()),
                     bg_color: Some("#000000".to_string()),
-                    country_code: Some(CountryCode::GB)
+                    country_code: Some(CountryCode::GB),
+                    availability: None
                 }]
             }
         );
@@ -647,6 +1186,7 @@ mod tests {
                 payment_id,
                 &SubmitProviderSelectionActionRequest {
                     provider_id: provider_id.to_string(),
+                    scheme_id: None,
                 },
             )
             .await
@@ -705,6 +1245,7 @@ mod tests {
                 payment_id,
                 &SubmitProviderSelectionActionRequest {
                     provider_id: provider_id.to_string(),
+                    scheme_id: None,
                 },
             )
             .await
@@ -714,7 +1255,7 @@ mod tests {
             res.status,
             AuthorizationFlowResponseStatus::Failed {
                 failure_stage: FailureStage::Authorizing,
-                failure_reason: "mock_reason".to_string()
+                failure_reason: FailureReason::Other("mock_reason".to_string())
             }
         );
         assert!(res
@@ -779,6 +1320,54 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn submit_consent_with_psu_context() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path(
+                "/payments/payment-id/authorization-flow/actions/consent",
+            ))
+            .and(header(PSU_IP_ADDRESS_HEADER, "192.168.0.1"))
+            .and(header(PSU_USER_AGENT_HEADER, "Mozilla/5.0"))
+            .and(header(PSU_DEVICE_ID_HEADER, "device-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "status": "authorizing"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .with_options(RequestOptions::default().with_psu_context(PsuContext {
+                ip_address: Some("192.168.0.1".parse().unwrap()),
+                user_agent: Some("Mozilla/5.0".to_string()),
+                device_id: Some("device-id".to_string()),
+            }))
+            .submit_consent("payment-id")
+            .await
+            .unwrap();
+
+        assert_eq!(res.status, AuthorizationFlowResponseStatus::Authorizing);
+    }
+
+    #[tokio::test]
+    async fn invalid_psu_context_is_rejected_before_sending() {
+        let (inner, _mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        let res = api
+            .with_options(RequestOptions::default().with_psu_context(PsuContext {
+                user_agent: Some("invalid\nuser agent".to_string()),
+                ..PsuContext::default()
+            }))
+            .submit_consent("payment-id")
+            .await;
+
+        assert!(matches!(res, Err(Error::Other(_))));
+    }
+
     #[tokio::test]
     async fn submit_form_inputs() {
         let (inner, mock_server) = mock_client_and_server().await;
@@ -851,6 +1440,52 @@ mod tests {
         api.cancel(payment_id).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn cancel_refund() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path(
+                "/payments/payment-id/refunds/refund-id/actions/cancel",
+            ))
+            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        api.cancel_refund("payment-id", "refund-id").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn retry() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payments/payment-id/actions/retry"))
+            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .and(body_partial_json(json!({ "provider_selection": null })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "payment-id",
+                "resource_token": "resource-token",
+                "status": "authorization_required"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .retry("payment-id", &RetryPaymentRequest::default())
+            .await
+            .unwrap();
+
+        assert_eq!(res.id, "payment-id");
+        assert_eq!(res.resource_token.expose_secret(), "resource-token");
+        assert_eq!(res.status, CreatePaymentStatus::AuthorizationRequired);
+    }
+
     #[tokio::test]
     async fn get_by_id_successful() {
         let (inner, mock_server) = mock_client_and_server().await;
@@ -877,6 +1512,9 @@ mod tests {
                     "id": "user-id"
                 },
                 "created_at": Utc::now(),
+                "risk_assessment": {
+                    "segment": "card_on_file"
+                },
                 "status": "authorization_required",
             })))
             .expect(1)
@@ -901,102 +1539,649 @@ mod tests {
                     merchant_account_id: "merchant-account-id".to_string(),
                     account_holder_name: None,
                     reference: None,
-                    statement_reference: None
+                    statement_reference: None,
+                    verification: None,
                 }
             }
         );
         assert_eq!(
             payment.user,
             User {
-                id: "user-id".to_string()
+                id: "user-id".to_string(),
+                date_of_birth: None,
+                address: None,
             }
         );
+        assert_eq!(
+            payment.risk_assessment,
+            Some(RiskAssessment {
+                segment: Some(RiskSegment::CardOnFile)
+            })
+        );
         assert_eq!(payment.status, PaymentStatus::AuthorizationRequired);
     }
 
     #[tokio::test]
-    async fn get_by_id_not_found() {
+    async fn create_and_get_scheduled_payment() {
         let (inner, mock_server) = mock_client_and_server().await;
         let api = PaymentsApi::new(Arc::new(inner));
 
-        Mock::given(method("GET"))
-            .and(path("/payments/non-existent"))
-            .respond_with(ResponseTemplate::new(404))
-            .expect(1)
-            .mount(&mock_server)
-            .await;
-
-        assert!(api.get_by_id("non-existent").await.unwrap().is_none());
-    }
-
-    #[tokio::test]
-    async fn submit_provider_return_parameters() {
-        let (inner, mock_server) = mock_client_and_server().await;
-        let api = PaymentsApi::new(Arc::new(inner));
+        let payment_id = "scheduled-payment-id";
+        let execution_date = NaiveDate::from_ymd_opt(2030, 1, 15).unwrap();
 
         Mock::given(method("POST"))
-            .and(path("/payments-provider-return"))
-            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .and(path("/payments"))
             .and(body_partial_json(json!({
-                "query": "query",
-                "fragment": "fragment"
+                "schedule": {
+                    "execution_date": "2030-01-15",
+                    "execution_window": null
+                }
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "id": payment_id,
+                "resource_token": "resource-token",
+                "user": {
+                    "id": "user-id"
+                },
+                "status": "authorization_required"
             })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path(format!("/payments/{}", payment_id)))
             .respond_with(ResponseTemplate::new(200).set_body_json(json!({
-                "resource": {
-                    "type": "payment",
-                    "payment_id": "payment-id"
-                }
+                "id": payment_id,
+                "amount_in_minor": 100,
+                "currency": "GBP",
+                "payment_method": {
+                    "type": "bank_transfer",
+                    "provider_selection": {
+                        "type": "user_selected"
+                    },
+                    "beneficiary": {
+                        "type": "merchant_account",
+                        "merchant_account_id": "merchant-account-id",
+                    }
+                },
+                "user": {
+                    "id": "user-id"
+                },
+                "created_at": Utc::now(),
+                "schedule": {
+                    "execution_date": "2030-01-15"
+                },
+                "status": "scheduled",
+                "scheduled_for": "2030-01-15"
             })))
             .expect(1)
             .mount(&mock_server)
             .await;
 
         let res = api
-            .submit_provider_return_parameters(&SubmitProviderReturnParametersRequest {
-                query: "query".into(),
-                fragment: "fragment".into(),
+            .create(&CreatePaymentRequest {
+                amount_in_minor: 100,
+                currency: Currency::Gbp,
+                payment_method: PaymentMethodRequest::BankTransfer {
+                    provider_selection: ProviderSelectionRequest::UserSelected {
+                        filter: None,
+                        scheme_selection: None,
+                    },
+                    beneficiary: Beneficiary::MerchantAccount {
+                        merchant_account_id: "merchant-account-id".to_string(),
+                        account_holder_name: None,
+                        reference: None,
+                        statement_reference: None,
+                        verification: None,
+                    },
+                },
+                user: CreatePaymentUserRequest::ExistingUser {
+                    id: "user-id".to_string(),
+                },
+                metadata: None,
+                schedule: Some(PaymentSchedule {
+                    execution_date,
+                    execution_window: None,
+                }),
+                related_products: None,
+                risk_assessment: None,
             })
             .await
             .unwrap();
 
+        let payment = api.get_by_id(&res.id).await.unwrap().unwrap();
+
         assert_eq!(
-            res,
-            SubmitProviderReturnParametersResponse {
-                resource: SubmitProviderReturnParametersResponseResource::Payment {
-                    payment_id: "payment-id".into()
-                }
+            payment.schedule,
+            Some(PaymentSchedule {
+                execution_date,
+                execution_window: None
+            })
+        );
+        assert_eq!(
+            payment.status,
+            PaymentStatus::Scheduled {
+                scheduled_for: execution_date,
+                execution_window: None,
+                authorization_flow: None
             }
         );
     }
 
+    fn failed_payment(failure_stage: FailureStage, failure_reason: &str) -> Payment {
+        Payment {
+            id: "payment-id".into(),
+            amount_in_minor: 100,
+            currency: Currency::Gbp,
+            user: User {
+                id: "user-id".to_string(),
+                date_of_birth: None,
+                address: None,
+            },
+            payment_method: PaymentMethod::BankTransfer {
+                provider_selection: ProviderSelection::UserSelected {
+                    filter: None,
+                    scheme_selection: None,
+                    provider_id: None,
+                    scheme_id: None,
+                },
+                beneficiary: Beneficiary::MerchantAccount {
+                    merchant_account_id: "merchant-account-id".to_string(),
+                    account_holder_name: None,
+                    reference: None,
+                    statement_reference: None,
+                    verification: None,
+                },
+            },
+            created_at: Utc::now(),
+            metadata: None,
+            schedule: None,
+            risk_assessment: None,
+            status: PaymentStatus::Failed {
+                failed_at: Utc::now(),
+                failure_stage,
+                failure_reason: failure_reason.into(),
+                authorization_flow: None,
+                provider_error: None,
+            },
+        }
+    }
+
     #[tokio::test]
-    async fn create_refund() {
+    async fn restart_authorization_flow() {
         let (inner, mock_server) = mock_client_and_server().await;
         let api = PaymentsApi::new(Arc::new(inner));
 
-        let payment_id = "payment-id";
-        let refund_id = "refund-id";
-
         Mock::given(method("POST"))
-            .and(path(format!("/payments/{}/refunds", payment_id)))
+            .and(path("/payments/payment-id/authorization-flow"))
             .and(header_exists(IDEMPOTENCY_KEY_HEADER))
-            .and(body_partial_json(json!({
-                "amount_in_minor": 100,
-                "reference": "some-reference"
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "authorization_flow": {
+                    "actions": {
+                        "next": {
+                            "type": "provider_selection",
+                            "providers": []
+                        }
+                    }
+                },
+                "status": "authorizing"
             })))
-            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": refund_id })))
             .expect(1)
             .mount(&mock_server)
             .await;
 
         let res = api
-            .create_refund(
-                payment_id,
-                &CreateRefundRequest {
-                    amount_in_minor: Some(100),
-                    reference: "some-reference".into(),
-                    metadata: None,
-                },
+            .restart_authorization_flow(
+                &failed_payment(FailureStage::Authorizing, "provider_rejected"),
+                &StartAuthorizationFlowRequest {
+                    provider_selection: Some(ProviderSelectionSupported {}),
+                    redirect: None,
+                    form: None,
+                    consent: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(res.status, AuthorizationFlowResponseStatus::Authorizing);
+    }
+
+    #[tokio::test]
+    async fn restart_authorization_flow_not_resumable() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .restart_authorization_flow(
+                &failed_payment(FailureStage::Authorizing, "expired"),
+                &StartAuthorizationFlowRequest {
+                    provider_selection: Some(ProviderSelectionSupported {}),
+                    redirect: None,
+                    form: None,
+                    consent: None,
+                },
+            )
+            .await;
+
+        assert!(matches!(res, Err(Error::Other(_))));
+    }
+
+    #[tokio::test]
+    async fn get_by_id_not_found() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/payments/non-existent"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert!(api.get_by_id("non-existent").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn get_many_maps_each_id_to_its_result() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "payment-id",
+                "amount_in_minor": 100,
+                "currency": "GBP",
+                "payment_method": {
+                    "type": "bank_transfer",
+                    "provider_selection": {
+                        "type": "user_selected"
+                    },
+                    "beneficiary": {
+                        "type": "merchant_account",
+                        "merchant_account_id": "merchant-account-id",
+                    }
+                },
+                "user": {
+                    "id": "user-id"
+                },
+                "created_at": Utc::now(),
+                "status": "authorization_required",
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/non-existent"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/failing"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let results = api
+            .get_many(&["payment-id".into(), "non-existent".into(), "failing".into()])
+            .await;
+
+        assert_eq!(results.len(), 3);
+        assert!(matches!(
+            &results[&PaymentId::from("payment-id")],
+            Ok(Some(payment)) if payment.id == "payment-id"
+        ));
+        assert!(matches!(
+            results[&PaymentId::from("non-existent")],
+            Ok(None)
+        ));
+        assert!(matches!(
+            &results[&PaymentId::from("failing")],
+            Err(Error::ApiError(e)) if e.status == 500
+        ));
+    }
+
+    #[tokio::test]
+    async fn await_authorization_polls_through_wait_action() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        let payment = |next_action: serde_json::Value| {
+            json!({
+                "id": "payment-id",
+                "amount_in_minor": 100,
+                "currency": "GBP",
+                "payment_method": {
+                    "type": "bank_transfer",
+                    "provider_selection": {
+                        "type": "user_selected"
+                    },
+                    "beneficiary": {
+                        "type": "merchant_account",
+                        "merchant_account_id": "merchant-account-id",
+                    }
+                },
+                "user": {
+                    "id": "user-id"
+                },
+                "created_at": Utc::now(),
+                "status": "authorizing",
+                "authorization_flow": {
+                    "actions": {
+                        "next": next_action
+                    }
+                }
+            })
+        };
+
+        // The first two fetches return a payment waiting for the provider, then it asks for a redirect
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(payment(json!({ "type": "wait" }))),
+            )
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(payment(json!({
+                "type": "redirect",
+                "uri": "https://provider.com"
+            }))))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let payment = api
+            .await_authorization(
+                "payment-id",
+                PollOptions::default()
+                    .with_min_wait(Duration::ZERO)
+                    .with_retry_policy(
+                        ExponentialBackoff::builder()
+                            .retry_bounds(Duration::from_millis(1), Duration::from_millis(10))
+                            .build_with_max_retries(5),
+                    ),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            payment.next_action(),
+            Some(&AuthorizationFlowNextAction::Redirect {
+                uri: "https://provider.com".to_string(),
+                metadata: None
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn get_signup_plus_user_data() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/signup-plus/payments"))
+            .and(query_param("payment_id", "payment-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "title": "Mr",
+                "first_name": "Sherlock",
+                "last_name": "Holmes",
+                "date_of_birth": "1854-01-06",
+                "address": {
+                    "address_line1": "221B Baker Street",
+                    "city": "London",
+                    "zip": "NW1 6XE",
+                    "country_code": "GB"
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let user_data = api
+            .get_signup_plus_user_data("payment-id")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            user_data,
+            SignupPlusUserData {
+                title: Some("Mr".to_string()),
+                first_name: "Sherlock".to_string(),
+                last_name: "Holmes".to_string(),
+                date_of_birth: NaiveDate::from_ymd_opt(1854, 1, 6).unwrap(),
+                address: SignupPlusAddress {
+                    address_line1: "221B Baker Street".to_string(),
+                    address_line2: None,
+                    city: "London".to_string(),
+                    state: None,
+                    zip: "NW1 6XE".to_string(),
+                    country_code: "GB".to_string(),
+                }
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn get_signup_plus_user_data_not_found() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/signup-plus/payments"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert!(api
+            .get_signup_plus_user_data("payment-id")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn submit_provider_return_parameters() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payments-provider-return"))
+            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .and(body_partial_json(json!({
+                "query": "query",
+                "fragment": "fragment"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "resource": {
+                    "type": "payment",
+                    "payment_id": "payment-id"
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .submit_provider_return_parameters(&SubmitProviderReturnParametersRequest {
+                query: "query".into(),
+                fragment: "fragment".into(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            res,
+            SubmitProviderReturnParametersResponse {
+                resource: SubmitProviderReturnParametersResponseResource::Payment {
+                    payment_id: "payment-id".into()
+                }
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn submit_provider_return_uri_payment() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payments-provider-return"))
+            .and(body_partial_json(json!({
+                "query": "code=some-code&state=some-state",
+                "fragment": "id_token=some-token"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "resource": {
+                    "type": "payment",
+                    "payment_id": "payment-id"
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let outcome = api
+            .submit_provider_return_uri(
+                "https://my.return.uri/redirect?code=some-code&state=some-state#id_token=some-token",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            ProviderReturnOutcome::PaymentReturn {
+                payment_id: "payment-id".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn submit_provider_return_uri_mandate() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payments-provider-return"))
+            .and(body_partial_json(json!({
+                "query": "",
+                "fragment": "code=some-code"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "resource": {
+                    "type": "mandate",
+                    "mandate_id": "mandate-id"
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let outcome = api
+            .submit_provider_return_uri("https://my.return.uri/redirect#code=some-code")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            ProviderReturnOutcome::MandateReturn {
+                mandate_id: "mandate-id".into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn submit_provider_return_uri_error() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payments-provider-return"))
+            .and(body_partial_json(json!({
+                "query": "error=access_denied&error_description=User+cancelled",
+                "fragment": ""
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "resource": {
+                    "type": "payment",
+                    "payment_id": "payment-id"
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let outcome = api
+            .submit_provider_return_uri(
+                "https://my.return.uri/redirect?error=access_denied&error_description=User+cancelled",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            ProviderReturnOutcome::Error {
+                resource: SubmitProviderReturnParametersResponseResource::Payment {
+                    payment_id: "payment-id".into()
+                },
+                error: "access_denied".into(),
+                error_description: Some("User cancelled".into())
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn submit_provider_return_uri_invalid() {
+        let (inner, _mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        let res = api.submit_provider_return_uri("not a uri").await;
+
+        assert!(matches!(res, Err(Error::Other(_))));
+    }
+
+    #[tokio::test]
+    async fn create_refund() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        let payment_id = "payment-id";
+        let refund_id = "refund-id";
+
+        Mock::given(method("POST"))
+            .and(path(format!("/payments/{}/refunds", payment_id)))
+            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .and(body_partial_json(json!({
+                "amount_in_minor": 100,
+                "reference": "some-reference"
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": refund_id })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .create_refund(
+                payment_id,
+                &CreateRefundRequest {
+                    amount_in_minor: Some(100),
+                    reference: "some-reference".into(),
+                    metadata: None,
+                },
             )
             .await
             .unwrap();
@@ -1004,6 +2189,168 @@ mod tests {
         assert_eq!(res.id, refund_id);
     }
 
+    #[tokio::test]
+    async fn payment_handle_is_bound_to_its_payment() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payments"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "payment-id",
+                "resource_token": "resource-token",
+                "user": {
+                    "id": "user-id"
+                },
+                "status": "authorization_required"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/payments/payment-id/refunds"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "refund-id" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/payments/payment-id/actions/cancel"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let payment = api
+            .create_handle(&CreatePaymentRequest {
+                amount_in_minor: 100,
+                currency: Currency::Gbp,
+                payment_method: PaymentMethodRequest::BankTransfer {
+                    provider_selection: ProviderSelectionRequest::UserSelected {
+                        filter: None,
+                        scheme_selection: None,
+                    },
+                    beneficiary: Beneficiary::MerchantAccount {
+                        merchant_account_id: "merchant-account-id".to_string(),
+                        account_holder_name: None,
+                        reference: None,
+                        statement_reference: None,
+                        verification: None,
+                    },
+                },
+                user: CreatePaymentUserRequest::ExistingUser {
+                    id: "user-id".to_string(),
+                },
+                metadata: None,
+                schedule: None,
+                related_products: None,
+                risk_assessment: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(payment.id(), &PaymentId::from("payment-id"));
+        assert_eq!(payment.user.id, "user-id");
+
+        let refund = payment
+            .refund(&CreateRefundRequest {
+                amount_in_minor: None,
+                reference: "some-reference".into(),
+                metadata: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(refund.id, "refund-id");
+
+        let hpp_link = payment.hpp_link("https://my.return.uri").await;
+        assert!(hpp_link
+            .url()
+            .fragment()
+            .unwrap()
+            .starts_with("payment_id=payment-id&resource_token=resource-token"));
+        assert_eq!(hpp_link.expires_at(), payment.resource_token.expires_at());
+
+        payment.cancel().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn strict_validation_rejects_refunds_without_contacting_truelayer() {
+        let (mut inner, mock_server) = mock_client_and_server().await;
+        inner.request_validation = RequestValidation::Strict;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .create_refund(
+                "payment-id",
+                &CreateRefundRequest {
+                    amount_in_minor: Some(0),
+                    reference: "some-reference".into(),
+                    metadata: None,
+                },
+            )
+            .await;
+
+        assert!(matches!(
+            res,
+            Err(Error::ValidationError(ValidationError {
+                kind: ValidationErrorKind::ZeroAmount,
+                ..
+            }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn strict_validation_rejects_unsupported_currencies_without_contacting_truelayer() {
+        let (mut inner, mock_server) = mock_client_and_server().await;
+        inner.request_validation = RequestValidation::Strict;
+        let api = PaymentsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .create(&CreatePaymentRequest {
+                amount_in_minor: 100,
+                currency: Currency::Gbp,
+                payment_method: PaymentMethodRequest::BankTransfer {
+                    provider_selection: ProviderSelectionRequest::UserSelected {
+                        filter: None,
+                        scheme_selection: None,
+                    },
+                    beneficiary: Beneficiary::ExternalAccount {
+                        account_holder_name: "Mr. Holder".to_string(),
+                        account_identifier: AccountIdentifier::Nrb {
+                            nrb: "12345678901234567890123456".to_string(),
+                        },
+                        reference: "reference".to_string(),
+                    },
+                },
+                user: CreatePaymentUserRequest::ExistingUser {
+                    id: "user-id".to_string(),
+                },
+                metadata: None,
+                schedule: None,
+                related_products: None,
+                risk_assessment: None,
+            })
+            .await;
+
+        assert!(matches!(
+            res,
+            Err(Error::ValidationError(ValidationError {
+                ref field,
+                kind: ValidationErrorKind::UnsupportedCurrency(Currency::Gbp),
+            })) if field == "payment_method.beneficiary.account_identifier"
+        ));
+    }
+
     #[tokio::test]
     async fn get_refund_by_id() {
         let (inner, mock_server) = mock_client_and_server().await;
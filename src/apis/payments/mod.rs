@@ -1,7 +1,10 @@
 //! APIs and models related to payments.
 
+#[cfg(feature = "payments")]
 mod api;
 mod model;
 
-pub use api::PaymentsApi;
+pub use crate::apis::ids::PaymentId;
+#[cfg(feature = "payments")]
+pub use api::{PaymentHandle, PaymentsApi};
 pub use model::*;
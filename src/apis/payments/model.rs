@@ -1,23 +1,460 @@
-use crate::{apis::auth::Token, pollable::IsInTerminalState, Error, Pollable, TrueLayerClient};
+use crate::{
+    apis::{
+        auth::ResourceToken, ids::PaymentId, payments_providers::supported_currencies, Missing,
+    },
+    pollable::IsInTerminalState,
+};
+#[cfg(any(feature = "payments", feature = "mandates"))]
+use crate::{
+    common::{PSU_DEVICE_ID_HEADER, PSU_IP_ADDRESS_HEADER, PSU_USER_AGENT_HEADER},
+    Error,
+};
+#[cfg(feature = "payments")]
+use crate::{Pollable, TrueLayerClient};
+#[cfg(any(feature = "payments", feature = "mandates"))]
 use anyhow::anyhow;
+#[cfg(feature = "payments")]
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+#[cfg(any(feature = "payments", feature = "mandates"))]
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{de::DeserializeOwned, ser::Error as _, Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::{
     collections::HashMap,
     fmt::{Display, Formatter},
+    net::IpAddr,
+    ops::{Deref, DerefMut},
+    str::FromStr,
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct CreatePaymentRequest {
     pub amount_in_minor: u64,
     pub currency: Currency,
     pub payment_method: PaymentMethodRequest,
     pub user: CreatePaymentUserRequest,
-    pub metadata: Option<HashMap<String, String>>,
+    pub metadata: Option<Metadata>,
+    /// Defers the execution of the payment to a future date.
+    /// If `None`, the payment is executed as soon as it is authorized.
+    pub schedule: Option<PaymentSchedule>,
+    /// Other TrueLayer products to enable alongside the payment.
+    pub related_products: Option<RelatedProducts>,
+    /// Information used by TrueLayer to assess the risk of the payment.
+    pub risk_assessment: Option<RiskAssessment>,
+}
+
+impl CreatePaymentRequest {
+    /// Starts building a request. `build()` becomes available once the amount, the currency,
+    /// the payment method and the user have all been set:
+    ///
+    /// ```rust
+    /// # use truelayer_rust::apis::payments::*;
+    /// let request = CreatePaymentRequest::builder()
+    ///     .amount_in_minor(100)
+    ///     .currency(Currency::Gbp)
+    ///     .payment_method(PaymentMethodRequest::BankTransfer {
+    ///         provider_selection: ProviderSelectionRequest::UserSelected {
+    ///             filter: None,
+    ///             scheme_selection: None,
+    ///         },
+    ///         beneficiary: Beneficiary::MerchantAccount {
+    ///             merchant_account_id: "merchant-account-id".to_string(),
+    ///             account_holder_name: None,
+    ///             reference: None,
+    ///             statement_reference: None,
+    ///             verification: None,
+    ///         },
+    ///     })
+    ///     .user(CreatePaymentUserRequest::ExistingUser {
+    ///         id: "user-id".to_string(),
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// Forgetting a required field is a compile time error:
+    ///
+    /// ```rust,compile_fail
+    /// # use truelayer_rust::apis::payments::*;
+    /// let request = CreatePaymentRequest::builder()
+    ///     .amount_in_minor(100)
+    ///     .currency(Currency::Gbp)
+    ///     .build();
+    /// ```
+    pub fn builder() -> CreatePaymentRequestBuilder {
+        CreatePaymentRequestBuilder {
+            amount_in_minor: Missing,
+            currency: Missing,
+            payment_method: Missing,
+            user: Missing,
+            metadata: None,
+            schedule: None,
+            related_products: None,
+            risk_assessment: None,
+        }
+    }
+
+    /// Sets the metadata of the payment from any value serializing to a JSON object.
+    pub fn set_metadata<T: Serialize>(&mut self, metadata: &T) -> Result<(), serde_json::Error> {
+        self.metadata = Some(Metadata::from_serializable(metadata)?);
+        Ok(())
+    }
+}
+
+/// Typestate builder for [`CreatePaymentRequest`], created with [`CreatePaymentRequest::builder()`].
+///
+/// Each type parameter is [`Missing`] until the corresponding required field is set.
+#[derive(Debug, Clone)]
+pub struct CreatePaymentRequestBuilder<A = Missing, C = Missing, M = Missing, U = Missing> {
+    amount_in_minor: A,
+    currency: C,
+    payment_method: M,
+    user: U,
+    metadata: Option<Metadata>,
+    schedule: Option<PaymentSchedule>,
+    related_products: Option<RelatedProducts>,
+    risk_assessment: Option<RiskAssessment>,
+}
+
+impl<A, C, M, U> CreatePaymentRequestBuilder<A, C, M, U> {
+    pub fn amount_in_minor(
+        self,
+        amount_in_minor: u64,
+    ) -> CreatePaymentRequestBuilder<u64, C, M, U> {
+        CreatePaymentRequestBuilder {
+            amount_in_minor,
+            currency: self.currency,
+            payment_method: self.payment_method,
+            user: self.user,
+            metadata: self.metadata,
+            schedule: self.schedule,
+            related_products: self.related_products,
+            risk_assessment: self.risk_assessment,
+        }
+    }
+
+    pub fn currency(self, currency: Currency) -> CreatePaymentRequestBuilder<A, Currency, M, U> {
+        CreatePaymentRequestBuilder {
+            amount_in_minor: self.amount_in_minor,
+            currency,
+            payment_method: self.payment_method,
+            user: self.user,
+            metadata: self.metadata,
+            schedule: self.schedule,
+            related_products: self.related_products,
+            risk_assessment: self.risk_assessment,
+        }
+    }
+
+    pub fn payment_method(
+        self,
+        payment_method: PaymentMethodRequest,
+    ) -> CreatePaymentRequestBuilder<A, C, PaymentMethodRequest, U> {
+        CreatePaymentRequestBuilder {
+            amount_in_minor: self.amount_in_minor,
+            currency: self.currency,
+            payment_method,
+            user: self.user,
+            metadata: self.metadata,
+            schedule: self.schedule,
+            related_products: self.related_products,
+            risk_assessment: self.risk_assessment,
+        }
+    }
+
+    pub fn user(
+        self,
+        user: CreatePaymentUserRequest,
+    ) -> CreatePaymentRequestBuilder<A, C, M, CreatePaymentUserRequest> {
+        CreatePaymentRequestBuilder {
+            amount_in_minor: self.amount_in_minor,
+            currency: self.currency,
+            payment_method: self.payment_method,
+            user,
+            metadata: self.metadata,
+            schedule: self.schedule,
+            related_products: self.related_products,
+            risk_assessment: self.risk_assessment,
+        }
+    }
+
+    pub fn metadata(mut self, metadata: impl Into<Metadata>) -> Self {
+        self.metadata = Some(metadata.into());
+        self
+    }
+
+    pub fn schedule(mut self, schedule: PaymentSchedule) -> Self {
+        self.schedule = Some(schedule);
+        self
+    }
+
+    pub fn related_products(mut self, related_products: RelatedProducts) -> Self {
+        self.related_products = Some(related_products);
+        self
+    }
+
+    pub fn risk_assessment(mut self, risk_assessment: RiskAssessment) -> Self {
+        self.risk_assessment = Some(risk_assessment);
+        self
+    }
+}
+
+impl CreatePaymentRequestBuilder<u64, Currency, PaymentMethodRequest, CreatePaymentUserRequest> {
+    /// Builds the request.
+    pub fn build(self) -> CreatePaymentRequest {
+        CreatePaymentRequest {
+            amount_in_minor: self.amount_in_minor,
+            currency: self.currency,
+            payment_method: self.payment_method,
+            user: self.user,
+            metadata: self.metadata,
+            schedule: self.schedule,
+            related_products: self.related_products,
+            risk_assessment: self.risk_assessment,
+        }
+    }
+}
+
+impl CreatePaymentRequest {
+    /// Checks that the currency of the payment can be used with the beneficiary,
+    /// the remitter and the countries of the provider filter.
+    ///
+    /// This only catches combinations that can never succeed, like paying GBP into an NRB account.
+    /// It is performed automatically by [`PaymentsApi::create`](crate::apis::payments::PaymentsApi::create)
+    /// when the client uses [`RequestValidation::Strict`](crate::validation::RequestValidation::Strict).
+    pub fn validate(&self) -> Result<(), PaymentRequestError> {
+        let PaymentMethodRequest::BankTransfer {
+            provider_selection,
+            beneficiary,
+        } = &self.payment_method;
+
+        if let Beneficiary::ExternalAccount {
+            account_identifier, ..
+        } = beneficiary
+        {
+            if !account_identifier
+                .supported_currencies()
+                .contains(&self.currency)
+            {
+                return Err(PaymentRequestError::UnsupportedBeneficiaryAccount {
+                    account_identifier: account_identifier.kind(),
+                    currency: self.currency.clone(),
+                });
+            }
+        }
+
+        match provider_selection {
+            ProviderSelectionRequest::UserSelected {
+                filter:
+                    Some(ProviderFilter {
+                        countries: Some(countries),
+                        ..
+                    }),
+                ..
+            } if !countries.is_empty()
+                && !countries
+                    .iter()
+                    .any(|c| supported_currencies(c).contains(&self.currency)) =>
+            {
+                Err(PaymentRequestError::UnsupportedCountries {
+                    currency: self.currency.clone(),
+                })
+            }
+            ProviderSelectionRequest::Preselected {
+                remitter:
+                    Some(Remitter {
+                        account_identifier: Some(account_identifier),
+                        ..
+                    }),
+                ..
+            } if !account_identifier
+                .supported_currencies()
+                .contains(&self.currency) =>
+            {
+                Err(PaymentRequestError::UnsupportedRemitterAccount {
+                    account_identifier: account_identifier.kind(),
+                    currency: self.currency.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Error returned by [`CreatePaymentRequest::validate`].
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum PaymentRequestError {
+    #[error("Beneficiary accounts identified by {account_identifier} cannot receive {currency} payments")]
+    UnsupportedBeneficiaryAccount {
+        account_identifier: &'static str,
+        currency: Currency,
+    },
+    #[error(
+        "Remitter accounts identified by {account_identifier} cannot send {currency} payments"
+    )]
+    UnsupportedRemitterAccount {
+        account_identifier: &'static str,
+        currency: Currency,
+    },
+    #[error("None of the countries in the provider filter support {currency} payments")]
+    UnsupportedCountries { currency: Currency },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct RiskAssessment {
+    /// Category of the payment, used to tailor the risk rules applied to it.
+    pub segment: Option<RiskSegment>,
+}
+
+/// Category of a payment for the purpose of risk assessment.
+///
+/// Segments not known to this version of the library are preserved in [`RiskSegment::Other`].
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum RiskSegment {
+    /// The payer has already paid the merchant with a card stored on file.
+    CardOnFile,
+    /// The payer has never paid the merchant before.
+    FirstTimeBuyer,
+    Other(String),
+}
+
+/// Serialized as the plain identifier, to preserve unknown values.
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for RiskSegment {
+    fn schema_name() -> String {
+        "RiskSegment".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl RiskSegment {
+    /// Identifier of the segment as used by the TrueLayer APIs.
+    pub fn as_str(&self) -> &str {
+        match self {
+            RiskSegment::CardOnFile => "card_on_file",
+            RiskSegment::FirstTimeBuyer => "first_time_buyer",
+            RiskSegment::Other(s) => s,
+        }
+    }
 }
 
+impl From<&str> for RiskSegment {
+    fn from(s: &str) -> Self {
+        match s {
+            "card_on_file" => RiskSegment::CardOnFile,
+            "first_time_buyer" => RiskSegment::FirstTimeBuyer,
+            other => RiskSegment::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for RiskSegment {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<RiskSegment> for String {
+    fn from(segment: RiskSegment) -> Self {
+        match segment {
+            RiskSegment::Other(s) => s,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl Display for RiskSegment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct RelatedProducts {
+    /// Retrieves the identity data of the payer once the payment is executed.
+    /// The data can then be fetched with [`PaymentsApi::get_signup_plus_user_data()`].
+    ///
+    /// [`PaymentsApi::get_signup_plus_user_data()`]: crate::apis::payments::PaymentsApi::get_signup_plus_user_data
+    pub signup_plus: Option<SignupPlus>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct SignupPlus {}
+
+/// Identity data of a payer, retrieved through Signup+.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct SignupPlusUserData {
+    pub title: Option<String>,
+    pub first_name: String,
+    pub last_name: String,
+    pub date_of_birth: NaiveDate,
+    pub address: SignupPlusAddress,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct SignupPlusAddress {
+    pub address_line1: String,
+    pub address_line2: Option<String>,
+    pub city: String,
+    pub state: Option<String>,
+    pub zip: String,
+    pub country_code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct PaymentSchedule {
+    pub execution_date: NaiveDate,
+    pub execution_window: Option<PaymentExecutionWindow>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct PaymentExecutionWindow {
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl From<Payment> for CreatePaymentRequest {
+    /// Builds a request for a new payment with the same details as an existing one,
+    /// made by the same user.
+    ///
+    /// The related products of the original payment are not returned by the API,
+    /// therefore they are not carried over.
+    fn from(p: Payment) -> Self {
+        CreatePaymentRequest {
+            amount_in_minor: p.amount_in_minor,
+            currency: p.currency,
+            payment_method: p.payment_method.into(),
+            user: p.user.into(),
+            metadata: p.metadata,
+            schedule: p.schedule,
+            related_products: None,
+            risk_assessment: p.risk_assessment,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum PaymentMethodRequest {
     BankTransfer {
@@ -41,7 +478,25 @@ impl From<PaymentMethod> for PaymentMethodRequest {
     }
 }
 
+impl From<PaymentMethodRequest> for PaymentMethod {
+    /// Builds the payment method of a payment created from the given request,
+    /// before any provider is selected by the user
+    fn from(p: PaymentMethodRequest) -> Self {
+        match p {
+            PaymentMethodRequest::BankTransfer {
+                provider_selection,
+                beneficiary,
+            } => PaymentMethod::BankTransfer {
+                provider_selection: provider_selection.into(),
+                beneficiary,
+            },
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ProviderSelectionRequest {
     UserSelected {
@@ -50,7 +505,7 @@ pub enum ProviderSelectionRequest {
     },
     Preselected {
         provider_id: String,
-        scheme_id: String,
+        scheme_id: SchemeId,
         remitter: Option<Remitter>,
     },
 }
@@ -80,7 +535,35 @@ impl From<ProviderSelection> for ProviderSelectionRequest {
     }
 }
 
+impl From<ProviderSelectionRequest> for ProviderSelection {
+    /// Builds the provider selection of a payment created from the given request,
+    /// before any provider is selected by the user
+    fn from(p: ProviderSelectionRequest) -> Self {
+        match p {
+            ProviderSelectionRequest::UserSelected {
+                filter,
+                scheme_selection,
+            } => ProviderSelection::UserSelected {
+                filter,
+                scheme_selection,
+                provider_id: None,
+                scheme_id: None,
+            },
+            ProviderSelectionRequest::Preselected {
+                provider_id,
+                scheme_id,
+                remitter,
+            } => ProviderSelection::Preselected {
+                provider_id,
+                scheme_id,
+                remitter,
+            },
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum CreatePaymentUserRequest {
     ExistingUser {
@@ -90,33 +573,132 @@ pub enum CreatePaymentUserRequest {
         name: Option<String>,
         email: Option<String>,
         phone: Option<String>,
+        /// Required by some providers and for anti-money laundering checks.
+        date_of_birth: Option<NaiveDate>,
+        /// Required by some providers and for anti-money laundering checks.
+        address: Option<UserAddress>,
     },
 }
 
+impl CreatePaymentUserRequest {
+    /// Starts building a [`NewUser`](CreatePaymentUserRequest::NewUser), with all fields unset.
+    pub fn new_user() -> NewUserBuilder {
+        NewUserBuilder::default()
+    }
+}
+
+/// Builder for [`CreatePaymentUserRequest::NewUser`].
+#[derive(Debug, Clone, Default)]
+pub struct NewUserBuilder {
+    name: Option<String>,
+    email: Option<String>,
+    phone: Option<String>,
+    date_of_birth: Option<NaiveDate>,
+    address: Option<UserAddress>,
+}
+
+impl NewUserBuilder {
+    /// Sets the full name of the user.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the email address of the user.
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    /// Sets the phone number of the user.
+    pub fn with_phone(mut self, phone: impl Into<String>) -> Self {
+        self.phone = Some(phone.into());
+        self
+    }
+
+    /// Sets the date of birth of the user.
+    pub fn with_date_of_birth(mut self, date_of_birth: NaiveDate) -> Self {
+        self.date_of_birth = Some(date_of_birth);
+        self
+    }
+
+    /// Sets the address of the user.
+    pub fn with_address(mut self, address: UserAddress) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Builds the user.
+    pub fn build(self) -> CreatePaymentUserRequest {
+        CreatePaymentUserRequest::NewUser {
+            name: self.name,
+            email: self.email,
+            phone: self.phone,
+            date_of_birth: self.date_of_birth,
+            address: self.address,
+        }
+    }
+}
+
+/// Address of a payment user.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct UserAddress {
+    pub address_line1: String,
+    pub address_line2: Option<String>,
+    pub city: String,
+    pub state: Option<String>,
+    pub zip: String,
+    /// ISO 3166-1 alpha-2 country code.
+    pub country_code: String,
+}
+
+impl From<User> for CreatePaymentUserRequest {
+    /// Refers to an existing user
+    fn from(u: User) -> Self {
+        CreatePaymentUserRequest::ExistingUser { id: u.id }
+    }
+}
+
+impl From<CreatePaymentUserResponse> for CreatePaymentUserRequest {
+    /// Refers to the user created along with a payment or a mandate
+    fn from(u: CreatePaymentUserResponse) -> Self {
+        CreatePaymentUserRequest::ExistingUser { id: u.id }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct CreatePaymentResponse {
-    pub id: String,
-    pub resource_token: Token,
+    pub id: PaymentId,
+    pub resource_token: ResourceToken,
     pub user: CreatePaymentUserResponse,
     #[serde(flatten)]
     pub status: CreatePaymentStatus,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum CreatePaymentStatus {
     AuthorizationRequired,
     Authorized,
     Failed {
         failure_stage: FailureStage,
-        failure_reason: String,
+        failure_reason: FailureReason,
     },
 }
 
+#[cfg(feature = "payments")]
 #[async_trait]
 impl Pollable for CreatePaymentResponse {
     type Output = Payment;
 
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+
     async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
         tl.payments
             .get_by_id(&self.id)
@@ -127,27 +709,122 @@ impl Pollable for CreatePaymentResponse {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct CreatePaymentUserResponse {
     pub id: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Payment {
-    pub id: String,
+    pub id: PaymentId,
     pub amount_in_minor: u64,
     pub currency: Currency,
     pub user: User,
     pub payment_method: PaymentMethod,
     pub created_at: DateTime<Utc>,
-    pub metadata: Option<HashMap<String, String>>,
+    pub metadata: Option<Metadata>,
+    pub schedule: Option<PaymentSchedule>,
+    /// Risk assessment information provided when the payment was created.
+    pub risk_assessment: Option<RiskAssessment>,
     #[serde(flatten)]
     pub status: PaymentStatus,
 }
 
+impl Payment {
+    /// Deserializes the metadata of the payment into a custom type.
+    pub fn metadata_as<T: DeserializeOwned>(&self) -> Result<Option<T>, serde_json::Error> {
+        self.metadata
+            .as_ref()
+            .map(Metadata::deserialize_into)
+            .transpose()
+    }
+
+    /// Settlement risk assessed by TrueLayer, if the payment has been executed.
+    pub fn settlement_risk(&self) -> Option<&SettlementRisk> {
+        match self.status {
+            PaymentStatus::Executed {
+                ref settlement_risk,
+                ..
+            }
+            | PaymentStatus::Settled {
+                ref settlement_risk,
+                ..
+            } => settlement_risk.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Next action of the authorization flow, if the payment is being authorized.
+    pub fn next_action(&self) -> Option<&AuthorizationFlowNextAction> {
+        match self.status {
+            PaymentStatus::Authorizing {
+                authorization_flow:
+                    AuthorizationFlow {
+                        actions: Some(AuthorizationFlowActions { ref next }),
+                        ..
+                    },
+            } => Some(next),
+            _ => None,
+        }
+    }
+
+    /// Whether the authorization flow of this payment can be restarted, for example
+    /// to let the user pick a different bank.
+    ///
+    /// This is the case for payments which have not been authorized yet, or which failed
+    /// before being authorized for reasons other than their expiration.
+    pub fn is_authorization_flow_resumable(&self) -> bool {
+        match self.status {
+            PaymentStatus::AuthorizationRequired | PaymentStatus::Authorizing { .. } => true,
+            PaymentStatus::Failed {
+                ref failure_stage,
+                ref failure_reason,
+                ..
+            } => {
+                matches!(
+                    failure_stage,
+                    FailureStage::AuthorizationRequired | FailureStage::Authorizing
+                ) && *failure_reason != FailureReason::Expired
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the funds of this payment can be paid out of the merchant account.
+    ///
+    /// A payment is eligible when it has been settled into a merchant account
+    /// and it has not been flagged as high risk.
+    pub fn is_eligible_for_payout(&self) -> bool {
+        let settled = matches!(self.status, PaymentStatus::Settled { .. });
+        let into_merchant_account = matches!(
+            self.payment_method,
+            PaymentMethod::BankTransfer {
+                beneficiary: Beneficiary::MerchantAccount { .. },
+                ..
+            }
+        );
+        let high_risk = matches!(
+            self.settlement_risk(),
+            Some(SettlementRisk {
+                category: SettlementRiskCategory::HighRisk
+            })
+        );
+
+        settled && into_merchant_account && !high_risk
+    }
+}
+
+#[cfg(feature = "payments")]
 #[async_trait]
 impl Pollable for Payment {
     type Output = Payment;
 
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+
     async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
         tl.payments
             .get_by_id(&self.id)
@@ -170,6 +847,7 @@ impl IsInTerminalState for Payment {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum PaymentStatus {
     AuthorizationRequired,
@@ -179,6 +857,12 @@ pub enum PaymentStatus {
     Authorized {
         authorization_flow: Option<AuthorizationFlow>,
     },
+    /// The payment has been authorized and is waiting for its scheduled execution date.
+    Scheduled {
+        scheduled_for: NaiveDate,
+        execution_window: Option<PaymentExecutionWindow>,
+        authorization_flow: Option<AuthorizationFlow>,
+    },
     Executed {
         executed_at: DateTime<Utc>,
         authorization_flow: Option<AuthorizationFlow>,
@@ -194,12 +878,160 @@ pub enum PaymentStatus {
     Failed {
         failed_at: DateTime<Utc>,
         failure_stage: FailureStage,
-        failure_reason: String,
+        failure_reason: FailureReason,
         authorization_flow: Option<AuthorizationFlow>,
+        /// Error returned by the provider, if the payment failed at the provider.
+        provider_error: Option<ProviderError>,
     },
 }
 
+/// Error returned by a provider for a failed payment.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct ProviderError {
+    pub code: String,
+    pub message: Option<String>,
+    /// Whether the same payment is likely to succeed if attempted again.
+    #[serde(default)]
+    pub retryable: bool,
+}
+
+/// Common reasons for a payment, refund, payout or mandate to fail.
+///
+/// Reasons not known to this version of the library are preserved in [`FailureReason::Other`].
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum FailureReason {
+    AuthorizationFailed,
+    Blocked,
+    Canceled,
+    Expired,
+    InsufficientFunds,
+    InvalidAccountDetails,
+    InvalidAccountHolderName,
+    NotAuthorized,
+    PaymentLimitExceeded,
+    ProviderError,
+    ProviderExpired,
+    ProviderRejected,
+    /// The funds were sent back by the receiving bank.
+    Returned,
+    SchemeUnavailable,
+    UserCanceledAtProvider,
+    VerificationDeclined,
+    Other(String),
+}
+
+/// Serialized as the plain identifier, to preserve unknown values.
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for FailureReason {
+    fn schema_name() -> String {
+        "FailureReason".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl FailureReason {
+    /// Identifier of the failure reason as used by the TrueLayer APIs.
+    pub fn as_str(&self) -> &str {
+        match self {
+            FailureReason::AuthorizationFailed => "authorization_failed",
+            FailureReason::Blocked => "blocked",
+            FailureReason::Canceled => "canceled",
+            FailureReason::Expired => "expired",
+            FailureReason::InsufficientFunds => "insufficient_funds",
+            FailureReason::InvalidAccountDetails => "invalid_account_details",
+            FailureReason::InvalidAccountHolderName => "invalid_account_holder_name",
+            FailureReason::NotAuthorized => "not_authorized",
+            FailureReason::PaymentLimitExceeded => "payment_limit_exceeded",
+            FailureReason::ProviderError => "provider_error",
+            FailureReason::ProviderExpired => "provider_expired",
+            FailureReason::ProviderRejected => "provider_rejected",
+            FailureReason::Returned => "returned",
+            FailureReason::SchemeUnavailable => "scheme_unavailable",
+            FailureReason::UserCanceledAtProvider => "user_canceled_at_provider",
+            FailureReason::VerificationDeclined => "verification_declined",
+            FailureReason::Other(s) => s,
+        }
+    }
+
+    /// Human readable description of the failure, suitable to be shown to the payer.
+    pub fn description(&self) -> &'static str {
+        match self {
+            FailureReason::AuthorizationFailed => "The payment could not be authorized.",
+            FailureReason::Blocked => "The payment was blocked.",
+            FailureReason::Canceled => "The payment was canceled.",
+            FailureReason::Expired => "The payment was not completed in time.",
+            FailureReason::InsufficientFunds => "There are not enough funds in the account.",
+            FailureReason::InvalidAccountDetails => "The account details are invalid.",
+            FailureReason::InvalidAccountHolderName => "The account holder name is invalid.",
+            FailureReason::NotAuthorized => "The payment was not authorized at the bank.",
+            FailureReason::PaymentLimitExceeded => {
+                "The payment exceeds the limits set by the bank."
+            }
+            FailureReason::ProviderError => "The bank could not process the payment.",
+            FailureReason::ProviderExpired => "The payment expired at the bank.",
+            FailureReason::ProviderRejected => "The payment was rejected by the bank.",
+            FailureReason::Returned => "The payment was returned by the receiving bank.",
+            FailureReason::SchemeUnavailable => "The payment scheme is currently unavailable.",
+            FailureReason::UserCanceledAtProvider => "The payment was canceled at the bank.",
+            FailureReason::VerificationDeclined => "The payer could not be verified.",
+            FailureReason::Other(_) => "The payment failed.",
+        }
+    }
+}
+
+impl From<&str> for FailureReason {
+    fn from(s: &str) -> Self {
+        match s {
+            "authorization_failed" => FailureReason::AuthorizationFailed,
+            "blocked" => FailureReason::Blocked,
+            "canceled" => FailureReason::Canceled,
+            "expired" => FailureReason::Expired,
+            "insufficient_funds" => FailureReason::InsufficientFunds,
+            "invalid_account_details" => FailureReason::InvalidAccountDetails,
+            "invalid_account_holder_name" => FailureReason::InvalidAccountHolderName,
+            "not_authorized" => FailureReason::NotAuthorized,
+            "payment_limit_exceeded" => FailureReason::PaymentLimitExceeded,
+            "provider_error" => FailureReason::ProviderError,
+            "provider_expired" => FailureReason::ProviderExpired,
+            "provider_rejected" => FailureReason::ProviderRejected,
+            "returned" => FailureReason::Returned,
+            "scheme_unavailable" => FailureReason::SchemeUnavailable,
+            "user_canceled_at_provider" => FailureReason::UserCanceledAtProvider,
+            "verification_declined" => FailureReason::VerificationDeclined,
+            other => FailureReason::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for FailureReason {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<FailureReason> for String {
+    fn from(failure_reason: FailureReason) -> Self {
+        match failure_reason {
+            FailureReason::Other(s) => s,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl Display for FailureReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Currency {
     Eur,
@@ -219,7 +1051,84 @@ impl Display for Currency {
     }
 }
 
+impl FromStr for Currency {
+    type Err = ParseCurrencyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "EUR" => Ok(Currency::Eur),
+            "GBP" => Ok(Currency::Gbp),
+            "NOK" => Ok(Currency::Nok),
+            "PLN" => Ok(Currency::Pln),
+            _ => Err(ParseCurrencyError(s.to_string())),
+        }
+    }
+}
+
+/// Error returned when parsing an unsupported [`Currency`].
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+#[error("Unsupported currency: {0}")]
+pub struct ParseCurrencyError(pub String);
+
+/// Custom data attached to a payment, refund, payout or mandate.
+///
+/// Values can be any JSON value, including nested objects and arrays.
+/// Flat string maps convert into `Metadata` with [`From`], so code written against the
+/// previous `HashMap<String, String>` representation only needs an `.into()`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct Metadata(pub Map<String, Value>);
+
+impl Metadata {
+    /// Builds metadata from any value serializing to a JSON object.
+    pub fn from_serializable<T: Serialize>(value: &T) -> Result<Self, serde_json::Error> {
+        match serde_json::to_value(value)? {
+            Value::Object(map) => Ok(Self(map)),
+            _ => Err(serde_json::Error::custom(
+                "metadata must serialize to a JSON object",
+            )),
+        }
+    }
+
+    /// Deserializes the metadata into a custom type.
+    pub fn deserialize_into<T: DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_value(Value::Object(self.0.clone()))
+    }
+}
+
+impl Deref for Metadata {
+    type Target = Map<String, Value>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Metadata {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl From<Map<String, Value>> for Metadata {
+    fn from(map: Map<String, Value>) -> Self {
+        Self(map)
+    }
+}
+
+impl From<HashMap<String, String>> for Metadata {
+    fn from(map: HashMap<String, String>) -> Self {
+        Self(
+            map.into_iter()
+                .map(|(k, v)| (k, Value::String(v)))
+                .collect(),
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum FailureStage {
     AuthorizationRequired,
@@ -228,6 +1137,8 @@ pub enum FailureStage {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct PaymentSource {
     pub id: String,
     pub user_id: Option<String>,
@@ -237,6 +1148,8 @@ pub struct PaymentSource {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum PaymentMethod {
     BankTransfer {
@@ -246,13 +1159,18 @@ pub enum PaymentMethod {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Beneficiary {
     MerchantAccount {
         merchant_account_id: String,
         account_holder_name: Option<String>,
+        /// Reference shown on the bank statement of the remitter.
         reference: Option<String>,
         statement_reference: Option<String>,
+        /// Checks to perform on the remitter account before the payment is executed.
+        verification: Option<RemitterVerification>,
     },
     ExternalAccount {
         account_holder_name: String,
@@ -261,7 +1179,24 @@ pub enum Beneficiary {
     },
 }
 
+/// Verification of the remitter of a payment into a merchant account.
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RemitterVerification {
+    /// TrueLayer checks the remitter account holder against the user details provided when creating the payment.
+    Automated {
+        #[serde(default)]
+        remitter_name: bool,
+        #[serde(default)]
+        remitter_date_of_birth: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AccountIdentifier {
     SortCodeAccountNumber {
@@ -279,41 +1214,282 @@ pub enum AccountIdentifier {
     },
 }
 
+impl AccountIdentifier {
+    /// Currencies that can be paid from or into an account with this kind of identifier.
+    pub fn supported_currencies(&self) -> &'static [Currency] {
+        match self {
+            AccountIdentifier::SortCodeAccountNumber { .. } => &[Currency::Gbp],
+            AccountIdentifier::Iban { .. } => {
+                &[Currency::Gbp, Currency::Eur, Currency::Nok, Currency::Pln]
+            }
+            AccountIdentifier::Bban { .. } => &[Currency::Nok],
+            AccountIdentifier::Nrb { .. } => &[Currency::Pln],
+        }
+    }
+
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            AccountIdentifier::SortCodeAccountNumber { .. } => "sort code and account number",
+            AccountIdentifier::Iban { .. } => "IBAN",
+            AccountIdentifier::Bban { .. } => "BBAN",
+            AccountIdentifier::Nrb { .. } => "NRB",
+        }
+    }
+
+    /// Builds a UK sort code and account number identifier.
+    ///
+    /// Whitespace and dashes are stripped from the sort code, so `"04-00-04"` is accepted.
+    /// The sort code must be made of 6 digits and the account number of 8 digits.
+    pub fn sort_code_account_number(
+        sort_code: &str,
+        account_number: &str,
+    ) -> Result<Self, AccountIdentifierError> {
+        let sort_code = strip(sort_code, |c| c.is_whitespace() || c == '-');
+        let account_number = strip(account_number, char::is_whitespace);
+
+        if !is_digits(&sort_code, 6) {
+            return Err(AccountIdentifierError::InvalidSortCode);
+        }
+        if !is_digits(&account_number, 8) {
+            return Err(AccountIdentifierError::InvalidAccountNumber);
+        }
+
+        Ok(Self::SortCodeAccountNumber {
+            sort_code,
+            account_number,
+        })
+    }
+
+    /// Builds an IBAN identifier.
+    ///
+    /// Whitespace is stripped and letters are uppercased. The check digits are validated.
+    pub fn iban(iban: &str) -> Result<Self, AccountIdentifierError> {
+        let iban = strip(iban, char::is_whitespace).to_ascii_uppercase();
+
+        let well_formed = (15..=34).contains(&iban.len())
+            && iban.bytes().all(|b| b.is_ascii_alphanumeric())
+            && iban.bytes().take(2).all(|b| b.is_ascii_alphabetic())
+            && iban.bytes().skip(2).take(2).all(|b| b.is_ascii_digit());
+        if !well_formed {
+            return Err(AccountIdentifierError::InvalidIban);
+        }
+
+        // ISO 13616: move the first four characters to the end, replace letters
+        // with two digits (A = 10, ..., Z = 35) and check that the result mod 97 is 1.
+        let remainder = iban[4..]
+            .chars()
+            .chain(iban[..4].chars())
+            .fold(0u32, |acc, c| {
+                let value = c.to_digit(36).unwrap();
+                let shift = if value < 10 { 10 } else { 100 };
+                (acc * shift + value) % 97
+            });
+        if remainder != 1 {
+            return Err(AccountIdentifierError::InvalidIbanChecksum);
+        }
+
+        Ok(Self::Iban { iban })
+    }
+
+    /// Builds a BBAN identifier.
+    ///
+    /// Whitespace is stripped and letters are uppercased. The BBAN must be made of
+    /// at most 30 alphanumeric characters.
+    pub fn bban(bban: &str) -> Result<Self, AccountIdentifierError> {
+        let bban = strip(bban, char::is_whitespace).to_ascii_uppercase();
+
+        if bban.is_empty() || bban.len() > 30 || !bban.bytes().all(|b| b.is_ascii_alphanumeric()) {
+            return Err(AccountIdentifierError::InvalidBban);
+        }
+
+        Ok(Self::Bban { bban })
+    }
+
+    /// Builds a Polish NRB identifier.
+    ///
+    /// Whitespace is stripped. The NRB must be made of 26 digits.
+    pub fn nrb(nrb: &str) -> Result<Self, AccountIdentifierError> {
+        let nrb = strip(nrb, char::is_whitespace);
+
+        if !is_digits(&nrb, 26) {
+            return Err(AccountIdentifierError::InvalidNrb);
+        }
+
+        Ok(Self::Nrb { nrb })
+    }
+}
+
+fn strip(s: &str, pred: impl Fn(char) -> bool) -> String {
+    s.chars().filter(|&c| !pred(c)).collect()
+}
+
+fn is_digits(s: &str, len: usize) -> bool {
+    s.len() == len && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Error returned when building an invalid [`AccountIdentifier`].
+#[derive(thiserror::Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AccountIdentifierError {
+    #[error("Invalid sort code: expected 6 digits")]
+    InvalidSortCode,
+    #[error("Invalid account number: expected 8 digits")]
+    InvalidAccountNumber,
+    #[error("Invalid IBAN: expected a country code, 2 check digits and up to 30 alphanumeric characters")]
+    InvalidIban,
+    #[error("Invalid IBAN: checksum mismatch")]
+    InvalidIbanChecksum,
+    #[error("Invalid BBAN: expected up to 30 alphanumeric characters")]
+    InvalidBban,
+    #[error("Invalid NRB: expected 26 digits")]
+    InvalidNrb,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct SettlementRisk {
-    pub category: String,
+    pub category: SettlementRiskCategory,
+}
+
+/// Likelihood of a payment being reversed after settlement, as assessed by TrueLayer.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementRiskCategory {
+    LowRisk,
+    LowToMediumRisk,
+    MediumRisk,
+    MediumToHighRisk,
+    HighRisk,
+    /// A category not known to this version of the library.
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ProviderSelection {
     UserSelected {
         filter: Option<ProviderFilter>,
         scheme_selection: Option<SchemeSelection>,
         provider_id: Option<String>,
-        scheme_id: Option<String>,
+        scheme_id: Option<SchemeId>,
     },
     Preselected {
         provider_id: String,
-        scheme_id: String,
+        scheme_id: SchemeId,
         remitter: Option<Remitter>,
     },
 }
 
+/// Payment scheme through which a bank transfer is executed.
+///
+/// Schemes not known to this version of the library are preserved in [`SchemeId::Other`].
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum SchemeId {
+    FasterPaymentsService,
+    SepaCreditTransfer,
+    SepaInstant,
+    /// Polish domestic transfers through Elixir.
+    PolishDomesticStandard,
+    /// Polish domestic transfers through Express Elixir.
+    PolishDomesticExpress,
+    NorwegianDomesticCreditTransfer,
+    Other(String),
+}
+
+/// Serialized as the plain identifier, to preserve unknown values.
+#[cfg(feature = "json-schema")]
+impl schemars::JsonSchema for SchemeId {
+    fn schema_name() -> String {
+        "SchemeId".to_string()
+    }
+
+    fn json_schema(gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(gen)
+    }
+}
+
+impl SchemeId {
+    /// Identifier of the scheme as used by the TrueLayer APIs.
+    pub fn as_str(&self) -> &str {
+        match self {
+            SchemeId::FasterPaymentsService => "faster_payments_service",
+            SchemeId::SepaCreditTransfer => "sepa_credit_transfer",
+            SchemeId::SepaInstant => "sepa_credit_transfer_instant",
+            SchemeId::PolishDomesticStandard => "polish_domestic_standard",
+            SchemeId::PolishDomesticExpress => "polish_domestic_express",
+            SchemeId::NorwegianDomesticCreditTransfer => "norwegian_domestic_credit_transfer",
+            SchemeId::Other(s) => s,
+        }
+    }
+}
+
+impl From<&str> for SchemeId {
+    fn from(s: &str) -> Self {
+        match s {
+            "faster_payments_service" => SchemeId::FasterPaymentsService,
+            "sepa_credit_transfer" => SchemeId::SepaCreditTransfer,
+            "sepa_credit_transfer_instant" => SchemeId::SepaInstant,
+            "polish_domestic_standard" => SchemeId::PolishDomesticStandard,
+            "polish_domestic_express" => SchemeId::PolishDomesticExpress,
+            "norwegian_domestic_credit_transfer" => SchemeId::NorwegianDomesticCreditTransfer,
+            other => SchemeId::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for SchemeId {
+    fn from(s: String) -> Self {
+        s.as_str().into()
+    }
+}
+
+impl From<SchemeId> for String {
+    fn from(scheme_id: SchemeId) -> Self {
+        match scheme_id {
+            SchemeId::Other(s) => s,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl Display for SchemeId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SchemeSelection {
-    InstantOnly { allow_remitter_fee: Option<bool> },
-    InstantPreferred { allow_remitter_fee: Option<bool> },
+    InstantOnly {
+        allow_remitter_fee: Option<bool>,
+    },
+    InstantPreferred {
+        allow_remitter_fee: Option<bool>,
+    },
+    /// The PSU chooses the scheme along with the provider,
+    /// see [`SubmitProviderSelectionActionRequest::scheme_id`].
+    UserSelected,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Remitter {
     pub account_holder_name: Option<String>,
     pub account_identifier: Option<AccountIdentifier>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct ExternalPaymentRemitter {
     pub account_holder_name: String,
     pub account_identifier: AccountIdentifier,
@@ -321,6 +1497,8 @@ pub struct ExternalPaymentRemitter {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct ProviderFilter {
     pub countries: Option<Vec<CountryCode>>,
     pub release_channel: Option<ReleaseChannel>,
@@ -330,6 +1508,7 @@ pub struct ProviderFilter {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "UPPERCASE")]
 pub enum CountryCode {
     AT,
@@ -351,6 +1530,7 @@ pub enum CountryCode {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum ReleaseChannel {
     GeneralAvailability,
@@ -359,6 +1539,7 @@ pub enum ReleaseChannel {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum CustomerSegment {
     Retail,
@@ -367,22 +1548,60 @@ pub enum CustomerSegment {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct ProviderFilterExcludes {
     pub provider_ids: Option<Vec<String>>,
+    /// Excludes the providers which are currently [unhealthy](ProviderAvailabilityStatus::Unhealthy),
+    /// so that users are not sent to banks which are likely to fail the payment.
+    pub unhealthy_providers: Option<bool>,
+}
+
+/// Health of a provider, as monitored by TrueLayer.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct ProviderAvailability {
+    /// Whether TrueLayer recommends sending users to the provider.
+    pub recommended_status: ProviderAvailabilityStatus,
+    /// When the status was last evaluated.
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl ProviderAvailability {
+    /// Returns `false` if TrueLayer recommends not sending users to the provider.
+    pub fn is_healthy(&self) -> bool {
+        self.recommended_status != ProviderAvailabilityStatus::Unhealthy
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderAvailabilityStatus {
+    Healthy,
+    /// The provider is experiencing an outage or an elevated error rate.
+    Unhealthy,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct AuthorizationFlow {
     pub actions: Option<AuthorizationFlowActions>,
     pub configuration: Option<AuthorizationFlowConfiguration>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct AuthorizationFlowActions {
     pub next: AuthorizationFlowNextAction,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AuthorizationFlowNextAction {
     ProviderSelection {
@@ -402,6 +1621,7 @@ pub enum AuthorizationFlowNextAction {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum SubsequentAction {
     Redirect,
@@ -409,6 +1629,8 @@ pub enum SubsequentAction {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Provider {
     pub id: String,
     pub display_name: Option<String>,
@@ -416,15 +1638,31 @@ pub struct Provider {
     pub logo_uri: Option<String>,
     pub bg_color: Option<String>,
     pub country_code: Option<CountryCode>,
+    /// Health of the provider, if reported.
+    pub availability: Option<ProviderAvailability>,
+}
+
+impl Provider {
+    /// Returns `false` if the provider is known to be unhealthy.
+    ///
+    /// Providers whose availability is not reported are assumed to be healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.availability
+            .as_ref()
+            .is_none_or(ProviderAvailability::is_healthy)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum RedirectActionMetadata {
     Provider(Provider),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AdditionalInput {
     Text {
@@ -460,12 +1698,15 @@ pub enum AdditionalInput {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct AdditionalInputDisplayText {
     pub key: String,
     pub default: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum AdditionalInputFormat {
     AccountNumber,
@@ -479,18 +1720,24 @@ pub enum AdditionalInputFormat {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct AdditionalInputRegex {
     pub regex: String,
     pub message: AdditionalInputDisplayText,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct AdditionalInputOption {
     pub id: String,
     pub display_text: AdditionalInputDisplayText,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum AdditionalInputImage {
     Uri { uri: String },
@@ -498,6 +1745,8 @@ pub enum AdditionalInputImage {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct AuthorizationFlowConfiguration {
     pub provider_selection: Option<ProviderSelectionSupported>,
     pub redirect: Option<RedirectSupported>,
@@ -506,23 +1755,30 @@ pub struct AuthorizationFlowConfiguration {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ProviderSelectionSupported {}
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct RedirectSupported {
     pub return_uri: String,
     pub direct_return_uri: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct ConsentSupported {}
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct FormSupported {
     pub input_types: Vec<AdditionalInputType>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "snake_case")]
 pub enum AdditionalInputType {
     Text,
@@ -531,11 +1787,37 @@ pub enum AdditionalInputType {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct User {
     pub id: String,
+    pub date_of_birth: Option<NaiveDate>,
+    pub address: Option<UserAddress>,
+}
+
+/// Request to retry a failed payment with a new authorization attempt.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct RetryPaymentRequest {
+    /// Provider selection of the new attempt.
+    /// If `None`, the provider selection of the failed attempt is used again.
+    pub provider_selection: Option<ProviderSelectionRequest>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+pub struct RetryPaymentResponse {
+    pub id: PaymentId,
+    /// New resource token to authorize the payment with.
+    pub resource_token: ResourceToken,
+    #[serde(flatten)]
+    pub status: CreatePaymentStatus,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct StartAuthorizationFlowRequest {
     pub provider_selection: Option<ProviderSelectionSupported>,
     pub redirect: Option<RedirectSupported>,
@@ -543,7 +1825,57 @@ pub struct StartAuthorizationFlowRequest {
     pub form: Option<FormSupported>,
 }
 
+/// Details of the PSU (the end user) required by some providers to authorize payments and mandates
+/// through an embedded flow, where the user never leaves your application.
+///
+/// Set it on an API client with [`RequestOptions::with_psu_context()`](crate::RequestOptions::with_psu_context):
+/// it is sent as headers when starting the authorization flow and submitting its actions.
+/// Fields left to `None` are not sent.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PsuContext {
+    /// IP address of the device of the PSU.
+    pub ip_address: Option<IpAddr>,
+    /// User agent of the browser or app of the PSU.
+    pub user_agent: Option<String>,
+    /// Identifier of the device of the PSU.
+    pub device_id: Option<String>,
+}
+
+impl PsuContext {
+    /// Returns the headers carrying this context, or an error if a value is not a valid header value.
+    #[cfg(any(feature = "payments", feature = "mandates"))]
+    pub(crate) fn headers(psu_context: Option<&PsuContext>) -> Result<HeaderMap, Error> {
+        let mut headers = HeaderMap::new();
+        let psu_context = match psu_context {
+            Some(psu_context) => psu_context,
+            None => return Ok(headers),
+        };
+
+        let values = [
+            (
+                PSU_IP_ADDRESS_HEADER,
+                psu_context.ip_address.map(|ip| ip.to_string()),
+            ),
+            (PSU_USER_AGENT_HEADER, psu_context.user_agent.clone()),
+            (PSU_DEVICE_ID_HEADER, psu_context.device_id.clone()),
+        ];
+        for (name, value) in values {
+            if let Some(value) = value {
+                let value = HeaderValue::from_str(&value)
+                    .map_err(|_| Error::Other(anyhow!("Invalid value for header {}", name)))?;
+                headers.insert(
+                    HeaderName::from_str(name).expect("valid PSU header name"),
+                    value,
+                );
+            }
+        }
+
+        Ok(headers)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct StartAuthorizationFlowResponse {
     pub authorization_flow: Option<AuthorizationFlow>,
     #[serde(flatten)]
@@ -551,11 +1883,17 @@ pub struct StartAuthorizationFlowResponse {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct SubmitProviderSelectionActionRequest {
     pub provider_id: String,
+    /// Scheme chosen by the PSU. Required if the payment was created with
+    /// [`SchemeSelection::UserSelected`], must be `None` otherwise.
+    pub scheme_id: Option<SchemeId>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct SubmitProviderSelectionActionResponse {
     pub authorization_flow: Option<AuthorizationFlow>,
     #[serde(flatten)]
@@ -563,6 +1901,7 @@ pub struct SubmitProviderSelectionActionResponse {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct SubmitConsentActionResponse {
     pub authorization_flow: Option<AuthorizationFlow>,
     #[serde(flatten)]
@@ -570,11 +1909,14 @@ pub struct SubmitConsentActionResponse {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct SubmitFormActionRequest {
     pub inputs: HashMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct SubmitFormActionResponse {
     pub authorization_flow: Option<AuthorizationFlow>,
     #[serde(flatten)]
@@ -582,60 +1924,106 @@ pub struct SubmitFormActionResponse {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum AuthorizationFlowResponseStatus {
     Authorizing,
     Failed {
         failure_stage: FailureStage,
-        failure_reason: String,
+        failure_reason: FailureReason,
     },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct SubmitProviderReturnParametersRequest {
     pub query: String,
     pub fragment: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct SubmitProviderReturnParametersResponse {
     pub resource: SubmitProviderReturnParametersResponseResource,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SubmitProviderReturnParametersResponseResource {
     Payment { payment_id: String },
+    Mandate { mandate_id: String },
+}
+
+/// Outcome of the redirect back from the provider, as returned by
+/// [`submit_provider_return_uri()`](crate::apis::payments::PaymentsApi::submit_provider_return_uri).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ProviderReturnOutcome {
+    /// The PSU came back from the provider after authorizing a payment.
+    PaymentReturn { payment_id: String },
+    /// The PSU came back from the provider after authorizing a mandate.
+    MandateReturn { mandate_id: String },
+    /// The provider redirected the PSU back with an error (e.g., the PSU cancelled the authorization).
+    Error {
+        resource: SubmitProviderReturnParametersResponseResource,
+        error: String,
+        error_description: Option<String>,
+    },
 }
 
 pub mod refunds {
-    use std::collections::HashMap;
+    use chrono::{DateTime, Utc};
+    use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+    use crate::pollable::IsInTerminalState;
+    #[cfg(feature = "payments")]
+    use crate::{Error, Pollable, TrueLayerClient};
+    #[cfg(feature = "payments")]
     use anyhow::anyhow;
+    #[cfg(feature = "payments")]
     use async_trait::async_trait;
-    use chrono::{DateTime, Utc};
-    use serde::{Deserialize, Serialize};
-
-    use crate::{pollable::IsInTerminalState, Error, Pollable, TrueLayerClient};
 
-    use super::Currency;
+    use super::{Currency, FailureReason, Metadata};
 
     #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+    #[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
     pub struct CreateRefundRequest {
         pub amount_in_minor: Option<u64>,
         pub reference: String,
-        pub metadata: Option<HashMap<String, String>>,
+        pub metadata: Option<Metadata>,
+    }
+
+    impl CreateRefundRequest {
+        /// Sets the metadata of the refund from any value serializing to a JSON object.
+        pub fn set_metadata<T: Serialize>(
+            &mut self,
+            metadata: &T,
+        ) -> Result<(), serde_json::Error> {
+            self.metadata = Some(Metadata::from_serializable(metadata)?);
+            Ok(())
+        }
     }
 
     #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+    #[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
     pub struct CreateRefundResponse {
         pub id: String,
     }
 
+    #[cfg(feature = "payments")]
     #[async_trait]
     impl Pollable for (&str, CreateRefundResponse) {
         type Output = Refund;
 
+        fn resource_id(&self) -> &str {
+            &self.1.id
+        }
+
         async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
             tl.payments
                 .get_refund_by_id(self.0, &self.1.id)
@@ -646,21 +2034,37 @@ pub mod refunds {
     }
 
     #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
     pub struct Refund {
         pub id: String,
         pub amount_in_minor: u64,
         pub currency: Currency,
         pub reference: String,
         pub created_at: DateTime<Utc>,
-        pub metadata: Option<HashMap<String, String>>,
+        pub metadata: Option<Metadata>,
         #[serde(flatten)]
         pub status: RefundStatus,
     }
 
+    impl Refund {
+        /// Deserializes the metadata of the refund into a custom type.
+        pub fn metadata_as<T: DeserializeOwned>(&self) -> Result<Option<T>, serde_json::Error> {
+            self.metadata
+                .as_ref()
+                .map(Metadata::deserialize_into)
+                .transpose()
+        }
+    }
+
+    #[cfg(feature = "payments")]
     #[async_trait]
     impl Pollable for (&str, Refund) {
         type Output = Refund;
 
+        fn resource_id(&self) -> &str {
+            &self.1.id
+        }
+
         async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
             tl.payments
                 .get_refund_by_id(self.0, &self.1.id)
@@ -671,16 +2075,19 @@ pub mod refunds {
     }
 
     impl IsInTerminalState for Refund {
-        /// A refund is considered to be in a terminal state if it is `Executed` or `Failed`.
+        /// A refund is considered to be in a terminal state if it is `Executed`, `Failed` or `Cancelled`.
         fn is_in_terminal_state(&self) -> bool {
             matches!(
                 self.status,
-                RefundStatus::Executed { .. } | RefundStatus::Failed { .. }
+                RefundStatus::Executed { .. }
+                    | RefundStatus::Failed { .. }
+                    | RefundStatus::Cancelled
             )
         }
     }
 
     #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
     #[serde(tag = "status", rename_all = "snake_case")]
     pub enum RefundStatus {
         Pending,
@@ -690,7 +2097,606 @@ pub mod refunds {
         },
         Failed {
             failed_at: DateTime<Utc>,
-            failure_reason: String,
+            failure_reason: FailureReason,
         },
+        /// The refund was cancelled with
+        /// [`PaymentsApi::cancel_refund`](crate::apis::payments::PaymentsApi::cancel_refund)
+        /// before being executed.
+        Cancelled,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payment(status: PaymentStatus, beneficiary: Beneficiary) -> Payment {
+        Payment {
+            id: "payment-id".into(),
+            amount_in_minor: 100,
+            currency: Currency::Gbp,
+            user: User {
+                id: "user-id".to_string(),
+                date_of_birth: None,
+                address: None,
+            },
+            payment_method: PaymentMethod::BankTransfer {
+                provider_selection: ProviderSelection::UserSelected {
+                    filter: None,
+                    scheme_selection: None,
+                    provider_id: None,
+                    scheme_id: None,
+                },
+                beneficiary,
+            },
+            created_at: Utc::now(),
+            metadata: None,
+            schedule: None,
+            risk_assessment: None,
+            status,
+        }
+    }
+
+    fn merchant_account() -> Beneficiary {
+        Beneficiary::MerchantAccount {
+            merchant_account_id: "merchant-account-id".to_string(),
+            account_holder_name: None,
+            reference: None,
+            statement_reference: None,
+            verification: None,
+        }
+    }
+
+    fn settled(category: Option<SettlementRiskCategory>) -> PaymentStatus {
+        PaymentStatus::Settled {
+            payment_source: PaymentSource {
+                id: "payment-source-id".to_string(),
+                user_id: None,
+                account_identifiers: vec![],
+                account_holder_name: None,
+            },
+            executed_at: Utc::now(),
+            settled_at: Utc::now(),
+            authorization_flow: None,
+            settlement_risk: category.map(|category| SettlementRisk { category }),
+        }
+    }
+
+    #[test]
+    fn payment_failed_before_authorization_is_resumable() {
+        let failed = |failure_stage, failure_reason| PaymentStatus::Failed {
+            failed_at: Utc::now(),
+            failure_stage,
+            failure_reason,
+            authorization_flow: None,
+            provider_error: None,
+        };
+
+        assert!(
+            payment(PaymentStatus::AuthorizationRequired, merchant_account())
+                .is_authorization_flow_resumable()
+        );
+        assert!(payment(
+            failed(FailureStage::Authorizing, FailureReason::ProviderRejected),
+            merchant_account()
+        )
+        .is_authorization_flow_resumable());
+        assert!(!payment(
+            failed(FailureStage::Authorizing, FailureReason::Expired),
+            merchant_account()
+        )
+        .is_authorization_flow_resumable());
+        assert!(!payment(
+            failed(FailureStage::Authorized, FailureReason::ProviderRejected),
+            merchant_account()
+        )
+        .is_authorization_flow_resumable());
+        assert!(!payment(settled(None), merchant_account()).is_authorization_flow_resumable());
+    }
+
+    #[test]
+    fn payment_converts_into_request_for_the_same_payment() {
+        let payment = payment(settled(None), merchant_account());
+
+        let request = CreatePaymentRequest::from(payment.clone());
+
+        assert_eq!(
+            request,
+            CreatePaymentRequest::builder()
+                .amount_in_minor(100)
+                .currency(Currency::Gbp)
+                .payment_method(PaymentMethodRequest::BankTransfer {
+                    provider_selection: ProviderSelectionRequest::UserSelected {
+                        filter: None,
+                        scheme_selection: None,
+                    },
+                    beneficiary: merchant_account(),
+                })
+                .user(CreatePaymentUserRequest::ExistingUser {
+                    id: "user-id".to_string(),
+                })
+                .build()
+        );
+        assert_eq!(
+            PaymentMethod::from(request.payment_method),
+            payment.payment_method
+        );
+    }
+
+    #[test]
+    fn failed_payment_deserialization() {
+        let status: PaymentStatus = serde_json::from_value(json!({
+            "status": "failed",
+            "failed_at": "2022-01-01T00:00:00Z",
+            "failure_stage": "authorized",
+            "failure_reason": "insufficient_funds",
+            "provider_error": {
+                "code": "AC04",
+                "message": "Closed account"
+            }
+        }))
+        .unwrap();
+
+        match status {
+            PaymentStatus::Failed {
+                failure_reason,
+                provider_error,
+                ..
+            } => {
+                assert_eq!(failure_reason, FailureReason::InsufficientFunds);
+                assert_eq!(
+                    failure_reason.description(),
+                    "There are not enough funds in the account."
+                );
+                assert_eq!(
+                    provider_error,
+                    Some(ProviderError {
+                        code: "AC04".to_string(),
+                        message: Some("Closed account".to_string()),
+                        retryable: false,
+                    })
+                );
+            }
+            other => panic!("Unexpected status: {:?}", other),
+        }
+
+        let unknown: FailureReason = serde_json::from_value(json!("something_new")).unwrap();
+        assert_eq!(unknown, FailureReason::Other("something_new".to_string()));
+        assert_eq!(
+            serde_json::to_value(&unknown).unwrap(),
+            json!("something_new")
+        );
+        assert_eq!(
+            serde_json::to_value(FailureReason::UserCanceledAtProvider).unwrap(),
+            json!("user_canceled_at_provider")
+        );
+    }
+
+    #[test]
+    fn settlement_risk_category_deserialization() {
+        let risk: SettlementRisk =
+            serde_json::from_value(json!({ "category": "medium_to_high_risk" })).unwrap();
+        assert_eq!(risk.category, SettlementRiskCategory::MediumToHighRisk);
+
+        let risk: SettlementRisk =
+            serde_json::from_value(json!({ "category": "something_new" })).unwrap();
+        assert_eq!(risk.category, SettlementRiskCategory::Unknown);
+    }
+
+    #[test]
+    fn settled_payment_into_merchant_account_is_eligible_for_payout() {
+        assert!(payment(settled(None), merchant_account()).is_eligible_for_payout());
+        assert!(payment(
+            settled(Some(SettlementRiskCategory::MediumRisk)),
+            merchant_account()
+        )
+        .is_eligible_for_payout());
+    }
+
+    #[test]
+    fn high_risk_payment_is_not_eligible_for_payout() {
+        let payment = payment(
+            settled(Some(SettlementRiskCategory::HighRisk)),
+            merchant_account(),
+        );
+
+        assert_eq!(
+            payment.settlement_risk().map(|r| r.category),
+            Some(SettlementRiskCategory::HighRisk)
+        );
+        assert!(!payment.is_eligible_for_payout());
+    }
+
+    #[test]
+    fn payment_not_settled_into_merchant_account_is_not_eligible_for_payout() {
+        let executed = PaymentStatus::Executed {
+            executed_at: Utc::now(),
+            authorization_flow: None,
+            settlement_risk: None,
+        };
+        assert!(!payment(executed, merchant_account()).is_eligible_for_payout());
+
+        let external_account = Beneficiary::ExternalAccount {
+            account_holder_name: "someone".to_string(),
+            account_identifier: AccountIdentifier::Iban {
+                iban: "GB33BUKB20201555555555".to_string(),
+            },
+            reference: "reference".to_string(),
+        };
+        assert!(!payment(settled(None), external_account).is_eligible_for_payout());
+    }
+
+    #[test]
+    fn sort_code_account_number_is_normalized() {
+        assert_eq!(
+            AccountIdentifier::sort_code_account_number("04-00 04", " 1234 5678 "),
+            Ok(AccountIdentifier::SortCodeAccountNumber {
+                sort_code: "040004".to_string(),
+                account_number: "12345678".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn sort_code_account_number_is_validated() {
+        assert_eq!(
+            AccountIdentifier::sort_code_account_number("04000", "12345678"),
+            Err(AccountIdentifierError::InvalidSortCode)
+        );
+        assert_eq!(
+            AccountIdentifier::sort_code_account_number("040004", "1234567a"),
+            Err(AccountIdentifierError::InvalidAccountNumber)
+        );
+    }
+
+    #[test]
+    fn iban_is_normalized_and_validated() {
+        assert_eq!(
+            AccountIdentifier::iban("gb33 bukb 2020 1555 5555 55"),
+            Ok(AccountIdentifier::Iban {
+                iban: "GB33BUKB20201555555555".to_string()
+            })
+        );
+        assert_eq!(
+            AccountIdentifier::iban("GB34BUKB20201555555555"),
+            Err(AccountIdentifierError::InvalidIbanChecksum)
+        );
+        assert_eq!(
+            AccountIdentifier::iban("3333BUKB20201555555555"),
+            Err(AccountIdentifierError::InvalidIban)
+        );
+        assert_eq!(
+            AccountIdentifier::iban("GB33"),
+            Err(AccountIdentifierError::InvalidIban)
+        );
+    }
+
+    #[test]
+    fn nrb_is_validated() {
+        assert_eq!(
+            AccountIdentifier::nrb("61 1090 1014 0000 0712 1981 2874"),
+            Ok(AccountIdentifier::Nrb {
+                nrb: "61109010140000071219812874".to_string()
+            })
+        );
+        assert_eq!(
+            AccountIdentifier::nrb("6110901014"),
+            Err(AccountIdentifierError::InvalidNrb)
+        );
+    }
+
+    #[test]
+    fn bban_is_validated() {
+        assert_eq!(
+            AccountIdentifier::bban("ab12 3456"),
+            Ok(AccountIdentifier::Bban {
+                bban: "AB123456".to_string()
+            })
+        );
+        assert_eq!(
+            AccountIdentifier::bban(""),
+            Err(AccountIdentifierError::InvalidBban)
+        );
+    }
+    #[test]
+    fn merchant_account_beneficiary_deserialization() {
+        let legacy: Beneficiary = serde_json::from_value(json!({
+            "type": "merchant_account",
+            "merchant_account_id": "merchant-account-id"
+        }))
+        .unwrap();
+        assert_eq!(legacy, merchant_account());
+
+        let verified: Beneficiary = serde_json::from_value(json!({
+            "type": "merchant_account",
+            "merchant_account_id": "merchant-account-id",
+            "reference": "statement-reference",
+            "verification": {
+                "type": "automated",
+                "remitter_name": true
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            verified,
+            Beneficiary::MerchantAccount {
+                merchant_account_id: "merchant-account-id".to_string(),
+                account_holder_name: None,
+                reference: Some("statement-reference".to_string()),
+                statement_reference: None,
+                verification: Some(RemitterVerification::Automated {
+                    remitter_name: true,
+                    remitter_date_of_birth: false
+                }),
+            }
+        );
+    }
+
+    fn create_payment_request(
+        currency: Currency,
+        provider_selection: ProviderSelectionRequest,
+        beneficiary: Beneficiary,
+    ) -> CreatePaymentRequest {
+        CreatePaymentRequest {
+            amount_in_minor: 100,
+            currency,
+            payment_method: PaymentMethodRequest::BankTransfer {
+                provider_selection,
+                beneficiary,
+            },
+            user: CreatePaymentUserRequest::ExistingUser {
+                id: "user-id".to_string(),
+            },
+            metadata: None,
+            schedule: None,
+            related_products: None,
+            risk_assessment: None,
+        }
+    }
+
+    fn user_selected(countries: Option<Vec<CountryCode>>) -> ProviderSelectionRequest {
+        ProviderSelectionRequest::UserSelected {
+            filter: Some(ProviderFilter {
+                countries,
+                release_channel: None,
+                customer_segments: None,
+                provider_ids: None,
+                excludes: None,
+            }),
+            scheme_selection: None,
+        }
+    }
+
+    fn external_account(account_identifier: AccountIdentifier) -> Beneficiary {
+        Beneficiary::ExternalAccount {
+            account_holder_name: "Mr. Holder".to_string(),
+            account_identifier,
+            reference: "reference".to_string(),
+        }
+    }
+
+    #[test]
+    fn compatible_payment_request_is_valid() {
+        let nrb = AccountIdentifier::Nrb {
+            nrb: "12345678901234567890123456".to_string(),
+        };
+
+        assert_eq!(
+            create_payment_request(
+                Currency::Pln,
+                user_selected(Some(vec![CountryCode::DE, CountryCode::PL])),
+                external_account(nrb)
+            )
+            .validate(),
+            Ok(())
+        );
+        assert_eq!(
+            create_payment_request(Currency::Eur, user_selected(None), merchant_account())
+                .validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn gbp_payment_into_iban_is_valid() {
+        let iban = AccountIdentifier::Iban {
+            iban: "GB33BUKB20201555555555".to_string(),
+        };
+
+        assert_eq!(
+            create_payment_request(Currency::Gbp, user_selected(None), external_account(iban))
+                .validate(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn beneficiary_account_identifier_must_support_currency() {
+        let nrb = AccountIdentifier::Nrb {
+            nrb: "12345678901234567890123456".to_string(),
+        };
+
+        assert_eq!(
+            create_payment_request(Currency::Gbp, user_selected(None), external_account(nrb))
+                .validate(),
+            Err(PaymentRequestError::UnsupportedBeneficiaryAccount {
+                account_identifier: "NRB",
+                currency: Currency::Gbp
+            })
+        );
+    }
+
+    #[test]
+    fn remitter_account_identifier_must_support_currency() {
+        let provider_selection = ProviderSelectionRequest::Preselected {
+            provider_id: "provider-id".to_string(),
+            scheme_id: SchemeId::FasterPaymentsService,
+            remitter: Some(Remitter {
+                account_holder_name: None,
+                account_identifier: Some(AccountIdentifier::Bban {
+                    bban: "12345678901".to_string(),
+                }),
+            }),
+        };
+
+        assert_eq!(
+            create_payment_request(Currency::Gbp, provider_selection, merchant_account())
+                .validate(),
+            Err(PaymentRequestError::UnsupportedRemitterAccount {
+                account_identifier: "BBAN",
+                currency: Currency::Gbp
+            })
+        );
+    }
+
+    #[test]
+    fn provider_filter_countries_must_support_currency() {
+        assert_eq!(
+            create_payment_request(
+                Currency::Gbp,
+                user_selected(Some(vec![CountryCode::FR, CountryCode::DE])),
+                merchant_account()
+            )
+            .validate(),
+            Err(PaymentRequestError::UnsupportedCountries {
+                currency: Currency::Gbp
+            })
+        );
+    }
+
+    #[test]
+    fn scheme_selection_serde() {
+        let cases = [
+            (
+                SchemeSelection::InstantOnly {
+                    allow_remitter_fee: Some(true),
+                },
+                json!({ "type": "instant_only", "allow_remitter_fee": true }),
+            ),
+            (
+                SchemeSelection::InstantPreferred {
+                    allow_remitter_fee: None,
+                },
+                json!({ "type": "instant_preferred", "allow_remitter_fee": null }),
+            ),
+            (
+                SchemeSelection::UserSelected,
+                json!({ "type": "user_selected" }),
+            ),
+        ];
+
+        for (scheme_selection, expected) in cases {
+            assert_eq!(serde_json::to_value(&scheme_selection).unwrap(), expected);
+            assert_eq!(
+                serde_json::from_value::<SchemeSelection>(expected).unwrap(),
+                scheme_selection
+            );
+        }
+    }
+
+    #[test]
+    fn currency_display_from_str_and_serde_agree() {
+        for currency in [Currency::Eur, Currency::Gbp, Currency::Nok, Currency::Pln] {
+            let s = currency.to_string();
+
+            assert_eq!(s.parse::<Currency>().unwrap(), currency);
+            assert_eq!(serde_json::to_value(&currency).unwrap(), json!(s));
+        }
+
+        assert_eq!(
+            "USD".parse::<Currency>(),
+            Err(ParseCurrencyError("USD".to_string()))
+        );
+    }
+
+    #[test]
+    fn metadata_helpers_round_trip_structured_values() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Order {
+            order_id: String,
+            lines: Vec<u32>,
+        }
+
+        let order = Order {
+            order_id: "order-id".to_string(),
+            lines: vec![1, 2],
+        };
+        let mut request =
+            create_payment_request(Currency::Gbp, user_selected(None), merchant_account());
+        request.set_metadata(&order).unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&request.metadata).unwrap(),
+            json!({ "order_id": "order-id", "lines": [1, 2] })
+        );
+
+        let mut created = payment(PaymentStatus::AuthorizationRequired, merchant_account());
+        created.metadata = request.metadata;
+        assert_eq!(created.metadata_as::<Order>().unwrap(), Some(order));
+    }
+
+    #[test]
+    fn metadata_must_be_an_object() {
+        assert!(Metadata::from_serializable(&"not an object").is_err());
+    }
+
+    #[test]
+    fn string_maps_convert_into_metadata() {
+        let metadata: Metadata = HashMap::from([("key".to_string(), "value".to_string())]).into();
+
+        assert_eq!(metadata.get("key"), Some(&json!("value")));
+    }
+
+    #[test]
+    fn builder_sets_required_and_optional_fields() {
+        let schedule = PaymentSchedule {
+            execution_date: NaiveDate::from_ymd_opt(2030, 1, 1).unwrap(),
+            execution_window: None,
+        };
+        let request = CreatePaymentRequest::builder()
+            .user(CreatePaymentUserRequest::ExistingUser {
+                id: "user-id".to_string(),
+            })
+            .currency(Currency::Gbp)
+            .payment_method(PaymentMethodRequest::BankTransfer {
+                provider_selection: user_selected(None),
+                beneficiary: merchant_account(),
+            })
+            .schedule(schedule.clone())
+            .amount_in_minor(100)
+            .build();
+
+        assert_eq!(
+            request,
+            CreatePaymentRequest {
+                schedule: Some(schedule),
+                ..create_payment_request(Currency::Gbp, user_selected(None), merchant_account())
+            }
+        );
+    }
+
+    #[test]
+    fn new_user_serializes_date_of_birth_and_address() {
+        let user = CreatePaymentUserRequest::new_user()
+            .with_name("Some One")
+            .with_date_of_birth(NaiveDate::from_ymd_opt(1990, 1, 31).unwrap())
+            .with_address(UserAddress {
+                address_line1: "Hauptstraße 1".to_string(),
+                address_line2: None,
+                city: "Berlin".to_string(),
+                state: None,
+                zip: "10115".to_string(),
+                country_code: "DE".to_string(),
+            })
+            .build();
+
+        let json = serde_json::to_value(&user).unwrap();
+        assert_eq!(json["date_of_birth"], json!("1990-01-31"));
+        assert_eq!(json["address"]["country_code"], json!("DE"));
+        assert_eq!(
+            serde_json::from_value::<CreatePaymentUserRequest>(json).unwrap(),
+            user
+        );
     }
 }
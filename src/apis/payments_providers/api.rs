@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use urlencoding::encode;
 
-use crate::{apis::TrueLayerClientInner, Error};
+use crate::{apis::TrueLayerClientInner, Error, RequestOptions};
 
 use super::model::Provider;
 
@@ -10,11 +10,23 @@ use super::model::Provider;
 #[derive(Clone, Debug)]
 pub struct PaymentsProvidersApi {
     inner: Arc<TrueLayerClientInner>,
+    options: RequestOptions,
 }
 
 impl PaymentsProvidersApi {
     pub(crate) fn new(inner: Arc<TrueLayerClientInner>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Returns a copy of this client which applies the given [`RequestOptions`] to all its requests.
+    pub fn with_options(&self, options: RequestOptions) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            options,
+        }
     }
 
     /// Gets the details of a payments provider.
@@ -23,37 +35,61 @@ impl PaymentsProvidersApi {
     ///
     /// This client always sets the `client_id` query parameter from the client configuration.
     /// Thus, only provider capabilities which are available to the `client_id` will be returned.
-    #[tracing::instrument(name = "Get Provider by ID", skip(self))]
+    ///
+    /// If the client has been built with a
+    /// [provider cache](crate::client::TrueLayerClientBuilder::with_provider_cache),
+    /// the cached details are returned until they expire.
+    #[tracing::instrument(
+        target = "truelayer_rust::payments_providers",
+        name = "Get Provider by ID",
+        skip(self)
+    )]
     pub async fn get_by_id(&self, id: &str) -> Result<Option<Provider>, Error> {
-        let res = self
+        if let Some(provider) = self.inner.provider_cache.as_ref().and_then(|c| c.get(id)) {
+            tracing::debug!(
+                target: "truelayer_rust::payments_providers",
+                "Returning cached provider"
+            );
+            return Ok(provider);
+        }
+
+        let provider = self
             .inner
-            .client
-            .get(
+            .get_optional(
                 self.inner
                     .environment
-                    .payments_url()
-                    .join(&format!("/payments-providers/{}", encode(id)))
-                    .unwrap(),
+                    .payments_endpoint(&format!("/payments-providers/{}", encode(id))),
+                &[("client_id", &self.inner.authenticator.client_id)],
+                &self.options,
             )
-            .query(&[("client_id", &self.inner.authenticator.client_id)])
-            .send()
-            .await
-            .map_err(Error::from);
-
-        // Return `None` if the server returned 404
-        let provider = match res {
-            Ok(body) => Some(body.json().await?),
-            Err(Error::ApiError(api_error)) if api_error.status == 404 => None,
-            Err(e) => return Err(e),
-        };
+            .await?;
+
+        if let Some(cache) = &self.inner.provider_cache {
+            cache.insert(id, provider.clone());
+        }
 
         Ok(provider)
     }
+
+    /// Removes the cached details of the given provider, if any,
+    /// so that the next lookup fetches them from the server.
+    pub fn invalidate_cache(&self, id: &str) {
+        if let Some(cache) = &self.inner.provider_cache {
+            cache.invalidate(id);
+        }
+    }
+
+    /// Removes the cached details of all the providers.
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.inner.provider_cache {
+            cache.clear();
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{sync::Arc, time::Duration};
 
     use reqwest::Url;
     use serde_json::json;
@@ -65,16 +101,18 @@ mod tests {
     use crate::{
         apis::{
             auth::Credentials,
-            payments::CountryCode,
+            payments::{CountryCode, ProviderAvailabilityStatus, ReleaseChannel, SchemeId},
             payments_providers::{
                 api::PaymentsProvidersApi,
                 model::{capabilities, Capabilities, PaymentScheme},
+                ProviderCache,
             },
             TrueLayerClientInner,
         },
         authenticator::Authenticator,
         client::Environment,
         middlewares::error_handling::ErrorHandlingMiddleware,
+        validation::RequestValidation,
     };
 
     async fn mock_client_and_server() -> (TrueLayerClientInner, MockServer) {
@@ -98,6 +136,9 @@ mod tests {
                 .build(),
             authenticator,
             environment: Environment::from_single_url(&Url::parse(&mock_server.uri()).unwrap()),
+            runtime: None,
+            provider_cache: None,
+            request_validation: RequestValidation::default(),
         };
 
         (inner, mock_server)
@@ -118,6 +159,10 @@ mod tests {
                 "logo_uri": "https://logo.uri",
                 "bg_color": "#FFFFFF",
                 "country_code": "ES",
+                "availability": {
+                    "recommended_status": "unhealthy",
+                    "updated_at": "2022-06-10T10:00:00Z"
+                },
                 "capabilities": {
                     "payments": {
                         "bank_transfer": {
@@ -131,6 +176,11 @@ mod tests {
                                 }
                             ]
                         }
+                    },
+                    "mandates": {
+                        "vrp_sweeping": {
+                            "release_channel": "private_beta"
+                        }
                     }
                 }
             })))
@@ -146,24 +196,75 @@ mod tests {
         assert_eq!(provider.logo_uri, Some("https://logo.uri".into()));
         assert_eq!(provider.bg_color, Some("#FFFFFF".into()));
         assert_eq!(provider.country_code, Some(CountryCode::ES));
+        assert_eq!(
+            provider
+                .availability
+                .as_ref()
+                .map(|a| &a.recommended_status),
+            Some(&ProviderAvailabilityStatus::Unhealthy)
+        );
+        assert!(!provider.is_healthy());
         assert_eq!(
             provider.capabilities,
             Capabilities {
                 payments: capabilities::Payments {
                     bank_transfer: Some(capabilities::BankTransfer {
-                        release_channel: crate::apis::payments::ReleaseChannel::GeneralAvailability,
+                        release_channel: ReleaseChannel::GeneralAvailability,
                         schemes: vec![
                             PaymentScheme {
-                                id: "sepa_credit_transfer".into()
+                                id: SchemeId::SepaCreditTransfer
                             },
                             PaymentScheme {
-                                id: "sepa_credit_transfer_instant".into()
+                                id: SchemeId::SepaInstant
                             }
                         ]
                     })
-                }
+                },
+                mandates: Some(capabilities::Mandates {
+                    vrp_sweeping: Some(capabilities::VrpMandate {
+                        release_channel: ReleaseChannel::PrivateBeta,
+                        schemes: None
+                    }),
+                    vrp_commercial: None
+                })
             }
         );
+
+        #[cfg(feature = "mandates")]
+        {
+            use crate::apis::mandates::{
+                MandateBeneficiary, MandateDetail, MandateProviderSelection,
+            };
+
+            let mandate = |commercial: bool| {
+                let provider_selection = MandateProviderSelection::Preselected {
+                    provider_id: provider_id.to_string(),
+                    remitter: None,
+                };
+                let beneficiary = MandateBeneficiary::MerchantAccount {
+                    merchant_account_id: "merchant-account-id".to_string(),
+                    account_holder_name: None,
+                };
+                if commercial {
+                    MandateDetail::Commercial {
+                        provider_selection,
+                        beneficiary,
+                        reference: None,
+                    }
+                } else {
+                    MandateDetail::Sweeping {
+                        provider_selection,
+                        beneficiary,
+                        reference: None,
+                    }
+                }
+            };
+            assert_eq!(
+                provider.mandate_release_channel(&mandate(false)),
+                Some(&ReleaseChannel::PrivateBeta)
+            );
+            assert_eq!(provider.mandate_release_channel(&mandate(true)), None);
+        }
     }
 
     #[tokio::test]
@@ -180,4 +281,25 @@ mod tests {
 
         assert!(api.get_by_id("non-existent").await.unwrap().is_none());
     }
+
+    #[tokio::test]
+    async fn cached_lookups_do_not_hit_the_network() {
+        let (mut inner, mock_server) = mock_client_and_server().await;
+        inner.provider_cache = Some(ProviderCache::new(Duration::from_secs(60)));
+        let api = PaymentsProvidersApi::new(Arc::new(inner));
+
+        Mock::given(method("GET"))
+            .and(path("/payments-providers/non-existent"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        assert!(api.get_by_id("non-existent").await.unwrap().is_none());
+        assert!(api.get_by_id("non-existent").await.unwrap().is_none());
+
+        // The next lookup after invalidating the cache hits the network again
+        api.invalidate_cache("non-existent");
+        assert!(api.get_by_id("non-existent").await.unwrap().is_none());
+    }
 }
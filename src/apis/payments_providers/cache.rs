@@ -0,0 +1,66 @@
+use crate::apis::payments_providers::Provider;
+use chrono::{DateTime, Utc};
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+/// In-memory cache of provider details, enabled with
+/// [`TrueLayerClientBuilder::with_provider_cache()`](crate::client::TrueLayerClientBuilder::with_provider_cache).
+///
+/// Unknown providers are cached too, so that repeated lookups of a wrong id don't hit the network either.
+#[derive(Debug)]
+pub(crate) struct ProviderCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    provider: Option<Provider>,
+    fetched_at: DateTime<Utc>,
+}
+
+impl ProviderCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the cached lookup of the given provider, or `None` if it's missing or expired.
+    pub(crate) fn get(&self, id: &str) -> Option<Option<Provider>> {
+        let mut entries = self.entries.lock().unwrap();
+
+        match entries.get(id) {
+            Some(entry) if !self.is_expired(entry) => Some(entry.provider.clone()),
+            Some(_) => {
+                entries.remove(id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(&self, id: &str, provider: Option<Provider>) {
+        self.entries.lock().unwrap().insert(
+            id.to_string(),
+            CacheEntry {
+                provider,
+                fetched_at: Utc::now(),
+            },
+        );
+    }
+
+    pub(crate) fn invalidate(&self, id: &str) {
+        self.entries.lock().unwrap().remove(id);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    fn is_expired(&self, entry: &CacheEntry) -> bool {
+        (Utc::now() - entry.fetched_at)
+            .to_std()
+            .is_ok_and(|age| age >= self.ttl)
+    }
+}
@@ -1,7 +1,13 @@
 //! APIs and models related to payments providers.
 
+#[cfg(feature = "payments")]
 mod api;
+#[cfg(feature = "payments")]
+mod cache;
 mod model;
 
+#[cfg(feature = "payments")]
 pub use api::PaymentsProvidersApi;
+#[cfg(feature = "payments")]
+pub(crate) use cache::ProviderCache;
 pub use model::*;
@@ -1,8 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-use crate::apis::payments::CountryCode;
+use crate::apis::payments::{CountryCode, Currency, ProviderAvailability, SchemeId};
+#[cfg(feature = "mandates")]
+use crate::apis::{mandates::MandateDetail, payments::ReleaseChannel};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Provider {
     pub id: String,
     pub display_name: Option<String>,
@@ -11,11 +15,41 @@ pub struct Provider {
     pub bg_color: Option<String>,
     pub country_code: Option<CountryCode>,
     pub capabilities: Capabilities,
+    /// Health of the provider, if reported.
+    pub availability: Option<ProviderAvailability>,
+}
+
+impl Provider {
+    /// Returns `false` if the provider is known to be unhealthy.
+    ///
+    /// Providers whose availability is not reported are assumed to be healthy.
+    pub fn is_healthy(&self) -> bool {
+        self.availability
+            .as_ref()
+            .is_none_or(ProviderAvailability::is_healthy)
+    }
+
+    /// Returns the release channel through which the provider supports the given kind of mandate,
+    /// or `None` if mandates of that kind cannot be created with this provider.
+    #[cfg(feature = "mandates")]
+    pub fn mandate_release_channel(&self, mandate: &MandateDetail) -> Option<&ReleaseChannel> {
+        let mandates = self.capabilities.mandates.as_ref()?;
+        let capability = match mandate {
+            MandateDetail::Sweeping { .. } => mandates.vrp_sweeping.as_ref(),
+            MandateDetail::Commercial { .. } => mandates.vrp_commercial.as_ref(),
+        };
+
+        capability.map(|c| &c.release_channel)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Capabilities {
     pub payments: capabilities::Payments,
+    /// Kinds of mandates supported by the provider, if any.
+    pub mandates: Option<capabilities::Mandates>,
 }
 
 pub mod capabilities {
@@ -26,18 +60,67 @@ pub mod capabilities {
     use super::PaymentScheme;
 
     #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+    #[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
     pub struct Payments {
         pub bank_transfer: Option<BankTransfer>,
     }
 
     #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+    #[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
     pub struct BankTransfer {
         pub release_channel: ReleaseChannel,
         pub schemes: Vec<PaymentScheme>,
     }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+    #[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+    pub struct Mandates {
+        /// Variable recurring payments between accounts owned by the same user.
+        pub vrp_sweeping: Option<VrpMandate>,
+        /// Variable recurring payments to a merchant.
+        pub vrp_commercial: Option<VrpMandate>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+    #[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+    #[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+    pub struct VrpMandate {
+        pub release_channel: ReleaseChannel,
+        /// Schemes through which payments against the mandate are made, when reported by the provider.
+        pub schemes: Option<Vec<PaymentScheme>>,
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct PaymentScheme {
-    pub id: String,
+    pub id: SchemeId,
+}
+
+/// Currencies that payments can be made in through providers of the given country.
+///
+/// Countries whose local currency is not yet supported by this library (e.g. `DK` and `RO`)
+/// return an empty list.
+pub fn supported_currencies(country_code: &CountryCode) -> &'static [Currency] {
+    match country_code {
+        CountryCode::GB => &[Currency::Gbp],
+        CountryCode::PL => &[Currency::Pln],
+        CountryCode::NO => &[Currency::Nok],
+        CountryCode::AT
+        | CountryCode::BE
+        | CountryCode::DE
+        | CountryCode::ES
+        | CountryCode::FI
+        | CountryCode::FR
+        | CountryCode::IE
+        | CountryCode::IT
+        | CountryCode::LT
+        | CountryCode::NL
+        | CountryCode::PT => &[Currency::Eur],
+        CountryCode::DK | CountryCode::RO => &[],
+    }
 }
@@ -1,11 +1,16 @@
 use crate::{
     apis::{
-        payouts::{CreatePayoutRequest, CreatePayoutResponse, Payout},
-        TrueLayerClientInner,
+        payments::{Beneficiary, Payment, PaymentMethod, PaymentStatus},
+        payouts::{
+            BatchOptions, CreatePayoutRequest, CreatePayoutResponse, Payout, PayoutBeneficiary,
+            PayoutId,
+        },
+        TrueLayerClientInner, NO_QUERY,
     },
-    common::IDEMPOTENCY_KEY_HEADER,
-    Error,
+    Error, IdempotentRequest, RequestOptions,
 };
+use anyhow::anyhow;
+use futures::{stream, StreamExt};
 use std::sync::Arc;
 use urlencoding::encode;
 use uuid::Uuid;
@@ -14,15 +19,28 @@ use uuid::Uuid;
 #[derive(Clone, Debug)]
 pub struct PayoutsApi {
     inner: Arc<TrueLayerClientInner>,
+    options: RequestOptions,
 }
 
 impl PayoutsApi {
     pub(crate) fn new(inner: Arc<TrueLayerClientInner>) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Returns a copy of this client which applies the given [`RequestOptions`] to all its requests.
+    pub fn with_options(&self, options: RequestOptions) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            options,
+        }
     }
 
     /// Payout from one of your merchant accounts.
     #[tracing::instrument(
+        target = "truelayer_rust::payouts",
         name = "Create Payout",
         skip(self, create_payout_request),
         fields(
@@ -34,56 +52,170 @@ impl PayoutsApi {
         &self,
         create_payout_request: &CreatePayoutRequest,
     ) -> Result<CreatePayoutResponse, Error> {
+        self.prepare_create(create_payout_request)?
+            .send(&self.inner, &self.options)
+            .await
+    }
+
+    /// Pays back (part of) a settled closed-loop payment to the account it was paid from.
+    ///
+    /// The payout is sent from the merchant account which received the payment, in the same currency,
+    /// to the [`PaymentSource`](crate::apis::payments::PaymentSource) the payment was made from.
+    /// An error is returned without contacting TrueLayer if the payment is not settled,
+    /// was not paid into a merchant account or if `amount_in_minor` exceeds the payment amount.
+    #[tracing::instrument(
+        target = "truelayer_rust::payouts",
+        name = "Refund via Payout",
+        skip(self, payment),
+        fields(payment_id = %payment.id)
+    )]
+    pub async fn refund_via_payout(
+        &self,
+        payment: &Payment,
+        amount_in_minor: u64,
+        reference: &str,
+    ) -> Result<CreatePayoutResponse, Error> {
+        let payment_source = match payment.status {
+            PaymentStatus::Settled {
+                ref payment_source, ..
+            } => payment_source,
+            _ => {
+                return Err(Error::Other(anyhow!(
+                    "Payment {} is not settled and cannot be paid back",
+                    payment.id
+                )))
+            }
+        };
+
+        let merchant_account_id = match payment.payment_method {
+            PaymentMethod::BankTransfer {
+                beneficiary:
+                    Beneficiary::MerchantAccount {
+                        ref merchant_account_id,
+                        ..
+                    },
+                ..
+            } => merchant_account_id,
+            _ => {
+                return Err(Error::Other(anyhow!(
+                    "Payment {} was not paid into a merchant account",
+                    payment.id
+                )))
+            }
+        };
+
+        if amount_in_minor > payment.amount_in_minor {
+            return Err(Error::Other(anyhow!(
+                "Cannot pay back {} out of a payment of {}",
+                amount_in_minor,
+                payment.amount_in_minor
+            )));
+        }
+
+        self.create(&CreatePayoutRequest {
+            merchant_account_id: merchant_account_id.clone(),
+            amount_in_minor,
+            currency: payment.currency.clone(),
+            beneficiary: PayoutBeneficiary::PaymentSource {
+                user_id: payment_source
+                    .user_id
+                    .clone()
+                    .unwrap_or_else(|| payment.user.id.clone()),
+                payment_source_id: payment_source.id.clone(),
+                reference: reference.to_string(),
+            },
+            metadata: None,
+        })
+        .await
+    }
+
+    /// Creates many payouts, keeping at most [`max_concurrency`] requests in flight at the same time.
+    ///
+    /// Each payout gets an idempotency key derived from the batch id and its position in the list.
+    /// If some of the payouts fail, the whole list can be submitted again with the same [`BatchOptions`]
+    /// (or the same [`batch_id`]) to retry only the failed ones: payouts which were already created
+    /// are not duplicated.
+    ///
+    /// Results are returned in the same order as the requests.
+    ///
+    /// [`max_concurrency`]: crate::apis::payouts::BatchOptions::with_max_concurrency
+    /// [`batch_id`]: crate::apis::payouts::BatchOptions::with_batch_id
+    #[tracing::instrument(
+        target = "truelayer_rust::payouts",
+        name = "Create Payouts Batch",
+        skip(self, create_payout_requests, options),
+        fields(
+            count = create_payout_requests.len(),
+            batch_id = %options.batch_id(),
+        )
+    )]
+    pub async fn create_batch(
+        &self,
+        create_payout_requests: Vec<CreatePayoutRequest>,
+        options: BatchOptions,
+    ) -> Vec<Result<CreatePayoutResponse, Error>> {
+        stream::iter(create_payout_requests.into_iter().enumerate())
+            .map(|(index, request)| {
+                let idempotency_key = options.idempotency_key(index);
+                async move {
+                    self.create_with_idempotency_key(&request, idempotency_key)
+                        .await
+                }
+            })
+            .buffered(options.max_concurrency())
+            .collect()
+            .await
+    }
+
+    /// Prepares the request to create a new payout without sending it.
+    ///
+    /// The returned [`IdempotentRequest`] can be persisted and submitted (even multiple times)
+    /// with [`TrueLayerClient::resubmit()`](crate::TrueLayerClient::resubmit):
+    /// the payout is created only once.
+    pub fn prepare_create(
+        &self,
+        create_payout_request: &CreatePayoutRequest,
+    ) -> Result<IdempotentRequest<CreatePayoutResponse>, Error> {
+        self.inner.validate(create_payout_request)?;
+
         // Generate a new random idempotency-key for this request
-        let idempotency_key = Uuid::new_v4();
+        IdempotentRequest::new("/payouts", create_payout_request, Uuid::new_v4())
+    }
 
-        let res = self
-            .inner
-            .client
-            .post(
-                self.inner
-                    .environment
-                    .payments_url()
-                    .join("/payouts")
-                    .unwrap(),
-            )
-            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key.to_string())
-            .json(create_payout_request)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        Ok(res)
+    async fn create_with_idempotency_key(
+        &self,
+        create_payout_request: &CreatePayoutRequest,
+        idempotency_key: Uuid,
+    ) -> Result<CreatePayoutResponse, Error> {
+        self.inner.validate(create_payout_request)?;
+
+        IdempotentRequest::new("/payouts", create_payout_request, idempotency_key)?
+            .send(&self.inner, &self.options)
+            .await
     }
 
     /// Gets the details of an existing payout.
     ///
     /// If there's no payout with the given id, `None` is returned.
-    #[tracing::instrument(name = "Get Payout by ID", skip(self))]
-    pub async fn get_by_id(&self, id: &str) -> Result<Option<Payout>, Error> {
-        let res = self
-            .inner
-            .client
-            .get(
+    #[tracing::instrument(
+        target = "truelayer_rust::payouts",
+        name = "Get Payout by ID",
+        skip(self, id),
+        fields(id = tracing::field::Empty)
+    )]
+    pub async fn get_by_id(&self, id: impl Into<PayoutId>) -> Result<Option<Payout>, Error> {
+        let id = id.into();
+        tracing::Span::current().record("id", id.as_str());
+
+        self.inner
+            .get_optional(
                 self.inner
                     .environment
-                    .payments_url()
-                    .join(&format!("/payouts/{}", encode(id)))
-                    .unwrap(),
+                    .payments_endpoint(&format!("/payouts/{}", encode(id.as_str()))),
+                NO_QUERY,
+                &self.options,
             )
-            .send()
             .await
-            .map_err(Error::from);
-
-        // Return `None` if the server returned 404
-        let payout = match res {
-            Ok(body) => Some(body.json().await?),
-            Err(Error::ApiError(api_error)) if api_error.status == 404 => None,
-            Err(e) => return Err(e),
-        };
-
-        Ok(payout)
     }
 }
 
@@ -93,17 +225,21 @@ mod tests {
     use crate::{
         apis::{
             auth::Credentials,
-            payments::{AccountIdentifier, Currency},
-            payouts::{PayoutBeneficiary, PayoutStatus},
+            payments::{AccountIdentifier, Currency, PaymentSource, ProviderSelection, User},
+            payouts::PayoutStatus,
         },
         authenticator::Authenticator,
         client::Environment,
+        common::IDEMPOTENCY_KEY_HEADER,
         middlewares::error_handling::ErrorHandlingMiddleware,
+        validation::{RequestValidation, ValidationError, ValidationErrorKind},
     };
     use chrono::{TimeZone, Utc};
     use serde_json::json;
+    use std::{collections::HashMap, str::FromStr};
     use url::Url;
     use wiremock::{
+        http::HeaderName,
         matchers::{body_partial_json, header_exists, method, path},
         Mock, MockServer, ResponseTemplate,
     };
@@ -129,6 +265,10 @@ mod tests {
                 .build(),
             authenticator,
             environment: Environment::from_single_url(&Url::parse(&mock_server.uri()).unwrap()),
+            runtime: None,
+            #[cfg(feature = "payments")]
+            provider_cache: None,
+            request_validation: RequestValidation::default(),
         };
 
         (inner, mock_server)
@@ -154,6 +294,9 @@ mod tests {
                         "iban": "some-iban"
                     },
                     "reference": "some-reference"
+                },
+                "metadata": {
+                    "ledger_id": "some-ledger-id"
                 }
             })))
             .respond_with(ResponseTemplate::new(200).set_body_json(json!({
@@ -174,7 +317,13 @@ mod tests {
                         iban: "some-iban".to_string(),
                     },
                     reference: "some-reference".to_string(),
+                    verification: None,
+                    date_of_birth: None,
+                    address: None,
                 },
+                metadata: Some(
+                    HashMap::from([("ledger_id".to_string(), "some-ledger-id".to_string())]).into(),
+                ),
             })
             .await
             .unwrap();
@@ -182,6 +331,255 @@ mod tests {
         assert_eq!(res.id, "payout-id");
     }
 
+    #[tokio::test]
+    async fn strict_validation_rejects_unsupported_currencies_without_contacting_truelayer() {
+        let (mut inner, mock_server) = mock_client_and_server().await;
+        inner.request_validation = RequestValidation::Strict;
+        let api = PayoutsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let res = api
+            .create(&CreatePayoutRequest {
+                merchant_account_id: "merchant-account-id".to_string(),
+                amount_in_minor: 100,
+                currency: Currency::Pln,
+                beneficiary: PayoutBeneficiary::ExternalAccount {
+                    account_holder_name: "Mr. Holder".to_string(),
+                    account_identifier: AccountIdentifier::Bban {
+                        bban: "12345678901".to_string(),
+                    },
+                    reference: "some-reference".to_string(),
+                    verification: None,
+                    date_of_birth: None,
+                    address: None,
+                },
+                metadata: None,
+            })
+            .await;
+
+        assert!(matches!(
+            res,
+            Err(Error::ValidationError(ValidationError {
+                ref field,
+                kind: ValidationErrorKind::UnsupportedCurrency(Currency::Pln),
+            })) if field == "beneficiary.account_identifier"
+        ));
+    }
+
+    fn mock_payment(status: PaymentStatus) -> Payment {
+        Payment {
+            id: "payment-id".into(),
+            amount_in_minor: 100,
+            currency: Currency::Gbp,
+            user: User {
+                id: "user-id".to_string(),
+                date_of_birth: None,
+                address: None,
+            },
+            payment_method: PaymentMethod::BankTransfer {
+                provider_selection: ProviderSelection::UserSelected {
+                    filter: None,
+                    scheme_selection: None,
+                    provider_id: None,
+                    scheme_id: None,
+                },
+                beneficiary: Beneficiary::MerchantAccount {
+                    merchant_account_id: "merchant-account-id".to_string(),
+                    account_holder_name: None,
+                    reference: None,
+                    statement_reference: None,
+                    verification: None,
+                },
+            },
+            created_at: Utc::now(),
+            metadata: None,
+            schedule: None,
+            risk_assessment: None,
+            status,
+        }
+    }
+
+    #[tokio::test]
+    async fn refund_via_payout() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PayoutsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payouts"))
+            .and(body_partial_json(json!({
+                "merchant_account_id": "merchant-account-id",
+                "amount_in_minor": 50,
+                "currency": "GBP",
+                "beneficiary": {
+                    "type": "payment_source",
+                    "user_id": "user-id",
+                    "payment_source_id": "payment-source-id",
+                    "reference": "some-reference"
+                }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "payout-id"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let payment = mock_payment(PaymentStatus::Settled {
+            payment_source: PaymentSource {
+                id: "payment-source-id".to_string(),
+                user_id: Some("user-id".to_string()),
+                account_identifiers: vec![],
+                account_holder_name: None,
+            },
+            executed_at: Utc::now(),
+            settled_at: Utc::now(),
+            authorization_flow: None,
+            settlement_risk: None,
+        });
+        let res = api
+            .refund_via_payout(&payment, 50, "some-reference")
+            .await
+            .unwrap();
+
+        assert_eq!(res.id, "payout-id");
+    }
+
+    #[tokio::test]
+    async fn refund_via_payout_payment_not_settled() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PayoutsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payouts"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let payment = mock_payment(PaymentStatus::Executed {
+            executed_at: Utc::now(),
+            authorization_flow: None,
+            settlement_risk: None,
+        });
+        let res = api.refund_via_payout(&payment, 50, "some-reference").await;
+
+        assert!(matches!(res, Err(Error::Other(_))));
+    }
+
+    fn mock_create_payout_request(reference: &str) -> CreatePayoutRequest {
+        CreatePayoutRequest {
+            merchant_account_id: "merchant-account-id".to_string(),
+            amount_in_minor: 100,
+            currency: Currency::Gbp,
+            beneficiary: PayoutBeneficiary::ExternalAccount {
+                account_holder_name: "Mr. Holder".to_string(),
+                account_identifier: AccountIdentifier::Iban {
+                    iban: "some-iban".to_string(),
+                },
+                reference: reference.to_string(),
+                verification: None,
+                date_of_birth: None,
+                address: None,
+            },
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn create_batch() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PayoutsApi::new(Arc::new(inner));
+
+        // Echo back the reference as the payout id, but fail the second payout
+        Mock::given(method("POST"))
+            .and(path("/payouts"))
+            .and(body_partial_json(
+                json!({ "beneficiary": { "reference": "ref-1" } }),
+            ))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/payouts"))
+            .and(header_exists(IDEMPOTENCY_KEY_HEADER))
+            .respond_with(|req: &wiremock::Request| {
+                let body: serde_json::Value = req.body_json().unwrap();
+                ResponseTemplate::new(200).set_body_json(json!({
+                    "id": body["beneficiary"]["reference"]
+                }))
+            })
+            .mount(&mock_server)
+            .await;
+
+        let requests = (0..5)
+            .map(|i| mock_create_payout_request(&format!("ref-{}", i)))
+            .collect::<Vec<_>>();
+        let res = api
+            .create_batch(requests, BatchOptions::default().with_max_concurrency(2))
+            .await;
+
+        // Results are in the same order as the requests
+        assert_eq!(res.len(), 5);
+        for (i, r) in res.into_iter().enumerate() {
+            if i == 1 {
+                assert!(matches!(r, Err(Error::ApiError(e)) if e.status == 500));
+            } else {
+                assert_eq!(r.unwrap().id, format!("ref-{}", i));
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn create_batch_idempotency_keys_are_stable() {
+        let (inner, mock_server) = mock_client_and_server().await;
+        let api = PayoutsApi::new(Arc::new(inner));
+
+        Mock::given(method("POST"))
+            .and(path("/payouts"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "payout-id"
+            })))
+            .expect(4)
+            .mount(&mock_server)
+            .await;
+
+        // Submit the same batch twice
+        let options = BatchOptions::default().with_max_concurrency(1);
+        let requests = vec![
+            mock_create_payout_request("ref-0"),
+            mock_create_payout_request("ref-1"),
+        ];
+        for _ in 0..2 {
+            for r in api.create_batch(requests.clone(), options.clone()).await {
+                r.unwrap();
+            }
+        }
+
+        // Both submissions used the same idempotency key for the same payout,
+        // and different payouts in the batch got different keys
+        let keys = mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| {
+                r.headers
+                    .get(&HeaderName::from_str(IDEMPOTENCY_KEY_HEADER).unwrap())
+                    .unwrap()
+                    .last()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(keys[0], keys[2]);
+        assert_eq!(keys[1], keys[3]);
+        assert_ne!(keys[0], keys[1]);
+    }
+
     #[tokio::test]
     async fn get_by_id_successful() {
         let (inner, mock_server) = mock_client_and_server().await;
@@ -206,7 +604,10 @@ mod tests {
                 },
                 "status": "executed",
                 "created_at": "2022-04-01T00:00:00Z",
-                "executed_at": "2022-04-01T00:00:00Z"
+                "executed_at": "2022-04-01T00:00:00Z",
+                "metadata": {
+                    "ledger_id": "some-ledger-id"
+                }
             })))
             .expect(1)
             .mount(&mock_server)
@@ -229,12 +630,19 @@ mod tests {
                     iban: "some-iban".to_string(),
                 },
                 reference: "some-reference".to_string(),
+                verification: None,
+                date_of_birth: None,
+                address: None,
             }
         );
         assert_eq!(
             payout.created_at,
             Utc.with_ymd_and_hms(2022, 4, 1, 0, 0, 0).unwrap()
         );
+        assert_eq!(
+            payout.metadata,
+            Some(HashMap::from([("ledger_id".to_string(), "some-ledger-id".to_string())]).into())
+        );
         assert_eq!(
             payout.status,
             PayoutStatus::Executed {
@@ -3,5 +3,6 @@
 mod api;
 mod model;
 
+pub use crate::apis::ids::PayoutId;
 pub use api::PayoutsApi;
 pub use model::*;
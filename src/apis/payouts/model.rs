@@ -1,30 +1,227 @@
 use crate::{
-    apis::payments::{AccountIdentifier, Currency},
+    apis::{
+        ids::PayoutId,
+        payments::{AccountIdentifier, Currency, FailureReason, Metadata},
+        Missing,
+    },
     pollable::IsInTerminalState,
     Error, Pollable, TrueLayerClient,
 };
 use anyhow::anyhow;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct CreatePayoutRequest {
     pub merchant_account_id: String,
     pub amount_in_minor: u64,
     pub currency: Currency,
     pub beneficiary: PayoutBeneficiary,
+    pub metadata: Option<Metadata>,
+}
+
+impl CreatePayoutRequest {
+    /// Starts building a request. `build()` becomes available once the merchant account,
+    /// the amount, the currency and the beneficiary have all been set.
+    pub fn builder() -> CreatePayoutRequestBuilder {
+        CreatePayoutRequestBuilder {
+            merchant_account_id: Missing,
+            amount_in_minor: Missing,
+            currency: Missing,
+            beneficiary: Missing,
+            metadata: None,
+        }
+    }
+
+    /// Sets the metadata of the payout from any value serializing to a JSON object.
+    pub fn set_metadata<T: Serialize>(&mut self, metadata: &T) -> Result<(), serde_json::Error> {
+        self.metadata = Some(Metadata::from_serializable(metadata)?);
+        Ok(())
+    }
+
+    /// Checks that the currency of the payout can be paid into the beneficiary account,
+    /// e.g. PLN into an NRB or IBAN and NOK into a BBAN or IBAN.
+    ///
+    /// It is performed automatically by [`PayoutsApi::create`](crate::apis::payouts::PayoutsApi::create)
+    /// when the client uses [`RequestValidation::Strict`](crate::validation::RequestValidation::Strict).
+    pub fn validate(&self) -> Result<(), PayoutRequestError> {
+        if let PayoutBeneficiary::ExternalAccount {
+            account_identifier, ..
+        } = &self.beneficiary
+        {
+            if !account_identifier
+                .supported_currencies()
+                .contains(&self.currency)
+            {
+                return Err(PayoutRequestError::UnsupportedBeneficiaryAccount {
+                    account_identifier: account_identifier.kind(),
+                    currency: self.currency.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned by [`CreatePayoutRequest::validate`].
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum PayoutRequestError {
+    #[error(
+        "Beneficiary accounts identified by {account_identifier} cannot receive {currency} payouts"
+    )]
+    UnsupportedBeneficiaryAccount {
+        account_identifier: &'static str,
+        currency: Currency,
+    },
+}
+
+/// Typestate builder for [`CreatePayoutRequest`], created with [`CreatePayoutRequest::builder()`].
+///
+/// Each type parameter is [`Missing`] until the corresponding required field is set.
+#[derive(Debug, Clone)]
+pub struct CreatePayoutRequestBuilder<I = Missing, A = Missing, C = Missing, B = Missing> {
+    merchant_account_id: I,
+    amount_in_minor: A,
+    currency: C,
+    beneficiary: B,
+    metadata: Option<Metadata>,
+}
+
+impl<I, A, C, B> CreatePayoutRequestBuilder<I, A, C, B> {
+    pub fn merchant_account_id(
+        self,
+        merchant_account_id: impl Into<String>,
+    ) -> CreatePayoutRequestBuilder<String, A, C, B> {
+        CreatePayoutRequestBuilder {
+            merchant_account_id: merchant_account_id.into(),
+            amount_in_minor: self.amount_in_minor,
+            currency: self.currency,
+            beneficiary: self.beneficiary,
+            metadata: self.metadata,
+        }
+    }
+
+    pub fn amount_in_minor(self, amount_in_minor: u64) -> CreatePayoutRequestBuilder<I, u64, C, B> {
+        CreatePayoutRequestBuilder {
+            merchant_account_id: self.merchant_account_id,
+            amount_in_minor,
+            currency: self.currency,
+            beneficiary: self.beneficiary,
+            metadata: self.metadata,
+        }
+    }
+
+    pub fn currency(self, currency: Currency) -> CreatePayoutRequestBuilder<I, A, Currency, B> {
+        CreatePayoutRequestBuilder {
+            merchant_account_id: self.merchant_account_id,
+            amount_in_minor: self.amount_in_minor,
+            currency,
+            beneficiary: self.beneficiary,
+            metadata: self.metadata,
+        }
+    }
+
+    pub fn beneficiary(
+        self,
+        beneficiary: PayoutBeneficiary,
+    ) -> CreatePayoutRequestBuilder<I, A, C, PayoutBeneficiary> {
+        CreatePayoutRequestBuilder {
+            merchant_account_id: self.merchant_account_id,
+            amount_in_minor: self.amount_in_minor,
+            currency: self.currency,
+            beneficiary,
+            metadata: self.metadata,
+        }
+    }
+
+    pub fn metadata(mut self, metadata: impl Into<Metadata>) -> Self {
+        self.metadata = Some(metadata.into());
+        self
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl CreatePayoutRequestBuilder<String, u64, Currency, PayoutBeneficiary> {
+    /// Builds the request.
+    pub fn build(self) -> CreatePayoutRequest {
+        CreatePayoutRequest {
+            merchant_account_id: self.merchant_account_id,
+            amount_in_minor: self.amount_in_minor,
+            currency: self.currency,
+            beneficiary: self.beneficiary,
+            metadata: self.metadata,
+        }
+    }
+}
+
+/// Options to configure the behaviour of [`PayoutsApi::create_batch`](crate::apis::payouts::PayoutsApi::create_batch).
+///
+/// The default is a new random batch id with at most 10 requests in flight at the same time.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    batch_id: Uuid,
+    max_concurrency: usize,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            batch_id: Uuid::new_v4(),
+            max_concurrency: 10,
+        }
+    }
+}
+
+impl BatchOptions {
+    /// Sets the batch id from which the idempotency keys of the single payouts are derived.
+    ///
+    /// Submitting the same list of payouts again with the same batch id is safe:
+    /// payouts that were already created will not be created twice.
+    pub fn with_batch_id(mut self, batch_id: Uuid) -> Self {
+        self.batch_id = batch_id;
+        self
+    }
+
+    /// Sets the maximum number of payout requests in flight at the same time.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Id of this batch. Store it to be able to resume the batch after a partial failure.
+    pub fn batch_id(&self) -> Uuid {
+        self.batch_id
+    }
+
+    /// Returns the idempotency key of the payout at the given position in the batch.
+    pub(crate) fn idempotency_key(&self, index: usize) -> Uuid {
+        Uuid::new_v5(&self.batch_id, index.to_string().as_bytes())
+    }
+
+    pub(crate) fn max_concurrency(&self) -> usize {
+        self.max_concurrency
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct CreatePayoutResponse {
-    pub id: String,
+    pub id: PayoutId,
 }
 
 #[async_trait]
 impl Pollable for CreatePayoutResponse {
     type Output = Payout;
 
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+
     async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
         tl.payouts
             .get_by_id(&self.id)
@@ -34,37 +231,204 @@ impl Pollable for CreatePayoutResponse {
     }
 }
 
+/// Account holder verification options for a payout beneficiary.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct AccountHolderVerification {
+    /// Verify that the name of the account holder matches `account_holder_name`.
+    #[serde(default)]
+    pub verify_name: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum PayoutBeneficiary {
     ExternalAccount {
         account_holder_name: String,
         account_identifier: AccountIdentifier,
         reference: String,
+        /// Checks to perform on the beneficiary account before the payout is executed.
+        verification: Option<AccountHolderVerification>,
+        /// Date of birth of the account holder, required by some schemes for international payouts.
+        date_of_birth: Option<NaiveDate>,
+        /// Address of the account holder, required for payouts outside the European Economic Area.
+        address: Option<BeneficiaryAddress>,
     },
     PaymentSource {
         user_id: String,
         payment_source_id: String,
         reference: String,
     },
+    /// Pays out to the business account registered for the merchant account.
+    BusinessAccount { reference: String },
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+impl PayoutBeneficiary {
+    /// Builds a payout to the business account registered for the merchant account.
+    pub fn business_account(reference: impl Into<String>) -> Self {
+        PayoutBeneficiary::BusinessAccount {
+            reference: reference.into(),
+        }
+    }
+
+    /// Starts building a payout to an external account.
+    ///
+    /// Use [`AccountIdentifier::iban`] for payouts identified by IBAN only.
+    pub fn external_account(
+        account_holder_name: impl Into<String>,
+        account_identifier: AccountIdentifier,
+        reference: impl Into<String>,
+    ) -> ExternalAccountBeneficiaryBuilder {
+        ExternalAccountBeneficiaryBuilder {
+            account_holder_name: account_holder_name.into(),
+            account_identifier,
+            reference: reference.into(),
+            verification: None,
+            date_of_birth: None,
+            address: None,
+        }
+    }
+}
+
+/// Builder for [`PayoutBeneficiary::ExternalAccount`] which checks that the fields
+/// required by the payment scheme are present.
+#[derive(Debug, Clone)]
+pub struct ExternalAccountBeneficiaryBuilder {
+    account_holder_name: String,
+    account_identifier: AccountIdentifier,
+    reference: String,
+    verification: Option<AccountHolderVerification>,
+    date_of_birth: Option<NaiveDate>,
+    address: Option<BeneficiaryAddress>,
+}
+
+impl ExternalAccountBeneficiaryBuilder {
+    /// Sets the account holder verification options.
+    pub fn with_verification(mut self, verification: AccountHolderVerification) -> Self {
+        self.verification = Some(verification);
+        self
+    }
+
+    /// Sets the date of birth of the account holder.
+    pub fn with_date_of_birth(mut self, date_of_birth: NaiveDate) -> Self {
+        self.date_of_birth = Some(date_of_birth);
+        self
+    }
+
+    /// Sets the address of the account holder.
+    pub fn with_address(mut self, address: BeneficiaryAddress) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    /// Builds the beneficiary.
+    ///
+    /// Fails if the account holder name or the reference are empty, or if the address is missing
+    /// for an IBAN from outside the European Economic Area.
+    pub fn build(self) -> Result<PayoutBeneficiary, PayoutBeneficiaryError> {
+        if self.account_holder_name.trim().is_empty() {
+            return Err(PayoutBeneficiaryError::EmptyAccountHolderName);
+        }
+        if self.reference.trim().is_empty() {
+            return Err(PayoutBeneficiaryError::EmptyReference);
+        }
+        if let AccountIdentifier::Iban { iban } = &self.account_identifier {
+            let country_code = iban.get(..2).unwrap_or_default();
+            if !EEA_COUNTRY_CODES.contains(&country_code) && self.address.is_none() {
+                return Err(PayoutBeneficiaryError::MissingAddress);
+            }
+        }
+
+        Ok(PayoutBeneficiary::ExternalAccount {
+            account_holder_name: self.account_holder_name,
+            account_identifier: self.account_identifier,
+            reference: self.reference,
+            verification: self.verification,
+            date_of_birth: self.date_of_birth,
+            address: self.address,
+        })
+    }
+}
+
+/// ISO 3166 codes of the countries of the European Economic Area.
+const EEA_COUNTRY_CODES: &[&str] = &[
+    "AT", "BE", "BG", "CY", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GR", "HR", "HU", "IE", "IS",
+    "IT", "LI", "LT", "LU", "LV", "MT", "NL", "NO", "PL", "PT", "RO", "SE", "SI", "SK",
+];
+
+/// Error returned when building an invalid [`PayoutBeneficiary`].
+#[derive(thiserror::Error, Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PayoutBeneficiaryError {
+    #[error("The account holder name cannot be empty")]
+    EmptyAccountHolderName,
+    #[error("The reference cannot be empty")]
+    EmptyReference,
+    #[error("The beneficiary address is required for payouts outside the European Economic Area")]
+    MissingAddress,
+}
+
+/// Address of the holder of a payout beneficiary account.
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct BeneficiaryAddress {
+    pub address_line1: String,
+    pub address_line2: Option<String>,
+    pub city: String,
+    pub state: Option<String>,
+    pub zip: String,
+    /// ISO 3166-1 alpha-2 country code.
+    pub country_code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 pub struct Payout {
-    pub id: String,
+    pub id: PayoutId,
     pub merchant_account_id: String,
     pub amount_in_minor: u64,
     pub currency: Currency,
     pub beneficiary: PayoutBeneficiary,
     pub created_at: DateTime<Utc>,
+    pub metadata: Option<Metadata>,
     #[serde(flatten)]
     pub status: PayoutStatus,
 }
 
+impl Payout {
+    /// Deserializes the metadata of the payout into a custom type.
+    pub fn metadata_as<T: DeserializeOwned>(&self) -> Result<Option<T>, serde_json::Error> {
+        self.metadata
+            .as_ref()
+            .map(Metadata::deserialize_into)
+            .transpose()
+    }
+}
+
+impl From<Payout> for CreatePayoutRequest {
+    /// Builds a request for a new payout with the same details as an existing one
+    fn from(p: Payout) -> Self {
+        CreatePayoutRequest {
+            merchant_account_id: p.merchant_account_id,
+            amount_in_minor: p.amount_in_minor,
+            currency: p.currency,
+            beneficiary: p.beneficiary,
+            metadata: p.metadata,
+        }
+    }
+}
+
 #[async_trait]
 impl Pollable for Payout {
     type Output = Payout;
 
+    fn resource_id(&self) -> &str {
+        self.id.as_str()
+    }
+
     async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
         tl.payouts
             .get_by_id(&self.id)
@@ -85,6 +449,7 @@ impl IsInTerminalState for Payout {
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "json-schema", derive(schemars::JsonSchema))]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum PayoutStatus {
     Pending,
@@ -94,6 +459,160 @@ pub enum PayoutStatus {
     },
     Failed {
         failed_at: DateTime<Utc>,
-        failure_reason: String,
+        failure_reason: FailureReason,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address() -> BeneficiaryAddress {
+        BeneficiaryAddress {
+            address_line1: "1 Bahnhofstrasse".to_string(),
+            address_line2: None,
+            city: "Zurich".to_string(),
+            state: None,
+            zip: "8001".to_string(),
+            country_code: "CH".to_string(),
+        }
+    }
+
+    #[test]
+    fn external_account_in_eea_does_not_require_address() {
+        let beneficiary = PayoutBeneficiary::external_account(
+            "Mr. Holder",
+            AccountIdentifier::iban("DE89 3704 0044 0532 0130 00").unwrap(),
+            "reference",
+        )
+        .build()
+        .unwrap();
+
+        assert!(matches!(
+            beneficiary,
+            PayoutBeneficiary::ExternalAccount { address: None, .. }
+        ));
+    }
+
+    #[test]
+    fn external_account_outside_eea_requires_address() {
+        let iban = AccountIdentifier::iban("CH93 0076 2011 6238 5295 7").unwrap();
+
+        assert_eq!(
+            PayoutBeneficiary::external_account("Mr. Holder", iban.clone(), "reference").build(),
+            Err(PayoutBeneficiaryError::MissingAddress)
+        );
+        assert!(
+            PayoutBeneficiary::external_account("Mr. Holder", iban, "reference")
+                .with_address(address())
+                .with_date_of_birth(NaiveDate::from_ymd_opt(1990, 1, 31).unwrap())
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn external_account_requires_name_and_reference() {
+        let iban = AccountIdentifier::iban("DE89370400440532013000").unwrap();
+
+        assert_eq!(
+            PayoutBeneficiary::external_account(" ", iban.clone(), "reference").build(),
+            Err(PayoutBeneficiaryError::EmptyAccountHolderName)
+        );
+        assert_eq!(
+            PayoutBeneficiary::external_account("Mr. Holder", iban, "").build(),
+            Err(PayoutBeneficiaryError::EmptyReference)
+        );
+    }
+
+    #[test]
+    fn external_account_must_support_currency() {
+        let request = |currency, account_identifier| {
+            CreatePayoutRequest::builder()
+                .merchant_account_id("merchant-account-id")
+                .amount_in_minor(100)
+                .currency(currency)
+                .beneficiary(
+                    PayoutBeneficiary::external_account("Mr. Holder", account_identifier, "ref")
+                        .build()
+                        .unwrap(),
+                )
+                .build()
+        };
+        let nrb = AccountIdentifier::nrb("12345678901234567890123456").unwrap();
+        let bban = AccountIdentifier::bban("12345678901").unwrap();
+
+        assert_eq!(request(Currency::Pln, nrb.clone()).validate(), Ok(()));
+        assert_eq!(request(Currency::Nok, bban.clone()).validate(), Ok(()));
+        assert_eq!(
+            request(Currency::Eur, nrb).validate(),
+            Err(PayoutRequestError::UnsupportedBeneficiaryAccount {
+                account_identifier: "NRB",
+                currency: Currency::Eur,
+            })
+        );
+        assert_eq!(
+            request(Currency::Pln, bban).validate(),
+            Err(PayoutRequestError::UnsupportedBeneficiaryAccount {
+                account_identifier: "BBAN",
+                currency: Currency::Pln,
+            })
+        );
+    }
+
+    #[test]
+    fn business_account_serialization() {
+        assert_eq!(
+            serde_json::to_value(PayoutBeneficiary::business_account("reference")).unwrap(),
+            serde_json::json!({
+                "type": "business_account",
+                "reference": "reference"
+            })
+        );
+    }
+
+    #[test]
+    fn create_payout_request_builder() {
+        let beneficiary = PayoutBeneficiary::PaymentSource {
+            user_id: "user-id".to_string(),
+            payment_source_id: "payment-source-id".to_string(),
+            reference: "reference".to_string(),
+        };
+        let request = CreatePayoutRequest::builder()
+            .beneficiary(beneficiary.clone())
+            .amount_in_minor(100)
+            .merchant_account_id("merchant-account-id")
+            .currency(Currency::Gbp)
+            .build();
+
+        assert_eq!(request.merchant_account_id, "merchant-account-id");
+        assert_eq!(request.amount_in_minor, 100);
+        assert_eq!(request.currency, Currency::Gbp);
+        assert_eq!(request.beneficiary, beneficiary);
+        assert_eq!(request.metadata, None);
+    }
+
+    #[test]
+    fn payout_converts_into_request_for_the_same_payout() {
+        let payout = Payout {
+            id: "payout-id".into(),
+            merchant_account_id: "merchant-account-id".to_string(),
+            amount_in_minor: 100,
+            currency: Currency::Gbp,
+            beneficiary: PayoutBeneficiary::business_account("reference"),
+            created_at: Utc::now(),
+            metadata: None,
+            status: PayoutStatus::Pending,
+        };
+
+        assert_eq!(
+            CreatePayoutRequest::from(payout),
+            CreatePayoutRequest::builder()
+                .merchant_account_id("merchant-account-id")
+                .amount_in_minor(100)
+                .currency(Currency::Gbp)
+                .beneficiary(PayoutBeneficiary::business_account("reference"))
+                .build()
+        );
+    }
+}
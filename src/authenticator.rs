@@ -1,21 +1,39 @@
 use crate::{
-    apis::auth::{AccessToken, AuthenticationResult, Credentials},
+    apis::auth::{
+        AccessToken, AccessTokenProvider, AuthenticationResult, Credentials, TokenSource,
+        TokenStatus,
+    },
+    common::{join_url, DecodeJson},
     error::Error,
 };
 use chrono::{Duration, Utc};
 use reqwest::Url;
 use reqwest_middleware::ClientWithMiddleware;
-use tokio::sync::{mpsc, oneshot};
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+use tokio::sync::Mutex;
+
+/// Time before expiration at which access tokens are refreshed, unless configured otherwise.
+pub(crate) const DEFAULT_REFRESH_MARGIN: std::time::Duration =
+    std::time::Duration::from_secs(10 * 60);
 
 /// Manager for credentials and access tokens.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Authenticator {
-    tx: mpsc::UnboundedSender<oneshot::Sender<Result<AuthenticationResult, Error>>>,
+    state: Arc<Mutex<AuthenticatorState>>,
+    refresh_margin: std::time::Duration,
+    provider: Option<Arc<dyn AccessTokenProvider>>,
     pub(crate) client_id: String,
 }
 
 impl Authenticator {
-    /// Starts a new authenticator with the given initial credentials.
+    /// Creates a new authenticator with the given initial credentials.
+    ///
+    /// No request is sent until an access token is first needed. Tokens are refreshed by
+    /// the callers of [`get_access_token()`](Self::get_access_token) themselves: no background
+    /// task is spawned, and the state is released with the last clone of the authenticator.
     pub fn new(client: ClientWithMiddleware, auth_url: Url, credentials: Credentials) -> Self {
         let state = AuthenticatorState {
             client,
@@ -24,24 +42,29 @@ impl Authenticator {
             access_token: None,
         };
 
-        // Spawn a long running task which will running forever until the authenticator is dropped
-        let (tx, rx) = mpsc::unbounded_channel();
-        #[cfg(test)]
-        tests::mocked_time::spawn(async move {
-            // We need to propagate the mocked time task-local in order to control time in the tests
-            process_loop(state, rx).await;
-        });
-        #[cfg(not(test))]
-        tokio::spawn(async move {
-            process_loop(state, rx).await;
-        });
-
         Self {
-            tx,
+            state: Arc::new(Mutex::new(state)),
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+            provider: None,
             client_id: credentials.client_id().into(),
         }
     }
 
+    /// Sets how long before its expiration an access token is refreshed.
+    ///
+    /// The margin is capped at half the lifetime of each token, so that a token
+    /// is never considered expired as soon as it is obtained.
+    pub fn with_refresh_margin(mut self, refresh_margin: std::time::Duration) -> Self {
+        self.refresh_margin = refresh_margin;
+        self
+    }
+
+    /// Gets access tokens from the given provider instead of the authentication server.
+    pub fn with_access_token_provider(mut self, provider: Arc<dyn AccessTokenProvider>) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
     /// Returns the current access token used for authentication against the TrueLayer APIs.
     /// If there's no access token available, or the available one has expired, a new one will be requested from the server
     /// using the provided credentials. If the server returns a refresh token, such token will be automatically
@@ -51,10 +74,73 @@ impl Authenticator {
     ///
     /// If the client is already authenticated, this is a no-op.
     pub async fn get_access_token(&self) -> Result<AuthenticationResult, Error> {
-        let (tx, rx) = oneshot::channel();
-        self.tx.send(tx).unwrap();
+        self.get_access_token_valid_for(std::time::Duration::ZERO)
+            .await
+    }
+
+    /// Like [`get_access_token()`](Self::get_access_token), but also refreshes the cached token
+    /// if it expires within `validity`, e.g. the time a request may take to complete.
+    pub async fn get_access_token_valid_for(
+        &self,
+        validity: std::time::Duration,
+    ) -> Result<AuthenticationResult, Error> {
+        let margin = validity.max(self.refresh_margin);
+
+        // Holding the lock for the whole refresh makes concurrent callers wait for it
+        // and then reuse the freshly obtained token
+        let mut state = self.state.lock().await;
+
+        process_get_access_token(&mut state, margin, self.provider.as_deref()).await
+    }
+
+    /// Replaces the credentials used to request access tokens, e.g. after a secret has been rotated.
+    ///
+    /// The cached access token is discarded, so that the next request authenticates
+    /// with the new credentials. Requests already in flight are not affected.
+    /// Waits for any refresh in progress to complete.
+    ///
+    /// The new credentials must belong to the same client id.
+    pub async fn update_credentials(&self, credentials: Credentials) -> Result<(), Error> {
+        if credentials.client_id() != self.client_id {
+            return Err(Error::Other(anyhow::anyhow!(
+                "Cannot update credentials of client id {} with credentials of client id {}",
+                self.client_id,
+                credentials.client_id()
+            )));
+        }
+
+        let mut state = self.state.lock().await;
+        state.credentials = credentials;
+        state.access_token = None;
+
+        tracing::info!(target: "truelayer_rust::auth", "Credentials updated");
+
+        Ok(())
+    }
+
+    /// Returns the state of the cached access token, without requesting a new one.
+    ///
+    /// Waits for any refresh in progress to complete.
+    pub async fn token_status(&self) -> TokenStatus {
+        let state = self.state.lock().await;
+
+        match &state.access_token {
+            None => TokenStatus::Missing,
+            Some(token) => TokenStatus::Cached {
+                obtained_at: token.obtained_at,
+                expires_at: token.expires_at,
+                scopes: token.scopes.clone(),
+                refresh_due: should_refresh_token(token, self.refresh_margin),
+            },
+        }
+    }
+}
 
-        rx.await.unwrap()
+impl Debug for Authenticator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Authenticator")
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
     }
 }
 
@@ -66,45 +152,53 @@ struct AuthenticatorState {
     access_token: Option<AccessToken>,
 }
 
-async fn process_loop(
-    mut state: AuthenticatorState,
-    mut rx: mpsc::UnboundedReceiver<oneshot::Sender<Result<AuthenticationResult, Error>>>,
-) {
-    // Infinite loop waiting for commands from the main client
-    while let Some(reply) = rx.recv().await {
-        if reply
-            .send(process_get_access_token(&mut state).await)
-            .is_err()
-        {
-            tracing::warn!("Receiver dropped before the reply");
-        }
-    }
-}
-
-#[tracing::instrument(name = "Get Access Token", level = "debug", skip(state))]
+#[tracing::instrument(
+    target = "truelayer_rust::auth",
+    name = "Get Access Token",
+    level = "debug",
+    skip(state, provider)
+)]
 async fn process_get_access_token(
     state: &mut AuthenticatorState,
+    margin: std::time::Duration,
+    provider: Option<&dyn AccessTokenProvider>,
 ) -> Result<AuthenticationResult, Error> {
     // If we are already authenticated, do nothing
     if let Some(token) = &state.access_token {
-        if !should_refresh_token(token) {
-            tracing::debug!("Reusing existing access token");
+        if !should_refresh_token(token, margin) {
+            tracing::debug!(target: "truelayer_rust::auth", "Reusing existing access token");
             return Ok(AuthenticationResult {
                 access_token: token.clone(),
                 refresh_token: state.credentials.refresh_token().cloned(),
+                source: TokenSource::Cache,
             });
         }
     }
 
+    // Tokens injected by a provider replace the whole exchange with the auth server
+    if let Some(provider) = provider {
+        let mut token = provider.get_access_token().await?;
+        token.obtained_at = now();
+        state.access_token = Some(token.clone());
+
+        tracing::info!(target: "truelayer_rust::auth", "Got new access token from provider");
+
+        return Ok(AuthenticationResult {
+            access_token: token,
+            refresh_token: None,
+            source: TokenSource::Provider,
+        });
+    }
+
     // Post to the auth server with the current credentials.
     // This will use whatever authentication method the user set up.
     let res: RawAuthenticationResponse = state
         .client
-        .post(state.auth_url.join("/connect/token").unwrap())
+        .post(join_url(&state.auth_url, "/connect/token"))
         .json(&state.credentials)
         .send()
         .await?
-        .json()
+        .decode_json()
         .await?;
 
     if res.token_type != "Bearer" {
@@ -115,13 +209,22 @@ async fn process_get_access_token(
     }
 
     // Store the access token
+    let obtained_at = now();
     let token = AccessToken {
         token: res.access_token.into(),
-        expires_at: Some(now() + Duration::seconds(res.expires_in)),
+        expires_at: Some(obtained_at + Duration::seconds(res.expires_in)),
+        obtained_at,
+        scopes: res
+            .scope
+            .as_deref()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
     };
     state.access_token = Some(token.clone());
 
-    tracing::info!("Got new access token");
+    tracing::info!(target: "truelayer_rust::auth", "Got new access token");
 
     // If a refresh token has been provided, use that for subsequent refreshes of the access token
     if let Some(refresh_token) = &res.refresh_token {
@@ -131,20 +234,28 @@ async fn process_get_access_token(
             refresh_token: refresh_token.clone().into(),
         };
 
-        tracing::info!("Switching to refresh token for subsequent authentication requests");
+        tracing::info!(
+            target: "truelayer_rust::auth",
+            "Switching to refresh token for subsequent authentication requests"
+        );
     }
 
     Ok(AuthenticationResult {
         access_token: token,
         refresh_token: res.refresh_token.map(Token::from),
+        source: TokenSource::Exchange,
     })
 }
 
-/// Returns `true` if the token is close to expiration (10 minutes before actual expiration)
+/// Returns `true` if the token expires within `margin` (capped at half its lifetime)
 /// and should be refreshed. If this token does not expire, this function always returns `false`.
-fn should_refresh_token(token: &AccessToken) -> bool {
-    token.expires_at.map_or(false, |expires_at| {
-        now() >= expires_at - Duration::minutes(10)
+fn should_refresh_token(token: &AccessToken, margin: std::time::Duration) -> bool {
+    token.expires_at.is_some_and(|expires_at| {
+        let half_lifetime = ((expires_at - token.obtained_at) / 2)
+            .to_std()
+            .unwrap_or_default();
+        let margin = Duration::from_std(margin.min(half_lifetime)).unwrap();
+        now() >= expires_at - margin
     })
 }
 
@@ -164,6 +275,8 @@ struct RawAuthenticationResponse {
     expires_in: i64,
     refresh_token: Option<String>,
     token_type: String,
+    /// Space separated list of granted scopes.
+    scope: Option<String>,
 }
 
 #[cfg(test)]
@@ -184,14 +297,16 @@ mod tests {
             future::Future,
             sync::{Arc, Mutex},
         };
-        use tokio::task::JoinHandle;
 
         tokio::task_local! {
             static MOCKED_NOW: Arc<Mutex<DateTime<Utc>>>;
         }
 
+        /// Returns the mocked time, or the real time outside of a [`scope`].
         pub fn now() -> DateTime<Utc> {
-            MOCKED_NOW.with(|now| *now.lock().unwrap())
+            MOCKED_NOW
+                .try_with(|now| *now.lock().unwrap())
+                .unwrap_or_else(|_| Utc::now())
         }
 
         pub fn set_now(new_now: DateTime<Utc>) {
@@ -207,15 +322,15 @@ mod tests {
                 .await
         }
 
-        pub fn spawn<F>(fut: F) -> JoinHandle<F::Output>
+        /// Wraps a future so that it sees the same mocked time as the caller, even when spawned.
+        pub fn propagate<F>(fut: F) -> impl Future<Output = F::Output>
         where
-            F: Future + Send + 'static,
-            F::Output: Send + 'static,
+            F: Future,
         {
             let arc = MOCKED_NOW
                 .try_with(|now| now.clone())
                 .unwrap_or_else(|_| Arc::new(Mutex::new(Utc::now())));
-            tokio::spawn(async move { MOCKED_NOW.scope(arc, fut).await })
+            MOCKED_NOW.scope(arc, fut)
         }
     }
 
@@ -235,6 +350,7 @@ mod tests {
                 "token_type": "Bearer",
                 "access_token": format!("{}-{}", MOCK_ACCESS_TOKEN, i),
                 "expires_in": 3600,
+                "scope": "payments paydirect",
                 "refresh_token": if include_refresh_token { Some(MOCK_REFRESH_TOKEN) } else { None }
             }))
         }
@@ -393,6 +509,103 @@ mod tests {
         .await;
     }
 
+    #[tokio::test]
+    async fn token_status_does_not_request_tokens() {
+        mocked_time::scope(Utc::now(), async move {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/connect/token"))
+                .respond_with(mock_response(false))
+                .expect(1) // Expect exactly one call
+                .mount(&mock_server)
+                .await;
+
+            let authenticator = mock_authenticator(&mock_server.uri());
+            assert_eq!(authenticator.token_status().await, TokenStatus::Missing);
+
+            let fresh = authenticator.get_access_token().await.unwrap();
+            let cached = authenticator.get_access_token().await.unwrap();
+            assert_eq!(fresh.source(), TokenSource::Exchange);
+            assert_eq!(cached.source(), TokenSource::Cache);
+            assert_eq!(fresh.scopes(), ["payments", "paydirect"]);
+
+            // Fast forward time until the token should be refreshed
+            mocked_time::set_now(fresh.expires_at().unwrap());
+
+            assert_eq!(
+                authenticator.token_status().await,
+                TokenStatus::Cached {
+                    obtained_at: fresh.access_token().obtained_at(),
+                    expires_at: fresh.expires_at(),
+                    scopes: vec!["payments".to_string(), "paydirect".to_string()],
+                    refresh_due: true
+                }
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn updated_credentials_are_used_for_next_token() {
+        mocked_time::scope(Utc::now(), async move {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/connect/token"))
+                .and(body_partial_json(json!({
+                    "client_id": MOCK_CLIENT_ID,
+                    "client_secret": MOCK_CLIENT_SECRET
+                })))
+                .respond_with(mock_response(false))
+                .expect(1)
+                .named("Old secret mock")
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("POST"))
+                .and(path("/connect/token"))
+                .and(body_partial_json(json!({
+                    "client_id": MOCK_CLIENT_ID,
+                    "client_secret": "rotated-secret"
+                })))
+                .respond_with(mock_response(false))
+                .expect(1)
+                .named("Rotated secret mock")
+                .mount(&mock_server)
+                .await;
+
+            let authenticator = mock_authenticator(&mock_server.uri());
+            authenticator.get_access_token().await.unwrap();
+
+            authenticator
+                .update_credentials(Credentials::ClientCredentials {
+                    client_id: MOCK_CLIENT_ID.into(),
+                    client_secret: "rotated-secret".into(),
+                    scope: "mock".into(),
+                })
+                .await
+                .unwrap();
+            assert_eq!(authenticator.token_status().await, TokenStatus::Missing);
+
+            let res = authenticator.get_access_token().await.unwrap();
+            assert_eq!(res.source(), TokenSource::Exchange);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn credentials_of_another_client_id_are_rejected() {
+        let authenticator = mock_authenticator("http://localhost");
+
+        let res = authenticator
+            .update_credentials(Credentials::ClientCredentials {
+                client_id: "another-client-id".into(),
+                client_secret: MOCK_CLIENT_SECRET.into(),
+                scope: "mock".into(),
+            })
+            .await;
+
+        assert!(matches!(res, Err(Error::Other(_))));
+    }
+
     #[tokio::test]
     async fn concurrent_requests_are_batched() {
         // Setup mock server
@@ -411,10 +624,9 @@ mod tests {
         let mut handles = Vec::new();
         for _ in 0..100 {
             let authenticator_clone = authenticator.clone();
-            let handle =
-                mocked_time::spawn(
-                    async move { authenticator_clone.get_access_token().await.unwrap() },
-                );
+            let handle = tokio::spawn(mocked_time::propagate(async move {
+                authenticator_clone.get_access_token().await.unwrap()
+            }));
             handles.push(handle);
         }
         let results = futures::future::join_all(handles)
@@ -431,4 +643,115 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    async fn token_is_refreshed_if_it_expires_within_validity() {
+        mocked_time::scope(Utc::now(), async move {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/connect/token"))
+                .respond_with(mock_response(false))
+                .expect(2) // Expect exactly two calls
+                .mount(&mock_server)
+                .await;
+
+            let authenticator = mock_authenticator(&mock_server.uri())
+                .with_refresh_margin(std::time::Duration::from_secs(60));
+            let res = authenticator.get_access_token().await.unwrap();
+
+            // 5 minutes before expiration the token is reused, unless it must last longer
+            mocked_time::set_now(res.access_token.expires_at().unwrap() - Duration::minutes(5));
+            let cached = authenticator
+                .get_access_token_valid_for(std::time::Duration::from_secs(60))
+                .await
+                .unwrap();
+            assert_eq!(cached.source(), TokenSource::Cache);
+
+            let refreshed = authenticator
+                .get_access_token_valid_for(std::time::Duration::from_secs(10 * 60))
+                .await
+                .unwrap();
+            assert_eq!(refreshed.source(), TokenSource::Exchange);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn refresh_margin_is_capped_at_half_the_token_lifetime() {
+        mocked_time::scope(Utc::now(), async move {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/connect/token"))
+                .respond_with(mock_response(false))
+                .expect(1) // Expect exactly one call
+                .mount(&mock_server)
+                .await;
+
+            // The margin is longer than the 1 hour lifetime of the token
+            let authenticator = mock_authenticator(&mock_server.uri())
+                .with_refresh_margin(std::time::Duration::from_secs(2 * 60 * 60));
+
+            authenticator.get_access_token().await.unwrap();
+            let res = authenticator.get_access_token().await.unwrap();
+
+            assert_eq!(res.source(), TokenSource::Cache);
+        })
+        .await;
+    }
+
+    #[derive(Debug, Default)]
+    struct CountingProvider {
+        calls: AtomicU32,
+    }
+
+    #[async_trait::async_trait]
+    impl AccessTokenProvider for CountingProvider {
+        async fn get_access_token(&self) -> Result<AccessToken, Error> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(AccessToken::new(
+                format!("{}-{}", MOCK_ACCESS_TOKEN, n),
+                Some(now() + Duration::hours(1)),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn access_token_provider_replaces_auth_server() {
+        mocked_time::scope(Utc::now(), async move {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/connect/token"))
+                .respond_with(mock_response(false))
+                .expect(0) // The auth server must never be called
+                .mount(&mock_server)
+                .await;
+
+            let provider = Arc::new(CountingProvider::default());
+            let authenticator =
+                mock_authenticator(&mock_server.uri()).with_access_token_provider(provider.clone());
+
+            let res1 = authenticator.get_access_token().await.unwrap();
+            let res2 = authenticator.get_access_token().await.unwrap();
+            assert_eq!(res1.source(), TokenSource::Provider);
+            assert_eq!(res2.source(), TokenSource::Cache);
+            assert_eq!(
+                res1.access_token.expose_secret(),
+                format!("{}-0", MOCK_ACCESS_TOKEN)
+            );
+            assert!(res1.refresh_token.is_none());
+            assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+
+            // The provider is called again once the token is about to expire
+            mocked_time::set_now(res1.access_token.expires_at().unwrap());
+
+            let res3 = authenticator.get_access_token().await.unwrap();
+            assert_eq!(res3.source(), TokenSource::Provider);
+            assert_eq!(
+                res3.access_token.expose_secret(),
+                format!("{}-1", MOCK_ACCESS_TOKEN)
+            );
+            assert_eq!(provider.calls.load(Ordering::SeqCst), 2);
+        })
+        .await;
+    }
 }
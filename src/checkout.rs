@@ -0,0 +1,363 @@
+//! One-stop flow to take a payment through the TrueLayer Hosted Payments Page.
+//!
+//! A [`CheckoutSession`] creates the payment, builds the link to the Hosted Payments Page
+//! and waits for the payment to either succeed or fail:
+//!
+//! ```rust,no_run
+//! # use truelayer_rust::{TrueLayerClient, apis::payments::*, checkout::*};
+//! #
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let tl: TrueLayerClient = unreachable!();
+//! let session = CheckoutSession::start(
+//!     &tl,
+//!     CheckoutParams {
+//!         amount_in_minor: 100,
+//!         currency: Currency::Gbp,
+//!         beneficiary: Beneficiary::MerchantAccount {
+//!             merchant_account_id: "some-merchant-account-id".to_string(),
+//!             account_holder_name: None,
+//!             reference: None,
+//!             statement_reference: None,
+//!             verification: None,
+//!         },
+//!         user: CreatePaymentUserRequest::new_user()
+//!             .with_name("Some One")
+//!             .with_email("some.one@email.com")
+//!             .build(),
+//!         return_uri: "https://my.website/checkout/done".to_string(),
+//!         metadata: None,
+//!     },
+//! )
+//! .await?;
+//!
+//! println!("Redirect the user to {}", session.hpp_url());
+//!
+//! match session.await_outcome().await? {
+//!     CheckoutOutcome::Succeeded { .. } => println!("Paid!"),
+//!     CheckoutOutcome::Failed { failure_reason, .. } => println!("Failed: {}", failure_reason),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    apis::payments::{
+        Beneficiary, CreatePaymentRequest, CreatePaymentResponse, CreatePaymentUserRequest,
+        Currency, FailureReason, FailureStage, Metadata, Payment, PaymentId, PaymentMethodRequest,
+        PaymentStatus, ProviderSelectionRequest,
+    },
+    pollable::{PollError, PollOptions, Pollable},
+    Error, TrueLayerClient,
+};
+use chrono::{DateTime, Utc};
+use retry_policies::RetryPolicy;
+use serde::Deserialize;
+use url::Url;
+
+/// Parameters of the payment taken by a [`CheckoutSession`].
+///
+/// The user picks their provider on the Hosted Payments Page.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CheckoutParams {
+    pub amount_in_minor: u64,
+    pub currency: Currency,
+    pub beneficiary: Beneficiary,
+    pub user: CreatePaymentUserRequest,
+    /// Where the user is sent back after the payment. Must be configured in your TrueLayer console.
+    pub return_uri: String,
+    pub metadata: Option<Metadata>,
+}
+
+/// Final outcome of a checkout.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum CheckoutOutcome {
+    /// The payment was executed by the provider.
+    Succeeded { executed_at: DateTime<Utc> },
+    /// The payment failed and will not be executed.
+    Failed {
+        failed_at: DateTime<Utc>,
+        failure_stage: FailureStage,
+        failure_reason: FailureReason,
+    },
+}
+
+impl CheckoutOutcome {
+    /// Returns the outcome of a payment, or `None` if the payment is not in a terminal state yet.
+    pub fn from_payment(payment: &Payment) -> Option<Self> {
+        match &payment.status {
+            PaymentStatus::Executed { executed_at, .. }
+            | PaymentStatus::Settled { executed_at, .. } => Some(CheckoutOutcome::Succeeded {
+                executed_at: *executed_at,
+            }),
+            PaymentStatus::Failed {
+                failed_at,
+                failure_stage,
+                failure_reason,
+                ..
+            } => Some(CheckoutOutcome::Failed {
+                failed_at: *failed_at,
+                failure_stage: failure_stage.clone(),
+                failure_reason: failure_reason.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// A payment in progress through the Hosted Payments Page.
+#[derive(Debug, Clone)]
+pub struct CheckoutSession {
+    client: TrueLayerClient,
+    payment: CreatePaymentResponse,
+    hpp_url: Url,
+}
+
+impl CheckoutSession {
+    /// Creates the payment and builds the link to the Hosted Payments Page the user should be sent to.
+    #[tracing::instrument(target = "truelayer_rust::checkout", name = "Start Checkout", skip_all)]
+    pub async fn start(client: &TrueLayerClient, params: CheckoutParams) -> Result<Self, Error> {
+        let payment = client
+            .payments
+            .create(&CreatePaymentRequest {
+                amount_in_minor: params.amount_in_minor,
+                currency: params.currency,
+                payment_method: PaymentMethodRequest::BankTransfer {
+                    provider_selection: ProviderSelectionRequest::UserSelected {
+                        filter: None,
+                        scheme_selection: None,
+                    },
+                    beneficiary: params.beneficiary,
+                },
+                user: params.user,
+                metadata: params.metadata,
+                schedule: None,
+                related_products: None,
+                risk_assessment: None,
+            })
+            .await?;
+
+        let hpp_url = client
+            .payments
+            .get_hosted_payments_page_link(&payment.id, &payment.resource_token, &params.return_uri)
+            .await;
+
+        Ok(Self {
+            client: client.clone(),
+            payment,
+            hpp_url,
+        })
+    }
+
+    /// Id of the payment.
+    pub fn payment_id(&self) -> &PaymentId {
+        &self.payment.id
+    }
+
+    /// Response returned when the payment was created, including the resource token.
+    pub fn payment(&self) -> &CreatePaymentResponse {
+        &self.payment
+    }
+
+    /// Link to the Hosted Payments Page for this payment.
+    pub fn hpp_url(&self) -> &Url {
+        &self.hpp_url
+    }
+
+    /// Polls the payment until it succeeds or fails, with the default [`PollOptions`].
+    pub async fn await_outcome(&self) -> Result<CheckoutOutcome, PollError> {
+        self.await_outcome_with(PollOptions::default()).await
+    }
+
+    /// Polls the payment until it succeeds or fails.
+    pub async fn await_outcome_with<R>(
+        &self,
+        poll_options: PollOptions<R>,
+    ) -> Result<CheckoutOutcome, PollError>
+    where
+        R: RetryPolicy + Send + Sync,
+    {
+        let payment = self
+            .payment
+            .poll_until(&self.client, poll_options, |p| {
+                CheckoutOutcome::from_payment(p).is_some()
+            })
+            .await?;
+
+        // The predicate guarantees the payment is in a terminal state
+        Ok(CheckoutOutcome::from_payment(&payment).expect("payment in terminal state"))
+    }
+
+    /// Extracts the outcome of this checkout from the JSON body of a webhook, instead of polling.
+    ///
+    /// Returns `None` if the event is about another payment or does not carry a final outcome.
+    pub fn outcome_from_event(&self, event: &serde_json::Value) -> Option<CheckoutOutcome> {
+        #[derive(Deserialize)]
+        struct PaymentEvent {
+            r#type: String,
+            payment_id: String,
+            executed_at: Option<DateTime<Utc>>,
+            failed_at: Option<DateTime<Utc>>,
+            failure_stage: Option<FailureStage>,
+            failure_reason: Option<FailureReason>,
+        }
+
+        let event = PaymentEvent::deserialize(event).ok()?;
+        if event.payment_id != self.payment.id {
+            return None;
+        }
+
+        match event.r#type.as_str() {
+            "payment_executed" | "payment_settled" => Some(CheckoutOutcome::Succeeded {
+                executed_at: event.executed_at?,
+            }),
+            "payment_failed" => Some(CheckoutOutcome::Failed {
+                failed_at: event.failed_at?,
+                failure_stage: event.failure_stage?,
+                failure_reason: event.failure_reason?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        apis::{
+            auth::{Credentials, Token},
+            payments::{CreatePaymentStatus, CreatePaymentUserResponse},
+        },
+        client::Environment,
+    };
+    use chrono::TimeZone;
+    use serde_json::json;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    async fn mock_session() -> (CheckoutSession, MockServer) {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_environment(Environment::from_single_url(
+            &Url::parse(&mock_server.uri()).unwrap(),
+        ))
+        .build();
+
+        let session = CheckoutSession {
+            client,
+            payment: CreatePaymentResponse {
+                id: "payment-id".into(),
+                resource_token: Token::new("resource-token").into(),
+                user: CreatePaymentUserResponse {
+                    id: "user-id".to_string(),
+                },
+                status: CreatePaymentStatus::AuthorizationRequired,
+            },
+            hpp_url: Url::parse("https://hpp.example.com").unwrap(),
+        };
+
+        (session, mock_server)
+    }
+
+    fn event(r#type: &str, mut body: serde_json::Value) -> serde_json::Value {
+        body["type"] = r#type.into();
+        body["event_id"] = "event-id".into();
+        body["event_version"] = 1.into();
+        body
+    }
+
+    #[tokio::test]
+    async fn await_outcome_polls_until_terminal_state() {
+        let (session, mock_server) = mock_session().await;
+        let executed_at = Utc.with_ymd_and_hms(2022, 1, 1, 12, 0, 0).unwrap();
+
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "payment-id",
+                "amount_in_minor": 100,
+                "currency": "GBP",
+                "user": { "id": "user-id" },
+                "payment_method": {
+                    "type": "bank_transfer",
+                    "provider_selection": { "type": "user_selected" },
+                    "beneficiary": {
+                        "type": "merchant_account",
+                        "merchant_account_id": "merchant-account-id"
+                    }
+                },
+                "created_at": executed_at,
+                "status": "executed",
+                "executed_at": executed_at
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        assert_eq!(
+            session.await_outcome().await.unwrap(),
+            CheckoutOutcome::Succeeded { executed_at }
+        );
+    }
+
+    #[tokio::test]
+    async fn outcome_from_payment_failed_event() {
+        let (session, _mock_server) = mock_session().await;
+        let failed_at = Utc.with_ymd_and_hms(2022, 1, 1, 12, 0, 0).unwrap();
+
+        assert_eq!(
+            session.outcome_from_event(&event(
+                "payment_failed",
+                json!({
+                    "payment_id": "payment-id",
+                    "failed_at": failed_at,
+                    "failure_stage": "authorizing",
+                    "failure_reason": "authorization_failed"
+                })
+            )),
+            Some(CheckoutOutcome::Failed {
+                failed_at,
+                failure_stage: FailureStage::Authorizing,
+                failure_reason: FailureReason::AuthorizationFailed
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn unrelated_events_are_ignored() {
+        let (session, _mock_server) = mock_session().await;
+
+        assert_eq!(
+            session.outcome_from_event(&event(
+                "payment_executed",
+                json!({ "payment_id": "another-payment-id", "executed_at": Utc::now() })
+            )),
+            None
+        );
+        assert_eq!(
+            session.outcome_from_event(&event(
+                "payment_authorized",
+                json!({ "payment_id": "payment-id" })
+            )),
+            None
+        );
+    }
+}
@@ -3,32 +3,61 @@
 
 use crate::{
     apis::{
-        auth::{AuthApi, Credentials},
-        merchant_accounts::MerchantAccountsApi,
-        payments::PaymentsApi,
-        payments_providers::PaymentsProvidersApi,
-        payouts::PayoutsApi,
+        auth::{AccessTokenProvider, AuthApi, Credentials},
         TrueLayerClientInner,
     },
-    authenticator::Authenticator,
+    authenticator::{Authenticator, DEFAULT_REFRESH_MARGIN},
     common::{
-        DEFAULT_AUTH_URL, DEFAULT_HOSTED_PAYMENTS_PAGE_URL, DEFAULT_PAYMENTS_URL,
+        join_url, DEFAULT_AUTH_URL, DEFAULT_HOSTED_PAYMENTS_PAGE_URL, DEFAULT_PAYMENTS_URL,
         DEFAULT_SANDBOX_AUTH_URL, DEFAULT_SANDBOX_HOSTED_PAYMENTS_PAGE_URL,
         DEFAULT_SANDBOX_PAYMENTS_URL,
     },
     middlewares::{
         authentication::AuthenticationMiddleware,
+        correlation_id::CorrelationIdMiddleware,
+        default_headers::DefaultHeadersMiddleware,
         error_handling::ErrorHandlingMiddleware,
-        inject_user_agent::InjectUserAgentMiddleware,
-        retry_idempotent::{DynRetryPolicy, RetryIdempotentMiddleware},
-        signing::SigningMiddleware,
+        inject_user_agent::{build_user_agent, InjectUserAgentMiddleware},
+        request_options::RequestOptionsMiddleware,
+        retry_idempotent::{DynRetryPolicy, RetryIdempotentMiddleware, RetryObserver},
+        scrubbed_tracing::{ScrubbedSpanBackend, UrlScrubberMiddleware},
+        signing::{PrivateKey, SigningMiddleware},
+        url_rewriter::{UrlRewriter, UrlRewriterMiddleware},
     },
+    retry::{RetryBudget, RetryEvent},
+    runtime::Runtime,
+    url_scrubber::UrlScrubber,
+    validation::RequestValidation,
+    BuildError, Error, IdempotentRequest, RequestOptions,
 };
-use reqwest::Url;
-use reqwest_middleware::ClientWithMiddleware;
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Url,
+};
+use reqwest_middleware::{ClientWithMiddleware, Middleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryPolicy};
 use reqwest_tracing::TracingMiddleware;
-use std::sync::Arc;
+use secrecy::{ExposeSecret, Secret};
+use serde::de::DeserializeOwned;
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+    time::Duration,
+};
+
+#[cfg(feature = "data")]
+use crate::apis::data::DataApi;
+#[cfg(feature = "mandates")]
+use crate::apis::mandates::MandatesApi;
+#[cfg(feature = "merchant-accounts")]
+use crate::apis::merchant_accounts::MerchantAccountsApi;
+#[cfg(feature = "payouts")]
+use crate::apis::payouts::PayoutsApi;
+#[cfg(feature = "payments")]
+use crate::apis::{
+    payments::PaymentsApi,
+    payments_providers::{PaymentsProvidersApi, ProviderCache},
+};
 
 /// Client for TrueLayer public APIs.
 ///
@@ -60,6 +89,11 @@ use std::sync::Arc;
 /// To change the retry policy (or to disable automatic retries entirely), use [`with_retry_policy()`]
 /// when building a new client.
 ///
+/// Each retry emits a `tracing` event with the attempt number, the wait duration, the idempotency key
+/// and the class of the failure. The same information can be received in a callback registered with
+/// [`with_retry_observer()`]. If the request still fails, [`ApiError::retries`] tells how many
+/// times it was retried.
+///
 /// ## Request signature
 ///
 /// Some endpoints that have notable side effects (like creating a new payment) require [requests signatures].
@@ -70,6 +104,8 @@ use std::sync::Arc;
 /// [`Credentials`]: crate::apis::auth::Credentials
 /// [`get_access_token()`]: crate::apis::auth::AuthApi::get_access_token
 /// [`with_retry_policy()`]: crate::client::TrueLayerClientBuilder::with_retry_policy
+/// [`with_retry_observer()`]: crate::client::TrueLayerClientBuilder::with_retry_observer
+/// [`ApiError::retries`]: crate::error::ApiError::retries
 /// [`with_signing_key()`]: crate::client::TrueLayerClientBuilder::with_signing_key
 /// [RFC 7231]: https://datatracker.ietf.org/doc/html/rfc7231#section-4.2.2
 /// [idempotency keys]: https://docs.truelayer.com/docs/idempotency
@@ -79,13 +115,24 @@ pub struct TrueLayerClient {
     /// Authentication APIs client.
     pub auth: AuthApi,
     /// Payments APIs client.
+    #[cfg(feature = "payments")]
     pub payments: PaymentsApi,
     /// Payments Providers APIs client.
+    #[cfg(feature = "payments")]
     pub payments_providers: PaymentsProvidersApi,
     /// Payouts APIs client.
+    #[cfg(feature = "payouts")]
     pub payouts: PayoutsApi,
     /// Merchant Accounts APIs client.
+    #[cfg(feature = "merchant-accounts")]
     pub merchant_accounts: MerchantAccountsApi,
+    /// Mandates APIs client.
+    #[cfg(feature = "mandates")]
+    pub mandates: MandatesApi,
+    /// Data APIs client.
+    #[cfg(feature = "data")]
+    pub data: DataApi,
+    pub(crate) inner: Arc<TrueLayerClientInner>,
 }
 
 impl TrueLayerClient {
@@ -98,45 +145,199 @@ impl TrueLayerClient {
     pub fn builder(credentials: Credentials) -> TrueLayerClientBuilder {
         TrueLayerClientBuilder::new(credentials)
     }
+
+    /// Replaces the credentials of this client, e.g. after the client secret has been rotated.
+    ///
+    /// Shorthand for [`AuthApi::update_credentials`](crate::apis::auth::AuthApi::update_credentials).
+    pub async fn update_credentials(&self, credentials: Credentials) -> Result<(), Error> {
+        self.auth.update_credentials(credentials).await
+    }
+
+    /// Authenticates and opens a connection to the APIs ahead of the first call,
+    /// so that the token request and the TLS handshakes are not on its critical path.
+    ///
+    /// Idle connections are closed after a while (see
+    /// [`TrueLayerClientBuilder::with_pool_idle_timeout()`]), therefore this should be called
+    /// shortly before the client is needed, e.g. when a checkout page is opened.
+    ///
+    /// Returns an error if authentication fails or the APIs cannot be reached.
+    #[tracing::instrument(target = "truelayer_rust::client", name = "Warm Up", skip(self))]
+    pub async fn warm_up(&self) -> Result<(), Error> {
+        self.inner.authenticator.get_access_token().await?;
+
+        // Any response means that the connection is established and back in the pool
+        let res = self
+            .inner
+            .client
+            .head(self.inner.environment.payments_url())
+            .send()
+            .await
+            .map_err(Error::from);
+        match res {
+            Ok(_) | Err(Error::ApiError(_)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Builds the API clients sharing the given inner client.
+    pub(crate) fn from_inner(inner: Arc<TrueLayerClientInner>) -> Self {
+        Self {
+            auth: AuthApi::new(inner.clone()),
+            #[cfg(feature = "payments")]
+            payments: PaymentsApi::new(inner.clone()),
+            #[cfg(feature = "payments")]
+            payments_providers: PaymentsProvidersApi::new(inner.clone()),
+            #[cfg(feature = "payouts")]
+            payouts: PayoutsApi::new(inner.clone()),
+            #[cfg(feature = "merchant-accounts")]
+            merchant_accounts: MerchantAccountsApi::new(inner.clone()),
+            #[cfg(feature = "mandates")]
+            mandates: MandatesApi::new(inner.clone()),
+            #[cfg(feature = "data")]
+            data: DataApi::new(inner.clone()),
+            inner,
+        }
+    }
+
+    /// Submits a request previously prepared with one of the `prepare_*` functions of the API clients.
+    ///
+    /// The request is always sent with the same idempotency key, therefore it's safe to call
+    /// this function multiple times (e.g., after a crash) without causing double side-effects.
+    #[tracing::instrument(
+        target = "truelayer_rust::client",
+        name = "Resubmit Idempotent Request",
+        skip(self, request),
+        fields(
+            path = request.path(),
+            idempotency_key = %request.idempotency_key(),
+        )
+    )]
+    pub async fn resubmit<T: DeserializeOwned>(
+        &self,
+        request: &IdempotentRequest<T>,
+    ) -> Result<T, Error> {
+        request.send(&self.inner, &RequestOptions::default()).await
+    }
 }
 
 /// Builder for a [`TrueLayerClient`](crate::client::TrueLayerClient).
 #[derive(Debug)]
 pub struct TrueLayerClientBuilder {
-    client: reqwest::Client,
+    client: Option<reqwest::Client>,
+    http_options: HttpOptions,
+    default_headers: HeaderMap,
     retry_policy: Option<DynRetryPolicy>,
+    retry_observer: Option<RetryObserver>,
+    retry_budget: Option<RetryBudget>,
     environment: Environment,
     credentials: Credentials,
-    signing_key: Option<(String, Vec<u8>)>,
+    signing_key: Option<(String, Secret<PrivateKey>)>,
+    signed_headers: Vec<HeaderName>,
+    url_rewriter: Option<UrlRewriter>,
+    url_scrubber: UrlScrubber,
+    additional_middlewares: Vec<(MiddlewarePosition, AdditionalMiddleware)>,
+    runtime: Option<Arc<dyn Runtime>>,
+    access_token_provider: Option<Arc<dyn AccessTokenProvider>>,
+    #[cfg(feature = "payments")]
+    provider_cache_ttl: Option<Duration>,
+    request_validation: RequestValidation,
+    token_refresh_margin: Duration,
+    app_info: Option<(String, String)>,
+    send_sdk_version: bool,
 }
 
 impl TrueLayerClientBuilder {
     /// Creates a new builder to configure a [`TrueLayerClient`](crate::client::TrueLayerClient).
     pub fn new(credentials: Credentials) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            client: None,
+            http_options: HttpOptions::default(),
+            default_headers: HeaderMap::new(),
             retry_policy: Some(DynRetryPolicy(Arc::new(
                 ExponentialBackoff::builder().build_with_max_retries(3),
             ))),
+            retry_observer: None,
+            retry_budget: None,
             environment: Environment::Live,
             credentials,
             signing_key: None,
+            signed_headers: Vec::new(),
+            url_rewriter: None,
+            url_scrubber: UrlScrubber::default(),
+            additional_middlewares: Vec::new(),
+            runtime: None,
+            access_token_provider: None,
+            #[cfg(feature = "payments")]
+            provider_cache_ttl: None,
+            request_validation: RequestValidation::default(),
+            token_refresh_margin: DEFAULT_REFRESH_MARGIN,
+            app_info: None,
+            send_sdk_version: true,
         }
     }
 
     /// Consumes the builder and builds a new [`TrueLayerClient`](crate::client::TrueLayerClient).
+    ///
+    /// Building a client does not require an async runtime: nothing is spawned
+    /// and no request is sent until the client is first used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder has been configured with invalid values.
+    /// Use [`try_build()`](Self::try_build) to handle these errors instead.
     pub fn build(self) -> TrueLayerClient {
+        self.try_build()
+            .unwrap_or_else(|e| panic!("Invalid TrueLayer client configuration: {}", e))
+    }
+
+    /// Consumes the builder and builds a new [`TrueLayerClient`](crate::client::TrueLayerClient),
+    /// returning an error if the builder has been configured with invalid values.
+    pub fn try_build(self) -> Result<TrueLayerClient, BuildError> {
+        self.validate()?;
+
+        let user_agent = build_user_agent(self.app_info.as_ref(), self.send_sdk_version)
+            .map_err(|e| BuildError::invalid_field("app_info", e.to_string()))?;
+
+        // Tokens must outlive the requests they authenticate
+        let token_refresh_margin = self
+            .token_refresh_margin
+            .max(self.http_options.timeout.unwrap_or_default());
+
+        // Build the HTTP client shared by the authenticator and the API clients
+        let client = match self.client {
+            Some(client) => client,
+            None => self.http_options.build().map_err(BuildError::HttpClient)?,
+        };
+
+        let retry_middleware = self.retry_policy.map(|retry_policy| {
+            RetryIdempotentMiddleware::new(
+                retry_policy,
+                self.retry_observer,
+                self.retry_budget,
+                self.runtime.clone(),
+            )
+        });
+
         // Build an authenticator
-        let authenticator = Authenticator::new(
+        let mut authenticator = Authenticator::new(
             build_client_with_middleware(
-                self.client.clone(),
-                self.retry_policy.clone(),
+                client.clone(),
+                user_agent.clone(),
+                self.default_headers.clone(),
+                retry_middleware.clone(),
                 None,
                 None,
+                self.url_rewriter.clone(),
+                self.url_scrubber.clone(),
+                &self.additional_middlewares,
             ),
             self.environment.auth_url(),
             self.credentials,
-        );
+        )
+        .with_refresh_margin(token_refresh_margin);
+        if let Some(provider) = self.access_token_provider {
+            authenticator = authenticator.with_access_token_provider(provider);
+        }
 
         // Prepare the middlewares
         let auth_middleware = Some(AuthenticationMiddleware {
@@ -147,32 +348,229 @@ impl TrueLayerClientBuilder {
             .map(|(key_id, private_key)| SigningMiddleware {
                 key_id,
                 private_key,
+                base_path: self
+                    .environment
+                    .payments_url()
+                    .path()
+                    .trim_end_matches('/')
+                    .to_string(),
+                signed_headers: self.signed_headers,
             });
 
         // Build the actual TL client
         let inner = Arc::new(TrueLayerClientInner {
             client: build_client_with_middleware(
-                self.client,
-                self.retry_policy.clone(),
+                client,
+                user_agent,
+                self.default_headers,
+                retry_middleware,
                 auth_middleware,
                 signing_middleware,
+                self.url_rewriter,
+                self.url_scrubber,
+                &self.additional_middlewares,
             ),
             environment: self.environment,
             authenticator,
+            runtime: self.runtime,
+            #[cfg(feature = "payments")]
+            provider_cache: self.provider_cache_ttl.map(ProviderCache::new),
+            request_validation: self.request_validation,
         });
 
-        TrueLayerClient {
-            auth: AuthApi::new(inner.clone()),
-            payments: PaymentsApi::new(inner.clone()),
-            payments_providers: PaymentsProvidersApi::new(inner.clone()),
-            payouts: PayoutsApi::new(inner.clone()),
-            merchant_accounts: MerchantAccountsApi::new(inner),
+        Ok(TrueLayerClient::from_inner(inner))
+    }
+
+    fn validate(&self) -> Result<(), BuildError> {
+        if self.credentials.client_id().is_empty() {
+            return Err(BuildError::invalid_field(
+                "credentials",
+                "the client id must not be empty",
+            ));
+        }
+
+        if let Some((key_id, private_key)) = &self.signing_key {
+            if key_id.is_empty() {
+                return Err(BuildError::invalid_field(
+                    "signing_key",
+                    "the key id must not be empty",
+                ));
+            }
+            if !String::from_utf8_lossy(&private_key.expose_secret().0)
+                .trim_start()
+                .starts_with("-----BEGIN")
+            {
+                return Err(BuildError::invalid_field(
+                    "signing_key",
+                    "the private key must be PEM encoded",
+                ));
+            }
         }
+
+        if let Some((name, version)) = &self.app_info {
+            let is_token =
+                |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_graphic() && b != b'/');
+            if !is_token(name) || !is_token(version) {
+                return Err(BuildError::invalid_field(
+                    "app_info",
+                    "the name and version must be non-empty and must not contain spaces or `/`",
+                ));
+            }
+        }
+
+        let urls = [
+            ("environment.auth_url", self.environment.auth_url()),
+            ("environment.payments_url", self.environment.payments_url()),
+            ("environment.hpp_url", self.environment.hpp_url()),
+        ];
+        for (field, url) in urls {
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(BuildError::invalid_field(
+                    field,
+                    format!("unsupported URL scheme `{}`", url.scheme()),
+                ));
+            }
+        }
+
+        Ok(())
     }
 
     /// Sets a specific reqwest [`Client`](reqwest::Client) to use.
+    ///
+    /// The same client is used both for authentication and for all the other requests.
+    /// When a custom client is set, the HTTP options of this builder ([`with_timeout()`](Self::with_timeout),
+    /// [`with_connect_timeout()`](Self::with_connect_timeout), [`with_pool_max_idle_per_host()`](Self::with_pool_max_idle_per_host)
+    /// [`with_pool_idle_timeout()`](Self::with_pool_idle_timeout), [`with_tcp_keepalive()`](Self::with_tcp_keepalive),
+    /// [`with_http2_keep_alive()`](Self::with_http2_keep_alive), [`with_http2_prior_knowledge()`](Self::with_http2_prior_knowledge),
+    /// [`with_proxy()`](Self::with_proxy), [`with_root_certificate()`](Self::with_root_certificate)
+    /// and `with_identity()`) are ignored and must be configured on the custom client instead.
     pub fn with_http_client(mut self, client: reqwest::Client) -> Self {
-        self.client = client;
+        self.client = Some(client);
+        self
+    }
+
+    /// Sets a timeout for each HTTP request, from when the request starts connecting
+    /// until the response body has finished.
+    ///
+    /// Default is no timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.http_options.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a timeout for the connect phase of each HTTP request.
+    ///
+    /// Default is no timeout.
+    pub fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.http_options.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Sets the maximum number of idle connections kept open in the pool for each host.
+    pub fn with_pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+        self.http_options.pool_max_idle_per_host = Some(max_idle);
+        self
+    }
+
+    /// Sets how long idle connections are kept open in the pool before being closed.
+    ///
+    /// Default is 90 seconds.
+    pub fn with_pool_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.http_options.pool_idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Sends TCP keepalive probes on open connections at the given interval,
+    /// so that idle connections are not silently dropped by firewalls and load balancers.
+    ///
+    /// Default is disabled.
+    pub fn with_tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.http_options.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Sends HTTP/2 keepalive pings at the given interval, also while connections are idle,
+    /// and closes connections whose pings are not acknowledged within `timeout`.
+    ///
+    /// Default is disabled.
+    pub fn with_http2_keep_alive(mut self, interval: Duration, timeout: Duration) -> Self {
+        self.http_options.http2_keep_alive = Some((interval, timeout));
+        self
+    }
+
+    /// Uses HTTP/2 without negotiating it first, saving a round trip on each new connection.
+    ///
+    /// Only use it if all the URLs of the environment, and any proxy in between, are known
+    /// to support HTTP/2: requests fail otherwise.
+    pub fn with_http2_prior_knowledge(mut self) -> Self {
+        self.http_options.http2_prior_knowledge = true;
+        self
+    }
+
+    /// Adds a [`Proxy`](reqwest::Proxy) through which HTTP requests are sent,
+    /// including authentication requests.
+    ///
+    /// Can be called multiple times to add more than one proxy.
+    /// Proxies requiring authentication are configured with [`Proxy::basic_auth()`](reqwest::Proxy::basic_auth):
+    ///
+    /// ```rust,no_run
+    /// # use truelayer_rust::{TrueLayerClient, apis::auth::Credentials};
+    /// # fn main() -> Result<(), reqwest::Error> {
+    /// # let credentials: Credentials = unreachable!();
+    /// let tl = TrueLayerClient::builder(credentials)
+    ///     .with_proxy(reqwest::Proxy::https("http://proxy.internal:3128")?.basic_auth("user", "pass"))
+    ///     .build();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.http_options.proxies.push(proxy);
+        self
+    }
+
+    /// Adds a root certificate trusted when connecting over TLS, in addition to the system ones,
+    /// e.g. the certificate of a TLS-intercepting proxy.
+    ///
+    /// Can be called multiple times to trust more than one certificate.
+    pub fn with_root_certificate(mut self, certificate: reqwest::Certificate) -> Self {
+        self.http_options.root_certificates.push(certificate);
+        self
+    }
+
+    /// Sets the client certificate presented when connecting over TLS (mutual TLS),
+    /// to both the authentication server and the APIs.
+    ///
+    /// Requires the `native-tls` or the `rustls-tls` feature,
+    /// which must match the format of the [`Identity`](reqwest::Identity).
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    pub fn with_identity(mut self, identity: reqwest::Identity) -> Self {
+        self.http_options.identity = Some(identity);
+        self
+    }
+
+    /// Adds a header which is sent with every request, including authentication requests.
+    ///
+    /// This is useful for headers identifying a partner or platform integrating with TrueLayer.
+    /// Headers set for a single call with [`RequestOptions`](crate::request_options::RequestOptions)
+    /// take precedence over default headers with the same name.
+    pub fn with_default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    /// Appends the name and version of your application to the `User-Agent` header,
+    /// e.g. `truelayer-rust/0.1.0 my-shop/2.1.0`.
+    ///
+    /// The header is sent with every request, including authentication requests.
+    pub fn with_app_info(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.app_info = Some((name.into(), version.into()));
+        self
+    }
+
+    /// Omits the version of this crate from the `User-Agent` header,
+    /// for environments which must not disclose the versions of their dependencies.
+    pub fn without_sdk_version(mut self) -> Self {
+        self.send_sdk_version = false;
         self
     }
 
@@ -187,12 +585,138 @@ impl TrueLayerClientBuilder {
         self
     }
 
+    /// Sets a function to rewrite the URL of every outgoing request, including authentication ones.
+    ///
+    /// The function is applied right before a request is sent, after it has been signed. This is
+    /// meant for advanced setups routing requests through a gateway: in most cases, a custom
+    /// [`Environment`] (optionally with a base path) is enough.
+    pub fn with_url_rewriter(
+        mut self,
+        rewriter: impl Fn(Url) -> Url + Send + Sync + 'static,
+    ) -> Self {
+        self.url_rewriter = Some(UrlRewriter(Arc::new(rewriter)));
+        self
+    }
+
+    /// Sets the deny-list of query and fragment parameters redacted from the URLs recorded
+    /// in the `tracing` spans and events emitted by the client, including [`RetryEvent`]s.
+    ///
+    /// Defaults to [`UrlScrubber::default()`], which redacts tokens, secrets and authorization codes.
+    pub fn with_url_scrubber(mut self, url_scrubber: UrlScrubber) -> Self {
+        self.url_scrubber = url_scrubber;
+        self
+    }
+
+    /// Adds a custom middleware to the stack of the client, e.g. to route requests through
+    /// an authenticating proxy, mirror them or inject failures in tests.
+    ///
+    /// The middleware applies to all requests, including authentication ones. Middlewares added
+    /// at the same position run in the order they were added.
+    pub fn with_additional_middleware(
+        mut self,
+        position: MiddlewarePosition,
+        middleware: impl Middleware,
+    ) -> Self {
+        self.additional_middlewares
+            .push((position, AdditionalMiddleware(Arc::new(middleware))));
+        self
+    }
+
+    /// Sets a callback invoked every time a request is retried after a transient failure.
+    ///
+    /// Retries are always reported as `tracing` events as well, regardless of this callback.
+    pub fn with_retry_observer(
+        mut self,
+        observer: impl Fn(&RetryEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_observer = Some(RetryObserver(Arc::new(observer)));
+        self
+    }
+
+    /// Sets a [`RetryBudget`] limiting the total number of retries across all the requests
+    /// sent by the client, including authentication requests.
+    ///
+    /// By default, each request is retried according to the retry policy regardless of the others.
+    pub fn with_retry_budget(mut self, retry_budget: RetryBudget) -> Self {
+        self.retry_budget = Some(retry_budget);
+        self
+    }
+
     /// Configures a signing key for [request signing](https://docs.truelayer.com/docs/signing-your-requests).
     /// Signing is required for some operations like initiating a new payment.
     ///
     /// The private key is expected to be PEM encoded.
     pub fn with_signing_key(mut self, key_id: &str, private_key: Vec<u8>) -> Self {
-        self.signing_key = Some((key_id.to_string(), private_key));
+        self.signing_key = Some((key_id.to_string(), Secret::new(PrivateKey(private_key))));
+        self
+    }
+
+    /// Adds a header to include in request signatures, when present on a request.
+    ///
+    /// The `Idempotency-Key` header is always included and doesn't need to be added.
+    pub fn with_signed_header(mut self, name: HeaderName) -> Self {
+        self.signed_headers.push(name);
+        self
+    }
+
+    /// Sets the [`Runtime`] used to wait between polling attempts.
+    ///
+    /// Defaults to the tokio runtime the client is used from.
+    pub fn with_runtime(mut self, runtime: impl Runtime) -> Self {
+        self.runtime = Some(Arc::new(runtime));
+        self
+    }
+
+    /// Caches the details of payments providers in memory for `ttl`,
+    /// so that repeated lookups with [`PaymentsProvidersApi::get_by_id()`] don't hit the network.
+    ///
+    /// Providers change rarely, so a TTL of a few minutes is usually safe.
+    /// Use [`PaymentsProvidersApi::invalidate_cache()`] and [`PaymentsProvidersApi::clear_cache()`]
+    /// to force a refresh. Default is no caching.
+    #[cfg(feature = "payments")]
+    pub fn with_provider_cache(mut self, ttl: Duration) -> Self {
+        self.provider_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets how thoroughly requests creating payments, refunds, payouts and mandates are checked
+    /// before being sent.
+    ///
+    /// With [`RequestValidation::Strict`], requests which cannot succeed or exceed the limits
+    /// of the TrueLayer APIs (e.g. paying PLN into a sort code and account number, a zero amount
+    /// or a reference too long for the payment scheme) fail with [`Error::ValidationError`]
+    /// without contacting TrueLayer. Default is [`RequestValidation::Disabled`].
+    pub fn with_request_validation(mut self, request_validation: RequestValidation) -> Self {
+        self.request_validation = request_validation;
+        self
+    }
+
+    /// Gets access tokens from the given provider (e.g. a central token broker)
+    /// instead of requesting them to the authentication server with the credentials of the client.
+    ///
+    /// The credentials passed to the builder are not used to authenticate,
+    /// but their client id still identifies the client.
+    /// A pre-fetched [`AccessToken`](crate::apis::auth::AccessToken) can be injected directly:
+    ///
+    /// ```rust,no_run
+    /// # use truelayer_rust::{TrueLayerClient, apis::auth::{AccessToken, Credentials}};
+    /// # let (credentials, expires_at): (Credentials, chrono::DateTime<chrono::Utc>) = unreachable!();
+    /// let tl = TrueLayerClient::builder(credentials)
+    ///     .with_access_token_provider(AccessToken::new("access-token", Some(expires_at)))
+    ///     .build();
+    /// ```
+    pub fn with_access_token_provider(mut self, provider: impl AccessTokenProvider) -> Self {
+        self.access_token_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sets how long before its expiration the access token is refreshed.
+    ///
+    /// The token is also refreshed if it could expire before a request times out, according to
+    /// [`with_timeout()`](Self::with_timeout) or [`RequestOptions::with_timeout()`](crate::RequestOptions::with_timeout).
+    /// The margin is capped at half the lifetime of each token. Default is 10 minutes.
+    pub fn with_token_refresh_margin(mut self, margin: Duration) -> Self {
+        self.token_refresh_margin = margin;
         self
     }
 
@@ -203,19 +727,134 @@ impl TrueLayerClientBuilder {
     }
 }
 
+/// Configuration of the underlying HTTP client, used when no custom client is provided.
+#[derive(Debug, Default)]
+struct HttpOptions {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    http2_keep_alive: Option<(Duration, Duration)>,
+    http2_prior_knowledge: bool,
+    proxies: Vec<reqwest::Proxy>,
+    root_certificates: Vec<reqwest::Certificate>,
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    identity: Option<reqwest::Identity>,
+}
+
+impl HttpOptions {
+    fn build(self) -> Result<reqwest::Client, reqwest::Error> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+
+        if let Some(idle_timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(idle_timeout);
+        }
+
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+
+        if let Some((interval, timeout)) = self.http2_keep_alive {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_timeout(timeout)
+                .http2_keep_alive_while_idle(true);
+        }
+
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        for proxy in self.proxies {
+            builder = builder.proxy(proxy);
+        }
+
+        for certificate in self.root_certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        if let Some(identity) = self.identity {
+            builder = builder.identity(identity);
+        }
+
+        builder.build()
+    }
+}
+
+/// Position of a middleware added with
+/// [`TrueLayerClientBuilder::with_additional_middleware()`] in the stack of the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MiddlewarePosition {
+    /// Before the middlewares of the SDK. The middleware sees each call once,
+    /// with the request as built by the API clients and the final outcome after retries.
+    /// Error responses reach it as [`Error`](crate::Error)s wrapped in
+    /// [`reqwest_middleware::Error::Middleware`].
+    BeforeSdk,
+    /// After the middlewares of the SDK, right before the request is sent. The middleware sees
+    /// every attempt, authenticated, signed and with its URL rewritten, and the raw responses.
+    AfterSdk,
+}
+
+/// Wrapper type around a custom middleware because `dyn Middleware` does not implement `Debug`.
+#[derive(Clone)]
+struct AdditionalMiddleware(Arc<dyn Middleware>);
+
+impl Debug for AdditionalMiddleware {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AdditionalMiddleware")
+            .finish_non_exhaustive()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn build_client_with_middleware(
     client: reqwest::Client,
-    retry_policy: Option<DynRetryPolicy>,
+    user_agent: HeaderValue,
+    default_headers: HeaderMap,
+    retry_middleware: Option<RetryIdempotentMiddleware>,
     auth_middleware: Option<AuthenticationMiddleware>,
     signing_middleware: Option<SigningMiddleware>,
+    url_rewriter: Option<UrlRewriter>,
+    url_scrubber: UrlScrubber,
+    additional_middlewares: &[(MiddlewarePosition, AdditionalMiddleware)],
 ) -> ClientWithMiddleware {
-    let mut builder = reqwest_middleware::ClientBuilder::new(client)
-        .with(InjectUserAgentMiddleware::new())
-        .with(TracingMiddleware::default())
-        .with(ErrorHandlingMiddleware);
+    let additional = |position| {
+        additional_middlewares
+            .iter()
+            .filter(move |(p, _)| *p == position)
+            .map(|(_, m)| m.0.clone())
+    };
+
+    let mut builder = reqwest_middleware::ClientBuilder::new(client);
+    for middleware in additional(MiddlewarePosition::BeforeSdk) {
+        builder = builder.with_arc(middleware);
+    }
 
-    if let Some(retry_policy) = retry_policy {
-        builder = builder.with(RetryIdempotentMiddleware::new(retry_policy));
+    builder = builder
+        .with(InjectUserAgentMiddleware::new(user_agent))
+        .with(UrlScrubberMiddleware::new(url_scrubber))
+        .with(TracingMiddleware::<ScrubbedSpanBackend>::new())
+        .with(RequestOptionsMiddleware)
+        .with(DefaultHeadersMiddleware::new(default_headers))
+        .with(ErrorHandlingMiddleware)
+        .with(CorrelationIdMiddleware);
+
+    if let Some(retry_middleware) = retry_middleware {
+        builder = builder.with(retry_middleware);
     }
 
     if let Some(auth_middleware) = auth_middleware {
@@ -226,6 +865,14 @@ fn build_client_with_middleware(
         builder = builder.with(signing_middleware);
     }
 
+    if let Some(url_rewriter) = url_rewriter {
+        builder = builder.with(UrlRewriterMiddleware::new(url_rewriter));
+    }
+
+    for middleware in additional(MiddlewarePosition::AfterSdk) {
+        builder = builder.with_arc(middleware);
+    }
+
     builder.build()
 }
 
@@ -237,7 +884,9 @@ pub enum Environment {
     Live,
     /// TrueLayer Sandbox environment.
     Sandbox,
-    /// Custom environment. This variant is mainly used for tests.
+    /// Custom environment.
+    ///
+    /// Use [`EnvironmentBuilder`] to override only some of the URLs of one of the other environments.
     Custom {
         auth_url: Url,
         payments_url: Url,
@@ -255,6 +904,26 @@ impl Environment {
         }
     }
 
+    /// Starts building an `Environment::Custom` from the URLs of this environment.
+    pub fn to_builder(&self) -> EnvironmentBuilder {
+        EnvironmentBuilder {
+            auth_url: self.auth_url(),
+            payments_url: self.payments_url(),
+            hpp_url: self.hpp_url(),
+        }
+    }
+
+    /// URL of an endpoint of the payments APIs, relative to [`payments_url()`](Self::payments_url).
+    pub(crate) fn payments_endpoint(&self, path: &str) -> Url {
+        join_url(&self.payments_url(), path)
+    }
+
+    /// URL of a page of the Hosted Payments Page, relative to [`hpp_url()`](Self::hpp_url).
+    #[cfg(feature = "payments")]
+    pub(crate) fn hpp_endpoint(&self, path: &str) -> Url {
+        join_url(&self.hpp_url(), path)
+    }
+
     /// Base URL for authentication-related requests.
     pub fn auth_url(&self) -> Url {
         match self {
@@ -282,3 +951,565 @@ impl Environment {
         }
     }
 }
+
+/// Builder for an [`Environment::Custom`] which starts from the URLs of a preset environment.
+///
+/// ```rust
+/// # use reqwest::Url;
+/// # use truelayer_rust::client::EnvironmentBuilder;
+/// let environment = EnvironmentBuilder::live()
+///     .with_hpp_url(Url::parse("https://checkout.my-company.com").unwrap())
+///     .build();
+///
+/// assert_eq!(environment.payments_url().as_str(), "https://api.truelayer.com/");
+/// assert_eq!(environment.hpp_url().as_str(), "https://checkout.my-company.com/");
+/// ```
+#[derive(Debug, Clone)]
+pub struct EnvironmentBuilder {
+    auth_url: Url,
+    payments_url: Url,
+    hpp_url: Url,
+}
+
+impl EnvironmentBuilder {
+    /// Starts from the URLs of the Live environment.
+    pub fn live() -> Self {
+        Environment::Live.to_builder()
+    }
+
+    /// Starts from the URLs of the Sandbox environment.
+    pub fn sandbox() -> Self {
+        Environment::Sandbox.to_builder()
+    }
+
+    /// Overrides the base URL for authentication-related requests.
+    pub fn with_auth_url(mut self, auth_url: Url) -> Self {
+        self.auth_url = auth_url;
+        self
+    }
+
+    /// Overrides the base URL for payments-related requests.
+    pub fn with_payments_url(mut self, payments_url: Url) -> Self {
+        self.payments_url = payments_url;
+        self
+    }
+
+    /// Overrides the base URL for the Hosted Payments Page.
+    pub fn with_hpp_url(mut self, hpp_url: Url) -> Self {
+        self.hpp_url = hpp_url;
+        self
+    }
+
+    /// Builds the environment.
+    pub fn build(self) -> Environment {
+        Environment::Custom {
+            auth_url: self.auth_url,
+            payments_url: self.payments_url,
+            hpp_url: self.hpp_url,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{apis::payments::CreatePaymentResponse, common::IDEMPOTENCY_KEY_HEADER};
+    use serde_json::json;
+    use uuid::Uuid;
+    use wiremock::{
+        matchers::{body_json, header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn mock_client(mock_server: &MockServer, timeout: Duration) -> TrueLayerClient {
+        TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_timeout(timeout)
+        .with_environment(Environment::from_single_url(
+            &Url::parse(&mock_server.uri()).unwrap(),
+        ))
+        .build()
+    }
+
+    #[test]
+    fn environment_builder_overrides_single_url() {
+        let environment = EnvironmentBuilder::sandbox()
+            .with_payments_url(Url::parse("https://payments.example.com").unwrap())
+            .build();
+
+        assert_eq!(environment.auth_url(), Environment::Sandbox.auth_url());
+        assert_eq!(
+            environment.payments_url().as_str(),
+            "https://payments.example.com/"
+        );
+        assert_eq!(environment.hpp_url(), Environment::Sandbox.hpp_url());
+    }
+
+    #[test]
+    fn builder_debug_does_not_expose_secrets() {
+        let builder = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "super-secret-client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_signing_key("kid", b"-----BEGIN super-secret-key".to_vec());
+
+        let debug = format!("{:?}", builder);
+        assert!(!debug.contains("super-secret"));
+    }
+
+    #[test]
+    fn try_build_rejects_invalid_signing_key() {
+        let res = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_signing_key("kid", b"not-a-pem-key".to_vec())
+        .try_build();
+
+        assert!(matches!(
+            res,
+            Err(BuildError::InvalidField {
+                field: "signing_key",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn try_build_rejects_unsupported_environment_url() {
+        let res = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_environment(
+            EnvironmentBuilder::live()
+                .with_hpp_url(Url::parse("ftp://payment.example.com").unwrap())
+                .build(),
+        )
+        .try_build();
+
+        assert!(matches!(
+            res,
+            Err(BuildError::InvalidField {
+                field: "environment.hpp_url",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn client_can_be_built_outside_of_a_runtime() {
+        let tl = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_environment(Environment::from_single_url(
+            &Url::parse("http://127.0.0.1:1").unwrap(),
+        ))
+        .build();
+
+        // Use the client later on, from within a runtime
+        let res = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(async move { tl.auth.get_access_token().await });
+
+        // Nothing is listening on the other side, but the request has been attempted
+        assert!(matches!(res, Err(Error::HttpError(_))));
+    }
+
+    #[tokio::test]
+    async fn timeout_is_respected_by_authenticator() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let tl = mock_client(&mock_server, Duration::from_millis(100));
+        let res = tl.auth.get_access_token().await;
+
+        assert!(matches!(res, Err(Error::HttpError(e)) if e.is_timeout()));
+    }
+
+    #[cfg(feature = "payments")]
+    #[tokio::test]
+    async fn timeout_is_respected_by_api_clients() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .respond_with(ResponseTemplate::new(404).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let tl = mock_client(&mock_server, Duration::from_millis(100));
+        let res = tl.payments.get_by_id("payment-id").await;
+
+        assert!(matches!(res, Err(Error::HttpError(e)) if e.is_timeout()));
+    }
+
+    #[cfg(feature = "payments")]
+    #[tokio::test]
+    async fn default_headers_are_attached_to_all_requests() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .and(header("tl-agent", "my-platform/1.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .and(header("tl-agent", "my-platform/1.0"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let tl = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_default_header(
+            HeaderName::from_static("tl-agent"),
+            HeaderValue::from_static("my-platform/1.0"),
+        )
+        .with_environment(Environment::from_single_url(
+            &Url::parse(&mock_server.uri()).unwrap(),
+        ))
+        .build();
+
+        let payment = tl.payments.get_by_id("payment-id").await.unwrap();
+        assert!(payment.is_none());
+    }
+
+    #[cfg(feature = "payments")]
+    #[tokio::test]
+    async fn user_agent_is_customizable() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .and(header("user-agent", "truelayer-rust my-shop/2.1.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .and(header("user-agent", "truelayer-rust my-shop/2.1.0"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let tl = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_app_info("my-shop", "2.1.0")
+        .without_sdk_version()
+        .with_environment(Environment::from_single_url(
+            &Url::parse(&mock_server.uri()).unwrap(),
+        ))
+        .build();
+
+        let payment = tl.payments.get_by_id("payment-id").await.unwrap();
+        assert!(payment.is_none());
+    }
+
+    #[test]
+    fn try_build_rejects_invalid_app_info() {
+        let res = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_app_info("my shop", "2.1.0")
+        .try_build();
+
+        assert!(matches!(
+            res,
+            Err(BuildError::InvalidField {
+                field: "app_info",
+                ..
+            })
+        ));
+    }
+
+    #[cfg(feature = "payments")]
+    #[tokio::test]
+    async fn environment_base_paths_are_preserved() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/gateway/auth/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/gateway/api/payments/payment-id"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let base = Url::parse(&mock_server.uri()).unwrap();
+        let tl = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_environment(
+            EnvironmentBuilder::live()
+                .with_auth_url(base.join("/gateway/auth").unwrap())
+                .with_payments_url(base.join("/gateway/api/").unwrap())
+                .build(),
+        )
+        .build();
+
+        assert!(tl.payments.get_by_id("payment-id").await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "payments")]
+    #[tokio::test]
+    async fn url_rewriter_is_applied_to_all_requests() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/rewritten/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/rewritten/payments/payment-id"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let tl = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_environment(Environment::from_single_url(
+            &Url::parse(&mock_server.uri()).unwrap(),
+        ))
+        .with_url_rewriter(|mut url| {
+            url.set_path(&format!("/rewritten{}", url.path()));
+            url
+        })
+        .build();
+
+        assert!(tl.payments.get_by_id("payment-id").await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "payments")]
+    #[tokio::test]
+    async fn additional_middlewares_run_before_and_after_sdk_middlewares() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        struct AddHeader;
+
+        #[async_trait::async_trait]
+        impl Middleware for AddHeader {
+            async fn handle(
+                &self,
+                mut req: reqwest::Request,
+                extensions: &mut task_local_extensions::Extensions,
+                next: reqwest_middleware::Next<'_>,
+            ) -> reqwest_middleware::Result<reqwest::Response> {
+                req.headers_mut()
+                    .insert("x-proxy-auth", HeaderValue::from_static("secret"));
+                next.run(req, extensions).await
+            }
+        }
+
+        struct RecordAuthorization(Arc<AtomicUsize>);
+
+        #[async_trait::async_trait]
+        impl Middleware for RecordAuthorization {
+            async fn handle(
+                &self,
+                req: reqwest::Request,
+                extensions: &mut task_local_extensions::Extensions,
+                next: reqwest_middleware::Next<'_>,
+            ) -> reqwest_middleware::Result<reqwest::Response> {
+                if req.headers().contains_key(reqwest::header::AUTHORIZATION) {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+                next.run(req, extensions).await
+            }
+        }
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .and(header("x-proxy-auth", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .and(header("x-proxy-auth", "secret"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let authorized = Arc::new(AtomicUsize::new(0));
+        let tl = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_environment(Environment::from_single_url(
+            &Url::parse(&mock_server.uri()).unwrap(),
+        ))
+        .with_additional_middleware(MiddlewarePosition::BeforeSdk, AddHeader)
+        .with_additional_middleware(
+            MiddlewarePosition::AfterSdk,
+            RecordAuthorization(authorized.clone()),
+        )
+        .build();
+
+        assert!(tl.payments.get_by_id("payment-id").await.unwrap().is_none());
+        // Only the API request is authenticated, the token request is not
+        assert_eq!(authorized.load(Ordering::SeqCst), 1);
+    }
+
+    #[cfg(feature = "payments")]
+    #[tokio::test]
+    async fn warm_up_authenticates_and_connects_ahead_of_the_first_call() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/"))
+            .and(header("authorization", "Bearer access-token"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let tl = mock_client(&mock_server, Duration::from_secs(5));
+        tl.warm_up().await.unwrap();
+
+        // The token obtained while warming up is reused
+        assert!(tl.payments.get_by_id("payment-id").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn resubmit_reuses_idempotency_key_and_body() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let idempotency_key = Uuid::new_v4();
+        Mock::given(method("POST"))
+            .and(path("/payments"))
+            .and(header(
+                IDEMPOTENCY_KEY_HEADER,
+                idempotency_key.to_string().as_str(),
+            ))
+            .and(body_json(json!({ "amount_in_minor": 1 })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "id": "payment-id",
+                "resource_token": "resource-token",
+                "user": { "id": "user-id" },
+                "status": "authorization_required"
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let tl = mock_client(&mock_server, Duration::from_secs(5));
+        let request = IdempotentRequest::<CreatePaymentResponse>::new(
+            "/payments",
+            &json!({ "amount_in_minor": 1 }),
+            idempotency_key,
+        )
+        .unwrap();
+
+        // Simulate persisting the request and loading it back
+        let persisted = serde_json::to_string(&request).unwrap();
+        let loaded: IdempotentRequest<CreatePaymentResponse> =
+            serde_json::from_str(&persisted).unwrap();
+        assert_eq!(loaded, request);
+
+        let first = tl.resubmit(&request).await.unwrap();
+        let second = tl.resubmit(&loaded).await.unwrap();
+        assert_eq!(first.id, "payment-id");
+        assert_eq!(first.id, second.id);
+    }
+}
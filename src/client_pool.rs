@@ -0,0 +1,260 @@
+//! Pool of [`TrueLayerClient`]s for platforms acting on behalf of many TrueLayer clients.
+//!
+//! Each tenant, identified by its `client_id`, needs its own [`TrueLayerClient`] since
+//! credentials, signing keys and access tokens differ. Building them independently would
+//! also give each of them its own HTTP connection pool. A [`TrueLayerClientPool`] instead
+//! builds the clients lazily on first use, makes all of them share the same reqwest
+//! [`Client`](reqwest::Client) and keeps only the most recently used ones around:
+//!
+//! ```rust,no_run
+//! # use truelayer_rust::{TrueLayerClient, apis::auth::Credentials, client_pool::TrueLayerClientPool};
+//! # fn lookup_secret(client_id: &str) -> Option<String> { unimplemented!() }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let pool = TrueLayerClientPool::new(1000, |client_id| {
+//!     let client_secret = lookup_secret(client_id)?;
+//!     Some(TrueLayerClient::builder(Credentials::ClientCredentials {
+//!         client_id: client_id.to_string(),
+//!         client_secret: client_secret.into(),
+//!         scope: "payments".into(),
+//!     }))
+//! });
+//!
+//! if let Some(tl) = pool.get("some-client-id")? {
+//!     // Use `tl` as any other client
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{client::TrueLayerClientBuilder, BuildError, TrueLayerClient};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    sync::{Arc, Mutex},
+};
+
+type ClientFactory = dyn Fn(&str) -> Option<TrueLayerClientBuilder> + Send + Sync;
+
+/// Lazily built, size-bounded set of [`TrueLayerClient`]s keyed by `client_id`.
+///
+/// When the pool is full, building a new client evicts the least recently used one.
+/// Evicted clients which are still in use keep working: the pool only drops its own handle,
+/// and a new client (with a new access token) is built the next time the tenant is requested.
+///
+/// The pool is cheap to clone and clones share the same clients.
+#[derive(Clone)]
+pub struct TrueLayerClientPool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    http_client: reqwest::Client,
+    factory: Box<ClientFactory>,
+    capacity: usize,
+    state: Mutex<PoolState>,
+}
+
+#[derive(Default)]
+struct PoolState {
+    clients: HashMap<String, PoolEntry>,
+    clock: u64,
+}
+
+struct PoolEntry {
+    client: TrueLayerClient,
+    last_used: u64,
+}
+
+impl TrueLayerClientPool {
+    /// Builds a pool holding at most `capacity` clients, with a default reqwest [`Client`](reqwest::Client).
+    ///
+    /// `factory` is called on first use of each `client_id` to configure its client,
+    /// and should return `None` for unknown tenants. The HTTP client set on the returned builder
+    /// is always replaced with the one shared by the pool.
+    ///
+    /// A `capacity` of zero is treated as one.
+    pub fn new<F>(capacity: usize, factory: F) -> Self
+    where
+        F: Fn(&str) -> Option<TrueLayerClientBuilder> + Send + Sync + 'static,
+    {
+        Self::with_http_client(reqwest::Client::new(), capacity, factory)
+    }
+
+    /// Builds a pool whose clients all share the given reqwest [`Client`](reqwest::Client).
+    ///
+    /// See [`new()`](Self::new) for the meaning of the other parameters.
+    pub fn with_http_client<F>(http_client: reqwest::Client, capacity: usize, factory: F) -> Self
+    where
+        F: Fn(&str) -> Option<TrueLayerClientBuilder> + Send + Sync + 'static,
+    {
+        Self {
+            inner: Arc::new(PoolInner {
+                http_client,
+                factory: Box::new(factory),
+                capacity: capacity.max(1),
+                state: Mutex::new(PoolState::default()),
+            }),
+        }
+    }
+
+    /// Returns the client of the given tenant, building it if it's not in the pool.
+    ///
+    /// Returns `Ok(None)` if the factory of the pool does not know about `client_id`.
+    pub fn get(&self, client_id: &str) -> Result<Option<TrueLayerClient>, BuildError> {
+        let mut state = self.inner.state.lock().unwrap();
+        state.clock += 1;
+        let now = state.clock;
+
+        if let Some(entry) = state.clients.get_mut(client_id) {
+            entry.last_used = now;
+            return Ok(Some(entry.client.clone()));
+        }
+
+        // Building a client is cheap and does not perform any I/O,
+        // so it's fine to do it while holding the lock
+        let builder = match (self.inner.factory)(client_id) {
+            Some(builder) => builder,
+            None => return Ok(None),
+        };
+        let client = builder
+            .with_http_client(self.inner.http_client.clone())
+            .try_build()?;
+
+        if state.clients.len() >= self.inner.capacity {
+            let lru = state
+                .clients
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(client_id, _)| client_id.clone());
+            if let Some(lru) = lru {
+                state.clients.remove(&lru);
+            }
+        }
+
+        state.clients.insert(
+            client_id.to_string(),
+            PoolEntry {
+                client: client.clone(),
+                last_used: now,
+            },
+        );
+
+        Ok(Some(client))
+    }
+
+    /// Removes the client of the given tenant from the pool, e.g. after its credentials are rotated.
+    ///
+    /// Returns whether the client was in the pool.
+    pub fn evict(&self, client_id: &str) -> bool {
+        self.inner
+            .state
+            .lock()
+            .unwrap()
+            .clients
+            .remove(client_id)
+            .is_some()
+    }
+
+    /// Returns the number of clients currently in the pool.
+    pub fn len(&self) -> usize {
+        self.inner.state.lock().unwrap().clients.len()
+    }
+
+    /// Returns `true` if the pool contains no clients.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of clients kept in the pool.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+impl Debug for TrueLayerClientPool {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrueLayerClientPool")
+            .field("capacity", &self.inner.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::auth::Credentials;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn pool(capacity: usize) -> (TrueLayerClientPool, Arc<AtomicUsize>) {
+        let builds = Arc::new(AtomicUsize::new(0));
+        let builds_clone = builds.clone();
+
+        let pool = TrueLayerClientPool::new(capacity, move |client_id| {
+            if client_id == "unknown" {
+                return None;
+            }
+
+            builds_clone.fetch_add(1, Ordering::SeqCst);
+            Some(TrueLayerClient::builder(Credentials::ClientCredentials {
+                client_id: client_id.to_string(),
+                client_secret: "client-secret".into(),
+                scope: "mock".into(),
+            }))
+        });
+
+        (pool, builds)
+    }
+
+    #[test]
+    fn clients_are_built_lazily_and_reused() {
+        let (pool, builds) = pool(10);
+        assert!(pool.is_empty());
+
+        let first = pool.get("tenant-a").unwrap().unwrap();
+        let second = pool.get("tenant-a").unwrap().unwrap();
+
+        assert!(Arc::ptr_eq(&first.inner, &second.inner));
+        assert_eq!(builds.load(Ordering::SeqCst), 1);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn unknown_tenants_are_not_pooled() {
+        let (pool, _) = pool(10);
+
+        assert!(pool.get("unknown").unwrap().is_none());
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn least_recently_used_client_is_evicted() {
+        let (pool, builds) = pool(2);
+
+        pool.get("tenant-a").unwrap();
+        pool.get("tenant-b").unwrap();
+        // Touch `tenant-a` so that `tenant-b` becomes the least recently used
+        pool.get("tenant-a").unwrap();
+        pool.get("tenant-c").unwrap();
+        assert_eq!(pool.len(), 2);
+        assert_eq!(builds.load(Ordering::SeqCst), 3);
+
+        // `tenant-a` is still pooled, `tenant-b` must be built again
+        pool.get("tenant-a").unwrap();
+        assert_eq!(builds.load(Ordering::SeqCst), 3);
+        pool.get("tenant-b").unwrap();
+        assert_eq!(builds.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn evict_removes_the_client() {
+        let (pool, builds) = pool(10);
+
+        pool.get("tenant-a").unwrap();
+        assert!(pool.evict("tenant-a"));
+        assert!(!pool.evict("tenant-a"));
+
+        pool.get("tenant-a").unwrap();
+        assert_eq!(builds.load(Ordering::SeqCst), 2);
+    }
+}
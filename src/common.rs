@@ -1,3 +1,8 @@
+use crate::Error;
+use async_trait::async_trait;
+use reqwest::{Response, Url};
+use serde::de::DeserializeOwned;
+
 // Default URLs
 pub static DEFAULT_AUTH_URL: &str = "https://auth.truelayer.com";
 pub static DEFAULT_PAYMENTS_URL: &str = "https://api.truelayer.com";
@@ -10,3 +15,134 @@ pub static DEFAULT_SANDBOX_HOSTED_PAYMENTS_PAGE_URL: &str = "https://payment.tru
 pub static IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
 pub static TL_SIGNATURE_HEADER: &str = "Tl-Signature";
 pub static TL_CORRELATION_ID_HEADER: &str = "X-Tl-Correlation-Id";
+#[cfg(any(feature = "payments", feature = "mandates"))]
+pub static PSU_IP_ADDRESS_HEADER: &str = "X-PSU-IP-Address";
+#[cfg(any(feature = "payments", feature = "mandates"))]
+pub static PSU_USER_AGENT_HEADER: &str = "X-PSU-User-Agent";
+#[cfg(any(feature = "payments", feature = "mandates"))]
+pub static PSU_DEVICE_ID_HEADER: &str = "X-PSU-Device-Id";
+
+/// Maximum number of bytes of the body included in [`Error::Deserialization`].
+pub(crate) const DESERIALIZATION_SNIPPET_LEN: usize = 512;
+
+/// Deserialization of JSON response bodies with enough context to diagnose model mismatches.
+#[async_trait]
+pub(crate) trait DecodeJson {
+    /// Deserializes the JSON body of a response, like [`Response::json()`], but returns an
+    /// [`Error::Deserialization`] carrying the endpoint, the status and the beginning of the body
+    /// if it does not match the expected model.
+    async fn decode_json<T: DeserializeOwned>(self) -> Result<T, Error>;
+}
+
+#[async_trait]
+impl DecodeJson for Response {
+    async fn decode_json<T: DeserializeOwned>(self) -> Result<T, Error> {
+        let endpoint = self.url().path().to_string();
+        let status = self.status().as_u16();
+        let body = self.bytes().await?;
+
+        serde_json::from_slice(&body).map_err(|source| {
+            tracing::warn!(
+                target: "truelayer_rust::http",
+                endpoint,
+                status,
+                error = %source,
+                "Cannot deserialize response body"
+            );
+            Error::Deserialization {
+                endpoint,
+                status,
+                snippet: String::from_utf8_lossy(
+                    &body[..body.len().min(DESERIALIZATION_SNIPPET_LEN)],
+                )
+                .into_owned(),
+                source,
+            }
+        })
+    }
+}
+
+/// Joins `path` to `base`, preserving any path already present in `base`.
+///
+/// Unlike [`Url::join()`], `join_url("https://gw.internal/truelayer", "/payments")`
+/// returns `https://gw.internal/truelayer/payments`.
+pub(crate) fn join_url(base: &Url, path: &str) -> Url {
+    let mut base = base.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+
+    base.join(path.trim_start_matches('/')).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn join_url_without_base_path() {
+        let base = Url::parse("https://api.truelayer.com").unwrap();
+
+        assert_eq!(
+            join_url(&base, "/payments/id").as_str(),
+            "https://api.truelayer.com/payments/id"
+        );
+    }
+
+    #[test]
+    fn join_url_preserves_base_path() {
+        for base in [
+            "https://gw.internal/truelayer",
+            "https://gw.internal/truelayer/",
+        ] {
+            let base = Url::parse(base).unwrap();
+
+            assert_eq!(
+                join_url(&base, "/payments/id").as_str(),
+                "https://gw.internal/truelayer/payments/id"
+            );
+            assert_eq!(
+                join_url(&base, "payments").as_str(),
+                "https://gw.internal/truelayer/payments"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn decode_json_reports_endpoint_status_and_body_on_mismatch() {
+        use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+        #[derive(serde::Deserialize, Debug)]
+        struct Model {
+            #[allow(dead_code)]
+            id: String,
+        }
+
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/payments/id"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"id":42}"#))
+            .mount(&mock_server)
+            .await;
+
+        let err = reqwest::get(format!("{}/payments/id", mock_server.uri()))
+            .await
+            .unwrap()
+            .decode_json::<Model>()
+            .await
+            .unwrap_err();
+
+        match err {
+            Error::Deserialization {
+                endpoint,
+                status,
+                snippet,
+                ..
+            } => {
+                assert_eq!(endpoint, "/payments/id");
+                assert_eq!(status, 200);
+                assert_eq!(snippet, r#"{"id":42}"#);
+            }
+            e => panic!("Unexpected error: {}", e),
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Access to the correlation id TrueLayer assigns to each request.
+//!
+//! TrueLayer returns a correlation id in the `X-Tl-Correlation-Id` header of every response,
+//! which their support needs to investigate a request. Failed calls carry it in the error
+//! (see [`Error::tl_correlation_id()`]); to get it for any call, successful or not,
+//! wrap the call with [`CorrelationIdExt::with_tl_correlation_id()`]:
+//!
+//! ```rust,no_run
+//! # use truelayer_rust::{TrueLayerClient, Error, correlation_id::CorrelationIdExt};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//! # let tl: TrueLayerClient = unreachable!();
+//! let res = tl
+//!     .payments
+//!     .get_by_id("some-payment-id")
+//!     .with_tl_correlation_id()
+//!     .await;
+//!
+//! tracing::info!(tl_correlation_id = ?res.tl_correlation_id(), "Fetched payment");
+//! let payment = res.into_inner()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Error::tl_correlation_id()`]: crate::Error::tl_correlation_id
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+use tokio::task::futures::TaskLocalFuture;
+
+type CorrelationIdSlot = Arc<Mutex<Option<String>>>;
+
+tokio::task_local! {
+    /// Correlation id of the last response received by the current call,
+    /// set only while the call is wrapped with [`CorrelationIdExt::with_tl_correlation_id()`].
+    static CORRELATION_ID: CorrelationIdSlot;
+}
+
+/// Records the correlation id of a response, if the current call is capturing it.
+pub(crate) fn record(tl_correlation_id: Option<&str>) {
+    let _ = CORRELATION_ID.try_with(|slot| {
+        *slot.lock().unwrap() = tl_correlation_id.map(str::to_string);
+    });
+}
+
+/// Extension trait to capture the correlation id of the requests sent by an SDK call.
+pub trait CorrelationIdExt: Future + Sized {
+    /// Runs the call and returns its output together with the correlation id of the last
+    /// response it received.
+    ///
+    /// If the call retried a request, the correlation id is the one of the last attempt.
+    fn with_tl_correlation_id(self) -> WithTlCorrelationId<Self>;
+}
+
+impl<F: Future> CorrelationIdExt for F {
+    fn with_tl_correlation_id(self) -> WithTlCorrelationId<Self> {
+        let slot = CorrelationIdSlot::default();
+
+        WithTlCorrelationId {
+            inner: Box::pin(CORRELATION_ID.scope(slot.clone(), self)),
+            slot,
+        }
+    }
+}
+
+/// Future returned by [`CorrelationIdExt::with_tl_correlation_id()`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct WithTlCorrelationId<F: Future> {
+    inner: Pin<Box<TaskLocalFuture<CorrelationIdSlot, F>>>,
+    slot: CorrelationIdSlot,
+}
+
+impl<F: Future> Future for WithTlCorrelationId<F> {
+    type Output = WithCorrelationId<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let output = futures::ready!(self.inner.as_mut().poll(cx));
+
+        Poll::Ready(WithCorrelationId {
+            output,
+            tl_correlation_id: self.slot.lock().unwrap().take(),
+        })
+    }
+}
+
+impl<F: Future> fmt::Debug for WithTlCorrelationId<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithTlCorrelationId")
+            .finish_non_exhaustive()
+    }
+}
+
+/// Output of an SDK call, together with the correlation id of the last response it received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithCorrelationId<T> {
+    output: T,
+    tl_correlation_id: Option<String>,
+}
+
+impl<T> WithCorrelationId<T> {
+    /// Correlation id of the last response, or `None` if no response was received
+    /// (e.g. because of a network error) or it had no correlation id.
+    pub fn tl_correlation_id(&self) -> Option<&str> {
+        self.tl_correlation_id.as_deref()
+    }
+
+    /// Returns the output of the call.
+    pub fn into_inner(self) -> T {
+        self.output
+    }
+
+    /// Returns the output of the call and the correlation id.
+    pub fn into_parts(self) -> (T, Option<String>) {
+        (self.output, self.tl_correlation_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn captures_last_recorded_correlation_id() {
+        let res = async {
+            record(Some("first"));
+            record(Some("second"));
+            42
+        }
+        .with_tl_correlation_id()
+        .await;
+
+        assert_eq!(res.tl_correlation_id(), Some("second"));
+        assert_eq!(res.into_inner(), 42);
+    }
+
+    #[tokio::test]
+    async fn recording_outside_of_a_capture_is_ignored() {
+        record(Some("ignored"));
+
+        let res = async {}.with_tl_correlation_id().await;
+
+        assert_eq!(res.tl_correlation_id(), None);
+    }
+}
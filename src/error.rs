@@ -1,6 +1,20 @@
 //! Standard errors used by all functions in the crate.
+//!
+//! # Resources not found
+//!
+//! All the API clients handle a 404 returned by TrueLayer in the same way:
+//!
+//! - functions getting a single resource (e.g. `get_by_id`) return `Ok(None)`;
+//! - every other function (lists, creations, actions on an existing resource) returns an
+//!   [`Error::ApiError`], which can be recognized with [`Error::is_not_found()`]
+//!   or turned into `Ok(None)` with [`OptionalResultExt::optional()`].
 
-use std::{collections::HashMap, fmt};
+use crate::{
+    apis::payments::Currency, common::TL_CORRELATION_ID_HEADER, validation::ValidationError,
+};
+use bytes::Bytes;
+use reqwest::header::HeaderMap;
+use std::{collections::HashMap, fmt, time::Duration};
 
 /// Error collecting all possible failures of the TrueLayer client.
 #[derive(thiserror::Error, Debug)]
@@ -16,11 +30,99 @@ pub enum Error {
     /// Read more about signing here: <https://docs.truelayer.com/docs/signing-your-requests>
     #[error("Error signing request: {0}")]
     SigningError(#[from] truelayer_signing::Error),
+    /// The request was cancelled through the cancellation token set in its
+    /// [`RequestOptions`](crate::request_options::RequestOptions).
+    #[error("Request cancelled")]
+    Cancelled,
+    /// The body of a successful response does not match the expected model,
+    /// e.g. because TrueLayer started returning a value unknown to this version of the SDK.
+    #[error(
+        "Cannot deserialize the response of {endpoint} (HTTP {status}): {source}\nBody: {snippet}"
+    )]
+    Deserialization {
+        /// Path of the endpoint which returned the response.
+        endpoint: String,
+        /// HTTP status of the response.
+        status: u16,
+        /// Beginning of the response body, truncated to 512 bytes.
+        snippet: String,
+        source: serde_json::Error,
+    },
+    /// The request was rejected by the strict local validation enabled with
+    /// [`TrueLayerClientBuilder::with_request_validation()`](crate::client::TrueLayerClientBuilder::with_request_validation).
+    #[error("Invalid request: {0}")]
+    ValidationError(#[from] ValidationError),
+    /// No merchant account exists in the requested currency.
+    #[error("No merchant account in {0}")]
+    NoMerchantAccount(Currency),
+    /// No [`Runtime`](crate::runtime::Runtime) was set with
+    /// [`TrueLayerClientBuilder::with_runtime()`](crate::client::TrueLayerClientBuilder::with_runtime)
+    /// and none could be detected, e.g. because the client is used outside of a tokio runtime.
+    #[error("No async runtime available: use the client from within a tokio runtime or set one with `with_runtime()`")]
+    NoRuntime,
     /// Catch-all variant for unexpected errors.
     #[error(transparent)]
     Other(anyhow::Error),
 }
 
+impl Error {
+    /// Returns `true` if TrueLayer returned 404 because the requested resource does not exist.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Error::ApiError(api_error) if api_error.status == 404)
+    }
+
+    /// Returns the correlation id TrueLayer assigned to the failed request, if it was received.
+    ///
+    /// To get it for successful calls too, use
+    /// [`CorrelationIdExt`](crate::correlation_id::CorrelationIdExt).
+    pub fn tl_correlation_id(&self) -> Option<&str> {
+        match self {
+            Error::ApiError(api_error) => api_error
+                .headers
+                .get(TL_CORRELATION_ID_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .or(api_error.trace_id.as_deref()),
+            _ => None,
+        }
+    }
+
+    /// Returns how long TrueLayer asked to wait before retrying the failed request,
+    /// from its `Retry-After` header.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::ApiError(api_error) => api_error.retry_after,
+            _ => None,
+        }
+    }
+}
+
+/// Extension trait to treat a missing resource as `None` instead of an error.
+pub trait OptionalResultExt<T> {
+    /// Converts an error for which [`Error::is_not_found()`] is `true` into `Ok(None)`.
+    ///
+    /// ```rust,no_run
+    /// # use truelayer_rust::{TrueLayerClient, Error, OptionalResultExt};
+    /// # async fn run(tl: TrueLayerClient) -> Result<(), Error> {
+    /// match tl.payments.list_refunds("payment-id").await.optional()? {
+    ///     Some(refunds) => println!("{} refunds", refunds.len()),
+    ///     None => println!("Payment not found"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn optional(self) -> Result<Option<T>, Error>;
+}
+
+impl<T> OptionalResultExt<T> for Result<T, Error> {
+    fn optional(self) -> Result<Option<T>, Error> {
+        match self {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
 impl From<reqwest_middleware::Error> for Error {
     fn from(e: reqwest_middleware::Error) -> Self {
         match e {
@@ -38,6 +140,31 @@ impl From<Error> for reqwest_middleware::Error {
     }
 }
 
+/// Error returned when a builder has been configured with invalid values.
+#[derive(thiserror::Error, Debug)]
+pub enum BuildError {
+    /// A field of the builder has an invalid value.
+    #[error("Invalid value for `{field}`: {reason}")]
+    InvalidField {
+        /// Name of the invalid field.
+        field: &'static str,
+        /// Why the value is invalid.
+        reason: String,
+    },
+    /// The underlying HTTP client could not be built.
+    #[error("Failed to build the HTTP client: {0}")]
+    HttpClient(#[source] reqwest::Error),
+}
+
+impl BuildError {
+    pub(crate) fn invalid_field(field: &'static str, reason: impl Into<String>) -> Self {
+        BuildError::InvalidField {
+            field,
+            reason: reason.into(),
+        }
+    }
+}
+
 /// TrueLayer HTTP APIs error.
 #[derive(thiserror::Error, Debug)]
 pub struct ApiError {
@@ -57,6 +184,39 @@ pub struct ApiError {
     ///
     /// In the case of validation errors, this map contains a list of all the fields that failed validation.
     pub errors: HashMap<String, Vec<String>>,
+    /// Raw body of the response, if it could not be parsed as a TrueLayer error
+    /// (e.g. an HTML page returned by a proxy in front of the APIs).
+    ///
+    /// Truncated to the first [`ApiError::MAX_RAW_BODY_LEN`] bytes.
+    pub raw_body: Option<Bytes>,
+    /// Headers of the response.
+    pub headers: HeaderMap,
+    /// Path of the endpoint which returned the error, e.g. `/v3/payments`.
+    pub endpoint: String,
+    /// How long to wait before retrying, from the `Retry-After` header of the response.
+    pub retry_after: Option<Duration>,
+    /// Rate limit of the client, from the `*-RateLimit-*` headers of the response.
+    pub rate_limit: Option<RateLimit>,
+    /// How many times the request was silently retried after a transient failure
+    /// before this response was returned.
+    pub retries: u32,
+}
+
+/// Quota of requests of the client, as reported by TrueLayer
+/// in the `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset` headers.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct RateLimit {
+    /// Maximum number of requests in the current window.
+    pub limit: Option<u64>,
+    /// Number of requests left in the current window.
+    pub remaining: Option<u64>,
+    /// Time until the current window resets.
+    pub reset: Option<Duration>,
+}
+
+impl ApiError {
+    /// Maximum number of bytes of an unparseable response body kept in [`ApiError::raw_body`].
+    pub const MAX_RAW_BODY_LEN: usize = 4096;
 }
 
 impl fmt::Display for ApiError {
@@ -85,3 +245,56 @@ impl fmt::Display for ApiError {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(status: u16) -> Error {
+        Error::ApiError(ApiError {
+            r#type: "https://docs.truelayer.com/errors#error".to_string(),
+            title: "Error".to_string(),
+            status,
+            trace_id: None,
+            detail: None,
+            errors: HashMap::new(),
+            raw_body: None,
+            headers: HeaderMap::new(),
+            endpoint: "/endpoint".to_string(),
+            retry_after: None,
+            rate_limit: None,
+            retries: 0,
+        })
+    }
+
+    #[test]
+    fn optional_turns_not_found_into_none() {
+        assert_eq!(Ok::<_, Error>(42).optional().unwrap(), Some(42));
+        assert_eq!(Err::<i32, _>(api_error(404)).optional().unwrap(), None);
+        assert!(matches!(
+            Err::<i32, _>(api_error(500)).optional(),
+            Err(Error::ApiError(e)) if e.status == 500
+        ));
+        assert!(Err::<i32, _>(Error::Cancelled).optional().is_err());
+    }
+
+    #[test]
+    fn tl_correlation_id_prefers_the_header() {
+        let mut err = api_error(500);
+        assert_eq!(err.tl_correlation_id(), None);
+
+        if let Error::ApiError(ref mut api_error) = err {
+            api_error.trace_id = Some("trace-id".to_string());
+        }
+        assert_eq!(err.tl_correlation_id(), Some("trace-id"));
+
+        if let Error::ApiError(ref mut api_error) = err {
+            api_error.headers.insert(
+                reqwest::header::HeaderName::from_bytes(TL_CORRELATION_ID_HEADER.as_bytes())
+                    .unwrap(),
+                reqwest::header::HeaderValue::from_static("correlation-id"),
+            );
+        }
+        assert_eq!(err.tl_correlation_id(), Some("correlation-id"));
+    }
+}
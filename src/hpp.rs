@@ -0,0 +1,174 @@
+//! Packaging of links to the Hosted Payments Page for other channels than a browser redirect.
+//!
+//! An [`HppLink`] is built from the link returned by
+//! [`PaymentsApi::get_hosted_payments_page_link()`](crate::apis::payments::PaymentsApi::get_hosted_payments_page_link)
+//! and knows until when it can be used, which is bounded by the lifetime of the resource token.
+//! It can be rendered as the payload of a QR code, for users paying on another device,
+//! or as deep links for app-to-app flows:
+//!
+//! ```rust,no_run
+//! # use truelayer_rust::{TrueLayerClient, Error, apis::payments::CreatePaymentRequest, hpp::HppLink};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//! # let tl: TrueLayerClient = unreachable!();
+//! # let req: CreatePaymentRequest = unreachable!();
+//! let res = tl.payments.create(&req).await?;
+//! let link = HppLink::build(&tl, &res.id, &res.resource_token, "myapp://checkout/done").await;
+//!
+//! // Encode this text in a QR code with the library of your choice
+//! println!("QR code payload: {}", link.qr_code_payload());
+//!
+//! // Open the app of the merchant if installed, or the browser otherwise
+//! let deep_links = link.deep_links("myapp")?;
+//! println!("App link: {}, fallback: {}", deep_links.app, deep_links.https);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{apis::auth::ResourceToken, Error};
+#[cfg(feature = "payments")]
+use crate::{apis::payments::PaymentId, TrueLayerClient};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use url::Url;
+
+/// A link to the Hosted Payments Page, valid until its resource token expires.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HppLink {
+    url: Url,
+    expires_at: DateTime<Utc>,
+}
+
+/// Deep links to the Hosted Payments Page for app-to-app flows.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HppDeepLinks {
+    /// Link opened in the browser, or in the app if it's registered as a universal or app link.
+    pub https: Url,
+    /// The same link with the custom scheme of the app.
+    pub app: Url,
+    /// When the links stop working, because the resource token embedded in them expires.
+    pub expires_at: DateTime<Utc>,
+}
+
+impl HppLink {
+    /// Builds the link to the Hosted Payments Page for a payment.
+    ///
+    /// Note that the `return_uri` must be configured in your TrueLayer console.
+    #[cfg(feature = "payments")]
+    pub async fn build(
+        client: &TrueLayerClient,
+        payment_id: impl Into<PaymentId>,
+        resource_token: &ResourceToken,
+        return_uri: &str,
+    ) -> Self {
+        let url = client
+            .payments
+            .get_hosted_payments_page_link(payment_id, resource_token, return_uri)
+            .await;
+
+        Self::new(url, resource_token)
+    }
+
+    /// Wraps a link previously built for the given resource token.
+    pub fn new(url: Url, resource_token: &ResourceToken) -> Self {
+        Self {
+            url,
+            expires_at: resource_token.expires_at(),
+        }
+    }
+
+    /// Link to the Hosted Payments Page.
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Estimated expiration date of the link, based on the known TTL of resource tokens.
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        self.expires_at
+    }
+
+    /// Returns `true` if the link has probably expired and a new resource token is needed.
+    pub fn is_probably_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+
+    /// Text to encode in a QR code, so that the user can open the Hosted Payments Page
+    /// by scanning it with another device.
+    pub fn qr_code_payload(&self) -> String {
+        self.url.to_string()
+    }
+
+    /// Returns the link both as is and with the custom scheme `app_scheme` (e.g. `myapp`),
+    /// so that mobile apps can be opened directly in app-to-app flows.
+    pub fn deep_links(&self, app_scheme: &str) -> Result<HppDeepLinks, Error> {
+        let invalid = || Error::Other(anyhow!("Invalid app scheme: {:?}", app_scheme));
+
+        let app_scheme = app_scheme.strip_suffix("://").unwrap_or(app_scheme);
+        if !app_scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            || !app_scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        {
+            return Err(invalid());
+        }
+
+        // `Url::set_scheme` refuses to turn `https` into a non-special scheme
+        let without_scheme = &self.url.as_str()[self.url.scheme().len()..];
+        let app =
+            Url::parse(&format!("{}{}", app_scheme, without_scheme)).map_err(|_| invalid())?;
+
+        Ok(HppDeepLinks {
+            https: self.url.clone(),
+            app,
+            expires_at: self.expires_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::auth::Token;
+
+    fn link() -> (HppLink, ResourceToken) {
+        let resource_token = ResourceToken::from(Token::new("resource-token"));
+        let url = Url::parse(
+            "https://payment.truelayer.com/payments#payment_id=payment-id&resource_token=resource-token&return_uri=myapp://done",
+        )
+        .unwrap();
+
+        (HppLink::new(url, &resource_token), resource_token)
+    }
+
+    #[test]
+    fn link_expires_with_the_resource_token() {
+        let (link, resource_token) = link();
+
+        assert_eq!(link.expires_at(), resource_token.expires_at());
+        assert!(!link.is_probably_expired());
+        assert_eq!(link.qr_code_payload(), link.url().as_str());
+    }
+
+    #[test]
+    fn deep_links_use_the_app_scheme() {
+        let (link, _) = link();
+
+        let deep_links = link.deep_links("myapp://").unwrap();
+
+        assert_eq!(&deep_links.https, link.url());
+        assert_eq!(deep_links.app.scheme(), "myapp");
+        assert_eq!(deep_links.app.host_str(), Some("payment.truelayer.com"));
+        assert_eq!(deep_links.app.path(), "/payments");
+        assert_eq!(deep_links.app.fragment(), link.url().fragment());
+        assert_eq!(deep_links.expires_at, link.expires_at());
+    }
+
+    #[test]
+    fn invalid_app_scheme_is_rejected() {
+        let (link, _) = link();
+
+        assert!(matches!(link.deep_links("my app"), Err(Error::Other(_))));
+        assert!(matches!(link.deep_links("1app"), Err(Error::Other(_))));
+        assert!(matches!(link.deep_links(""), Err(Error::Other(_))));
+    }
+}
@@ -0,0 +1,129 @@
+//! Requests which can be persisted and safely re-submitted.
+
+use crate::{
+    apis::TrueLayerClientInner,
+    common::{DecodeJson, IDEMPOTENCY_KEY_HEADER},
+    Error, RequestOptions,
+};
+use reqwest::header::CONTENT_TYPE;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::marker::PhantomData;
+use uuid::Uuid;
+
+/// A request against an endpoint supporting [idempotency keys], together with the
+/// idempotency key it is sent with.
+///
+/// An `IdempotentRequest` can be serialized and persisted by the caller before being submitted.
+/// If the process crashes before a response is received, the request can be loaded back and
+/// re-submitted with [`TrueLayerClient::resubmit()`]: since the idempotency key and the body
+/// are exactly the same, the operation is executed at most once.
+///
+/// `T` is the type of the response returned by the endpoint.
+///
+/// ```rust,no_run
+/// # use truelayer_rust::{TrueLayerClient, Error, IdempotentRequest};
+/// # use truelayer_rust::apis::payments::{CreatePaymentRequest, CreatePaymentResponse};
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// # let tl: TrueLayerClient = unreachable!();
+/// # let create_payment_request: CreatePaymentRequest = unreachable!();
+/// let request = tl.payments.prepare_create(&create_payment_request)?;
+///
+/// // Persist the request somewhere before submitting it
+/// let persisted = serde_json::to_string(&request).unwrap();
+///
+/// // ... and, possibly after a restart, submit it
+/// let request: IdempotentRequest<CreatePaymentResponse> = serde_json::from_str(&persisted).unwrap();
+/// let res = tl.resubmit(&request).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [idempotency keys]: https://docs.truelayer.com/docs/idempotency
+/// [`TrueLayerClient::resubmit()`]: crate::TrueLayerClient::resubmit
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(bound = "")]
+pub struct IdempotentRequest<T> {
+    idempotency_key: Uuid,
+    path: String,
+    body: String,
+    #[serde(skip)]
+    _response: PhantomData<fn() -> T>,
+}
+
+// Implemented by hand as deriving would require `T` to implement these traits too,
+// while it is only a marker for the type of the response.
+impl<T> Clone for IdempotentRequest<T> {
+    fn clone(&self) -> Self {
+        Self {
+            idempotency_key: self.idempotency_key,
+            path: self.path.clone(),
+            body: self.body.clone(),
+            _response: PhantomData,
+        }
+    }
+}
+
+impl<T> PartialEq for IdempotentRequest<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.idempotency_key == other.idempotency_key
+            && self.path == other.path
+            && self.body == other.body
+    }
+}
+
+impl<T> Eq for IdempotentRequest<T> {}
+
+impl<T> IdempotentRequest<T> {
+    pub(crate) fn new<B: Serialize>(
+        path: &str,
+        body: &B,
+        idempotency_key: Uuid,
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            idempotency_key,
+            path: path.to_string(),
+            body: serde_json::to_string(body).map_err(|e| Error::Other(e.into()))?,
+            _response: PhantomData,
+        })
+    }
+
+    /// Idempotency key sent with the request.
+    pub fn idempotency_key(&self) -> Uuid {
+        self.idempotency_key
+    }
+
+    /// Path of the endpoint, relative to the payments API base URL.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Serialized JSON body of the request.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+}
+
+impl<T: DeserializeOwned> IdempotentRequest<T> {
+    pub(crate) async fn send(
+        &self,
+        inner: &TrueLayerClientInner,
+        options: &RequestOptions,
+    ) -> Result<T, Error> {
+        let res = inner
+            .client
+            .post(inner.environment.payments_endpoint(&self.path))
+            .header(IDEMPOTENCY_KEY_HEADER, self.idempotency_key.to_string())
+            .header(CONTENT_TYPE, "application/json")
+            // A buffered body can be replayed when the request is retried
+            .body(self.body.clone())
+            .with_extension(options.clone())
+            .send()
+            .await?
+            .decode_json()
+            .await?;
+
+        Ok(res)
+    }
+}
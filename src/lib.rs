@@ -66,14 +66,17 @@
 //!                 account_holder_name: None,
 //!                 reference: None,
 //!                 statement_reference: None,
+//!                 verification: None,
 //!             },
 //!         },
-//!         user: CreatePaymentUserRequest::NewUser {
-//!             name: Some("Some One".to_string()),
-//!             email: Some("some.one@email.com".to_string()),
-//!             phone: None,
-//!         },
-//!         metadata: None
+//!         user: CreatePaymentUserRequest::new_user()
+//!             .with_name("Some One")
+//!             .with_email("some.one@email.com")
+//!             .build(),
+//!         metadata: None,
+//!         schedule: None,
+//!         related_products: None,
+//!         risk_assessment: None,
 //!     })
 //!     .await?;
 //!
@@ -128,6 +131,29 @@
 //! # }
 //! ```
 //!
+//! ## Tracing
+//!
+//! The library emits spans and events through [`tracing`](https://docs.rs/tracing)
+//! under the following targets, which can be filtered independently
+//! (e.g. `RUST_LOG=info,truelayer_rust::auth=debug,truelayer_rust::polling=warn`):
+//!
+//! | Target | Level | Emitted for |
+//! |---|---|---|
+//! | `truelayer_rust::auth` | `debug` span, `info` events | Access token requests and credential updates |
+//! | `truelayer_rust::payments` | `info` spans | Payments and refunds operations |
+//! | `truelayer_rust::payments_providers` | `info` spans, `debug` events | Provider lookups and cache hits |
+//! | `truelayer_rust::payouts` | `info` spans | Payouts operations |
+//! | `truelayer_rust::mandates` | `info` spans | Mandates operations |
+//! | `truelayer_rust::merchant_accounts` | `info` spans | Merchant accounts, sweeping and transactions |
+//! | `truelayer_rust::data` | `info` spans | Accounts, balances and transactions of the Data API |
+//! | `truelayer_rust::checkout` | `info` spans | [`CheckoutSession`](checkout::CheckoutSession) |
+//! | `truelayer_rust::reconciliation` | `info` spans | Reconciliation runs |
+//! | `truelayer_rust::client` | `info` spans | Client warm up and idempotent resubmissions |
+//! | `truelayer_rust::polling` | `info` span, `debug` events | Each poll loop and the waits between attempts |
+//! | `truelayer_rust::http` | `info` spans, `debug`/`warn` events | Outgoing HTTP requests, failed responses and undecodable bodies |
+//! | `truelayer_rust::retry` | `warn`/`debug` events | Retried requests, exhausted retry budgets and non-replayable bodies |
+//! | `truelayer_rust::sandbox` | `info` spans | Sandbox tools such as the mock bank |
+//!
 //! ## More examples
 //!
 //! Look into the [`examples`](../examples) for more example usages of this library.
@@ -140,15 +166,40 @@
 
 #![deny(missing_debug_implementations)]
 #![forbid(unsafe_code)]
+// `ApiError` carries the details of the failed response and is returned by value so that
+// callers can match on it: boxing it would only trade these lints for an allocation on every
+// error and a less ergonomic `Error`.
+#![allow(clippy::result_large_err, clippy::large_enum_variant)]
+// The helpers shared by the API clients are unused when all of them are disabled.
+#![cfg_attr(
+    not(any(feature = "payments", feature = "payouts", feature = "mandates")),
+    allow(dead_code)
+)]
 
 pub mod apis;
 pub(crate) mod authenticator;
+#[cfg(feature = "payments")]
+pub mod checkout;
 pub mod client;
+pub mod client_pool;
 mod common;
+pub mod correlation_id;
 pub mod error;
+pub mod hpp;
+pub mod idempotent_request;
 mod middlewares;
 pub mod pollable;
+#[cfg(all(feature = "payments", feature = "merchant-accounts"))]
+pub mod reconciliation;
+pub mod request_options;
+pub mod retry;
+pub mod runtime;
+pub mod sandbox;
+pub mod url_scrubber;
+pub mod validation;
 
 pub use client::TrueLayerClient;
-pub use error::Error;
+pub use error::{BuildError, Error, OptionalResultExt};
+pub use idempotent_request::IdempotentRequest;
 pub use pollable::{Pollable, PollableUntilTerminalState};
+pub use request_options::RequestOptions;
@@ -6,6 +6,9 @@ use task_local_extensions::Extensions;
 
 /// Reqwest middleware to inject the access token into outgoing HTTP requests.
 /// On the first request, an additional HTTP request will be fired to get a new access token.
+///
+/// If the request has a timeout, the token is refreshed beforehand when it could expire
+/// before the request completes.
 pub struct AuthenticationMiddleware {
     pub(crate) authenticator: Authenticator,
 }
@@ -18,8 +21,12 @@ impl Middleware for AuthenticationMiddleware {
         extensions: &mut Extensions,
         next: Next<'_>,
     ) -> reqwest_middleware::Result<Response> {
-        // Request an access token from the authenticator
-        let access_token = self.authenticator.get_access_token().await?;
+        // Request an access token which is still valid when the request times out
+        let validity = req.timeout().copied().unwrap_or_default();
+        let access_token = self
+            .authenticator
+            .get_access_token_valid_for(validity)
+            .await?;
 
         // Inject the access token as a header
         let mut header_value = HeaderValue::from_str(&format!(
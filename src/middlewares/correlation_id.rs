@@ -0,0 +1,66 @@
+use crate::{common::TL_CORRELATION_ID_HEADER, correlation_id};
+use async_trait::async_trait;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+/// Middleware which records the correlation id of every response for
+/// [`CorrelationIdExt`](crate::correlation_id::CorrelationIdExt).
+///
+/// It must run before the response is turned into an error, so that failed calls are recorded too.
+pub struct CorrelationIdMiddleware;
+
+#[async_trait]
+impl Middleware for CorrelationIdMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let res = next.run(req, extensions).await;
+
+        match res {
+            Ok(ref response) => correlation_id::record(
+                response
+                    .headers()
+                    .get(TL_CORRELATION_ID_HEADER)
+                    .and_then(|v| v.to_str().ok()),
+            ),
+            Err(_) => correlation_id::record(None),
+        }
+
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::correlation_id::CorrelationIdExt;
+    use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn correlation_id_is_recorded() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/test"))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .append_header(TL_CORRELATION_ID_HEADER, "correlation-id"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(CorrelationIdMiddleware)
+            .build();
+
+        let res = client
+            .get(format!("{}/test", mock_server.uri()))
+            .send()
+            .with_tl_correlation_id()
+            .await;
+
+        assert_eq!(res.tl_correlation_id(), Some("correlation-id"));
+    }
+}
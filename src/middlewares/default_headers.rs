@@ -0,0 +1,35 @@
+use async_trait::async_trait;
+use reqwest::{header::HeaderMap, Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+/// Middleware to inject a set of default headers to all outgoing requests.
+///
+/// Headers already present on the request are left untouched.
+pub struct DefaultHeadersMiddleware {
+    headers: HeaderMap,
+}
+
+impl DefaultHeadersMiddleware {
+    pub fn new(headers: HeaderMap) -> Self {
+        Self { headers }
+    }
+}
+
+#[async_trait]
+impl Middleware for DefaultHeadersMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        for (name, value) in &self.headers {
+            req.headers_mut()
+                .entry(name)
+                .or_insert_with(|| value.clone());
+        }
+
+        next.run(req, extensions).await
+    }
+}
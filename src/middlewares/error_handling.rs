@@ -1,11 +1,16 @@
 use crate::{
     common::TL_CORRELATION_ID_HEADER,
-    error::{ApiError, Error},
+    error::{ApiError, Error, RateLimit},
+    middlewares::retry_idempotent::RetriesPerformed,
 };
 use async_trait::async_trait;
-use reqwest::{Request, Response};
+use chrono::{DateTime, Utc};
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    Request, Response,
+};
 use reqwest_middleware::{Middleware, Next};
-use std::collections::HashMap;
+use std::{collections::HashMap, time::Duration};
 use task_local_extensions::Extensions;
 
 /// Reqwest middleware which translates JSON error responses returned from TrueLayer APIs
@@ -25,9 +30,17 @@ impl Middleware for ErrorHandlingMiddleware {
 
         // Build an ApiError if the response is not a success
         if !response.status().is_success() {
-            tracing::debug!("Failed HTTP request. Status code: {}", response.status());
+            tracing::debug!(
+                target: "truelayer_rust::http",
+                "Failed HTTP request. Status code: {}",
+                response.status()
+            );
 
-            let api_error = api_error_from_response(response).await?;
+            let retries = extensions
+                .get::<RetriesPerformed>()
+                .copied()
+                .unwrap_or_default();
+            let api_error = api_error_from_response(response, retries).await?;
             return Err(Error::ApiError(api_error).into());
         }
 
@@ -54,13 +67,19 @@ enum ErrorResponseBody {
     Unknown,
 }
 
-async fn api_error_from_response(response: Response) -> reqwest_middleware::Result<ApiError> {
+async fn api_error_from_response(
+    response: Response,
+    RetriesPerformed(retries): RetriesPerformed,
+) -> reqwest_middleware::Result<ApiError> {
     let status = response.status().as_u16();
-    let tl_correlation_id = response
-        .headers()
+    let headers = response.headers().clone();
+    let tl_correlation_id = headers
         .get(TL_CORRELATION_ID_HEADER)
         .and_then(|v| v.to_str().ok())
         .map(|v| v.to_string());
+    let endpoint = response.url().path().to_string();
+    let retry_after = parse_retry_after(&headers, Utc::now());
+    let rate_limit = parse_rate_limit(&headers);
 
     // Parse the response body as JSON
     let bytes = response.bytes().await?;
@@ -82,6 +101,12 @@ async fn api_error_from_response(response: Response) -> reqwest_middleware::Resu
             trace_id: Some(trace_id),
             detail: Some(detail),
             errors: errors.unwrap_or_default(),
+            raw_body: None,
+            headers,
+            endpoint,
+            retry_after,
+            rate_limit,
+            retries,
         },
         ErrorResponseBody::V1ErrorResponse {
             error,
@@ -96,6 +121,12 @@ async fn api_error_from_response(response: Response) -> reqwest_middleware::Resu
             errors: error_details
                 .map(|errors| errors.into_iter().map(|(k, v)| (k, vec![v])).collect())
                 .unwrap_or_default(),
+            raw_body: None,
+            headers,
+            endpoint,
+            retry_after,
+            rate_limit,
+            retries,
         },
         ErrorResponseBody::Unknown => ApiError {
             r#type: "https://docs.truelayer.com/docs/error-types".to_string(),
@@ -104,12 +135,56 @@ async fn api_error_from_response(response: Response) -> reqwest_middleware::Resu
             trace_id: tl_correlation_id,
             detail: None,
             errors: Default::default(),
+            // Keep what was actually returned, as it's the only clue to what went wrong
+            raw_body: (!bytes.is_empty())
+                .then(|| bytes.slice(..bytes.len().min(ApiError::MAX_RAW_BODY_LEN))),
+            headers,
+            endpoint,
+            retry_after,
+            rate_limit,
+            retries,
         },
     };
 
     Ok(api_error)
 }
 
+/// Parses the `Retry-After` header, given either as a number of seconds or as an HTTP date.
+fn parse_retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => {
+            let date = DateTime::parse_from_rfc2822(value).ok()?;
+            Some(
+                (date.with_timezone(&Utc) - now)
+                    .to_std()
+                    .unwrap_or_default(),
+            )
+        }
+    }
+}
+
+/// Parses the rate limit headers, whatever their prefix (e.g. `X-RateLimit-Remaining`).
+fn parse_rate_limit(headers: &HeaderMap) -> Option<RateLimit> {
+    // Header names are always lowercase
+    let header = |suffix: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.as_str().ends_with(suffix))
+            .and_then(|(_, value)| value.to_str().ok()?.trim().parse::<u64>().ok())
+    };
+
+    let rate_limit = RateLimit {
+        limit: header("ratelimit-limit"),
+        remaining: header("ratelimit-remaining"),
+        reset: header("ratelimit-reset").map(Duration::from_secs),
+    };
+
+    (rate_limit != RateLimit::default()).then_some(rate_limit)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -281,4 +356,112 @@ mod tests {
         assert_eq!(api_error.errors, HashMap::new());
         assert_eq!(api_error.trace_id.as_deref(), Some("correlation-id"));
     }
+
+    #[tokio::test]
+    async fn unparseable_server_errors_keep_raw_body_and_headers() {
+        let mock_server = MockServer::start().await;
+        let html = format!(
+            "<html><body>502 Bad Gateway{}</body></html>",
+            " ".repeat(ApiError::MAX_RAW_BODY_LEN)
+        );
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(502).set_body_raw(html.clone(), "text/html"))
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(ErrorHandlingMiddleware)
+            .build();
+
+        let err: Error = client
+            .get(mock_server.uri())
+            .send()
+            .await
+            .expect_err("Call succeeded")
+            .into();
+
+        let api_error = match err {
+            Error::ApiError(api_error) => api_error,
+            e => panic!("Unexpected error: {}", e),
+        };
+
+        assert_eq!(api_error.status, 502);
+        assert_eq!(api_error.title, "server_error");
+        assert_eq!(
+            api_error.raw_body.as_deref(),
+            Some(&html.as_bytes()[..ApiError::MAX_RAW_BODY_LEN])
+        );
+        assert_eq!(api_error.headers.get("Content-Type").unwrap(), "text/html");
+    }
+
+    #[tokio::test]
+    async fn rate_limit_metadata_is_parsed() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .append_header("Retry-After", "30")
+                    .append_header("X-RateLimit-Limit", "100")
+                    .append_header("X-RateLimit-Remaining", "0")
+                    .append_header("X-RateLimit-Reset", "30")
+                    .set_body_json(json!({
+                        "type": "https://docs.truelayer.com/docs/error-types#too-many-requests",
+                        "title": "Too Many Requests",
+                        "status": 429,
+                        "trace_id": "trace-id",
+                        "detail": "Rate limit exceeded"
+                    })),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(ErrorHandlingMiddleware)
+            .build();
+
+        let err: Error = client
+            .get(format!("{}/v3/payments", mock_server.uri()))
+            .send()
+            .await
+            .expect_err("Call succeeded")
+            .into();
+
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(30)));
+        let api_error = match err {
+            Error::ApiError(api_error) => api_error,
+            e => panic!("Unexpected error: {}", e),
+        };
+        assert_eq!(api_error.endpoint, "/v3/payments");
+        assert_eq!(
+            api_error.rate_limit,
+            Some(RateLimit {
+                limit: Some(100),
+                remaining: Some(0),
+                reset: Some(Duration::from_secs(30)),
+            })
+        );
+    }
+
+    #[test]
+    fn retry_after_accepts_http_dates() {
+        let now = "2022-06-10T10:00:00Z".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            RETRY_AFTER,
+            "Fri, 10 Jun 2022 10:01:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(
+            parse_retry_after(&headers, now),
+            Some(Duration::from_secs(60))
+        );
+
+        // Dates in the past mean the request can be retried straight away
+        headers.insert(
+            RETRY_AFTER,
+            "Fri, 10 Jun 2022 09:00:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(parse_retry_after(&headers, now), Some(Duration::ZERO));
+
+        assert_eq!(parse_rate_limit(&headers), None);
+    }
 }
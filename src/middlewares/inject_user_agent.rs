@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use reqwest::{
-    header::{HeaderValue, USER_AGENT},
+    header::{HeaderValue, InvalidHeaderValue, USER_AGENT},
     Request, Response,
 };
 use reqwest_middleware::{Middleware, Next};
@@ -12,15 +12,32 @@ pub struct InjectUserAgentMiddleware {
 }
 
 impl InjectUserAgentMiddleware {
-    pub fn new() -> Self {
-        Self {
-            user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"))
-                .parse()
-                .unwrap(),
-        }
+    pub fn new(user_agent: HeaderValue) -> Self {
+        Self { user_agent }
     }
 }
 
+/// Builds the `User-Agent` header sent by the client.
+///
+/// The header starts with the name of this crate, followed by its version unless `send_sdk_version`
+/// is `false`, and ends with the application identifier if one is given.
+pub fn build_user_agent(
+    app_info: Option<&(String, String)>,
+    send_sdk_version: bool,
+) -> Result<HeaderValue, InvalidHeaderValue> {
+    let mut user_agent = if send_sdk_version {
+        concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_string()
+    } else {
+        env!("CARGO_PKG_NAME").to_string()
+    };
+
+    if let Some((name, version)) = app_info {
+        user_agent.push_str(&format!(" {}/{}", name, version));
+    }
+
+    HeaderValue::from_str(&user_agent)
+}
+
 #[async_trait]
 impl Middleware for InjectUserAgentMiddleware {
     async fn handle(
@@ -35,3 +52,34 @@ impl Middleware for InjectUserAgentMiddleware {
         next.run(req, extensions).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_agent_includes_sdk_version_by_default() {
+        assert_eq!(
+            build_user_agent(None, true).unwrap(),
+            concat!("truelayer-rust/", env!("CARGO_PKG_VERSION"))
+        );
+    }
+
+    #[test]
+    fn user_agent_with_app_info_and_without_sdk_version() {
+        let app_info = ("my-shop".to_string(), "2.1.0".to_string());
+
+        assert_eq!(
+            build_user_agent(Some(&app_info), true).unwrap(),
+            concat!(
+                "truelayer-rust/",
+                env!("CARGO_PKG_VERSION"),
+                " my-shop/2.1.0"
+            )
+        );
+        assert_eq!(
+            build_user_agent(Some(&app_info), false).unwrap(),
+            "truelayer-rust my-shop/2.1.0"
+        );
+    }
+}
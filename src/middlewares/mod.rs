@@ -1,5 +1,10 @@
 pub mod authentication;
+pub mod correlation_id;
+pub mod default_headers;
 pub mod error_handling;
 pub mod inject_user_agent;
+pub mod request_options;
 pub mod retry_idempotent;
+pub mod scrubbed_tracing;
 pub mod signing;
+pub mod url_rewriter;
@@ -0,0 +1,195 @@
+use crate::{error::Error, request_options::RequestOptions};
+use async_trait::async_trait;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next};
+use task_local_extensions::Extensions;
+
+/// Middleware which applies the [`RequestOptions`](crate::request_options::RequestOptions)
+/// attached to a request as an extension.
+pub struct RequestOptionsMiddleware;
+
+#[async_trait]
+impl Middleware for RequestOptionsMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let options = match extensions.get::<RequestOptions>() {
+            Some(options) => options.clone(),
+            None => return next.run(req, extensions).await,
+        };
+
+        // Custom headers
+        for (name, value) in &options.headers {
+            req.headers_mut().insert(name, value.clone());
+        }
+
+        // Extra body fields
+        #[cfg(feature = "extra-fields")]
+        if !options.extra_fields.is_empty() {
+            add_extra_fields(&mut req, &options.extra_fields)?;
+        }
+
+        // Timeout
+        if let Some(timeout) = options.timeout {
+            *req.timeout_mut() = Some(timeout);
+        }
+
+        // Cancellation
+        match options.cancellation_token {
+            Some(cancellation_token) => {
+                tokio::select! {
+                    _ = cancellation_token.cancelled() => Err(Error::Cancelled.into()),
+                    res = next.run(req, extensions) => res,
+                }
+            }
+            None => next.run(req, extensions).await,
+        }
+    }
+}
+
+/// Merges the extra fields into the JSON object in the body of the request.
+#[cfg(feature = "extra-fields")]
+fn add_extra_fields(
+    req: &mut Request,
+    extra_fields: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(), Error> {
+    let body = match req.body() {
+        Some(body) => body,
+        None => return Ok(()),
+    };
+
+    let mut object: serde_json::Map<String, serde_json::Value> = body
+        .as_bytes()
+        .and_then(|bytes| serde_json::from_slice(bytes).ok())
+        .ok_or_else(|| {
+            Error::Other(anyhow::anyhow!(
+                "Extra fields can only be added to a JSON object body"
+            ))
+        })?;
+    object.extend(extra_fields.clone());
+
+    let body = serde_json::to_vec(&object).map_err(|e| Error::Other(e.into()))?;
+    *req.body_mut() = Some(body.into());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderName, HeaderValue};
+    use reqwest_middleware::ClientWithMiddleware;
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+    use wiremock::{
+        matchers::{header, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    fn mock_client() -> ClientWithMiddleware {
+        reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(RequestOptionsMiddleware)
+            .build()
+    }
+
+    #[tokio::test]
+    async fn headers_are_attached() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/test"))
+            .and(header("x-custom-header", "custom-value"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let res = mock_client()
+            .get(format!("{}/test", mock_server.uri()))
+            .with_extension(RequestOptions::default().with_header(
+                HeaderName::from_static("x-custom-header"),
+                HeaderValue::from_static("custom-value"),
+            ))
+            .send()
+            .await
+            .unwrap();
+
+        assert!(res.status().is_success());
+    }
+
+    #[tokio::test]
+    async fn timeout_is_applied() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/test"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let err: Error = mock_client()
+            .get(format!("{}/test", mock_server.uri()))
+            .with_extension(RequestOptions::default().with_timeout(Duration::from_millis(100)))
+            .send()
+            .await
+            .expect_err("Call succeeded")
+            .into();
+
+        assert!(matches!(err, Error::HttpError(e) if e.is_timeout()));
+    }
+
+    #[tokio::test]
+    async fn cancellation_aborts_request() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/test"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+            .mount(&mock_server)
+            .await;
+
+        let cancellation_token = CancellationToken::new();
+        let cancellation_token_clone = cancellation_token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            cancellation_token_clone.cancel();
+        });
+
+        let err: Error = mock_client()
+            .get(format!("{}/test", mock_server.uri()))
+            .with_extension(RequestOptions::default().with_cancellation_token(cancellation_token))
+            .send()
+            .await
+            .expect_err("Call succeeded")
+            .into();
+
+        assert!(matches!(err, Error::Cancelled));
+    }
+
+    #[cfg(feature = "extra-fields")]
+    #[tokio::test]
+    async fn extra_fields_are_merged_into_the_body() {
+        use wiremock::matchers::body_json;
+
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/test"))
+            .and(body_json(serde_json::json!({
+                "amount_in_minor": 100,
+                "new_field": { "enabled": true },
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let res = mock_client()
+            .post(format!("{}/test", mock_server.uri()))
+            .json(&serde_json::json!({ "amount_in_minor": 100 }))
+            .with_extension(
+                RequestOptions::default()
+                    .with_extra_field("new_field", serde_json::json!({ "enabled": true })),
+            )
+            .send()
+            .await
+            .unwrap();
+
+        assert!(res.status().is_success());
+    }
+}
@@ -1,8 +1,14 @@
-use crate::common::IDEMPOTENCY_KEY_HEADER;
+use crate::{
+    common::IDEMPOTENCY_KEY_HEADER,
+    middlewares::scrubbed_tracing::url_scrubber,
+    retry::{RetryBudget, RetryCause, RetryEvent},
+    runtime::{resolve_runtime, Runtime},
+};
 use async_trait::async_trait;
+use chrono::Utc;
 use reqwest::{Method, Request, Response};
 use reqwest_middleware::{Middleware, Next};
-use reqwest_retry::RetryTransientMiddleware;
+use reqwest_retry::Retryable;
 use retry_policies::{RetryDecision, RetryPolicy};
 use std::{
     fmt::{Debug, Formatter},
@@ -18,16 +24,125 @@ use task_local_extensions::Extensions;
 ///
 /// For more information regarding idempotent methods, check section 4.2.2 of
 /// [RFC 7231](https://datatracker.ietf.org/doc/html/rfc7231#section-4.2.2).
+///
+/// Every retry is reported as a `tracing` event and to the optional [`RetryObserver`],
+/// with the URL redacted by the [`UrlScrubber`](crate::url_scrubber::UrlScrubber) of the client.
+/// If a [`RetryBudget`] is set, retries are skipped once it is exhausted.
+///
+/// Requests with a streaming body cannot be replayed, so they are sent once without retries.
+///
+/// The number of retries performed is stored as [`RetriesPerformed`] in the extensions of the request.
+#[derive(Clone)]
 pub struct RetryIdempotentMiddleware {
-    inner: RetryTransientMiddleware<DynRetryPolicy>,
+    retry_policy: DynRetryPolicy,
+    observer: Option<RetryObserver>,
+    budget: Option<RetryBudget>,
+    runtime: Option<Arc<dyn Runtime>>,
 }
 
 impl RetryIdempotentMiddleware {
-    pub fn new(retry_policy: DynRetryPolicy) -> Self {
+    pub fn new(
+        retry_policy: DynRetryPolicy,
+        observer: Option<RetryObserver>,
+        budget: Option<RetryBudget>,
+        runtime: Option<Arc<dyn Runtime>>,
+    ) -> Self {
         Self {
-            inner: RetryTransientMiddleware::new_with_policy(retry_policy),
+            retry_policy,
+            observer,
+            budget,
+            runtime,
         }
     }
+
+    async fn handle_with_retries(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        let mut n_past_retries = 0;
+        let result = loop {
+            let duplicate_request = match req.try_clone() {
+                Some(duplicate_request) => duplicate_request,
+                None => {
+                    // Streaming bodies (e.g. file uploads) are consumed while being sent,
+                    // therefore these requests cannot be replayed: send them only once
+                    tracing::debug!(
+                        target: "truelayer_rust::retry",
+                        "Request body cannot be replayed, retries are disabled",
+                    );
+                    break next.run(req, extensions).await;
+                }
+            };
+            let result = next.clone().run(duplicate_request, extensions).await;
+
+            if !matches!(
+                Retryable::from_reqwest_response(&result),
+                Some(Retryable::Transient)
+            ) {
+                break result;
+            }
+
+            let execute_after = match self.retry_policy.should_retry(n_past_retries) {
+                RetryDecision::Retry { execute_after } => execute_after,
+                RetryDecision::DoNotRetry => break result,
+            };
+            let wait = (execute_after - Utc::now()).to_std().unwrap_or_default();
+
+            let event = RetryEvent {
+                attempt: n_past_retries + 2,
+                wait,
+                method: req.method().clone(),
+                url: url_scrubber(extensions).scrub(req.url()),
+                idempotency_key: req
+                    .headers()
+                    .get(IDEMPOTENCY_KEY_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+                cause: retry_cause(&result),
+            };
+
+            if let Some(budget) = &self.budget {
+                if !budget.try_acquire() {
+                    tracing::warn!(
+                        target: "truelayer_rust::retry",
+                        attempt = event.attempt,
+                        method = %event.method,
+                        url = %event.url,
+                        idempotency_key = event.idempotency_key.as_deref(),
+                        cause = %event.cause,
+                        "Not retrying request after a transient failure: retry budget exhausted"
+                    );
+                    budget.report_exhausted(&event);
+                    break result;
+                }
+            }
+
+            n_past_retries += 1;
+            tracing::warn!(
+                target: "truelayer_rust::retry",
+                attempt = event.attempt,
+                wait_ms = event.wait.as_millis() as u64,
+                method = %event.method,
+                url = %event.url,
+                idempotency_key = event.idempotency_key.as_deref(),
+                cause = %event.cause,
+                "Retrying request after a transient failure"
+            );
+            if let Some(observer) = &self.observer {
+                (observer.0)(&event);
+            }
+
+            match resolve_runtime(self.runtime.as_ref()) {
+                Ok(runtime) => runtime.sleep(wait).await,
+                Err(e) => break Err(reqwest_middleware::Error::Middleware(e.into())),
+            }
+        };
+
+        extensions.insert(RetriesPerformed(n_past_retries));
+        result
+    }
 }
 
 #[async_trait]
@@ -48,19 +163,33 @@ impl Middleware for RetryIdempotentMiddleware {
             Method::POST | Method::PATCH => req
                 .headers()
                 .get(IDEMPOTENCY_KEY_HEADER)
-                .map_or(false, |v| !v.is_empty()),
+                .is_some_and(|v| !v.is_empty()),
             _ => false,
         };
 
-        // If the request is idempotent, use the retry middleware, otherwise, do nothing
+        // If the request is idempotent, retry transient failures, otherwise, do nothing
         if is_idempotent {
-            self.inner.handle(req, extensions, next).await
+            self.handle_with_retries(req, extensions, next).await
         } else {
             next.run(req, extensions).await
         }
     }
 }
 
+/// Number of times a request was retried by the [`RetryIdempotentMiddleware`]
+/// after a transient failure, before its final response.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct RetriesPerformed(pub u32);
+
+fn retry_cause(result: &reqwest_middleware::Result<Response>) -> RetryCause {
+    match result {
+        Ok(res) => RetryCause::Status(res.status()),
+        Err(reqwest_middleware::Error::Reqwest(e)) if e.is_timeout() => RetryCause::Timeout,
+        Err(reqwest_middleware::Error::Reqwest(e)) if e.is_connect() => RetryCause::Connect,
+        Err(_) => RetryCause::Other,
+    }
+}
+
 /// Wrapper type around a retry policy because `dyn RetryPolicy` does not implement `RetryPolicy`.
 #[derive(Clone)]
 pub struct DynRetryPolicy(pub Arc<dyn RetryPolicy + Send + Sync + 'static>);
@@ -77,11 +206,23 @@ impl Debug for DynRetryPolicy {
     }
 }
 
+/// Wrapper type around a callback invoked every time a request is retried.
+#[derive(Clone)]
+pub struct RetryObserver(pub Arc<dyn Fn(&RetryEvent) + Send + Sync + 'static>);
+
+impl Debug for RetryObserver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryObserver").finish_non_exhaustive()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{middlewares::error_handling::ErrorHandlingMiddleware, Error};
     use reqwest_middleware::ClientWithMiddleware;
     use reqwest_retry::policies::ExponentialBackoff;
+    use std::{sync::Mutex, time::Duration};
     use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
 
     async fn mock_client_and_server(expects_retry: bool) -> (ClientWithMiddleware, MockServer) {
@@ -103,9 +244,12 @@ mod tests {
 
         let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
         let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
-            .with(RetryIdempotentMiddleware::new(DynRetryPolicy(Arc::new(
-                retry_policy,
-            ))))
+            .with(RetryIdempotentMiddleware::new(
+                DynRetryPolicy(Arc::new(retry_policy)),
+                None,
+                None,
+                None,
+            ))
             .build();
 
         (client, mock_server)
@@ -175,4 +319,146 @@ mod tests {
             assert!(res.status().is_client_error());
         }
     }
+
+    #[tokio::test]
+    async fn streaming_bodies_are_sent_once() {
+        let (client, mock_server) = mock_client_and_server(false).await;
+
+        let body = futures::stream::iter(vec![Ok::<_, std::io::Error>("streamed-body")]);
+        let res = client
+            .put(mock_server.uri())
+            .body(reqwest::Body::wrap_stream(body))
+            .send()
+            .await
+            .unwrap();
+        assert!(res.status().is_client_error());
+    }
+
+    #[tokio::test]
+    async fn api_errors_report_the_number_of_retries() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(Duration::from_millis(1), Duration::from_millis(10))
+            .build_with_max_retries(2);
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(ErrorHandlingMiddleware)
+            .with(RetryIdempotentMiddleware::new(
+                DynRetryPolicy(Arc::new(retry_policy)),
+                None,
+                None,
+                None,
+            ))
+            .build();
+
+        let err: Error = client
+            .get(mock_server.uri())
+            .send()
+            .await
+            .expect_err("Call succeeded")
+            .into();
+
+        assert!(matches!(err, Error::ApiError(e) if e.status == 503 && e.retries == 2));
+    }
+
+    #[tokio::test]
+    async fn reports_retries_to_observer() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(path("/"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock_server)
+            .await;
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let observer = {
+            let events = events.clone();
+            RetryObserver(Arc::new(move |event: &RetryEvent| {
+                events.lock().unwrap().push(event.clone())
+            }))
+        };
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(Duration::from_millis(1), Duration::from_millis(10))
+            .build_with_max_retries(3);
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(RetryIdempotentMiddleware::new(
+                DynRetryPolicy(Arc::new(retry_policy)),
+                Some(observer),
+                None,
+                None,
+            ))
+            .build();
+
+        let res = client
+            .post(mock_server.uri())
+            .header(IDEMPOTENCY_KEY_HEADER, "some-idempotency-key")
+            .send()
+            .await
+            .unwrap();
+        assert!(res.status().is_success());
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        for (i, event) in events.iter().enumerate() {
+            assert_eq!(event.attempt, i as u32 + 2);
+            assert_eq!(event.method, Method::POST);
+            assert_eq!(
+                event.idempotency_key.as_deref(),
+                Some("some-idempotency-key")
+            );
+            assert_eq!(
+                event.cause,
+                RetryCause::Status(reqwest::StatusCode::SERVICE_UNAVAILABLE)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn exhausted_budget_skips_retries() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let skipped = Arc::new(Mutex::new(Vec::new()));
+        let budget = {
+            let skipped = skipped.clone();
+            RetryBudget::new(1, Duration::from_secs(3600)).with_exhausted_observer(
+                move |event: &RetryEvent| skipped.lock().unwrap().push(event.clone()),
+            )
+        };
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(Duration::from_millis(1), Duration::from_millis(10))
+            .build_with_max_retries(3);
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(RetryIdempotentMiddleware::new(
+                DynRetryPolicy(Arc::new(retry_policy)),
+                None,
+                Some(budget),
+                None,
+            ))
+            .build();
+
+        // The first request uses the only retry of the budget, the second one is not retried
+        for _ in 0..2 {
+            let res = client.get(mock_server.uri()).send().await.unwrap();
+            assert_eq!(res.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        let skipped = skipped.lock().unwrap();
+        assert_eq!(skipped.len(), 2);
+        assert_eq!(skipped[0].attempt, 3);
+        assert_eq!(skipped[1].attempt, 2);
+    }
 }
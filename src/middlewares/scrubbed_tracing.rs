@@ -0,0 +1,70 @@
+use crate::url_scrubber::UrlScrubber;
+use async_trait::async_trait;
+use reqwest::{Request, Response};
+use reqwest_middleware::{Middleware, Next, Result};
+use reqwest_tracing::{default_on_request_end, ReqwestOtelSpanBackend};
+use task_local_extensions::Extensions;
+use tracing::Span;
+
+/// Middleware making the [`UrlScrubber`] of the client available to the middlewares
+/// which follow it, through the request extensions.
+///
+/// Must be registered before [`TracingMiddleware`](reqwest_tracing::TracingMiddleware).
+pub struct UrlScrubberMiddleware {
+    scrubber: UrlScrubber,
+}
+
+impl UrlScrubberMiddleware {
+    pub fn new(scrubber: UrlScrubber) -> Self {
+        Self { scrubber }
+    }
+}
+
+#[async_trait]
+impl Middleware for UrlScrubberMiddleware {
+    async fn handle(
+        &self,
+        req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> Result<Response> {
+        extensions.insert(self.scrubber.clone());
+
+        next.run(req, extensions).await
+    }
+}
+
+/// Returns the [`UrlScrubber`] set by [`UrlScrubberMiddleware`], or the default one.
+pub fn url_scrubber(extensions: &Extensions) -> UrlScrubber {
+    extensions.get::<UrlScrubber>().cloned().unwrap_or_default()
+}
+
+/// Span backend for [`TracingMiddleware`](reqwest_tracing::TracingMiddleware)
+/// which records request URLs after redacting them with the [`UrlScrubber`] of the client.
+pub struct ScrubbedSpanBackend;
+
+impl ReqwestOtelSpanBackend for ScrubbedSpanBackend {
+    fn on_request_start(req: &Request, extensions: &mut Extensions) -> Span {
+        let url = url_scrubber(extensions).scrub(req.url());
+        let method = req.method();
+
+        tracing::info_span!(
+            target: "truelayer_rust::http",
+            "HTTP request",
+            http.method = %method,
+            http.scheme = %url.scheme(),
+            http.host = %url.host_str().unwrap_or_default(),
+            http.url = %url,
+            otel.kind = "client",
+            otel.name = %format!("{} {}", method, url.path()),
+            otel.status_code = tracing::field::Empty,
+            http.status_code = tracing::field::Empty,
+            error.message = tracing::field::Empty,
+            error.cause_chain = tracing::field::Empty,
+        )
+    }
+
+    fn on_request_end(span: &Span, outcome: &Result<Response>, _extensions: &mut Extensions) {
+        default_on_request_end(span, outcome)
+    }
+}
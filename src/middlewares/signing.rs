@@ -3,16 +3,102 @@ use crate::{
     error::Error,
 };
 use async_trait::async_trait;
-use reqwest::{header::HeaderValue, Method, Request, Response};
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Request, Response,
+};
 use reqwest_middleware::{Middleware, Next};
+use secrecy::{zeroize::Zeroize, DebugSecret, ExposeSecret, Secret};
 use task_local_extensions::Extensions;
 
-/// Middleware to attach signatures to all outgoing `POST`, `PUT` and `DELETE` requests.
+/// Middleware to attach signatures to outgoing requests.
+///
+/// Whether a request is signed is decided by [`SIGNING_ROUTES`]: requests not matching any
+/// route are signed if and only if they have a `POST`, `PUT` or `DELETE` method.
+///
+/// Uses [`truelayer_signing`](truelayer_signing) to build the signatures. The signature always includes
+/// the `Idempotency-Key` header (if present), plus any of the `signed_headers` present on the request.
 ///
-/// Uses [`truelayer_signing`](truelayer_signing) to build the signatures.
+/// The signed path is relative to `base_path`, which is the path of the configured payments URL
+/// (if any): TrueLayer verifies the signature against the path it receives, not the one
+/// of a gateway in front of it.
 pub struct SigningMiddleware {
     pub(crate) key_id: String,
-    pub(crate) private_key: Vec<u8>,
+    pub(crate) private_key: Secret<PrivateKey>,
+    pub(crate) base_path: String,
+    pub(crate) signed_headers: Vec<HeaderName>,
+}
+
+/// PEM encoded private key used to sign requests.
+///
+/// Wrapped in a [`Secret`] so that it is redacted in `Debug` output and wiped on drop.
+#[derive(Clone)]
+pub(crate) struct PrivateKey(pub(crate) Vec<u8>);
+
+impl Zeroize for PrivateKey {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl DebugSecret for PrivateKey {}
+
+/// Signing requirement of a TrueLayer endpoint.
+pub struct SigningRoute {
+    pub method: &'static str,
+    /// Path of the endpoint, where `*` matches any single path segment.
+    pub path: &'static str,
+    pub signed: bool,
+    /// Whether the body is part of the signature.
+    ///
+    /// Endpoints accepting multipart or streamed bodies (e.g. file uploads) exclude the body,
+    /// which cannot be read before being sent.
+    pub sign_body: bool,
+}
+
+/// Endpoints whose signing requirement differs from the default one.
+pub static SIGNING_ROUTES: &[SigningRoute] = &[
+    // Provider return parameters are authenticated by the payment they refer to
+    SigningRoute {
+        method: "POST",
+        path: "/payments-provider-return",
+        signed: false,
+        sign_body: false,
+    },
+];
+
+impl SigningRoute {
+    fn matches(&self, method: &str, path: &str) -> bool {
+        let mut pattern = self.path.trim_matches('/').split('/');
+        let mut segments = path.trim_matches('/').split('/');
+
+        self.method == method
+            && loop {
+                match (pattern.next(), segments.next()) {
+                    (None, None) => break true,
+                    (Some(p), Some(s)) if p == "*" || p == s => continue,
+                    _ => break false,
+                }
+            }
+    }
+}
+
+/// How a request with the given method and path (relative to the base path) must be signed.
+fn signing_requirement(routes: &[SigningRoute], method: &str, path: &str) -> SigningRequirement {
+    match routes.iter().find(|route| route.matches(method, path)) {
+        Some(route) if !route.signed => SigningRequirement::Unsigned,
+        Some(route) if !route.sign_body => SigningRequirement::SignedWithoutBody,
+        Some(_) => SigningRequirement::Signed,
+        None if matches!(method, "POST" | "PUT" | "DELETE") => SigningRequirement::Signed,
+        None => SigningRequirement::Unsigned,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SigningRequirement {
+    Unsigned,
+    Signed,
+    SignedWithoutBody,
 }
 
 #[async_trait]
@@ -23,23 +109,33 @@ impl Middleware for SigningMiddleware {
         extensions: &mut Extensions,
         next: Next<'_>,
     ) -> reqwest_middleware::Result<Response> {
-        // Sign only POST, PUT and DELETE requests
-        if let Method::POST | Method::PUT | Method::DELETE = *req.method() {
+        let path = req.url().path();
+        let path = path.strip_prefix(&self.base_path).unwrap_or(path);
+
+        let requirement = signing_requirement(SIGNING_ROUTES, req.method().as_str(), path);
+        if requirement != SigningRequirement::Unsigned {
             // Include method and path
-            let mut signer = truelayer_signing::sign_with_pem(&self.key_id, &self.private_key)
-                .method(req.method().as_str())
-                .path(req.url().path());
+            let mut signer =
+                truelayer_signing::sign_with_pem(&self.key_id, &self.private_key.expose_secret().0)
+                    .method(req.method().as_str())
+                    .path(path);
 
-            // Include the idempotency key header
-            if let Some(idempotency_key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) {
-                signer = signer.header(IDEMPOTENCY_KEY_HEADER, idempotency_key.as_bytes());
+            // Include the idempotency key header and any other configured header
+            let headers = std::iter::once(IDEMPOTENCY_KEY_HEADER)
+                .chain(self.signed_headers.iter().map(HeaderName::as_str));
+            for name in headers {
+                if let Some(value) = req.headers().get(name) {
+                    signer = signer.header(name, value.as_bytes());
+                }
             }
 
-            // Include the body
-            if let Some(body) = req.body() {
-                let bytes = body
-                    .as_bytes()
-                    .ok_or_else(|| anyhow::anyhow!("Cannot sign a streaming request body"))?;
+            // Include the body, unless the endpoint excludes it from the signature
+            if let (SigningRequirement::Signed, Some(body)) = (requirement, req.body()) {
+                let bytes = body.as_bytes().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Cannot sign a streaming request body: the endpoint must exclude the body from the signature"
+                    )
+                })?;
                 signer = signer.body(bytes);
             }
 
@@ -62,18 +158,31 @@ mod tests {
         nid::Nid,
         pkey::Private,
     };
+    use reqwest::Method;
     use reqwest_middleware::ClientWithMiddleware;
     use std::str::FromStr;
     use wiremock::{http::HeaderName, matchers::path, Mock, MockServer, ResponseTemplate};
 
     fn mock_client() -> (ClientWithMiddleware, EcKey<Private>) {
+        mock_client_with(&[], "")
+    }
+
+    fn mock_client_with(
+        signed_headers: &[&str],
+        base_path: &str,
+    ) -> (ClientWithMiddleware, EcKey<Private>) {
         // Generate a new EC private key
         let key = EcKey::generate(&EcGroup::from_curve_name(Nid::SECP521R1).unwrap()).unwrap();
 
         let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
             .with(SigningMiddleware {
                 key_id: "mock-key-id".to_string(),
-                private_key: key.private_key_to_pem().unwrap(),
+                private_key: Secret::new(PrivateKey(key.private_key_to_pem().unwrap())),
+                base_path: base_path.to_string(),
+                signed_headers: signed_headers
+                    .iter()
+                    .map(|h| reqwest::header::HeaderName::from_str(h).unwrap())
+                    .collect(),
             })
             .build();
 
@@ -139,4 +248,171 @@ mod tests {
             }
         }
     }
+
+    #[tokio::test]
+    async fn signed_path_is_relative_to_base_path() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/gateway/test"))
+            .respond_with(|req: &wiremock::Request| {
+                ResponseTemplate::new(200).set_body_string(
+                    req.headers
+                        .get(&HeaderName::from_str(TL_SIGNATURE_HEADER).unwrap())
+                        .map(|v| v.last().to_string())
+                        .unwrap_or_default(),
+                )
+            })
+            .mount(&mock_server)
+            .await;
+
+        let (client, key) = mock_client_with(&[], "/gateway");
+        let signature = client
+            .post(format!("{}/gateway/test", mock_server.uri()))
+            .body("request-body")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        truelayer_signing::verify_with_pem(key.public_key_to_pem().unwrap().as_slice())
+            .method("POST")
+            .path("/test")
+            .body("request-body".as_bytes())
+            .verify(&signature)
+            .unwrap();
+    }
+
+    async fn mock_server_echoing_signature(path_str: &str) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(path(path_str))
+            .respond_with(|req: &wiremock::Request| {
+                ResponseTemplate::new(200).set_body_string(
+                    req.headers
+                        .get(&HeaderName::from_str(TL_SIGNATURE_HEADER).unwrap())
+                        .map(|v| v.last().to_string())
+                        .unwrap_or_default(),
+                )
+            })
+            .mount(&mock_server)
+            .await;
+
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn configured_headers_are_signed() {
+        let mock_server = mock_server_echoing_signature("/test").await;
+
+        let (client, key) = mock_client_with(&["x-custom-header"], "");
+        let signature = client
+            .post(format!("{}/test", mock_server.uri()))
+            .header(IDEMPOTENCY_KEY_HEADER, "idempotency-key")
+            .header("X-Custom-Header", "custom-value")
+            .body("request-body")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        truelayer_signing::verify_with_pem(key.public_key_to_pem().unwrap().as_slice())
+            .method("POST")
+            .path("/test")
+            .header(IDEMPOTENCY_KEY_HEADER, b"idempotency-key")
+            .header("X-Custom-Header", b"custom-value")
+            .require_header("X-Custom-Header")
+            .body("request-body".as_bytes())
+            .verify(&signature)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn routes_not_requiring_signature_are_not_signed() {
+        let mock_server = mock_server_echoing_signature("/payments-provider-return").await;
+
+        let (client, _) = mock_client();
+        let signature = client
+            .post(format!("{}/payments-provider-return", mock_server.uri()))
+            .body("request-body")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        assert!(signature.is_empty());
+    }
+
+    #[test]
+    fn signing_route_matching() {
+        let route = SigningRoute {
+            method: "POST",
+            path: "/payments/*/refunds",
+            signed: true,
+            sign_body: true,
+        };
+
+        assert!(route.matches("POST", "/payments/some-id/refunds"));
+        assert!(!route.matches("GET", "/payments/some-id/refunds"));
+        assert!(!route.matches("POST", "/payments/some-id"));
+        assert!(!route.matches("POST", "/payments/some-id/refunds/other"));
+    }
+
+    #[test]
+    fn signing_requirement_of_routes() {
+        let routes = [
+            SigningRoute {
+                method: "POST",
+                path: "/unsigned",
+                signed: false,
+                sign_body: false,
+            },
+            SigningRoute {
+                method: "POST",
+                path: "/uploads/*",
+                signed: true,
+                sign_body: false,
+            },
+        ];
+
+        assert_eq!(
+            signing_requirement(&routes, "POST", "/unsigned"),
+            SigningRequirement::Unsigned
+        );
+        assert_eq!(
+            signing_requirement(&routes, "POST", "/uploads/some-id"),
+            SigningRequirement::SignedWithoutBody
+        );
+        assert_eq!(
+            signing_requirement(&routes, "POST", "/payments"),
+            SigningRequirement::Signed
+        );
+        assert_eq!(
+            signing_requirement(&routes, "GET", "/payments"),
+            SigningRequirement::Unsigned
+        );
+    }
+
+    #[tokio::test]
+    async fn streaming_bodies_cannot_be_signed_unless_excluded() {
+        let mock_server = mock_server_echoing_signature("/test").await;
+
+        let (client, _) = mock_client();
+        let form = reqwest::multipart::Form::new().part(
+            "file",
+            reqwest::multipart::Part::stream(reqwest::Body::wrap_stream(futures::stream::iter(
+                vec![Ok::<_, std::io::Error>("file-content")],
+            ))),
+        );
+        let res = client
+            .post(format!("{}/test", mock_server.uri()))
+            .multipart(form)
+            .send()
+            .await;
+
+        assert!(matches!(res, Err(reqwest_middleware::Error::Middleware(_))));
+    }
 }
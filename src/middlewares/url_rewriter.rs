@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use reqwest::{Request, Response, Url};
+use reqwest_middleware::{Middleware, Next};
+use std::{
+    fmt::{Debug, Formatter},
+    sync::Arc,
+};
+use task_local_extensions::Extensions;
+
+/// Middleware to rewrite the URL of all outgoing requests right before they are sent.
+///
+/// Requests are signed before being rewritten, so the rewriter is free to change the
+/// URL in ways TrueLayer is not aware of (e.g. routing requests through a gateway).
+pub struct UrlRewriterMiddleware {
+    rewriter: UrlRewriter,
+}
+
+impl UrlRewriterMiddleware {
+    pub fn new(rewriter: UrlRewriter) -> Self {
+        Self { rewriter }
+    }
+}
+
+#[async_trait]
+impl Middleware for UrlRewriterMiddleware {
+    async fn handle(
+        &self,
+        mut req: Request,
+        extensions: &mut Extensions,
+        next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+        *req.url_mut() = (self.rewriter.0)(req.url().clone());
+
+        next.run(req, extensions).await
+    }
+}
+
+/// Wrapper type around a function rewriting request URLs.
+#[derive(Clone)]
+pub struct UrlRewriter(pub Arc<dyn Fn(Url) -> Url + Send + Sync + 'static>);
+
+impl Debug for UrlRewriter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UrlRewriter").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{matchers::path, Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn rewrites_request_url() {
+        let mock_server = MockServer::start().await;
+        Mock::given(path("/rewritten"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+            .with(UrlRewriterMiddleware::new(UrlRewriter(Arc::new(
+                |mut url| {
+                    url.set_path("/rewritten");
+                    url
+                },
+            ))))
+            .build();
+
+        let res = client
+            .get(format!("{}/original", mock_server.uri()))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(res.status(), 200);
+    }
+}
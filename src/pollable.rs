@@ -3,15 +3,33 @@
 use crate::{Error, TrueLayerClient};
 use async_trait::async_trait;
 use chrono::Utc;
+use futures::future::pending;
+use rand::Rng;
 use retry_policies::{policies::ExponentialBackoff, RetryDecision, RetryPolicy};
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
 
 /// Options to configure the behaviour of [`Pollable::poll_until`](crate::pollable::Pollable::poll_until).
 ///
-/// The default is an exponential backoff between retries from 1 to 30 seconds for a total of 5 minutes.
+/// The default is an exponential backoff between retries from 1 to 30 seconds for a total of 5 minutes,
+/// waiting at least 1 second between polls.
 #[derive(Debug)]
 pub struct PollOptions<R: RetryPolicy> {
     retry_policy: R,
+    min_wait: Duration,
+    jitter: Duration,
+    max_attempts: Option<u32>,
+    deadline: Option<Duration>,
+    observer: Option<PollObserver>,
+    cancellation_token: Option<CancellationToken>,
+    notifier: Option<Arc<dyn Notifier>>,
 }
 
 impl Default for PollOptions<ExponentialBackoff> {
@@ -20,6 +38,13 @@ impl Default for PollOptions<ExponentialBackoff> {
             retry_policy: ExponentialBackoff::builder()
                 .retry_bounds(Duration::from_secs(1), Duration::from_secs(30))
                 .build_with_total_retry_duration(Duration::from_secs(60 * 5 /* 5 mins */)),
+            min_wait: Duration::from_secs(1),
+            jitter: Duration::ZERO,
+            max_attempts: None,
+            deadline: None,
+            observer: None,
+            cancellation_token: None,
+            notifier: None,
         }
     }
 }
@@ -27,7 +52,150 @@ impl Default for PollOptions<ExponentialBackoff> {
 impl<R: RetryPolicy> PollOptions<R> {
     /// Sets a retry policy.
     pub fn with_retry_policy<T: RetryPolicy>(self, retry_policy: T) -> PollOptions<T> {
-        PollOptions { retry_policy }
+        PollOptions {
+            retry_policy,
+            min_wait: self.min_wait,
+            jitter: self.jitter,
+            max_attempts: self.max_attempts,
+            deadline: self.deadline,
+            observer: self.observer,
+            cancellation_token: self.cancellation_token,
+            notifier: self.notifier,
+        }
+    }
+
+    /// Sets the minimum time to wait between two polls, regardless of the retry policy.
+    ///
+    /// Defaults to 1 second. Lower it to speed up tests against the sandbox.
+    pub fn with_min_wait(mut self, min_wait: Duration) -> Self {
+        self.min_wait = min_wait;
+        self
+    }
+
+    /// Adds a random delay between zero and `jitter` to each wait,
+    /// so that many resources polled together do not hit the API at the same time.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Gives up with [`PollError::Timeout`] after polling the resource `max_attempts` times.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Gives up with [`PollError::Timeout`] once `deadline` has elapsed since polling started.
+    ///
+    /// The resource is polled one last time when the deadline is reached.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets a callback invoked after every poll.
+    pub fn with_poll_observer(
+        mut self,
+        observer: impl Fn(&PollProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.observer = Some(PollObserver(Arc::new(observer)));
+        self
+    }
+
+    /// Sets a token which stops polling with [`Error::Cancelled`] as soon as it is cancelled.
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Sets a [`Notifier`] which can wake up the poller before the next scheduled poll.
+    ///
+    /// The retry policy still decides how long to wait when no notification is received,
+    /// and when to give up.
+    pub fn with_notifier(mut self, notifier: impl Notifier) -> Self {
+        self.notifier = Some(Arc::new(notifier));
+        self
+    }
+}
+
+/// Progress of a polling loop, reported to the callback set with [`PollOptions::with_poll_observer`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PollProgress {
+    /// Number of polls made so far, starting from 1.
+    pub attempt: u32,
+    /// Time elapsed since polling started.
+    pub elapsed: Duration,
+}
+
+/// Callback invoked after every poll.
+#[derive(Clone)]
+pub struct PollObserver(pub Arc<dyn Fn(&PollProgress) + Send + Sync + 'static>);
+
+impl Debug for PollObserver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PollObserver").finish_non_exhaustive()
+    }
+}
+
+/// Source of notifications that a resource has changed, e.g. because a webhook was received.
+///
+/// When configured with [`PollOptions::with_notifier`], polling stops waiting and fetches the resource
+/// as soon as [`notified`](Notifier::notified) completes.
+#[async_trait]
+pub trait Notifier: Debug + Send + Sync + 'static {
+    /// Completes when a change to the resource with the given id is signalled.
+    async fn notified(&self, resource_id: &str);
+}
+
+/// [`Notifier`] backed by tokio `watch` channels keyed by resource id.
+///
+/// Call [`notify`](WatchNotifier::notify) from your webhook handler to wake up
+/// all the pollers waiting for that resource. Notifications sent while no poller
+/// is waiting are dropped: the poller will pick up the change on its next scheduled poll.
+#[derive(Debug, Clone, Default)]
+pub struct WatchNotifier {
+    channels: Arc<Mutex<HashMap<String, watch::Sender<()>>>>,
+}
+
+impl WatchNotifier {
+    /// Creates a new notifier.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Signals that the resource with the given id has changed.
+    pub fn notify(&self, resource_id: &str) {
+        if let Some(sender) = self.channels.lock().unwrap().get(resource_id) {
+            let _ = sender.send(());
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WatchNotifier {
+    async fn notified(&self, resource_id: &str) {
+        // Drops the channel once the last waiting poller is gone, even if cancelled
+        struct Cleanup<'a>(&'a WatchNotifier, &'a str);
+
+        impl Drop for Cleanup<'_> {
+            fn drop(&mut self) {
+                let mut channels = self.0.channels.lock().unwrap();
+                if matches!(channels.get(self.1), Some(s) if s.receiver_count() == 0) {
+                    channels.remove(self.1);
+                }
+            }
+        }
+
+        let _cleanup = Cleanup(self, resource_id);
+        let mut receiver = self
+            .channels
+            .lock()
+            .unwrap()
+            .entry(resource_id.to_string())
+            .or_insert_with(|| watch::channel(()).0)
+            .subscribe();
+
+        let _ = receiver.changed().await;
     }
 }
 
@@ -43,15 +211,26 @@ pub enum PollError {
 }
 
 /// A resource that can be continuously polled for updates.
+///
+/// This trait is sealed: custom resources can be polled with [`poll_fn()`]. It can only be
+/// implemented outside of this crate with the `unstable-pollable` feature, and its API may
+/// change in minor releases.
 #[async_trait]
 pub trait Pollable: private::Sealed {
     type Output: Send;
 
+    /// Id of the resource, used to match notifications from a [`Notifier`].
+    fn resource_id(&self) -> &str;
+
     /// Makes a single request to retrieve the most up-to-date version of this resource from the server.
     async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error>;
 
     /// Continuously polls the server for updates on this resource until the given condition is met.
-    #[tracing::instrument(name = "Poll for updates", skip_all)]
+    #[tracing::instrument(
+        target = "truelayer_rust::polling",
+        name = "Poll for updates",
+        skip_all
+    )]
     async fn poll_until<R, F>(
         &self,
         tl: &TrueLayerClient,
@@ -62,30 +241,69 @@ pub trait Pollable: private::Sealed {
         R: RetryPolicy + Send + Sync,
         F: for<'a> Fn(&'a Self::Output) -> bool + Send,
     {
+        let started_at = Utc::now();
+        let cancellation_token = poll_options.cancellation_token.as_ref();
+
         // Loop until we match the predicate
         let mut i = 0;
         loop {
             // Update the resource
-            let res = self.poll_once(tl).await?;
+            let res = tokio::select! {
+                res = self.poll_once(tl) => res?,
+                _ = cancelled(cancellation_token) => return Err(Error::Cancelled.into()),
+            };
+
+            let elapsed = (Utc::now() - started_at).to_std().unwrap_or_default();
+            if let Some(observer) = &poll_options.observer {
+                (observer.0)(&PollProgress {
+                    attempt: i + 1,
+                    elapsed,
+                });
+            }
 
             // Check predicate
             if predicate(&res) {
                 return Ok(res);
             }
 
+            if matches!(poll_options.max_attempts, Some(max) if i + 1 >= max) {
+                return Err(PollError::Timeout);
+            }
+
             // Wait
             match poll_options.retry_policy.should_retry(i) {
                 RetryDecision::Retry { execute_after } => {
-                    // Wait at least 1 second between each retry
-                    let wait_time = Duration::from_secs(1)
+                    let mut wait_time = poll_options
+                        .min_wait
                         .max((execute_after - Utc::now()).to_std().unwrap_or_default());
+                    if !poll_options.jitter.is_zero() {
+                        wait_time +=
+                            rand::thread_rng().gen_range(Duration::ZERO..=poll_options.jitter);
+                    }
+                    if let Some(deadline) = poll_options.deadline {
+                        if elapsed >= deadline {
+                            return Err(PollError::Timeout);
+                        }
+                        wait_time = wait_time.min(deadline - elapsed);
+                    }
 
                     tracing::debug!(
+                        target: "truelayer_rust::polling",
                         "Waiting {} seconds before trying again",
                         wait_time.as_secs_f64()
                     );
 
-                    tokio::time::sleep(wait_time).await;
+                    let runtime = tl.inner.runtime()?;
+                    tokio::select! {
+                        _ = runtime.sleep(wait_time) => {}
+                        _ = notified(poll_options.notifier.as_deref(), self.resource_id()) => {
+                            tracing::debug!(
+                                target: "truelayer_rust::polling",
+                                "Notified of a change, polling again",
+                            );
+                        }
+                        _ = cancelled(cancellation_token) => return Err(Error::Cancelled.into()),
+                    }
                 }
                 RetryDecision::DoNotRetry => {
                     return Err(PollError::Timeout);
@@ -97,6 +315,20 @@ pub trait Pollable: private::Sealed {
     }
 }
 
+async fn cancelled(cancellation_token: Option<&CancellationToken>) {
+    match cancellation_token {
+        Some(token) => token.cancelled().await,
+        None => pending().await,
+    }
+}
+
+async fn notified(notifier: Option<&dyn Notifier>, resource_id: &str) {
+    match notifier {
+        Some(notifier) => notifier.notified(resource_id).await,
+        None => pending().await,
+    }
+}
+
 /// A resource that can be in a terminal state.
 pub trait IsInTerminalState {
     /// Returns `true` if this resource is in a terminal state.
@@ -130,19 +362,112 @@ where
     }
 }
 
-// Prevent users from implementing the `Pollable` trait.
+/// Builds a [`Pollable`] resource out of a function fetching its latest version,
+/// for resources the library does not know about, such as an order of the merchant
+/// aggregating a payment and its refunds.
+///
+/// The function is called on each attempt with a clone of the client. To poll until a
+/// terminal state is reached, implement [`IsInTerminalState`] for the type it returns.
+///
+/// ```rust,no_run
+/// # use truelayer_rust::{TrueLayerClient, Error, pollable::{poll_fn, PollOptions}};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let tl: TrueLayerClient = unreachable!();
+/// use truelayer_rust::Pollable;
+///
+/// let payment_id = "payment-id".to_string();
+/// let order = poll_fn(payment_id.clone(), move |tl| {
+///     let payment_id = payment_id.clone();
+///     async move {
+///         let payment = tl.payments.get_by_id(&payment_id).await?;
+///         // ... load the rest of the order from the database
+///         Ok(payment)
+///     }
+/// });
+///
+/// order
+///     .poll_until(&tl, PollOptions::default(), |payment| payment.is_some())
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn poll_fn<F, Fut, T>(resource_id: impl Into<String>, f: F) -> PollFn<F>
+where
+    F: Fn(TrueLayerClient) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<T, Error>> + Send,
+    T: Send,
+{
+    PollFn {
+        resource_id: resource_id.into(),
+        f,
+    }
+}
+
+/// [`Pollable`] resource built with [`poll_fn()`].
+#[derive(Clone)]
+pub struct PollFn<F> {
+    resource_id: String,
+    f: F,
+}
+
+impl<F> Debug for PollFn<F> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PollFn")
+            .field("resource_id", &self.resource_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<F, Fut, T> Pollable for PollFn<F>
+where
+    F: Fn(TrueLayerClient) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<T, Error>> + Send,
+    T: Send,
+{
+    type Output = T;
+
+    fn resource_id(&self) -> &str {
+        &self.resource_id
+    }
+
+    async fn poll_once(&self, tl: &TrueLayerClient) -> Result<Self::Output, Error> {
+        (self.f)(tl.clone()).await
+    }
+}
+
+// Prevent users from implementing the `Pollable` trait, unless the `unstable-pollable` feature
+// is enabled. Its API may change in minor releases: use `poll_fn` for custom resources instead.
 mod private {
     pub trait Sealed {}
 
-    impl Sealed for crate::apis::payments::Payment {}
-    impl Sealed for crate::apis::payments::CreatePaymentResponse {}
-    impl Sealed for (&str, crate::apis::payments::refunds::Refund) {}
-    impl Sealed for (&str, crate::apis::payments::refunds::CreateRefundResponse) {}
-    impl Sealed for crate::apis::payouts::Payout {}
-    impl Sealed for crate::apis::payouts::CreatePayoutResponse {}
-
-    #[cfg(test)]
-    impl<F> Sealed for super::tests::PollableMock<F> {}
+    #[cfg(feature = "unstable-pollable")]
+    impl<T: ?Sized> Sealed for T {}
+
+    #[cfg(not(feature = "unstable-pollable"))]
+    mod impls {
+        use super::Sealed;
+
+        impl Sealed for crate::apis::payments::Payment {}
+        impl Sealed for crate::apis::payments::CreatePaymentResponse {}
+        impl Sealed for (&str, crate::apis::payments::refunds::Refund) {}
+        impl Sealed for (&str, crate::apis::payments::refunds::CreateRefundResponse) {}
+        #[cfg(feature = "payouts")]
+        impl Sealed for crate::apis::payouts::Payout {}
+        #[cfg(feature = "payouts")]
+        impl Sealed for crate::apis::payouts::CreatePayoutResponse {}
+        #[cfg(feature = "merchant-accounts")]
+        impl Sealed for crate::apis::merchant_accounts::MerchantAccount {}
+        #[cfg(feature = "mandates")]
+        impl Sealed for crate::apis::mandates::Mandate {}
+        #[cfg(feature = "mandates")]
+        impl Sealed for crate::apis::mandates::CreateMandateResponse {}
+        impl<F> Sealed for crate::pollable::PollFn<F> {}
+
+        #[cfg(test)]
+        impl<F> Sealed for crate::pollable::tests::PollableMock<F> {}
+    }
 }
 
 #[cfg(test)]
@@ -208,6 +533,10 @@ mod tests {
     {
         type Output = PollableMock<F>;
 
+        fn resource_id(&self) -> &str {
+            "mock-id"
+        }
+
         async fn poll_once(&self, _tl: &TrueLayerClient) -> Result<Self::Output, Error> {
             self.polled_count.fetch_add(1, Ordering::SeqCst);
 
@@ -220,8 +549,8 @@ mod tests {
 
     fn mock_tl_client() -> TrueLayerClient {
         TrueLayerClient::builder(Credentials::ClientCredentials {
-            client_id: "".into(),
-            client_secret: "".into(),
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
             scope: "".into(),
         })
         .with_environment(Environment::from_single_url(
@@ -318,4 +647,159 @@ mod tests {
         assert!(pollable.is_in_terminal_state());
         assert!(elapsed >= Duration::from_secs(1));
     }
+
+    #[tokio::test]
+    async fn poll_until_is_woken_up_by_notifier() {
+        let pollable = PollableMock::new(|_| None);
+        let notifier = WatchNotifier::new();
+
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(Duration::from_secs(30), Duration::from_secs(30))
+            .build_with_max_retries(1);
+
+        // Keep notifying until the poller is done, as notifications sent before it starts waiting are dropped
+        let notifications = tokio::spawn({
+            let notifier = notifier.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    notifier.notify("mock-id");
+                }
+            }
+        });
+
+        let start = Instant::now();
+        pollable
+            .poll_until(
+                &mock_tl_client(),
+                PollOptions::default()
+                    .with_retry_policy(retry_policy)
+                    .with_notifier(notifier.clone()),
+                |_| pollable.polled_count() >= 2,
+            )
+            .await
+            .unwrap();
+        let elapsed = Instant::now() - start;
+        notifications.abort();
+
+        // Assert that we did not wait for the 30 seconds scheduled by the retry policy
+        assert_eq!(pollable.polled_count(), 2);
+        assert!(elapsed < Duration::from_secs(10));
+        assert!(notifier.channels.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn poll_until_max_attempts() {
+        let pollable = PollableMock::new(|_| None);
+        let progress = Arc::new(Mutex::new(Vec::new()));
+
+        let res = pollable
+            .poll_until(
+                &mock_tl_client(),
+                PollOptions::default()
+                    .with_retry_policy(
+                        ExponentialBackoff::builder()
+                            .retry_bounds(Duration::from_millis(1), Duration::from_millis(1))
+                            .build_with_max_retries(100),
+                    )
+                    .with_min_wait(Duration::ZERO)
+                    .with_max_attempts(3)
+                    .with_poll_observer({
+                        let progress = progress.clone();
+                        move |p| progress.lock().unwrap().push(p.attempt)
+                    }),
+                |_| false,
+            )
+            .await;
+
+        assert!(matches!(res, Err(PollError::Timeout)));
+        assert_eq!(pollable.polled_count(), 3);
+        assert_eq!(*progress.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn poll_until_deadline() {
+        let pollable = PollableMock::new(|_| None);
+
+        // The retry policy would wait 30 seconds, but the deadline cuts it short
+        let start = Instant::now();
+        let res = pollable
+            .poll_until(
+                &mock_tl_client(),
+                PollOptions::default()
+                    .with_retry_policy(
+                        ExponentialBackoff::builder()
+                            .retry_bounds(Duration::from_secs(30), Duration::from_secs(30))
+                            .build_with_max_retries(10),
+                    )
+                    .with_deadline(Duration::from_millis(200)),
+                |_| false,
+            )
+            .await;
+        let elapsed = Instant::now() - start;
+
+        assert!(matches!(res, Err(PollError::Timeout)));
+        assert_eq!(pollable.polled_count(), 2);
+        assert!(elapsed < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn poll_until_cancelled() {
+        let pollable = PollableMock::new(|_| None);
+        let cancellation_token = CancellationToken::new();
+
+        tokio::spawn({
+            let cancellation_token = cancellation_token.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                cancellation_token.cancel();
+            }
+        });
+
+        let start = Instant::now();
+        let res = pollable
+            .poll_until(
+                &mock_tl_client(),
+                PollOptions::default().with_cancellation_token(cancellation_token),
+                |_| false,
+            )
+            .await;
+        let elapsed = Instant::now() - start;
+
+        assert!(matches!(res, Err(PollError::Error(Error::Cancelled))));
+        assert_eq!(pollable.polled_count(), 1);
+        assert!(elapsed < Duration::from_secs(1));
+    }
+
+    #[derive(Debug)]
+    struct Order {
+        attempt: u32,
+    }
+
+    impl IsInTerminalState for Order {
+        fn is_in_terminal_state(&self) -> bool {
+            self.attempt >= 2
+        }
+    }
+
+    #[tokio::test]
+    async fn poll_fn_until_terminal_state() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let order = poll_fn("order-id", {
+            let attempts = attempts.clone();
+            move |_tl| {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                async move { Ok(Order { attempt }) }
+            }
+        });
+
+        let res = order
+            .poll_until_terminal_state(&mock_tl_client(), PollOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(order.resource_id(), "order-id");
+        assert_eq!(res.attempt, 2);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
 }
@@ -0,0 +1,375 @@
+//! Reconciliation of the ledger of a merchant with the transactions of a merchant account.
+//!
+//! The merchant lists the payments, refunds and payouts it expects to have moved money
+//! in a date range (e.g. as recorded from webhooks), and [`Reconciler::reconcile()`]
+//! cross-references them with what TrueLayer reports:
+//!
+//! - payments and payouts are looked up among the transactions of the merchant account;
+//! - refunds, which are not listed among the transactions, are fetched one by one.
+//!
+//! ```rust,no_run
+//! # use truelayer_rust::{TrueLayerClient, Error};
+//! # use truelayer_rust::reconciliation::{ExpectedEntry, Reconciler};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//! # let tl: TrueLayerClient = unreachable!();
+//! # let (from, to) = (chrono::Utc::now(), chrono::Utc::now());
+//! let report = Reconciler::new(&tl)
+//!     .reconcile(
+//!         "merchant-account-id",
+//!         from,
+//!         to,
+//!         &[ExpectedEntry::Payment {
+//!             payment_id: "payment-id".into(),
+//!             amount_in_minor: 100,
+//!         }],
+//!     )
+//!     .await?;
+//!
+//! for entry in &report.missing {
+//!     tracing::warn!(?entry, "Entry not found in the merchant account");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requires the `merchant-accounts` feature.
+
+use crate::{
+    apis::{
+        merchant_accounts::{
+            ListTransactionsRequest, MerchantAccountId, MerchantAccountsApi, Transaction,
+            TransactionPayoutStatus, TransactionType,
+        },
+        payments::{refunds::RefundStatus, PaymentId, PaymentsApi},
+        payouts::PayoutId,
+    },
+    Error, TrueLayerClient,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+
+/// A payment, refund or payout recorded by the merchant.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ExpectedEntry {
+    /// A payment into the merchant account, expected to be settled.
+    Payment {
+        payment_id: PaymentId,
+        amount_in_minor: u64,
+    },
+    /// A refund of a payment, expected to be executed.
+    Refund {
+        payment_id: PaymentId,
+        refund_id: String,
+        amount_in_minor: u64,
+    },
+    /// A payout from the merchant account, expected to be executed.
+    Payout {
+        payout_id: PayoutId,
+        amount_in_minor: u64,
+    },
+}
+
+impl ExpectedEntry {
+    /// Amount the merchant expects to have moved, in minor units.
+    pub fn amount_in_minor(&self) -> u64 {
+        match self {
+            ExpectedEntry::Payment {
+                amount_in_minor, ..
+            }
+            | ExpectedEntry::Refund {
+                amount_in_minor, ..
+            }
+            | ExpectedEntry::Payout {
+                amount_in_minor, ..
+            } => *amount_in_minor,
+        }
+    }
+}
+
+/// An expected entry found with a different amount.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AmountMismatch {
+    pub entry: ExpectedEntry,
+    /// Amount reported by TrueLayer, in minor units.
+    pub actual_amount_in_minor: u64,
+}
+
+/// Outcome of a reconciliation.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ReconciliationReport {
+    /// Entries found with the expected amount.
+    pub matched: Vec<ExpectedEntry>,
+    /// Entries which were not found, or were found but not yet settled or executed.
+    pub missing: Vec<ExpectedEntry>,
+    /// Entries found with a different amount.
+    pub amount_mismatches: Vec<AmountMismatch>,
+    /// Transactions of the merchant account which match none of the expected entries,
+    /// including external payments (e.g. top-ups) and pending payouts.
+    pub unexpected: Vec<Transaction>,
+}
+
+impl ReconciliationReport {
+    /// Returns `true` if every expected entry matched and there are no unexpected transactions.
+    pub fn is_balanced(&self) -> bool {
+        self.missing.is_empty() && self.amount_mismatches.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+/// Cross-references expected entries with the transactions of a merchant account.
+#[derive(Debug, Clone)]
+pub struct Reconciler {
+    merchant_accounts: MerchantAccountsApi,
+    payments: PaymentsApi,
+}
+
+impl Reconciler {
+    /// Creates a new reconciler that fetches data through the given client.
+    pub fn new(tl: &TrueLayerClient) -> Self {
+        Self {
+            merchant_accounts: tl.merchant_accounts.clone(),
+            payments: tl.payments.clone(),
+        }
+    }
+
+    /// Reconciles the entries expected between `from` and `to` with the transactions
+    /// of the merchant account in the same range.
+    ///
+    /// Payments match settled payin transactions and payouts match executed payout transactions
+    /// with the same id. Refunds match if they are executed, regardless of when.
+    #[tracing::instrument(
+        target = "truelayer_rust::reconciliation",
+        name = "Reconcile",
+        skip(self, merchant_account_id, expected)
+    )]
+    pub async fn reconcile(
+        &self,
+        merchant_account_id: impl Into<MerchantAccountId>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        expected: &[ExpectedEntry],
+    ) -> Result<ReconciliationReport, Error> {
+        let transactions = self
+            .merchant_accounts
+            .list_transactions(
+                merchant_account_id,
+                &ListTransactionsRequest {
+                    from,
+                    to,
+                    r#type: None,
+                },
+            )
+            .await?;
+
+        // Index the transactions which can be matched by the id of their payment or payout
+        let mut by_related_id = HashMap::new();
+        let mut report = ReconciliationReport::default();
+        for transaction in transactions {
+            match &transaction.r#type {
+                TransactionType::MerchantAccountPayment { payment_id, .. } => {
+                    by_related_id.insert(payment_id.clone(), transaction);
+                }
+                TransactionType::Payout {
+                    status: TransactionPayoutStatus::Executed { .. },
+                    payout_id,
+                    ..
+                } => {
+                    by_related_id.insert(payout_id.clone(), transaction);
+                }
+                _ => report.unexpected.push(transaction),
+            }
+        }
+
+        for entry in expected {
+            let actual_amount_in_minor = match entry {
+                ExpectedEntry::Payment { payment_id, .. } => by_related_id
+                    .remove(payment_id.as_str())
+                    .map(|t| t.amount_in_minor.unsigned_abs()),
+                ExpectedEntry::Payout { payout_id, .. } => by_related_id
+                    .remove(payout_id.as_str())
+                    .map(|t| t.amount_in_minor.unsigned_abs()),
+                ExpectedEntry::Refund {
+                    payment_id,
+                    refund_id,
+                    ..
+                } => self
+                    .payments
+                    .get_refund_by_id(payment_id.clone(), refund_id)
+                    .await?
+                    .filter(|r| matches!(r.status, RefundStatus::Executed { .. }))
+                    .map(|r| r.amount_in_minor),
+            };
+
+            match actual_amount_in_minor {
+                None => report.missing.push(entry.clone()),
+                Some(amount) if amount == entry.amount_in_minor() => {
+                    report.matched.push(entry.clone())
+                }
+                Some(amount) => report.amount_mismatches.push(AmountMismatch {
+                    entry: entry.clone(),
+                    actual_amount_in_minor: amount,
+                }),
+            }
+        }
+
+        report.unexpected.extend(by_related_id.into_values());
+        report.unexpected.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{apis::auth::Credentials, client::Environment};
+    use serde_json::json;
+    use url::Url;
+    use wiremock::{
+        matchers::{method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn reconcile_reports_matched_missing_and_mismatched_entries() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/connect/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "token_type": "Bearer",
+                "access_token": "access-token",
+                "expires_in": 3600
+            })))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/merchant-accounts/merchant-account-id/transactions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "items": [
+                    {
+                        "id": "transaction-1",
+                        "currency": "GBP",
+                        "amount_in_minor": 100,
+                        "type": "merchant_account_payment",
+                        "status": "settled",
+                        "settled_at": "2022-06-10T10:16:03Z",
+                        "payment_source": {
+                            "id": "payment-source-id",
+                            "account_identifiers": [],
+                            "account_holder_name": "Jane Doe"
+                        },
+                        "payment_id": "settled-payment-id"
+                    },
+                    {
+                        "id": "transaction-2",
+                        "currency": "GBP",
+                        "amount_in_minor": 5000,
+                        "type": "external_payment",
+                        "status": "settled",
+                        "settled_at": "2022-06-10T12:00:00Z",
+                        "remitter": {
+                            "account_holder_name": "John Doe",
+                            "account_identifier": {
+                                "type": "sort_code_account_number",
+                                "sort_code": "040662",
+                                "account_number": "00002724"
+                            },
+                            "reference": "top-up"
+                        }
+                    },
+                    {
+                        "id": "transaction-3",
+                        "currency": "GBP",
+                        "amount_in_minor": -1000,
+                        "type": "payout",
+                        "status": "executed",
+                        "created_at": "2022-06-11T09:00:00Z",
+                        "executed_at": "2022-06-11T09:00:05Z",
+                        "beneficiary": {
+                            "type": "payment_source",
+                            "user_id": "user-id",
+                            "payment_source_id": "payment-source-id",
+                            "reference": "refund-123"
+                        },
+                        "context_code": "withdrawal",
+                        "payout_id": "payout-id"
+                    }
+                ]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/payments/payment-id/refunds/refund-id"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "refund-id",
+                "amount_in_minor": 20,
+                "currency": "GBP",
+                "reference": "refund",
+                "created_at": "2022-06-11T10:00:00Z",
+                "metadata": null,
+                "status": "executed",
+                "executed_at": "2022-06-11T10:00:05Z"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let tl = TrueLayerClient::builder(Credentials::ClientCredentials {
+            client_id: "client-id".into(),
+            client_secret: "client-secret".into(),
+            scope: "mock".into(),
+        })
+        .with_retry_policy(None)
+        .with_environment(Environment::from_single_url(
+            &Url::parse(&mock_server.uri()).unwrap(),
+        ))
+        .build();
+
+        let settled_payment = ExpectedEntry::Payment {
+            payment_id: "settled-payment-id".into(),
+            amount_in_minor: 100,
+        };
+        let unknown_payment = ExpectedEntry::Payment {
+            payment_id: "unknown-payment-id".into(),
+            amount_in_minor: 100,
+        };
+        let payout = ExpectedEntry::Payout {
+            payout_id: "payout-id".into(),
+            amount_in_minor: 900,
+        };
+        let refund = ExpectedEntry::Refund {
+            payment_id: "payment-id".into(),
+            refund_id: "refund-id".to_string(),
+            amount_in_minor: 20,
+        };
+
+        let report = Reconciler::new(&tl)
+            .reconcile(
+                "merchant-account-id",
+                "2022-06-10T00:00:00Z".parse().unwrap(),
+                "2022-06-12T00:00:00Z".parse().unwrap(),
+                &[
+                    settled_payment.clone(),
+                    unknown_payment.clone(),
+                    payout.clone(),
+                    refund.clone(),
+                ],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(report.matched, vec![settled_payment, refund]);
+        assert_eq!(report.missing, vec![unknown_payment]);
+        assert_eq!(
+            report.amount_mismatches,
+            vec![AmountMismatch {
+                entry: payout,
+                actual_amount_in_minor: 1000,
+            }]
+        );
+        assert_eq!(report.unexpected.len(), 1);
+        assert_eq!(report.unexpected[0].id, "transaction-2");
+        assert!(!report.is_balanced());
+    }
+}
@@ -0,0 +1,106 @@
+//! Per-call options to customize the behaviour of single requests.
+
+#[cfg(any(feature = "payments", feature = "mandates"))]
+use crate::apis::payments::PsuContext;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Options applied to all the requests sent by an API client.
+///
+/// Use the `with_options` function of any of the API clients to get a copy of that client
+/// which applies these options to every request:
+///
+/// ```rust,no_run
+/// # use truelayer_rust::{TrueLayerClient, Error, RequestOptions};
+/// # use std::time::Duration;
+/// # use tokio_util::sync::CancellationToken;
+/// #
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Error> {
+/// # let tl: TrueLayerClient = unreachable!();
+/// let cancellation_token = CancellationToken::new();
+/// let payment = tl
+///     .payments
+///     .with_options(
+///         RequestOptions::default()
+///             .with_timeout(Duration::from_secs(5))
+///             .with_cancellation_token(cancellation_token.clone()),
+///     )
+///     .get_by_id("some-payment-id")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) cancellation_token: Option<CancellationToken>,
+    pub(crate) headers: HeaderMap,
+    #[cfg(feature = "extra-fields")]
+    pub(crate) extra_fields: serde_json::Map<String, serde_json::Value>,
+    #[cfg(any(feature = "payments", feature = "mandates"))]
+    pub(crate) psu_context: Option<PsuContext>,
+}
+
+impl RequestOptions {
+    /// Sets a timeout for each attempt of a request.
+    ///
+    /// This overrides the timeout configured on the HTTP client.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a token which aborts in-flight requests with [`Error::Cancelled`](crate::Error::Cancelled)
+    /// as soon as it is cancelled.
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Adds a custom header to each request, replacing any previous value of the same header.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Adds a field to the JSON body of each request, replacing any value the SDK set for it.
+    ///
+    /// This allows sending fields recently launched by TrueLayer before the request models
+    /// of the SDK support them. Requests without a body are left untouched, while requests
+    /// whose body is not a JSON object fail.
+    ///
+    /// ```rust,no_run
+    /// # use truelayer_rust::{TrueLayerClient, Error, RequestOptions, apis::payments::*};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Error> {
+    /// # let tl: TrueLayerClient = unreachable!();
+    /// # let create_payment_request: CreatePaymentRequest = unreachable!();
+    /// let res = tl
+    ///     .payments
+    ///     .with_options(
+    ///         RequestOptions::default()
+    ///             .with_extra_field("some_new_field", serde_json::json!({ "enabled": true })),
+    ///     )
+    ///     .create(&create_payment_request)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "extra-fields")]
+    pub fn with_extra_field(mut self, key: impl Into<String>, value: serde_json::Value) -> Self {
+        self.extra_fields.insert(key.into(), value);
+        self
+    }
+
+    /// Sets the details about the user which are sent when starting an authorization flow
+    /// and submitting its actions, as required by some providers for embedded flows.
+    ///
+    /// Requests to other endpoints ignore it.
+    #[cfg(any(feature = "payments", feature = "mandates"))]
+    pub fn with_psu_context(mut self, psu_context: PsuContext) -> Self {
+        self.psu_context = Some(psu_context);
+        self
+    }
+}
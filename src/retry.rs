@@ -0,0 +1,176 @@
+//! Information about the automatic retries of failed requests.
+
+use chrono::{DateTime, Utc};
+use reqwest::{Method, StatusCode, Url};
+use std::{
+    fmt::{Debug, Display, Formatter},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// A request is about to be retried after a transient failure.
+///
+/// Retries are reported as `tracing` events and, if one is configured with
+/// [`TrueLayerClientBuilder::with_retry_observer()`](crate::client::TrueLayerClientBuilder::with_retry_observer),
+/// to a callback.
+#[derive(Debug, Clone)]
+pub struct RetryEvent {
+    /// Number of the upcoming attempt. The first retry is attempt `2`.
+    pub attempt: u32,
+    /// Time waited before sending the request again.
+    pub wait: Duration,
+    pub method: Method,
+    pub url: Url,
+    /// Idempotency key of the request, if any.
+    pub idempotency_key: Option<String>,
+    /// Why the previous attempt failed.
+    pub cause: RetryCause,
+}
+
+/// Class of transient failure which caused a request to be retried.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RetryCause {
+    /// The server responded with a retryable status code (e.g. `429` or `5xx`).
+    Status(StatusCode),
+    /// The request timed out.
+    Timeout,
+    /// The connection to the server could not be established.
+    Connect,
+    /// Any other transient transport error.
+    Other,
+}
+
+impl Display for RetryCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RetryCause::Status(status) => write!(f, "status_{}", status.as_u16()),
+            RetryCause::Timeout => write!(f, "timeout"),
+            RetryCause::Connect => write!(f, "connect"),
+            RetryCause::Other => write!(f, "other"),
+        }
+    }
+}
+
+/// Limits the total number of retries performed by a client, across all its concurrent requests.
+///
+/// The budget is a token bucket holding up to `max_retries` tokens, refilled at a steady rate
+/// of `max_retries` tokens per `window`. Every retry takes a token: when the bucket is empty,
+/// transient failures are returned to the caller instead of being retried, so that an outage
+/// on TrueLayer's side does not multiply the load generated by the client.
+///
+/// Set it with [`TrueLayerClientBuilder::with_retry_budget()`](crate::client::TrueLayerClientBuilder::with_retry_budget).
+/// Clones share the same bucket.
+#[derive(Clone)]
+pub struct RetryBudget {
+    max_retries: u32,
+    window: Duration,
+    bucket: Arc<Mutex<TokenBucket>>,
+    on_exhausted: Option<ExhaustedCallback>,
+}
+
+type ExhaustedCallback = Arc<dyn Fn(&RetryEvent) + Send + Sync + 'static>;
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: DateTime<Utc>,
+}
+
+impl RetryBudget {
+    /// Creates a budget allowing up to `max_retries` retries per `window`.
+    pub fn new(max_retries: u32, window: Duration) -> Self {
+        Self {
+            max_retries,
+            window,
+            bucket: Arc::new(Mutex::new(TokenBucket {
+                tokens: max_retries as f64,
+                refilled_at: Utc::now(),
+            })),
+            on_exhausted: None,
+        }
+    }
+
+    /// Sets a callback invoked every time a retry is skipped because the budget is exhausted.
+    ///
+    /// The callback receives the retry which would have been attempted.
+    /// Skipped retries are always reported as `tracing` events as well.
+    pub fn with_exhausted_observer(
+        mut self,
+        observer: impl Fn(&RetryEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_exhausted = Some(Arc::new(observer));
+        self
+    }
+
+    /// Number of retries currently available.
+    pub fn available(&self) -> u32 {
+        let mut bucket = self.bucket.lock().unwrap();
+        self.refill(&mut bucket);
+        bucket.tokens as u32
+    }
+
+    /// Takes a token from the bucket, returning `false` if the budget is exhausted.
+    pub(crate) fn try_acquire(&self) -> bool {
+        let mut bucket = self.bucket.lock().unwrap();
+        self.refill(&mut bucket);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn report_exhausted(&self, event: &RetryEvent) {
+        if let Some(on_exhausted) = &self.on_exhausted {
+            on_exhausted(event);
+        }
+    }
+
+    fn refill(&self, bucket: &mut TokenBucket) {
+        let now = Utc::now();
+        let elapsed = (now - bucket.refilled_at).to_std().unwrap_or_default();
+        if self.window.is_zero() {
+            bucket.tokens = self.max_retries as f64;
+        } else {
+            let refilled =
+                elapsed.as_secs_f64() / self.window.as_secs_f64() * self.max_retries as f64;
+            bucket.tokens = (bucket.tokens + refilled).min(self.max_retries as f64);
+        }
+        bucket.refilled_at = now;
+    }
+}
+
+impl Debug for RetryBudget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryBudget")
+            .field("max_retries", &self.max_retries)
+            .field("window", &self.window)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_is_shared_between_clones() {
+        let budget = RetryBudget::new(2, Duration::from_secs(3600));
+        let clone = budget.clone();
+
+        assert!(budget.try_acquire());
+        assert!(clone.try_acquire());
+        assert!(!budget.try_acquire());
+        assert_eq!(clone.available(), 0);
+    }
+
+    #[test]
+    fn budget_is_refilled_over_time() {
+        let budget = RetryBudget::new(10, Duration::from_millis(100));
+        while budget.try_acquire() {}
+
+        std::thread::sleep(Duration::from_millis(120));
+        assert_eq!(budget.available(), 10);
+    }
+}
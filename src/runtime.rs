@@ -0,0 +1,95 @@
+//! Abstraction over the async runtime used by the client.
+//!
+//! The client needs to wait between polling attempts. This is delegated to a [`Runtime`], which can
+//! be set with [`TrueLayerClientBuilder::with_runtime()`](crate::client::TrueLayerClientBuilder::with_runtime)
+//! to run the client under executors other than tokio.
+//!
+//! Note that the HTTP stack (`reqwest`) still needs a tokio reactor to be reachable to perform I/O.
+
+use crate::Error;
+use std::{fmt::Debug, sync::Arc, time::Duration};
+
+/// Future returned by a [`Runtime`].
+pub type RuntimeFuture<T> = futures::future::BoxFuture<'static, T>;
+
+/// An async runtime able to sleep.
+pub trait Runtime: Debug + Send + Sync + 'static {
+    /// Returns a future which completes after `duration` has elapsed.
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<()>;
+}
+
+/// [`Runtime`] backed by tokio.
+#[cfg(feature = "tokio-runtime")]
+#[derive(Debug, Clone)]
+pub struct TokioRuntime {
+    handle: tokio::runtime::Handle,
+}
+
+#[cfg(feature = "tokio-runtime")]
+impl TokioRuntime {
+    /// Uses the tokio runtime the caller is currently running on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn current() -> Self {
+        Self::from_handle(tokio::runtime::Handle::current())
+    }
+
+    /// Uses the tokio runtime identified by the given handle.
+    pub fn from_handle(handle: tokio::runtime::Handle) -> Self {
+        Self { handle }
+    }
+}
+
+#[cfg(feature = "tokio-runtime")]
+impl Runtime for TokioRuntime {
+    fn sleep(&self, duration: Duration) -> RuntimeFuture<()> {
+        // Timers must be registered within the runtime they belong to
+        let _guard = self.handle.enter();
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Runtime used when none is explicitly configured, if one can be detected.
+pub(crate) fn default_runtime() -> Option<Arc<dyn Runtime>> {
+    #[cfg(feature = "tokio-runtime")]
+    {
+        tokio::runtime::Handle::try_current()
+            .ok()
+            .map(|handle| Arc::new(TokioRuntime::from_handle(handle)) as Arc<dyn Runtime>)
+    }
+
+    #[cfg(not(feature = "tokio-runtime"))]
+    {
+        None
+    }
+}
+
+/// Returns `runtime` if set, falling back to the one the caller is running on.
+///
+/// Fails with [`Error::NoRuntime`] if no runtime is set and none can be detected.
+pub(crate) fn resolve_runtime(
+    runtime: Option<&Arc<dyn Runtime>>,
+) -> Result<Arc<dyn Runtime>, Error> {
+    runtime
+        .cloned()
+        .or_else(default_runtime)
+        .ok_or(Error::NoRuntime)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_runtime_is_an_error() {
+        assert!(matches!(resolve_runtime(None), Err(Error::NoRuntime)));
+    }
+
+    #[cfg(feature = "tokio-runtime")]
+    #[tokio::test]
+    async fn current_tokio_runtime_is_detected() {
+        assert!(resolve_runtime(None).is_ok());
+    }
+}
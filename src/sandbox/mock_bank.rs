@@ -0,0 +1,182 @@
+//! Headless authorization of payments on the mock bank page of the sandbox.
+//!
+//! Providers of the sandbox (see [`MockProvider`](super::scenarios::MockProvider)) redirect
+//! the user to a mock bank page, where the outcome of the payment is chosen by clicking a button.
+//! [`MockBank`] makes the same choice with plain HTTP requests, so that tests of an integration
+//! can authorize payments end-to-end without a browser:
+//!
+//! ```rust,no_run
+//! # use truelayer_rust::{TrueLayerClient, Error};
+//! # use truelayer_rust::sandbox::{mock_bank::MockBank, scenarios::MockBankOutcome};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//! # let tl: TrueLayerClient = unreachable!();
+//! # let redirect_uri: url::Url = unreachable!();
+//! // `redirect_uri` is the URI of the `Redirect` action of the authorization flow
+//! let outcome = MockBank::new()
+//!     .authorize_and_return(&tl, &redirect_uri, MockBankOutcome::Execute)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requires the `sandbox-tools` feature.
+
+use super::scenarios::MockBankOutcome;
+use crate::{apis::payments::ProviderReturnOutcome, Error, TrueLayerClient};
+use anyhow::anyhow;
+use serde_json::json;
+use url::{form_urlencoded, Url};
+
+/// Driver for the mock bank page of the sandbox.
+#[derive(Debug, Clone, Default)]
+pub struct MockBank {
+    client: reqwest::Client,
+}
+
+impl MockBank {
+    /// Creates a new driver, with its own HTTP client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new driver sending requests with the given HTTP client,
+    /// e.g. to go through a proxy.
+    pub fn with_http_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Chooses the outcome of a payment on the mock bank page the user would be redirected to,
+    /// and returns the URI the mock bank would send the user back to.
+    ///
+    /// `redirect_uri` is the URI of the
+    /// [`Redirect`](crate::apis::payments::AuthorizationFlowNextAction::Redirect) action of the
+    /// authorization flow, in the form `https://{mock bank}/login/{id}#token={token}`.
+    #[tracing::instrument(
+        target = "truelayer_rust::sandbox",
+        name = "Mock Bank Authorization",
+        skip_all,
+        fields(?outcome)
+    )]
+    pub async fn authorize(
+        &self,
+        redirect_uri: &Url,
+        outcome: MockBankOutcome,
+    ) -> Result<Url, Error> {
+        let (id, token) = parse_redirect_uri(redirect_uri)?;
+
+        let return_uri = self
+            .client
+            .post(
+                redirect_uri
+                    .join(&format!("/api/single-immediate-payments/{}/action", id))
+                    .map_err(|e| Error::Other(anyhow!("Invalid mock bank redirect URI: {}", e)))?,
+            )
+            .bearer_auth(token)
+            .json(&json!({
+                "redirect": false,
+                "action": action(outcome),
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+
+        Url::parse(return_uri.trim())
+            .map_err(|e| Error::Other(anyhow!("Invalid URI returned by the mock bank: {}", e)))
+    }
+
+    /// Like [`authorize()`](Self::authorize), but also submits the parameters the mock bank
+    /// returns to TrueLayer, as the return page of a direct return flow would.
+    pub async fn authorize_and_return(
+        &self,
+        tl: &TrueLayerClient,
+        redirect_uri: &Url,
+        outcome: MockBankOutcome,
+    ) -> Result<ProviderReturnOutcome, Error> {
+        let return_uri = self.authorize(redirect_uri, outcome).await?;
+
+        tl.payments
+            .submit_provider_return_uri(return_uri.as_str())
+            .await
+    }
+}
+
+/// Name of the button of the mock bank page matching the outcome.
+fn action(outcome: MockBankOutcome) -> &'static str {
+    match outcome {
+        MockBankOutcome::Execute => "Execute",
+        MockBankOutcome::RejectAuthorisation => "RejectAuthorisation",
+        MockBankOutcome::RejectExecution => "RejectExecution",
+        MockBankOutcome::Cancel => "Cancel",
+    }
+}
+
+/// Extracts the id of the payment and the token of the session from a mock bank redirect URI.
+fn parse_redirect_uri(redirect_uri: &Url) -> Result<(&str, String), Error> {
+    let invalid = || Error::Other(anyhow!("Invalid mock bank redirect URI: {}", redirect_uri));
+
+    let id = redirect_uri
+        .path_segments()
+        .and_then(|mut segments| segments.nth(1))
+        .filter(|id| !id.is_empty())
+        .ok_or_else(invalid)?;
+    let token = form_urlencoded::parse(redirect_uri.fragment().unwrap_or_default().as_bytes())
+        .find(|(k, _)| k == "token")
+        .map(|(_, v)| v.into_owned())
+        .ok_or_else(invalid)?;
+
+    Ok((id, token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::{
+        matchers::{body_json, header, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    #[tokio::test]
+    async fn authorize_chooses_the_outcome_and_returns_the_return_uri() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/single-immediate-payments/payment-id/action"))
+            .and(header("authorization", "Bearer mock-token"))
+            .and(body_json(json!({
+                "redirect": false,
+                "action": "RejectExecution"
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("https://console.truelayer.com/redirect-page?code=c#state=s"),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let redirect_uri = Url::parse(&format!(
+            "{}/login/payment-id#token=mock-token",
+            mock_server.uri()
+        ))
+        .unwrap();
+        let return_uri = MockBank::new()
+            .authorize(&redirect_uri, MockBankOutcome::RejectExecution)
+            .await
+            .unwrap();
+
+        assert_eq!(return_uri.query(), Some("code=c"));
+        assert_eq!(return_uri.fragment(), Some("state=s"));
+    }
+
+    #[test]
+    fn redirect_uri_without_token_is_rejected() {
+        let redirect_uri = Url::parse("https://mock.bank/login/payment-id").unwrap();
+
+        assert!(matches!(
+            parse_redirect_uri(&redirect_uri),
+            Err(Error::Other(_))
+        ));
+    }
+}
@@ -0,0 +1,7 @@
+//! Helpers to test an integration against the TrueLayer sandbox.
+//!
+//! None of these work in the Live environment.
+
+#[cfg(feature = "sandbox-tools")]
+pub mod mock_bank;
+pub mod scenarios;
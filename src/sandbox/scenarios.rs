@@ -0,0 +1,180 @@
+//! Payments pre-configured for the test scenarios of the TrueLayer sandbox.
+//!
+//! The sandbox offers mock providers instead of real banks. Each of them accepts a single
+//! currency, may require additional inputs during the authorization flow, and redirects
+//! the user to a mock bank page where the outcome of the payment is chosen.
+//!
+//! ```rust,no_run
+//! # use truelayer_rust::{TrueLayerClient, Error, apis::payments::*};
+//! # use truelayer_rust::sandbox::scenarios::{self, MockBankOutcome, MockProvider};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Error> {
+//! # let tl: TrueLayerClient = unreachable!();
+//! # let beneficiary: Beneficiary = unreachable!();
+//! let provider = MockProvider::GbRedirect;
+//! let res = tl
+//!     .payments
+//!     .create(&scenarios::create_payment_request(
+//!         provider,
+//!         100,
+//!         beneficiary,
+//!         CreatePaymentUserRequest::ExistingUser { id: "user-id".to_string() },
+//!     ))
+//!     .await?;
+//!
+//! // Select `provider.provider_id()` in the authorization flow, then pick the outcome
+//! // on the mock bank page and check the payment ends up as expected
+//! let expected_failure = MockBankOutcome::RejectExecution.expected_failure();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::apis::payments::{
+    Beneficiary, CreatePaymentRequest, CreatePaymentUserRequest, Currency, FailureStage,
+    PaymentMethodRequest, ProviderSelectionRequest,
+};
+use std::collections::HashMap;
+
+/// Mock providers available in the sandbox.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockProvider {
+    /// UK provider redirecting to the mock bank page.
+    GbRedirect,
+    /// Polish provider, requiring the name and the NRB of the remitter.
+    PlRedirect,
+    /// Norwegian provider, requiring the name and the BBAN of the remitter.
+    NoRedirect,
+    /// German provider, requiring the branch code, account number and sub-account of the user.
+    DeAdditionalInputs,
+}
+
+impl MockProvider {
+    /// Id of the provider, to be submitted as provider selection.
+    pub const fn provider_id(&self) -> &'static str {
+        match self {
+            MockProvider::GbRedirect => "mock-payments-gb-redirect",
+            MockProvider::PlRedirect => "mock-payments-pl-redirect",
+            MockProvider::NoRedirect => "mock-payments-no-redirect",
+            MockProvider::DeAdditionalInputs => "mock-payments-de-redirect-additional-input-text",
+        }
+    }
+
+    /// The only currency accepted by the provider.
+    pub fn currency(&self) -> Currency {
+        match self {
+            MockProvider::GbRedirect => Currency::Gbp,
+            MockProvider::PlRedirect => Currency::Pln,
+            MockProvider::NoRedirect => Currency::Nok,
+            MockProvider::DeAdditionalInputs => Currency::Eur,
+        }
+    }
+
+    /// Values accepted by the provider for the additional inputs it asks for
+    /// in the authorization flow, to be submitted with
+    /// [`PaymentsApi::submit_form_inputs`](crate::apis::payments::PaymentsApi::submit_form_inputs).
+    ///
+    /// Empty if the provider requires none.
+    pub fn form_inputs(&self) -> HashMap<String, String> {
+        let inputs: &[(&str, &str)] = match self {
+            MockProvider::GbRedirect => &[],
+            MockProvider::PlRedirect => &[
+                ("remitter-name", "Rem Itter"),
+                ("remitter-nrb", "12345678901234567890123456"),
+            ],
+            MockProvider::NoRedirect => &[
+                ("remitter-name", "Rem Itter"),
+                ("remitter-bban", "NL39ABNA8234998285"),
+            ],
+            MockProvider::DeAdditionalInputs => &[
+                ("psu-branch-code", "123"),
+                ("psu-account-number", "1234567"),
+                ("psu-sub-account", "01"),
+            ],
+        };
+
+        inputs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+}
+
+/// Outcomes which can be chosen on the mock bank page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockBankOutcome {
+    /// The payment is executed.
+    Execute,
+    /// The bank rejects the authorization.
+    RejectAuthorisation,
+    /// The payment is authorized, but the bank rejects its execution.
+    RejectExecution,
+    /// The user cancels the authorization.
+    Cancel,
+}
+
+impl MockBankOutcome {
+    /// Failure stage and failure reason the payment ends up with,
+    /// or `None` if it's executed.
+    pub fn expected_failure(&self) -> Option<(FailureStage, &'static str)> {
+        match self {
+            MockBankOutcome::Execute => None,
+            MockBankOutcome::RejectAuthorisation => {
+                Some((FailureStage::Authorizing, "authorization_failed"))
+            }
+            MockBankOutcome::RejectExecution => {
+                Some((FailureStage::Authorized, "provider_rejected"))
+            }
+            MockBankOutcome::Cancel => Some((FailureStage::Authorizing, "not_authorized")),
+        }
+    }
+}
+
+/// Builds a request for a payment to be authorized with the given mock provider.
+///
+/// The payment is in the currency of the provider and lets the user select the provider.
+pub fn create_payment_request(
+    provider: MockProvider,
+    amount_in_minor: u64,
+    beneficiary: Beneficiary,
+    user: CreatePaymentUserRequest,
+) -> CreatePaymentRequest {
+    CreatePaymentRequest::builder()
+        .amount_in_minor(amount_in_minor)
+        .currency(provider.currency())
+        .payment_method(PaymentMethodRequest::BankTransfer {
+            provider_selection: ProviderSelectionRequest::UserSelected {
+                filter: None,
+                scheme_selection: None,
+            },
+            beneficiary,
+        })
+        .user(user)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payment_request_uses_the_currency_of_the_provider() {
+        let request = create_payment_request(
+            MockProvider::PlRedirect,
+            100,
+            Beneficiary::MerchantAccount {
+                merchant_account_id: "merchant-account-id".to_string(),
+                account_holder_name: None,
+                reference: None,
+                statement_reference: None,
+                verification: None,
+            },
+            CreatePaymentUserRequest::ExistingUser {
+                id: "user-id".to_string(),
+            },
+        );
+
+        assert_eq!(request.currency, Currency::Pln);
+        assert_eq!(request.amount_in_minor, 100);
+        assert!(MockProvider::GbRedirect.form_inputs().is_empty());
+    }
+}
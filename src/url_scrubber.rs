@@ -0,0 +1,155 @@
+//! Redaction of sensitive parameters from the URLs recorded in `tracing` spans and events.
+//!
+//! The SDK records the URL of every request it sends, both in the span of the request
+//! and in the events reporting retries. Values of the query and fragment parameters
+//! listed in a [`UrlScrubber`] are replaced with [`REDACTED`] before being recorded.
+//! The requests themselves are never altered.
+
+use reqwest::Url;
+use std::sync::Arc;
+use url::form_urlencoded;
+
+/// Placeholder replacing the value of sensitive parameters.
+pub const REDACTED: &str = "REDACTED";
+
+/// Parameters redacted by [`UrlScrubber::default()`].
+pub const DEFAULT_SENSITIVE_PARAMS: &[&str] = &[
+    "access_token",
+    "refresh_token",
+    "resource_token",
+    "client_secret",
+    "code",
+    "id_token",
+];
+
+/// Deny-list of query and fragment parameters whose values must not be recorded.
+///
+/// Parameter names are matched case-insensitively.
+///
+/// ```rust
+/// # use truelayer_rust::url_scrubber::UrlScrubber;
+/// # use reqwest::Url;
+/// let scrubber = UrlScrubber::default().with_param("user_id");
+///
+/// let url = Url::parse("https://api.truelayer.com/path?user_id=123&currency=GBP").unwrap();
+/// assert_eq!(
+///     scrubber.scrub(&url).as_str(),
+///     "https://api.truelayer.com/path?user_id=REDACTED&currency=GBP"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct UrlScrubber {
+    params: Arc<Vec<String>>,
+}
+
+impl Default for UrlScrubber {
+    fn default() -> Self {
+        Self::new(DEFAULT_SENSITIVE_PARAMS.iter().copied())
+    }
+}
+
+impl UrlScrubber {
+    /// Builds a scrubber redacting exactly the given parameters.
+    ///
+    /// Pass an empty list to record URLs as they are.
+    pub fn new<S: Into<String>>(params: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            params: Arc::new(
+                params
+                    .into_iter()
+                    .map(|p| p.into().to_ascii_lowercase())
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns a copy of this scrubber which additionally redacts the given parameter.
+    pub fn with_param(self, param: impl Into<String>) -> Self {
+        let mut params = (*self.params).clone();
+        params.push(param.into().to_ascii_lowercase());
+
+        Self {
+            params: Arc::new(params),
+        }
+    }
+
+    /// Returns a copy of `url` with the values of all the sensitive parameters redacted.
+    ///
+    /// The fragment is redacted too when it contains parameters,
+    /// as in the links to the Hosted Payments Page.
+    pub fn scrub(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+
+        if let Some(query) = url.query().and_then(|q| self.scrub_params(q)) {
+            url.set_query(Some(&query));
+        }
+        if let Some(fragment) = url.fragment().and_then(|f| self.scrub_params(f)) {
+            url.set_fragment(Some(&fragment));
+        }
+
+        url
+    }
+
+    /// Redacts the sensitive parameters of a form-urlencoded string,
+    /// returning `None` if there's nothing to redact.
+    fn scrub_params(&self, params: &str) -> Option<String> {
+        let is_sensitive = |name: &str| self.params.iter().any(|p| p.eq_ignore_ascii_case(name));
+        if !form_urlencoded::parse(params.as_bytes()).any(|(k, _)| is_sensitive(&k)) {
+            return None;
+        }
+
+        Some(
+            form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(form_urlencoded::parse(params.as_bytes()).map(|(k, v)| {
+                    if is_sensitive(&k) {
+                        (k, REDACTED.into())
+                    } else {
+                        (k, v)
+                    }
+                }))
+                .finish(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_default_params_in_query_and_fragment() {
+        let url = Url::parse(
+            "https://payment.truelayer.com/payments?code=abc#payment_id=id&resource_token=token",
+        )
+        .unwrap();
+
+        assert_eq!(
+            UrlScrubber::default().scrub(&url).as_str(),
+            "https://payment.truelayer.com/payments?code=REDACTED#payment_id=id&resource_token=REDACTED"
+        );
+    }
+
+    #[test]
+    fn params_are_matched_case_insensitively() {
+        let url = Url::parse("https://api.truelayer.com/path?Access_Token=abc").unwrap();
+
+        assert_eq!(
+            UrlScrubber::default().scrub(&url).as_str(),
+            "https://api.truelayer.com/path?Access_Token=REDACTED"
+        );
+    }
+
+    #[test]
+    fn urls_without_sensitive_params_are_untouched() {
+        let url = Url::parse("https://api.truelayer.com/path?a=1%202&b#fragment").unwrap();
+
+        assert_eq!(UrlScrubber::default().scrub(&url), url);
+    }
+
+    #[test]
+    fn empty_scrubber_redacts_nothing() {
+        let url = Url::parse("https://api.truelayer.com/path?code=abc").unwrap();
+
+        assert_eq!(UrlScrubber::new(Vec::<String>::new()).scrub(&url), url);
+    }
+}
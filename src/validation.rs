@@ -0,0 +1,433 @@
+//! Local validation of requests before they are sent.
+//!
+//! By default, requests are sent as they are and only validated by TrueLayer. With
+//! [`TrueLayerClientBuilder::with_request_validation(RequestValidation::Strict)`](crate::client::TrueLayerClientBuilder::with_request_validation),
+//! the client rejects currencies which cannot be used with the accounts or countries of the request
+//! (see [`CreatePaymentRequest::validate()`]) and checks amounts, references, account holder names
+//! and metadata against the limits documented by TrueLayer, returning
+//! [`Error::ValidationError`](crate::Error::ValidationError) without contacting TrueLayer.
+
+use crate::apis::payments::{
+    refunds::CreateRefundRequest, Beneficiary, CreatePaymentRequest, Currency, Metadata,
+    PaymentMethodRequest, PaymentRequestError,
+};
+use serde_json::Value;
+
+/// How thoroughly requests are checked before being sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RequestValidation {
+    /// Requests are sent without being checked locally.
+    #[default]
+    Disabled,
+    /// Currencies, amounts, references, account holder names and metadata are checked
+    /// before the request is sent.
+    Strict,
+}
+
+/// A request field which does not satisfy the limits of the TrueLayer APIs.
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+#[error("`{field}` {kind}")]
+pub struct ValidationError {
+    /// Path of the invalid field, e.g. `payment_method.beneficiary.reference`.
+    pub field: String,
+    pub kind: ValidationErrorKind,
+}
+
+/// Reason why a field is invalid.
+#[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
+pub enum ValidationErrorKind {
+    #[error("must be greater than zero")]
+    ZeroAmount,
+    #[error("must not be empty")]
+    Empty,
+    #[error("must be at most {max} characters long")]
+    TooLong { max: usize },
+    #[error("contains the character {0:?}, which is not allowed by the payment scheme")]
+    InvalidCharacter(char),
+    #[error("must have at most {max} entries")]
+    TooManyEntries { max: usize },
+    #[error("cannot be used with {0}")]
+    UnsupportedCurrency(Currency),
+}
+
+impl From<PaymentRequestError> for ValidationError {
+    fn from(e: PaymentRequestError) -> Self {
+        let (field, currency) = match e {
+            PaymentRequestError::UnsupportedBeneficiaryAccount { currency, .. } => {
+                ("payment_method.beneficiary.account_identifier", currency)
+            }
+            PaymentRequestError::UnsupportedRemitterAccount { currency, .. } => (
+                "payment_method.provider_selection.remitter.account_identifier",
+                currency,
+            ),
+            PaymentRequestError::UnsupportedCountries { currency } => (
+                "payment_method.provider_selection.filter.countries",
+                currency,
+            ),
+        };
+        invalid(field, ValidationErrorKind::UnsupportedCurrency(currency))
+    }
+}
+
+/// Maximum length of the name of an account holder.
+const MAX_ACCOUNT_HOLDER_NAME_LENGTH: usize = 140;
+
+/// Maximum number of metadata entries.
+const MAX_METADATA_ENTRIES: usize = 20;
+
+/// Maximum length of a metadata key.
+const MAX_METADATA_KEY_LENGTH: usize = 40;
+
+/// Maximum length of a metadata value, as JSON for values which are not strings.
+const MAX_METADATA_VALUE_LENGTH: usize = 500;
+
+/// Requests which can be checked with [`RequestValidation::Strict`].
+pub(crate) trait StrictValidation {
+    fn validate_strict(&self) -> Result<(), ValidationError>;
+}
+
+impl StrictValidation for CreatePaymentRequest {
+    fn validate_strict(&self) -> Result<(), ValidationError> {
+        self.validate()?;
+        check_amount("amount_in_minor", self.amount_in_minor)?;
+
+        let PaymentMethodRequest::BankTransfer { beneficiary, .. } = &self.payment_method;
+        match beneficiary {
+            Beneficiary::MerchantAccount {
+                account_holder_name,
+                reference,
+                statement_reference,
+                ..
+            } => {
+                if let Some(name) = account_holder_name {
+                    check_account_holder("payment_method.beneficiary.account_holder_name", name)?;
+                }
+                if let Some(r) = reference {
+                    check_reference("payment_method.beneficiary.reference", r, &self.currency)?;
+                }
+                if let Some(r) = statement_reference {
+                    check_reference(
+                        "payment_method.beneficiary.statement_reference",
+                        r,
+                        &self.currency,
+                    )?;
+                }
+            }
+            Beneficiary::ExternalAccount {
+                account_holder_name,
+                reference,
+                ..
+            } => {
+                check_account_holder(
+                    "payment_method.beneficiary.account_holder_name",
+                    account_holder_name,
+                )?;
+                check_reference(
+                    "payment_method.beneficiary.reference",
+                    reference,
+                    &self.currency,
+                )?;
+            }
+        }
+
+        check_metadata(self.metadata.as_ref())
+    }
+}
+
+impl StrictValidation for CreateRefundRequest {
+    fn validate_strict(&self) -> Result<(), ValidationError> {
+        if let Some(amount_in_minor) = self.amount_in_minor {
+            check_amount("amount_in_minor", amount_in_minor)?;
+        }
+        // Refunds are sent in the currency of the payment, which is not part of the request
+        if self.reference.is_empty() {
+            return Err(invalid("reference", ValidationErrorKind::Empty));
+        }
+
+        check_metadata(self.metadata.as_ref())
+    }
+}
+
+#[cfg(feature = "payouts")]
+impl StrictValidation for crate::apis::payouts::CreatePayoutRequest {
+    fn validate_strict(&self) -> Result<(), ValidationError> {
+        use crate::apis::payouts::{PayoutBeneficiary, PayoutRequestError};
+
+        self.validate().map_err(|e| match e {
+            PayoutRequestError::UnsupportedBeneficiaryAccount { currency, .. } => invalid(
+                "beneficiary.account_identifier",
+                ValidationErrorKind::UnsupportedCurrency(currency),
+            ),
+        })?;
+        check_amount("amount_in_minor", self.amount_in_minor)?;
+
+        let reference = match &self.beneficiary {
+            PayoutBeneficiary::ExternalAccount {
+                account_holder_name,
+                reference,
+                ..
+            } => {
+                check_account_holder("beneficiary.account_holder_name", account_holder_name)?;
+                reference
+            }
+            PayoutBeneficiary::PaymentSource { reference, .. }
+            | PayoutBeneficiary::BusinessAccount { reference } => reference,
+        };
+        check_reference("beneficiary.reference", reference, &self.currency)?;
+
+        check_metadata(self.metadata.as_ref())
+    }
+}
+
+#[cfg(feature = "mandates")]
+impl StrictValidation for crate::apis::mandates::CreateMandateRequest {
+    fn validate_strict(&self) -> Result<(), ValidationError> {
+        use crate::apis::mandates::{MandateBeneficiary, MandateDetail};
+
+        check_amount(
+            "constraints.maximum_individual_amount",
+            self.constraints.maximum_individual_amount,
+        )?;
+
+        let (beneficiary, reference) = match &self.mandate {
+            MandateDetail::Sweeping {
+                beneficiary,
+                reference,
+                ..
+            }
+            | MandateDetail::Commercial {
+                beneficiary,
+                reference,
+                ..
+            } => (beneficiary, reference),
+        };
+        match beneficiary {
+            MandateBeneficiary::MerchantAccount {
+                account_holder_name: Some(name),
+                ..
+            }
+            | MandateBeneficiary::ExternalAccount {
+                account_holder_name: name,
+                ..
+            } => check_account_holder("mandate.beneficiary.account_holder_name", name)?,
+            MandateBeneficiary::MerchantAccount { .. } => {}
+        }
+        if let Some(r) = reference {
+            check_reference("mandate.reference", r, &self.currency)?;
+        }
+
+        check_metadata(self.metadata.as_ref())
+    }
+}
+
+fn invalid(field: impl Into<String>, kind: ValidationErrorKind) -> ValidationError {
+    ValidationError {
+        field: field.into(),
+        kind,
+    }
+}
+
+fn check_amount(field: &str, amount_in_minor: u64) -> Result<(), ValidationError> {
+    if amount_in_minor == 0 {
+        return Err(invalid(field, ValidationErrorKind::ZeroAmount));
+    }
+    Ok(())
+}
+
+fn check_max_length(field: &str, value: &str, max: usize) -> Result<(), ValidationError> {
+    if value.chars().count() > max {
+        return Err(invalid(field, ValidationErrorKind::TooLong { max }));
+    }
+    Ok(())
+}
+
+fn check_account_holder(field: &str, name: &str) -> Result<(), ValidationError> {
+    if name.trim().is_empty() {
+        return Err(invalid(field, ValidationErrorKind::Empty));
+    }
+    check_max_length(field, name, MAX_ACCOUNT_HOLDER_NAME_LENGTH)
+}
+
+/// Checks a reference against the limits of the scheme used for payments in the given currency:
+/// Faster Payments for GBP, SEPA for the other currencies.
+fn check_reference(
+    field: &str,
+    reference: &str,
+    currency: &Currency,
+) -> Result<(), ValidationError> {
+    let (max, allowed): (usize, &str) = match currency {
+        Currency::Gbp => (18, " -&./"),
+        _ => (140, " /-?:().,'+"),
+    };
+
+    if reference.trim().is_empty() {
+        return Err(invalid(field, ValidationErrorKind::Empty));
+    }
+    check_max_length(field, reference, max)?;
+    match reference
+        .chars()
+        .find(|c| !c.is_ascii_alphanumeric() && !allowed.contains(*c))
+    {
+        Some(c) => Err(invalid(field, ValidationErrorKind::InvalidCharacter(c))),
+        None => Ok(()),
+    }
+}
+
+fn check_metadata(metadata: Option<&Metadata>) -> Result<(), ValidationError> {
+    let metadata = match metadata {
+        Some(metadata) => metadata,
+        None => return Ok(()),
+    };
+
+    if metadata.len() > MAX_METADATA_ENTRIES {
+        return Err(invalid(
+            "metadata",
+            ValidationErrorKind::TooManyEntries {
+                max: MAX_METADATA_ENTRIES,
+            },
+        ));
+    }
+    for (key, value) in metadata.iter() {
+        let field = format!("metadata.{}", key);
+        check_max_length(&field, key, MAX_METADATA_KEY_LENGTH)?;
+        match value {
+            Value::String(s) => check_max_length(&field, s, MAX_METADATA_VALUE_LENGTH)?,
+            other => check_max_length(&field, &other.to_string(), MAX_METADATA_VALUE_LENGTH)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::payments::{
+        AccountIdentifier, CreatePaymentUserRequest, ProviderSelectionRequest,
+    };
+    use serde_json::json;
+
+    fn payment_request(currency: Currency, reference: &str) -> CreatePaymentRequest {
+        CreatePaymentRequest::builder()
+            .amount_in_minor(100)
+            .currency(currency)
+            .payment_method(PaymentMethodRequest::BankTransfer {
+                provider_selection: ProviderSelectionRequest::UserSelected {
+                    filter: None,
+                    scheme_selection: None,
+                },
+                beneficiary: Beneficiary::ExternalAccount {
+                    account_holder_name: "Mr. Holder".to_string(),
+                    account_identifier: AccountIdentifier::Iban {
+                        iban: "GB33BUKB20201555555555".to_string(),
+                    },
+                    reference: reference.to_string(),
+                },
+            })
+            .user(CreatePaymentUserRequest::ExistingUser {
+                id: "user-id".to_string(),
+            })
+            .build()
+    }
+
+    #[test]
+    fn valid_payment_request() {
+        assert_eq!(
+            payment_request(Currency::Gbp, "Order 123/A").validate_strict(),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn zero_amount_is_invalid() {
+        let mut request = payment_request(Currency::Gbp, "reference");
+        request.amount_in_minor = 0;
+
+        assert_eq!(
+            request.validate_strict(),
+            Err(invalid("amount_in_minor", ValidationErrorKind::ZeroAmount))
+        );
+    }
+
+    #[test]
+    fn currency_must_be_supported_by_the_beneficiary_account() {
+        let mut request = payment_request(Currency::Gbp, "reference");
+        request.payment_method = PaymentMethodRequest::BankTransfer {
+            provider_selection: ProviderSelectionRequest::UserSelected {
+                filter: None,
+                scheme_selection: None,
+            },
+            beneficiary: Beneficiary::ExternalAccount {
+                account_holder_name: "Mr. Holder".to_string(),
+                account_identifier: AccountIdentifier::Nrb {
+                    nrb: "12345678901234567890123456".to_string(),
+                },
+                reference: "reference".to_string(),
+            },
+        };
+
+        assert_eq!(
+            request.validate_strict(),
+            Err(invalid(
+                "payment_method.beneficiary.account_identifier",
+                ValidationErrorKind::UnsupportedCurrency(Currency::Gbp)
+            ))
+        );
+    }
+
+    #[test]
+    fn reference_limits_depend_on_the_scheme() {
+        let long = "a".repeat(19);
+
+        assert_eq!(
+            payment_request(Currency::Gbp, &long).validate_strict(),
+            Err(invalid(
+                "payment_method.beneficiary.reference",
+                ValidationErrorKind::TooLong { max: 18 }
+            ))
+        );
+        assert_eq!(
+            payment_request(Currency::Eur, &long).validate_strict(),
+            Ok(())
+        );
+        assert_eq!(
+            payment_request(Currency::Gbp, "Invoice #1").validate_strict(),
+            Err(invalid(
+                "payment_method.beneficiary.reference",
+                ValidationErrorKind::InvalidCharacter('#')
+            ))
+        );
+    }
+
+    #[test]
+    fn metadata_size_is_capped() {
+        let mut request = payment_request(Currency::Gbp, "reference");
+        request.metadata =
+            Some(Metadata::from_serializable(&json!({ "k": "v".repeat(501) })).unwrap());
+
+        assert_eq!(
+            request.validate_strict(),
+            Err(invalid(
+                "metadata.k",
+                ValidationErrorKind::TooLong {
+                    max: MAX_METADATA_VALUE_LENGTH
+                }
+            ))
+        );
+
+        let entries = (0..=MAX_METADATA_ENTRIES)
+            .map(|i| (i.to_string(), json!(i)))
+            .collect::<serde_json::Map<_, _>>();
+        request.metadata = Some(entries.into());
+
+        assert_eq!(
+            request.validate_strict(),
+            Err(invalid(
+                "metadata",
+                ValidationErrorKind::TooManyEntries {
+                    max: MAX_METADATA_ENTRIES
+                }
+            ))
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::common::mock_server::MockServerConfiguration;
+use crate::common::mock_server::{MockServerConfiguration, MockServerStorage, ReceivedRequest};
 use actix_web::{
     body::BoxBody,
     dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform},
@@ -45,13 +45,57 @@ pub(super) async fn ensure_idempotency_key(req: &mut ServiceRequest) -> Result<(
             .get("Idempotency-Key")
             .map(|v| v.to_str())
             .transpose()?
-            .map_or(false, |v| !v.is_empty()),
+            .is_some_and(|v| !v.is_empty()),
         "Invalid or missing Idempotency Key"
     );
 
     Ok(())
 }
 
+/// Applies the changes of status which are due before the request is handled
+pub(super) fn apply_due_transitions(
+    storage: MockServerStorage,
+) -> impl Fn(&mut ServiceRequest) -> LocalBoxFuture<'_, Result<(), anyhow::Error>> {
+    move |_req: &mut ServiceRequest| {
+        storage.write().unwrap().apply_due_transitions();
+
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Records the request in the storage, so that tests can assert on what the client sent
+pub(super) fn record_request(
+    storage: MockServerStorage,
+) -> impl Fn(&mut ServiceRequest) -> LocalBoxFuture<'_, Result<(), anyhow::Error>> {
+    move |req: &mut ServiceRequest| {
+        let storage = storage.clone();
+
+        Box::pin(async move {
+            let body = buffer_payload(req).await?;
+
+            storage
+                .write()
+                .unwrap()
+                .received_requests
+                .push(ReceivedRequest {
+                    method: req.method().to_string(),
+                    path: req.path().to_string(),
+                    query: req.query_string().to_string(),
+                    headers: req
+                        .headers()
+                        .iter()
+                        .filter_map(|(name, value)| {
+                            Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                        })
+                        .collect(),
+                    body: body.to_vec(),
+                });
+
+            Ok(())
+        })
+    }
+}
+
 /// Validates a full request signature
 pub(super) fn validate_signature(
     configuration: MockServerConfiguration,
@@ -68,15 +112,7 @@ pub(super) fn validate_signature(
                 return Ok(());
             }
 
-            // Buffer all the body in memory
-            let body = req
-                .take_payload()
-                .try_fold(BytesMut::new(), |mut body, chunk| async move {
-                    body.extend_from_slice(&chunk);
-                    Ok::<_, PayloadError>(body)
-                })
-                .map_err(anyhow::Error::from)
-                .await?;
+            let body = buffer_payload(req).await?;
 
             let mut verifier =
                 truelayer_signing::verify_with_pem(configuration.signing_public_key.as_slice())
@@ -107,19 +143,32 @@ pub(super) fn validate_signature(
             }
             verifier.body(&body).verify(signature)?;
 
-            // Put the body back into the request so that it can be consumed by other middlewares
-            req.set_payload(Payload::Stream {
-                payload: futures::stream::once(
-                    async move { Ok::<_, PayloadError>(Bytes::from(body)) },
-                )
-                .boxed(),
-            });
-
             Ok(())
         })
     }
 }
 
+/// Buffers all the body of the request in memory and puts it back into the request,
+/// so that it can be consumed by other middlewares and by the route.
+async fn buffer_payload(req: &mut ServiceRequest) -> Result<Bytes, anyhow::Error> {
+    let body = req
+        .take_payload()
+        .try_fold(BytesMut::new(), |mut body, chunk| async move {
+            body.extend_from_slice(&chunk);
+            Ok::<_, PayloadError>(body)
+        })
+        .map_err(anyhow::Error::from)
+        .await?
+        .freeze();
+
+    let payload = body.clone();
+    req.set_payload(Payload::Stream {
+        payload: futures::stream::once(async move { Ok::<_, PayloadError>(payload) }).boxed(),
+    });
+
+    Ok(body)
+}
+
 /// Helper trait used to circumvent a limitation of Rust's Higher Ranked Trait Bounds
 /// in the implementation of `MiddlewareFnWrapper::call`.
 /// For more info see: https://users.rust-lang.org/t/higher-rank-trait-bounds-use-bound-lifetime-in-another-generic/45121
@@ -4,23 +4,33 @@ mod routes;
 use crate::common::{mock_server::middlewares::MiddlewareFn, MockBankAction};
 use actix_web::{web, App, HttpServer};
 use anyhow::Context;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use reqwest::Url;
+use serde::Serialize;
 use std::{
     collections::HashMap,
     str::FromStr,
     sync::{Arc, RwLock},
+    time::Duration,
 };
 use tokio::sync::oneshot;
+#[cfg(feature = "mandates")]
+use truelayer_rust::apis::mandates::{Mandate, MandateStatus};
+#[cfg(feature = "merchant-accounts")]
+use truelayer_rust::apis::merchant_accounts::{
+    SweepingFrequency, SweepingSettings, SweepingStatus,
+};
+#[cfg(feature = "payouts")]
+use truelayer_rust::apis::payouts::{Payout, PayoutStatus};
 use truelayer_rust::apis::{
-    merchant_accounts::{MerchantAccount, SweepingSettings},
     payments::{
-        refunds::Refund, AccountIdentifier, AuthorizationFlow, AuthorizationFlowActions,
-        AuthorizationFlowNextAction, Beneficiary, CountryCode, Currency, FailureStage, Payment,
-        PaymentMethod, PaymentSource, PaymentStatus, ReleaseChannel,
+        refunds::{Refund, RefundStatus},
+        AccountIdentifier, AuthorizationFlow, AuthorizationFlowActions,
+        AuthorizationFlowNextAction, Beneficiary, CountryCode, Currency, FailureReason,
+        FailureStage, Payment, PaymentMethod, PaymentSource, PaymentStatus, ReleaseChannel,
+        SchemeId,
     },
     payments_providers::{capabilities, Capabilities, PaymentScheme, Provider},
-    payouts::Payout,
 };
 use uuid::Uuid;
 
@@ -30,6 +40,78 @@ const MOCK_PROVIDER_NO_REDIRECT_ADDITIONAL_INPUTS: &str = "mock-payments-no-redi
 const MOCK_PROVIDER_DE_ADDITIONAL_INPUTS: &str = "mock-payments-de-redirect-additional-input-text";
 const MOCK_REDIRECT_URI: &str = "https://mock.redirect.uri/";
 
+/// Payouts and refunds created with this reference fail instead of being executed.
+pub const MOCK_FAILURE_REFERENCE: &str = "mock-failure";
+
+/// A request received by the mock server, recorded before any validation.
+#[derive(Clone, Debug)]
+pub struct ReceivedRequest {
+    pub method: String,
+    /// Path of the request, without the query.
+    pub path: String,
+    pub query: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl ReceivedRequest {
+    /// Value of the `Idempotency-Key` header, if any.
+    pub fn idempotency_key(&self) -> Option<&str> {
+        self.header("Idempotency-Key")
+    }
+
+    /// Returns `true` if the request carries a `Tl-Signature` header.
+    pub fn is_signed(&self) -> bool {
+        self.header("Tl-Signature").is_some()
+    }
+
+    /// Value of a header, looked up case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Body of the request parsed as JSON, or `None` if the request has no JSON body.
+    pub fn body_json(&self) -> Option<serde_json::Value> {
+        serde_json::from_slice(&self.body).ok()
+    }
+}
+
+/// Simulated lifecycle of the resources created on the mock server.
+#[derive(Clone, Debug)]
+pub struct MockLifecycle {
+    /// Time after which pending payouts and refunds are executed, or failed if their reference
+    /// is [`MOCK_FAILURE_REFERENCE`].
+    pub execution_delay: Duration,
+    /// Time after which executed payments into a merchant account are settled.
+    pub settlement_delay: Duration,
+    /// Time after which a mandate is authorized once its authorization flow has been started.
+    pub authorization_delay: Duration,
+}
+
+impl Default for MockLifecycle {
+    fn default() -> Self {
+        Self {
+            execution_delay: Duration::from_millis(100),
+            settlement_delay: Duration::from_millis(100),
+            authorization_delay: Duration::from_millis(100),
+        }
+    }
+}
+
+/// A merchant account of the mock server, served with the fields of a `MerchantAccount`
+/// so that it is available even when the `merchant-accounts` feature is disabled.
+#[derive(Clone, Debug, Serialize)]
+pub struct MockMerchantAccount {
+    pub id: String,
+    pub currency: Currency,
+    pub account_identifiers: Vec<AccountIdentifier>,
+    pub available_balance_in_minor: i64,
+    pub current_balance_in_minor: i64,
+    pub account_holder_name: String,
+}
+
 #[derive(Clone)]
 struct MockServerConfiguration {
     client_id: String,
@@ -37,16 +119,153 @@ struct MockServerConfiguration {
     signing_key_id: String,
     signing_public_key: Vec<u8>,
     access_token: String,
-    merchant_accounts: HashMap<Currency, MerchantAccount>,
+    merchant_accounts: HashMap<Currency, MockMerchantAccount>,
     payments_providers: Vec<Provider>,
     sweeping_approved_ibans: HashMap<String, String>,
+    lifecycle: MockLifecycle,
 }
 
 #[derive(Clone, Default)]
 struct MockServerStorageInner {
     payments: HashMap<String, (Payment, HashMap<String, Refund>)>,
+    #[cfg(feature = "payouts")]
     payouts: HashMap<String, Payout>,
+    #[cfg(feature = "mandates")]
+    mandates: HashMap<String, Mandate>,
+    #[cfg(feature = "merchant-accounts")]
     sweeping: HashMap<String, SweepingSettings>,
+    /// Times at which sweeping ran for each merchant account.
+    #[cfg(feature = "merchant-accounts")]
+    sweeping_runs: HashMap<String, Vec<DateTime<Utc>>>,
+    transitions: Vec<ScheduledTransition>,
+    /// How far the virtual clock of the mock server is ahead of the real one.
+    clock_offset: Duration,
+    /// Every request received, in the order they arrived.
+    received_requests: Vec<ReceivedRequest>,
+}
+
+/// A change of status of a resource which is scheduled to happen in the future.
+#[derive(Clone)]
+struct ScheduledTransition {
+    due_at: DateTime<Utc>,
+    change: StatusChange,
+}
+
+#[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
+enum StatusChange {
+    Payment {
+        id: String,
+        status: PaymentStatus,
+    },
+    Refund {
+        payment_id: String,
+        id: String,
+        status: RefundStatus,
+    },
+    #[cfg(feature = "payouts")]
+    Payout {
+        id: String,
+        status: PayoutStatus,
+    },
+    #[cfg(feature = "mandates")]
+    Mandate {
+        id: String,
+        status: MandateStatus,
+    },
+}
+
+impl MockServerStorageInner {
+    /// Current time according to the virtual clock of the mock server.
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now() + chrono::Duration::from_std(self.clock_offset).unwrap()
+    }
+
+    /// Schedules a change of status to happen after the given delay.
+    fn schedule(&mut self, delay: Duration, change: StatusChange) {
+        self.transitions.push(ScheduledTransition {
+            due_at: self.now() + chrono::Duration::from_std(delay).unwrap(),
+            change,
+        });
+    }
+
+    /// Applies all the scheduled changes of status which are due, in chronological order.
+    ///
+    /// Transitions are applied lazily whenever a request is received, so that tests
+    /// do not depend on background tasks being scheduled in time.
+    fn apply_due_transitions(&mut self) {
+        let now = self.now();
+        let (mut due, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.transitions)
+            .into_iter()
+            .partition(|t| t.due_at <= now);
+        self.transitions = pending;
+        due.sort_by_key(|t| t.due_at);
+
+        for transition in due {
+            match transition.change {
+                StatusChange::Payment { id, status } => {
+                    if let Some((payment, _)) = self.payments.get_mut(&id) {
+                        payment.status = status;
+                    }
+                }
+                StatusChange::Refund {
+                    payment_id,
+                    id,
+                    status,
+                } => {
+                    if let Some(refund) = self
+                        .payments
+                        .get_mut(&payment_id)
+                        .and_then(|(_, refunds)| refunds.get_mut(&id))
+                    {
+                        // Cancelled refunds are never executed
+                        if refund.status != RefundStatus::Cancelled {
+                            refund.status = status;
+                        }
+                    }
+                }
+                #[cfg(feature = "payouts")]
+                StatusChange::Payout { id, status } => {
+                    if let Some(payout) = self.payouts.get_mut(&id) {
+                        payout.status = status;
+                    }
+                }
+                #[cfg(feature = "mandates")]
+                StatusChange::Mandate { id, status } => {
+                    if let Some(mandate) = self.mandates.get_mut(&id) {
+                        mandate.status = status;
+                    }
+                }
+            }
+        }
+
+        #[cfg(feature = "merchant-accounts")]
+        self.run_due_sweeps(now);
+    }
+
+    /// Runs sweeping for every merchant account whose next sweep is due,
+    /// once for each period elapsed since then.
+    #[cfg(feature = "merchant-accounts")]
+    fn run_due_sweeps(&mut self, now: DateTime<Utc>) {
+        for (merchant_account_id, settings) in &mut self.sweeping {
+            if settings.status != SweepingStatus::Active {
+                continue;
+            }
+            let period = match settings.frequency {
+                SweepingFrequency::Daily => chrono::Duration::days(1),
+                SweepingFrequency::Weekly => chrono::Duration::weeks(1),
+                SweepingFrequency::Fortnightly => chrono::Duration::weeks(2),
+            };
+
+            while let Some(next_execution_at) = settings.next_execution_at.filter(|t| *t <= now) {
+                self.sweeping_runs
+                    .entry(merchant_account_id.clone())
+                    .or_default()
+                    .push(next_execution_at);
+                settings.next_execution_at = Some(next_execution_at + period);
+            }
+        }
+    }
 }
 
 /// In-memory storage for payments created on the mock server.
@@ -66,6 +285,7 @@ impl TrueLayerMockServer {
         client_secret: &str,
         signing_key_id: &str,
         signing_public_key: Vec<u8>,
+        lifecycle: MockLifecycle,
     ) -> Self {
         // Prepare the mock server configuration
         let merchant_account_gbp_id = Uuid::new_v4().to_string();
@@ -78,7 +298,7 @@ impl TrueLayerMockServer {
             merchant_accounts: [
                 (
                     Currency::Gbp,
-                    MerchantAccount {
+                    MockMerchantAccount {
                         id: merchant_account_gbp_id.clone(),
                         currency: Currency::Gbp,
                         account_identifiers: vec![AccountIdentifier::SortCodeAccountNumber {
@@ -92,7 +312,7 @@ impl TrueLayerMockServer {
                 ),
                 (
                     Currency::Eur,
-                    MerchantAccount {
+                    MockMerchantAccount {
                         id: Uuid::new_v4().to_string(),
                         currency: Currency::Eur,
                         account_identifiers: vec![AccountIdentifier::Iban {
@@ -103,6 +323,42 @@ impl TrueLayerMockServer {
                         account_holder_name: "Mr. Holder".to_string(),
                     },
                 ),
+                (
+                    Currency::Pln,
+                    MockMerchantAccount {
+                        id: Uuid::new_v4().to_string(),
+                        currency: Currency::Pln,
+                        account_identifiers: vec![
+                            AccountIdentifier::Nrb {
+                                nrb: "12345678901234567890123456".to_string(),
+                            },
+                            AccountIdentifier::Iban {
+                                iban: "some-pl-iban".to_string(),
+                            },
+                        ],
+                        available_balance_in_minor: 100,
+                        current_balance_in_minor: 200,
+                        account_holder_name: "Mr. Holder".to_string(),
+                    },
+                ),
+                (
+                    Currency::Nok,
+                    MockMerchantAccount {
+                        id: Uuid::new_v4().to_string(),
+                        currency: Currency::Nok,
+                        account_identifiers: vec![
+                            AccountIdentifier::Bban {
+                                bban: "12345678901".to_string(),
+                            },
+                            AccountIdentifier::Iban {
+                                iban: "some-no-iban".to_string(),
+                            },
+                        ],
+                        available_balance_in_minor: 100,
+                        current_balance_in_minor: 200,
+                        account_holder_name: "Mr. Holder".to_string(),
+                    },
+                ),
             ]
             .into_iter()
             .collect(),
@@ -119,11 +375,13 @@ impl TrueLayerMockServer {
                             bank_transfer: Some(capabilities::BankTransfer {
                                 release_channel: ReleaseChannel::GeneralAvailability,
                                 schemes: vec![PaymentScheme {
-                                    id: "polish_domestic_standard".into(),
+                                    id: SchemeId::PolishDomesticStandard,
                                 }],
                             }),
                         },
+                        mandates: None,
                     },
+                    availability: None,
                 },
                 Provider {
                     id: MOCK_PROVIDER_NO_REDIRECT_ADDITIONAL_INPUTS.into(),
@@ -137,11 +395,13 @@ impl TrueLayerMockServer {
                             bank_transfer: Some(capabilities::BankTransfer {
                                 release_channel: ReleaseChannel::GeneralAvailability,
                                 schemes: vec![PaymentScheme {
-                                    id: "norwegian_domestic_credit_transfer".into(),
+                                    id: SchemeId::NorwegianDomesticCreditTransfer,
                                 }],
                             }),
                         },
+                        mandates: None,
                     },
+                    availability: None,
                 },
                 Provider {
                     id: MOCK_PROVIDER_GB_REDIRECT.into(),
@@ -155,11 +415,19 @@ impl TrueLayerMockServer {
                             bank_transfer: Some(capabilities::BankTransfer {
                                 release_channel: ReleaseChannel::GeneralAvailability,
                                 schemes: vec![PaymentScheme {
-                                    id: "faster_payments_service".into(),
+                                    id: SchemeId::FasterPaymentsService,
                                 }],
                             }),
                         },
+                        mandates: Some(capabilities::Mandates {
+                            vrp_sweeping: Some(capabilities::VrpMandate {
+                                release_channel: ReleaseChannel::GeneralAvailability,
+                                schemes: None,
+                            }),
+                            vrp_commercial: None,
+                        }),
                     },
+                    availability: None,
                 },
                 Provider {
                     id: MOCK_PROVIDER_DE_ADDITIONAL_INPUTS.into(),
@@ -175,11 +443,13 @@ impl TrueLayerMockServer {
                             bank_transfer: Some(capabilities::BankTransfer {
                                 release_channel: ReleaseChannel::GeneralAvailability,
                                 schemes: vec![PaymentScheme {
-                                    id: "sepa_credit_transfer".into(),
+                                    id: SchemeId::SepaCreditTransfer,
                                 }],
                             }),
                         },
+                        mandates: None,
                     },
+                    availability: None,
                 },
             ],
             sweeping_approved_ibans: [
@@ -188,6 +458,7 @@ impl TrueLayerMockServer {
             ]
             .into_iter()
             .collect(),
+            lifecycle,
         };
         let configuration_clone = configuration.clone();
         // Setup the in-memory storage
@@ -196,11 +467,19 @@ impl TrueLayerMockServer {
 
         // Setup the mock HTTP server and bind it to a random port
         let http_server_factory = HttpServer::new(move || {
-            App::new()
+            let app = App::new()
                 .app_data(web::Data::new(configuration.clone()))
                 .app_data(web::Data::new(storage.clone()))
                 // User agent must be validated for each request
                 .wrap(MiddlewareFn::new(middlewares::validate_user_agent))
+                // Move resources forward in their lifecycle before serving each request
+                .wrap(MiddlewareFn::new(middlewares::apply_due_transitions(
+                    storage.clone(),
+                )))
+                // Record every request, including the ones rejected by the other middlewares
+                .wrap(MiddlewareFn::new(middlewares::record_request(
+                    storage.clone(),
+                )))
                 // Mock routes
                 .service(web::resource("/connect/token").route(web::post().to(routes::post_auth)))
                 .service(
@@ -261,6 +540,15 @@ impl TrueLayerMockServer {
                         )))
                         .route(web::post().to(routes::cancel_payment)),
                 )
+                .service(
+                    web::resource("/payments/{id}/actions/retry")
+                        .wrap(MiddlewareFn::new(middlewares::ensure_idempotency_key))
+                        .wrap(MiddlewareFn::new(middlewares::validate_signature(
+                            configuration.clone(),
+                            true,
+                        )))
+                        .route(web::post().to(routes::retry_payment)),
+                )
                 .service(
                     web::resource("/payments/{id}/refunds")
                         .wrap(MiddlewareFn::new(middlewares::ensure_idempotency_key))
@@ -280,10 +568,27 @@ impl TrueLayerMockServer {
                         )))
                         .route(web::get().to(routes::get_refund_by_id)),
                 )
+                .service(
+                    web::resource("/payments/{payment_id}/refunds/{id}/actions/cancel")
+                        .wrap(MiddlewareFn::new(middlewares::ensure_idempotency_key))
+                        .wrap(MiddlewareFn::new(middlewares::validate_signature(
+                            configuration.clone(),
+                            true,
+                        )))
+                        .route(web::post().to(routes::cancel_refund)),
+                )
                 .service(
                     web::resource("/payments-providers/{id}")
                         .route(web::get().to(routes::get_payments_provider_by_id)),
                 )
+                .service(
+                    web::resource("/payments-provider-return")
+                        .route(web::post().to(routes::submit_provider_return_parameters)),
+                );
+
+            // Mock routes of the optional APIs
+            #[cfg(feature = "merchant-accounts")]
+            let app = app
                 .service(
                     web::resource("/merchant-accounts")
                         .route(web::get().to(routes::list_merchant_accounts)),
@@ -311,14 +616,50 @@ impl TrueLayerMockServer {
                     web::resource("/merchant-accounts/{id}/payment-sources")
                         .route(web::get().to(routes::list_payment_sources)),
                 )
+                .service(
+                    web::resource("/merchant-accounts/{id}/payment-sources/{payment_source_id}")
+                        .route(web::get().to(routes::get_payment_source_by_id)),
+                );
+            #[cfg(feature = "payouts")]
+            let app = app
                 .service(web::resource("/payouts").route(web::post().to(routes::create_payout)))
                 .service(
                     web::resource("/payouts/{id}").route(web::get().to(routes::get_payout_by_id)),
+                );
+            #[cfg(feature = "mandates")]
+            let app = app
+                .service(
+                    web::resource("/mandates")
+                        .wrap(MiddlewareFn::new(middlewares::ensure_idempotency_key))
+                        .wrap(MiddlewareFn::new(middlewares::validate_signature(
+                            configuration.clone(),
+                            true,
+                        )))
+                        .route(web::post().to(routes::create_mandate)),
                 )
                 .service(
-                    web::resource("/payments-provider-return")
-                        .route(web::post().to(routes::submit_provider_return_parameters)),
+                    web::resource("/mandates/{id}").route(web::get().to(routes::get_mandate_by_id)),
+                )
+                .service(
+                    web::resource("/mandates/{id}/authorization-flow")
+                        .wrap(MiddlewareFn::new(middlewares::ensure_idempotency_key))
+                        .wrap(MiddlewareFn::new(middlewares::validate_signature(
+                            configuration.clone(),
+                            true,
+                        )))
+                        .route(web::post().to(routes::start_mandate_authorization_flow)),
                 )
+                .service(
+                    web::resource("/mandates/{id}/revoke")
+                        .wrap(MiddlewareFn::new(middlewares::ensure_idempotency_key))
+                        .wrap(MiddlewareFn::new(middlewares::validate_signature(
+                            configuration.clone(),
+                            true,
+                        )))
+                        .route(web::post().to(routes::revoke_mandate)),
+                );
+
+            app
         })
         .workers(1)
         .bind("127.0.0.1:0")
@@ -351,10 +692,38 @@ impl TrueLayerMockServer {
         &self.url
     }
 
-    pub fn merchant_account(&self, currency: Currency) -> Option<&MerchantAccount> {
+    pub fn merchant_account(&self, currency: Currency) -> Option<&MockMerchantAccount> {
         self.configuration.merchant_accounts.get(&currency)
     }
 
+    /// Moves the virtual clock of the mock server forward, applying all the changes of status
+    /// and running all the sweeps which become due in the meantime.
+    ///
+    /// Use it with long [`MockLifecycle`] delays to control when resources move forward,
+    /// instead of sleeping.
+    pub fn advance_clock(&self, by: Duration) {
+        let mut storage = self.storage.write().unwrap();
+        storage.clock_offset += by;
+        storage.apply_due_transitions();
+    }
+
+    /// Times at which sweeping ran for the given merchant account, in chronological order.
+    #[cfg(feature = "merchant-accounts")]
+    pub fn sweeping_runs(&self, merchant_account_id: &str) -> Vec<DateTime<Utc>> {
+        self.storage
+            .read()
+            .unwrap()
+            .sweeping_runs
+            .get(merchant_account_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Requests received so far, in the order they arrived.
+    pub fn received_requests(&self) -> Vec<ReceivedRequest> {
+        self.storage.read().unwrap().received_requests.clone()
+    }
+
     pub fn sweeping_iban(&self, merchant_account_id: &str) -> Option<String> {
         self.configuration
             .sweeping_approved_ibans
@@ -374,10 +743,12 @@ impl TrueLayerMockServer {
             .context("Missing payment id")?;
 
         let mut storage = self.storage.write().unwrap();
+        let now = storage.now();
         let (payment, _) = storage
             .payments
             .get_mut(payment_id)
             .context("Payment not found")?;
+        let mut settlement = None;
 
         // Ensure the payment was in Authorizing state waiting for the redirect to complete
         let auth_flow_configuration = match payment.status {
@@ -408,15 +779,26 @@ impl TrueLayerMockServer {
                     ..
                 } = payment.payment_method
                 {
-                    PaymentStatus::Settled {
+                    // Payments into a merchant account are settled some time after their execution
+                    let executed_at = now;
+                    settlement = Some(PaymentStatus::Settled {
                         payment_source: PaymentSource {
                             id: "source-id".into(),
                             user_id: None,
                             account_identifiers: vec![],
                             account_holder_name: None,
                         },
-                        executed_at: Utc::now(),
-                        settled_at: Utc::now(),
+                        executed_at,
+                        settled_at: executed_at
+                            + chrono::Duration::from_std(
+                                self.configuration.lifecycle.settlement_delay,
+                            )
+                            .unwrap(),
+                        authorization_flow: Some(next_auth_flow.clone()),
+                        settlement_risk: None,
+                    });
+                    PaymentStatus::Executed {
+                        executed_at,
                         authorization_flow: Some(next_auth_flow),
                         settlement_risk: None,
                     }
@@ -431,23 +813,36 @@ impl TrueLayerMockServer {
             MockBankAction::RejectAuthorisation => PaymentStatus::Failed {
                 failed_at: Utc::now(),
                 failure_stage: FailureStage::Authorizing,
-                failure_reason: "authorization_failed".to_string(),
+                failure_reason: FailureReason::AuthorizationFailed,
                 authorization_flow: Some(next_auth_flow),
+                provider_error: None,
             },
             MockBankAction::RejectExecution => PaymentStatus::Failed {
                 failed_at: Utc::now(),
                 failure_stage: FailureStage::Authorized,
-                failure_reason: "provider_rejected".to_string(),
+                failure_reason: FailureReason::ProviderRejected,
                 authorization_flow: Some(next_auth_flow),
+                provider_error: None,
             },
             MockBankAction::Cancel => PaymentStatus::Failed {
                 failed_at: Utc::now(),
                 failure_stage: FailureStage::Authorizing,
-                failure_reason: "not_authorized".to_string(),
+                failure_reason: FailureReason::NotAuthorized,
                 authorization_flow: Some(next_auth_flow),
+                provider_error: None,
             },
         };
 
+        if let Some(status) = settlement {
+            storage.schedule(
+                self.configuration.lifecycle.settlement_delay,
+                StatusChange::Payment {
+                    id: payment_id.to_string(),
+                    status,
+                },
+            );
+        }
+
         Ok(Url::from_str(&format!(
             "https://mock.return.uri/#{}",
             payment_id
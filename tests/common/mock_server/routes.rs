@@ -1,31 +1,37 @@
 use std::collections::HashMap;
 
 use crate::common::mock_server::{
-    MockServerConfiguration, MockServerStorage, MOCK_PROVIDER_DE_ADDITIONAL_INPUTS,
-    MOCK_PROVIDER_GB_REDIRECT, MOCK_PROVIDER_NO_REDIRECT_ADDITIONAL_INPUTS,
-    MOCK_PROVIDER_PL_REDIRECT_ADDITIONAL_INPUTS, MOCK_REDIRECT_URI,
+    MockServerConfiguration, MockServerStorage, StatusChange, MOCK_FAILURE_REFERENCE,
+    MOCK_PROVIDER_DE_ADDITIONAL_INPUTS, MOCK_PROVIDER_GB_REDIRECT,
+    MOCK_PROVIDER_NO_REDIRECT_ADDITIONAL_INPUTS, MOCK_PROVIDER_PL_REDIRECT_ADDITIONAL_INPUTS,
+    MOCK_REDIRECT_URI,
 };
 use actix_web::{web, HttpResponse};
 use chrono::offset::Utc;
 use serde_json::json;
+#[cfg(feature = "mandates")]
+use truelayer_rust::apis::mandates::{CreateMandateRequest, Mandate, MandateStatus};
+#[cfg(feature = "merchant-accounts")]
+use truelayer_rust::apis::merchant_accounts::{
+    ListPaymentSourcesRequest, SetupSweepingRequest, SweepingSettings, SweepingStatus, Transaction,
+    TransactionPayinStatus, TransactionType,
+};
+#[cfg(feature = "payouts")]
+use truelayer_rust::apis::payouts::{CreatePayoutRequest, Payout, PayoutBeneficiary, PayoutStatus};
 use truelayer_rust::apis::{
     auth::Credentials,
-    merchant_accounts::{
-        ListPaymentSourcesRequest, SetupSweepingRequest, SweepingSettings, Transaction,
-        TransactionPayinStatus, TransactionType,
-    },
     payments::{
         refunds::{CreateRefundRequest, Refund, RefundStatus},
         AccountIdentifier, AdditionalInput, AdditionalInputDisplayText, AdditionalInputFormat,
         AdditionalInputRegex, AuthorizationFlow, AuthorizationFlowActions,
         AuthorizationFlowNextAction, AuthorizationFlowResponseStatus, CreatePaymentRequest,
-        CreatePaymentUserRequest, Currency, FailureStage, Payment, PaymentMethod, PaymentSource,
-        PaymentStatus, Provider, ProviderSelection, ProviderSelectionRequest,
-        StartAuthorizationFlowRequest, StartAuthorizationFlowResponse, SubmitFormActionRequest,
+        CreatePaymentUserRequest, Currency, FailureReason, FailureStage, Payment, PaymentMethod,
+        PaymentSource, PaymentStatus, Provider, ProviderSelection, ProviderSelectionRequest,
+        RetryPaymentRequest, SchemeSelection, StartAuthorizationFlowRequest,
+        StartAuthorizationFlowResponse, SubmitFormActionRequest,
         SubmitProviderReturnParametersRequest, SubmitProviderSelectionActionRequest,
         SubsequentAction, User,
     },
-    payouts::{CreatePayoutRequest, Payout, PayoutStatus},
 };
 use uuid::Uuid;
 
@@ -62,13 +68,19 @@ pub(super) async fn create_payment(
     let id = Uuid::new_v4().to_string();
     let user = match create_payment_request.user.clone() {
         CreatePaymentUserRequest::NewUser {
-            name: _,
-            email: _,
-            phone: _,
+            date_of_birth,
+            address,
+            ..
         } => User {
             id: "payment-source-user-id".to_string(),
+            date_of_birth,
+            address,
+        },
+        CreatePaymentUserRequest::ExistingUser { id } => User {
+            id,
+            date_of_birth: None,
+            address: None,
         },
-        CreatePaymentUserRequest::ExistingUser { id } => User { id },
     };
 
     let payment_method = match create_payment_request.payment_method.clone() {
@@ -104,7 +116,7 @@ pub(super) async fn create_payment(
         id.clone(),
         (
             Payment {
-                id: id.clone(),
+                id: id.clone().into(),
                 amount_in_minor: create_payment_request.amount_in_minor,
                 currency: create_payment_request.currency.clone(),
                 user: user.clone(),
@@ -112,6 +124,8 @@ pub(super) async fn create_payment(
                 created_at: Utc::now(),
                 status: PaymentStatus::AuthorizationRequired,
                 metadata: create_payment_request.metadata.clone(),
+                schedule: create_payment_request.schedule.clone(),
+                risk_assessment: create_payment_request.risk_assessment.clone(),
             },
             HashMap::new(),
         ),
@@ -196,6 +210,7 @@ pub(super) async fn start_authorization_flow(
                             logo_uri: None,
                             bg_color: None,
                             country_code: None,
+                            availability: p.availability.clone(),
                         })
                         .collect(),
                 },
@@ -238,13 +253,22 @@ pub(super) async fn submit_provider_selection(
     if let PaymentMethod::BankTransfer {
         provider_selection:
             ProviderSelection::UserSelected {
+                ref scheme_selection,
                 ref mut provider_id,
+                ref mut scheme_id,
                 ..
             },
         ..
     } = payment.payment_method
     {
+        // The scheme must be chosen by the PSU if and only if the payment asks so
+        let user_selects_scheme = matches!(scheme_selection, Some(SchemeSelection::UserSelected));
+        if user_selects_scheme != body.scheme_id.is_some() {
+            return HttpResponse::BadRequest().finish();
+        }
+
         *provider_id = Some(body.provider_id.clone());
+        *scheme_id = body.scheme_id.clone();
     }
 
     match payment.status {
@@ -484,16 +508,84 @@ pub(super) async fn cancel_payment(
         None => return HttpResponse::NotFound().finish(),
     };
 
+    // Only payments which have not been authorized yet can be cancelled
+    if !matches!(
+        payment.status,
+        PaymentStatus::AuthorizationRequired | PaymentStatus::Authorizing { .. }
+    ) {
+        return HttpResponse::BadRequest().finish();
+    }
+
     payment.status = PaymentStatus::Failed {
         failed_at: Utc::now(),
         failure_stage: FailureStage::AuthorizationRequired,
-        failure_reason: "canceled".into(),
+        failure_reason: FailureReason::Canceled,
         authorization_flow: None,
+        provider_error: None,
     };
 
     HttpResponse::Accepted().finish()
 }
 
+/// POST /payments/{id}/actions/retry
+pub(super) async fn retry_payment(
+    storage: web::Data<MockServerStorage>,
+    path: web::Path<String>,
+    body: web::Json<RetryPaymentRequest>,
+) -> HttpResponse {
+    let id = path.into_inner();
+
+    // Extract the payment from its id
+    let mut map = storage.write().unwrap();
+    let (payment, _) = match map.payments.get_mut(&id) {
+        Some(payment) => payment,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    // Only failed payments can be retried
+    if !matches!(payment.status, PaymentStatus::Failed { .. }) {
+        return HttpResponse::BadRequest().finish();
+    }
+
+    if let (
+        PaymentMethod::BankTransfer {
+            provider_selection, ..
+        },
+        Some(new_provider_selection),
+    ) = (
+        &mut payment.payment_method,
+        body.into_inner().provider_selection,
+    ) {
+        *provider_selection = match new_provider_selection {
+            ProviderSelectionRequest::UserSelected {
+                filter,
+                scheme_selection,
+            } => ProviderSelection::UserSelected {
+                filter,
+                scheme_selection,
+                provider_id: None,
+                scheme_id: None,
+            },
+            ProviderSelectionRequest::Preselected {
+                provider_id,
+                scheme_id,
+                remitter,
+            } => ProviderSelection::Preselected {
+                provider_id,
+                scheme_id,
+                remitter,
+            },
+        };
+    }
+    payment.status = PaymentStatus::AuthorizationRequired;
+
+    HttpResponse::Ok().json(json!({
+        "id": id,
+        "resource_token": format!("resource-token-{}", id),
+        "status": "authorization_required"
+    }))
+}
+
 /// GET /payments
 pub(super) async fn hpp_page() -> HttpResponse {
     // Intentionally empty. We don't need to do anything here.
@@ -520,6 +612,7 @@ pub(super) async fn get_payments_provider_by_id(
 
 /// POST /payments/{id}/refunds
 pub(super) async fn create_refund(
+    configuration: web::Data<MockServerConfiguration>,
     storage: web::Data<MockServerStorage>,
     path: web::Path<String>,
     request: web::Json<CreateRefundRequest>,
@@ -532,19 +625,57 @@ pub(super) async fn create_refund(
         None => return HttpResponse::NotFound().finish(),
     };
 
+    // Only settled payments can be refunded, up to their amount
+    if !matches!(payment.status, PaymentStatus::Settled { .. }) {
+        return HttpResponse::BadRequest().finish();
+    }
+    let refunded_amount: u64 = refunds
+        .values()
+        .filter(|r| {
+            !matches!(
+                r.status,
+                RefundStatus::Failed { .. } | RefundStatus::Cancelled
+            )
+        })
+        .map(|r| r.amount_in_minor)
+        .sum();
+    let amount_in_minor = request
+        .amount_in_minor
+        .unwrap_or(payment.amount_in_minor - refunded_amount);
+    if amount_in_minor == 0 || refunded_amount + amount_in_minor > payment.amount_in_minor {
+        return HttpResponse::BadRequest().finish();
+    }
+
     let refund_id = Uuid::new_v4().to_string();
     refunds.insert(
         refund_id.clone(),
         Refund {
             id: refund_id.clone(),
-            amount_in_minor: request.amount_in_minor.unwrap_or(payment.amount_in_minor),
+            amount_in_minor,
             currency: payment.currency.clone(),
             reference: request.reference.clone(),
             created_at: Utc::now(),
             metadata: request.metadata.clone(),
-            status: RefundStatus::Executed {
-                executed_at: Utc::now(),
-            },
+            status: RefundStatus::Pending,
+        },
+    );
+
+    let status = if request.reference == MOCK_FAILURE_REFERENCE {
+        RefundStatus::Failed {
+            failed_at: Utc::now(),
+            failure_reason: FailureReason::InsufficientFunds,
+        }
+    } else {
+        RefundStatus::Executed {
+            executed_at: Utc::now(),
+        }
+    };
+    map.schedule(
+        configuration.lifecycle.execution_delay,
+        StatusChange::Refund {
+            payment_id,
+            id: refund_id.clone(),
+            status,
         },
     );
 
@@ -572,6 +703,32 @@ pub(super) async fn get_refund_by_id(
     }
 }
 
+/// POST /payments/{id}/refunds/{id}/actions/cancel
+pub(super) async fn cancel_refund(
+    storage: web::Data<MockServerStorage>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (payment_id, refund_id) = path.into_inner();
+
+    let mut map = storage.write().unwrap();
+    let refund = match map
+        .payments
+        .get_mut(&payment_id)
+        .and_then(|(_, refunds)| refunds.get_mut(&refund_id))
+    {
+        Some(refund) => refund,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    // Only pending refunds can be cancelled
+    if refund.status != RefundStatus::Pending {
+        return HttpResponse::BadRequest().finish();
+    }
+    refund.status = RefundStatus::Cancelled;
+
+    HttpResponse::Accepted().finish()
+}
+
 /// GET /payments/{id}/refunds
 pub(super) async fn list_refunds(
     storage: web::Data<MockServerStorage>,
@@ -591,6 +748,7 @@ pub(super) async fn list_refunds(
 }
 
 /// GET /merchant-accounts
+#[cfg(feature = "merchant-accounts")]
 pub(super) async fn list_merchant_accounts(
     configuration: web::Data<MockServerConfiguration>,
 ) -> HttpResponse {
@@ -600,6 +758,7 @@ pub(super) async fn list_merchant_accounts(
 }
 
 /// GET /merchant-accounts/{id}
+#[cfg(feature = "merchant-accounts")]
 pub(super) async fn get_merchant_account_by_id(
     configuration: web::Data<MockServerConfiguration>,
     id: web::Path<String>,
@@ -616,6 +775,7 @@ pub(super) async fn get_merchant_account_by_id(
 }
 
 /// GET /merchant-accounts/{id}/sweeping
+#[cfg(feature = "merchant-accounts")]
 pub(super) async fn get_merchant_account_sweeping_by_id(
     storage: web::Data<MockServerStorage>,
     id: web::Path<String>,
@@ -629,6 +789,7 @@ pub(super) async fn get_merchant_account_sweeping_by_id(
 }
 
 /// POST /merchant-accounts/{id}/sweeping
+#[cfg(feature = "merchant-accounts")]
 pub(super) async fn setup_merchant_account_sweeping(
     configuration: web::Data<MockServerConfiguration>,
     storage: web::Data<MockServerStorage>,
@@ -640,22 +801,35 @@ pub(super) async fn setup_merchant_account_sweeping(
     match iban {
         Some(iban) => {
             let request = request.into_inner();
-            storage.write().unwrap().sweeping.insert(
-                id.clone(),
-                SweepingSettings {
-                    max_amount_in_minor: request.max_amount_in_minor,
-                    currency: request.currency,
-                    frequency: request.frequency,
-                    destination: AccountIdentifier::Iban { iban },
-                },
-            );
-            HttpResponse::Ok().finish()
+            let destination = AccountIdentifier::Iban { iban };
+
+            // Only the pre-approved account can be selected as destination
+            if request
+                .destination
+                .as_ref()
+                .is_some_and(|d| *d != destination)
+            {
+                return HttpResponse::BadRequest().finish();
+            }
+
+            let mut map = storage.write().unwrap();
+            let settings = SweepingSettings {
+                max_amount_in_minor: request.max_amount_in_minor,
+                currency: request.currency,
+                frequency: request.frequency,
+                destination,
+                status: SweepingStatus::Active,
+                next_execution_at: Some(map.now() + chrono::Duration::days(1)),
+            };
+            map.sweeping.insert(id.clone(), settings.clone());
+            HttpResponse::Ok().json(settings)
         }
         None => HttpResponse::NotFound().finish(),
     }
 }
 
 /// DELETE /merchant-accounts/{id}/sweeping
+#[cfg(feature = "merchant-accounts")]
 pub(super) async fn disable_merchant_account_sweeping(
     storage: web::Data<MockServerStorage>,
     id: web::Path<String>,
@@ -669,6 +843,7 @@ pub(super) async fn disable_merchant_account_sweeping(
 }
 
 /// GET /merchant-accounts/{id}/transactions
+#[cfg(feature = "merchant-accounts")]
 pub(super) async fn list_transactions(
     configuration: web::Data<MockServerConfiguration>,
     id: web::Path<String>,
@@ -705,6 +880,7 @@ pub(super) async fn list_transactions(
 }
 
 /// GET /merchant-accounts/{id}/payment-sources
+#[cfg(feature = "merchant-accounts")]
 pub(super) async fn list_payment_sources(
     configuration: web::Data<MockServerConfiguration>,
     id: web::Path<String>,
@@ -731,47 +907,103 @@ pub(super) async fn list_payment_sources(
     }
 }
 
+/// GET /merchant-accounts/{id}/payment-sources/{payment_source_id}
+#[cfg(feature = "merchant-accounts")]
+pub(super) async fn get_payment_source_by_id(
+    configuration: web::Data<MockServerConfiguration>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (id, payment_source_id) = path.into_inner();
+    let merchant_account = configuration
+        .merchant_accounts
+        .values()
+        .find(|m| m.id == id);
+
+    match (merchant_account, &*payment_source_id) {
+        (Some(_), "payment-source-id") => HttpResponse::Ok().json(PaymentSource {
+            id: "payment-source-id".into(),
+            user_id: Some("payment-source-user-id".into()),
+            account_identifiers: vec![AccountIdentifier::SortCodeAccountNumber {
+                sort_code: "sort-code".to_string(),
+                account_number: "account-number".to_string(),
+            }],
+            account_holder_name: Some("Mr. Holder".into()),
+        }),
+        _ => HttpResponse::NotFound().finish(),
+    }
+}
+
 /// POST /payouts
+#[cfg(feature = "payouts")]
 pub(super) async fn create_payout(
     configuration: web::Data<MockServerConfiguration>,
     storage: web::Data<MockServerStorage>,
     request: web::Json<CreatePayoutRequest>,
 ) -> HttpResponse {
+    // Payouts are sent in the currency of the merchant account, to accounts which can receive it
     if !configuration
         .merchant_accounts
         .values()
-        .any(|m| m.id == request.merchant_account_id)
+        .any(|m| m.id == request.merchant_account_id && m.currency == request.currency)
     {
         return HttpResponse::BadRequest().finish();
     }
+    if let PayoutBeneficiary::ExternalAccount {
+        account_identifier, ..
+    } = &request.beneficiary
+    {
+        if !account_identifier
+            .supported_currencies()
+            .contains(&request.currency)
+        {
+            return HttpResponse::BadRequest().finish();
+        }
+    }
 
     let payout_id = Uuid::new_v4().to_string();
     storage.write().unwrap().payouts.insert(
         payout_id.clone(),
         Payout {
-            id: payout_id.clone(),
+            id: payout_id.clone().into(),
             merchant_account_id: request.merchant_account_id.clone(),
             amount_in_minor: request.amount_in_minor,
             currency: request.currency.clone(),
             beneficiary: request.beneficiary.clone(),
             created_at: Utc::now(),
+            metadata: request.metadata.clone(),
             status: PayoutStatus::Pending,
         },
     );
 
-    // Automatically make the payout executed after 1 second
-    let payout_id_clone = payout_id.clone();
-    tokio::spawn(async move {
-        let mut guard = storage.write().unwrap();
-        guard.payouts.get_mut(&payout_id_clone).unwrap().status = PayoutStatus::Executed {
+    // Execute the payout (or make it fail) after a while
+    let reference = match &request.beneficiary {
+        PayoutBeneficiary::ExternalAccount { reference, .. }
+        | PayoutBeneficiary::PaymentSource { reference, .. }
+        | PayoutBeneficiary::BusinessAccount { reference } => reference,
+    };
+    let status = if reference == MOCK_FAILURE_REFERENCE {
+        PayoutStatus::Failed {
+            failed_at: Utc::now(),
+            failure_reason: FailureReason::InsufficientFunds,
+        }
+    } else {
+        PayoutStatus::Executed {
             executed_at: Utc::now(),
-        };
-    });
+        }
+    };
+    storage.write().unwrap().schedule(
+        configuration.lifecycle.execution_delay,
+        StatusChange::Payout {
+            id: payout_id.clone(),
+            status,
+        },
+    );
 
     HttpResponse::Created().json(json!({ "id": payout_id }))
 }
 
 /// GET /payouts/{id}
+#[cfg(feature = "payouts")]
 pub(super) async fn get_payout_by_id(
     storage: web::Data<MockServerStorage>,
     id: web::Path<String>,
@@ -782,6 +1014,129 @@ pub(super) async fn get_payout_by_id(
     )
 }
 
+/// POST /mandates
+#[cfg(feature = "mandates")]
+pub(super) async fn create_mandate(
+    storage: web::Data<MockServerStorage>,
+    request: web::Json<CreateMandateRequest>,
+) -> HttpResponse {
+    let id = Uuid::new_v4().to_string();
+    let user = match request.user.clone() {
+        CreatePaymentUserRequest::NewUser { .. } => User {
+            id: "mandate-user-id".to_string(),
+            date_of_birth: None,
+            address: None,
+        },
+        CreatePaymentUserRequest::ExistingUser { id } => User {
+            id,
+            date_of_birth: None,
+            address: None,
+        },
+    };
+
+    storage.write().unwrap().mandates.insert(
+        id.clone(),
+        Mandate {
+            id: id.clone().into(),
+            currency: request.currency.clone(),
+            mandate: request.mandate.clone(),
+            constraints: request.constraints.clone(),
+            user: Some(user.clone()),
+            created_at: Utc::now(),
+            metadata: request.metadata.clone(),
+            status: MandateStatus::AuthorizationRequired,
+        },
+    );
+
+    HttpResponse::Created().json(json!({
+        "id": id,
+        "resource_token": format!("resource-token-{}", id),
+        "user": {
+            "id": user.id
+        }
+    }))
+}
+
+/// GET /mandates/{id}
+#[cfg(feature = "mandates")]
+pub(super) async fn get_mandate_by_id(
+    storage: web::Data<MockServerStorage>,
+    id: web::Path<String>,
+) -> HttpResponse {
+    storage.read().unwrap().mandates.get(&*id).map_or_else(
+        || HttpResponse::NotFound().finish(),
+        |mandate| HttpResponse::Ok().json(mandate),
+    )
+}
+
+/// POST /mandates/{id}/authorization-flow
+#[cfg(feature = "mandates")]
+pub(super) async fn start_mandate_authorization_flow(
+    configuration: web::Data<MockServerConfiguration>,
+    storage: web::Data<MockServerStorage>,
+    id: web::Path<String>,
+    _body: web::Json<StartAuthorizationFlowRequest>, // Just for validation of the body
+) -> HttpResponse {
+    let mut map = storage.write().unwrap();
+    let mandate = match map.mandates.get_mut(&*id) {
+        Some(mandate) => mandate,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    if mandate.status != MandateStatus::AuthorizationRequired {
+        return HttpResponse::BadRequest().finish();
+    }
+    mandate.status = MandateStatus::Authorizing;
+
+    // The user is expected to authorize the mandate on the provider's website
+    map.schedule(
+        configuration.lifecycle.authorization_delay,
+        StatusChange::Mandate {
+            id: id.clone(),
+            status: MandateStatus::Authorized {
+                authorized_at: Utc::now(),
+            },
+        },
+    );
+
+    HttpResponse::Ok().json(StartAuthorizationFlowResponse {
+        authorization_flow: Some(AuthorizationFlow {
+            configuration: None,
+            actions: Some(AuthorizationFlowActions {
+                next: AuthorizationFlowNextAction::Redirect {
+                    uri: format!("{}{}", MOCK_REDIRECT_URI, id),
+                    metadata: None,
+                },
+            }),
+        }),
+        status: AuthorizationFlowResponseStatus::Authorizing,
+    })
+}
+
+/// POST /mandates/{id}/revoke
+#[cfg(feature = "mandates")]
+pub(super) async fn revoke_mandate(
+    storage: web::Data<MockServerStorage>,
+    id: web::Path<String>,
+) -> HttpResponse {
+    let mut map = storage.write().unwrap();
+    let mandate = match map.mandates.get_mut(&*id) {
+        Some(mandate) => mandate,
+        None => return HttpResponse::NotFound().finish(),
+    };
+
+    // Only authorized mandates can be revoked
+    if !matches!(mandate.status, MandateStatus::Authorized { .. }) {
+        return HttpResponse::BadRequest().finish();
+    }
+    mandate.status = MandateStatus::Revoked {
+        revoked_at: Utc::now(),
+        revoked_by: "client".to_string(),
+    };
+
+    HttpResponse::NoContent().finish()
+}
+
 /// POST /payments-provider-return
 pub(super) async fn submit_provider_return_parameters(
     req: web::Json<SubmitProviderReturnParametersRequest>,
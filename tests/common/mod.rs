@@ -7,6 +7,9 @@ use std::{
 mod mock_server;
 pub mod test_context;
 
+#[cfg(not(feature = "acceptance-tests"))]
+pub use mock_server::{MockLifecycle, MOCK_FAILURE_REFERENCE};
+
 #[derive(serde::Serialize, Clone, Debug, Eq, PartialEq)]
 pub enum MockBankAction {
     Execute,
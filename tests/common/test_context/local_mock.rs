@@ -1,8 +1,13 @@
-use crate::common::{mock_server::TrueLayerMockServer, MockBankAction};
+use crate::common::{
+    mock_server::{MockLifecycle, ReceivedRequest, TrueLayerMockServer},
+    MockBankAction,
+};
+use chrono::{DateTime, Utc};
 use openssl::{
     ec::{EcGroup, EcKey},
     nid::Nid,
 };
+use std::time::Duration;
 use truelayer_rust::{
     apis::{auth::Credentials, payments::Currency},
     client::Environment,
@@ -20,6 +25,11 @@ pub struct TestContext {
 
 impl TestContext {
     pub async fn start() -> Self {
+        Self::start_with_lifecycle(MockLifecycle::default()).await
+    }
+
+    /// Starts a mock server which simulates the lifecycle of resources with the given timings.
+    pub async fn start_with_lifecycle(lifecycle: MockLifecycle) -> Self {
         // Generate a new set of random credentials for this specific test
         let client_id = Uuid::new_v4().to_string();
         let client_secret = Uuid::new_v4().to_string();
@@ -33,6 +43,7 @@ impl TestContext {
             &client_secret,
             &signing_key_id,
             signing_private_key.public_key_to_pem().unwrap(),
+            lifecycle,
         )
         .await;
 
@@ -52,7 +63,7 @@ impl TestContext {
 
         let merchant_account_gbp_id = mock_server
             .merchant_account(Currency::Gbp)
-            .map(|m| m.id.clone())
+            .map(|m| m.id.to_string())
             .unwrap();
 
         Self {
@@ -65,10 +76,34 @@ impl TestContext {
         }
     }
 
+    /// Id of the merchant account of the mock server in the given currency.
+    pub fn merchant_account_id(&self, currency: Currency) -> String {
+        self.mock_server
+            .merchant_account(currency)
+            .map(|m| m.id.to_string())
+            .unwrap()
+    }
+
     pub fn tl_environment(&self) -> Environment {
         Environment::from_single_url(self.mock_server.url())
     }
 
+    /// Moves the virtual clock of the mock server forward.
+    pub fn advance_clock(&self, by: Duration) {
+        self.mock_server.advance_clock(by)
+    }
+
+    /// Times at which sweeping ran for the given merchant account.
+    #[cfg(feature = "merchant-accounts")]
+    pub fn sweeping_runs(&self, merchant_account_id: &str) -> Vec<DateTime<Utc>> {
+        self.mock_server.sweeping_runs(merchant_account_id)
+    }
+
+    /// Requests received by the mock server so far, in the order they arrived.
+    pub fn received_requests(&self) -> Vec<ReceivedRequest> {
+        self.mock_server.received_requests()
+    }
+
     pub async fn complete_mock_bank_redirect_authorization(
         &self,
         redirect_uri: &Url,
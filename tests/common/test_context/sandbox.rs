@@ -1,8 +1,11 @@
 use crate::common::MockBankAction;
-use anyhow::Context;
 use serde_json::json;
-use std::str::FromStr;
-use truelayer_rust::{apis::auth::Credentials, client::Environment, TrueLayerClient};
+use truelayer_rust::{
+    apis::auth::Credentials,
+    client::Environment,
+    sandbox::{mock_bank::MockBank, scenarios::MockBankOutcome},
+    TrueLayerClient,
+};
 use url::Url;
 
 static SANDBOX_RETURN_PARAMETERS_URI: &str = "https://pay-api.truelayer-sandbox.com";
@@ -51,37 +54,14 @@ impl TestContext {
         redirect_uri: &Url,
         action: MockBankAction,
     ) -> Result<Url, anyhow::Error> {
-        // The redirect uri from mock-bank looks like this:
-        // https://pay-mock-connect.truelayer-sandbox.com/login/{simp_id}#token={auth_token}
-        let simp_id = redirect_uri
-            .path_segments()
-            .context("Invalid redirect uri")?
-            .nth(1)
-            .context("Invalid redirect uri")?;
-        let token = &redirect_uri.fragment().context("Invalid redirect uri")?[6..];
+        let outcome = match action {
+            MockBankAction::Execute => MockBankOutcome::Execute,
+            MockBankAction::RejectAuthorisation => MockBankOutcome::RejectAuthorisation,
+            MockBankAction::RejectExecution => MockBankOutcome::RejectExecution,
+            MockBankAction::Cancel => MockBankOutcome::Cancel,
+        };
 
-        // Make a POST to mock-bank to set the authorization result
-        let provider_return_uri = reqwest::Client::new()
-            .post(
-                redirect_uri
-                    .join(&format!(
-                        "/api/single-immediate-payments/{}/action",
-                        simp_id
-                    ))
-                    .unwrap(),
-            )
-            .bearer_auth(token)
-            .json(&serde_json::json!({
-                "redirect": false,
-                "action": action
-            }))
-            .send()
-            .await?
-            .error_for_status()?
-            .text()
-            .await?;
-
-        Ok(Url::from_str(&provider_return_uri)?)
+        Ok(MockBank::new().authorize(redirect_uri, outcome).await?)
     }
 
     pub async fn submit_provider_return_parameters(
@@ -0,0 +1,207 @@
+//! Round-trip tests against payloads recorded from the TrueLayer APIs, stored under `tests/fixtures/`.
+//!
+//! Each fixture must deserialize into the corresponding model and serialize back without losing any field,
+//! so that we notice when TrueLayer starts returning fields we don't know about yet.
+//! Run with `--features strict-models` to additionally reject unknown fields during deserialization.
+//! Run with `--features json-schema` to also check that the JSON Schemas of the models describe every field.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{fmt::Debug, path::Path};
+use test_case::test_case;
+use truelayer_rust::apis::{
+    mandates::Mandate,
+    merchant_accounts::{MerchantAccount, Transaction},
+    payments::{refunds::Refund, Payment},
+    payments_providers::Provider,
+    payouts::Payout,
+};
+
+fn read_fixture(name: &str) -> Value {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(name);
+    let content = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Cannot read fixture {}: {}", path.display(), e));
+
+    serde_json::from_str(&content).unwrap()
+}
+
+/// Asserts that every field of `original` is also present in `reserialized`.
+///
+/// Only the structure is compared, as some values (e.g. timestamps) are not serialized
+/// in the exact same format they are received in.
+fn assert_no_dropped_fields(original: &Value, reserialized: &Value, path: &str) {
+    match (original, reserialized) {
+        (Value::Object(original), Value::Object(reserialized)) => {
+            for (key, value) in original {
+                let field_path = format!("{}.{}", path, key);
+                match reserialized.get(key) {
+                    Some(reserialized_value) => {
+                        assert_no_dropped_fields(value, reserialized_value, &field_path)
+                    }
+                    None => panic!("Field `{}` was dropped during deserialization", field_path),
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(reserialized)) => {
+            assert_eq!(
+                original.len(),
+                reserialized.len(),
+                "Array `{}` changed length",
+                path
+            );
+            for (i, (o, r)) in original.iter().zip(reserialized).enumerate() {
+                assert_no_dropped_fields(o, r, &format!("{}[{}]", path, i));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn assert_round_trip<T>(fixture: &str)
+where
+    T: DeserializeOwned + Serialize + Debug + PartialEq,
+{
+    let original = read_fixture(fixture);
+
+    let model: T = serde_json::from_value(original.clone())
+        .unwrap_or_else(|e| panic!("Cannot deserialize {}: {}", fixture, e));
+    let reserialized = serde_json::to_value(&model).unwrap();
+
+    assert_no_dropped_fields(&original, &reserialized, "$");
+    assert_eq!(serde_json::from_value::<T>(reserialized).unwrap(), model);
+}
+
+#[test_case("payment_settled.json" ; "settled payment")]
+#[test_case("payment_failed.json" ; "failed payment")]
+fn payment(fixture: &str) {
+    assert_round_trip::<Payment>(fixture);
+}
+
+#[test]
+fn payout() {
+    assert_round_trip::<Payout>("payout_executed.json");
+}
+
+#[test]
+fn refund() {
+    assert_round_trip::<Refund>("refund_executed.json");
+}
+
+#[test_case("merchant_account.json" ; "merchant account")]
+#[test_case("merchant_account_overdrawn.json" ; "overdrawn merchant account")]
+fn merchant_account(fixture: &str) {
+    assert_round_trip::<MerchantAccount>(fixture);
+}
+
+#[test_case("transactions.json" ; "transactions")]
+#[test_case("transactions_signed.json" ; "transactions with negative amounts")]
+fn transactions(fixture: &str) {
+    assert_round_trip::<Vec<Transaction>>(fixture);
+}
+
+#[test]
+fn amounts_out_of_range_are_errors() {
+    // Amounts of payments are never negative
+    let mut payment = read_fixture("payment_settled.json");
+    payment["amount_in_minor"] = (-100).into();
+    assert!(serde_json::from_value::<Payment>(payment).is_err());
+
+    // Balances do not fit in an i64 beyond i64::MAX
+    let mut merchant_account = read_fixture("merchant_account.json");
+    merchant_account["available_balance_in_minor"] = u64::MAX.into();
+    assert!(serde_json::from_value::<MerchantAccount>(merchant_account).is_err());
+}
+
+#[test]
+fn provider() {
+    assert_round_trip::<Provider>("provider.json");
+}
+
+#[test]
+fn mandate() {
+    assert_round_trip::<Mandate>("mandate_authorized.json");
+}
+
+/// Collects the names of the fields of all the objects in a JSON value, at any depth.
+///
+/// The contents of `maps` (e.g. `metadata`) are free-form, so their keys are not collected.
+#[cfg(feature = "json-schema")]
+fn collect_fields(
+    value: &Value,
+    maps: &std::collections::BTreeSet<String>,
+    fields: &mut std::collections::BTreeSet<String>,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                fields.insert(key.clone());
+                if !maps.contains(key) {
+                    collect_fields(value, maps, fields);
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_fields(item, maps, fields)),
+        _ => {}
+    }
+}
+
+/// Collects the names of the properties described anywhere in a JSON Schema,
+/// together with the names of those which are maps with arbitrary keys.
+#[cfg(feature = "json-schema")]
+fn collect_properties(
+    schema: &Value,
+    properties: &mut std::collections::BTreeSet<String>,
+    maps: &mut std::collections::BTreeSet<String>,
+) {
+    match schema {
+        Value::Object(map) => {
+            if let Some(Value::Object(described)) = map.get("properties") {
+                for (name, property) in described {
+                    properties.insert(name.clone());
+                    if matches!(property.get("additionalProperties"), Some(v) if v != false) {
+                        maps.insert(name.clone());
+                    }
+                }
+            }
+            map.values()
+                .for_each(|value| collect_properties(value, properties, maps));
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_properties(item, properties, maps)),
+        _ => {}
+    }
+}
+
+#[cfg(feature = "json-schema")]
+#[test_case("payment_settled.json", schemars::schema_for!(Payment) ; "payment")]
+#[test_case("payout_executed.json", schemars::schema_for!(Payout) ; "payout")]
+#[test_case("refund_executed.json", schemars::schema_for!(Refund) ; "refund")]
+#[test_case("merchant_account.json", schemars::schema_for!(MerchantAccount) ; "merchant account")]
+#[test_case("transactions.json", schemars::schema_for!(Vec<Transaction>) ; "transactions")]
+#[test_case("provider.json", schemars::schema_for!(Provider) ; "provider")]
+#[test_case("mandate_authorized.json", schemars::schema_for!(Mandate) ; "mandate")]
+fn json_schema_describes_all_fields(fixture: &str, schema: schemars::schema::RootSchema) {
+    let mut properties = std::collections::BTreeSet::new();
+    let mut maps = std::collections::BTreeSet::new();
+    collect_properties(
+        &serde_json::to_value(&schema).unwrap(),
+        &mut properties,
+        &mut maps,
+    );
+
+    let mut fields = std::collections::BTreeSet::new();
+    collect_fields(&read_fixture(fixture), &maps, &mut fields);
+
+    let undescribed: Vec<_> = fields.difference(&properties).collect();
+    assert!(
+        undescribed.is_empty(),
+        "Fields of {} missing from the schema: {:?}",
+        fixture,
+        undescribed
+    );
+}
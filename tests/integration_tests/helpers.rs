@@ -6,7 +6,8 @@ use truelayer_rust::{
     apis::payments::{
         AuthorizationFlowNextAction, Beneficiary, ConsentSupported, CreatePaymentRequest,
         CreatePaymentResponse, CreatePaymentUserRequest, Currency, Payment, PaymentMethodRequest,
-        PaymentStatus, ProviderSelectionRequest, RedirectSupported, StartAuthorizationFlowRequest,
+        PaymentStatus, ProviderSelectionRequest, RedirectSupported, SchemeId,
+        StartAuthorizationFlowRequest,
     },
     pollable::PollOptions,
     Pollable,
@@ -28,7 +29,7 @@ pub async fn create_closed_loop_payment(
             payment_method: PaymentMethodRequest::BankTransfer {
                 provider_selection: ProviderSelectionRequest::Preselected {
                     provider_id: "mock-payments-gb-redirect".into(),
-                    scheme_id: "faster_payments_service".into(),
+                    scheme_id: SchemeId::FasterPaymentsService,
                     remitter: None,
                 },
                 beneficiary: Beneficiary::MerchantAccount {
@@ -36,22 +37,29 @@ pub async fn create_closed_loop_payment(
                     account_holder_name: None,
                     reference: None,
                     statement_reference: None,
+                    verification: None,
                 },
             },
             user: CreatePaymentUserRequest::NewUser {
                 name: Some("someone".to_string()),
                 email: Some("some.one@email.com".to_string()),
                 phone: None,
+                date_of_birth: None,
+                address: None,
             },
             metadata: None,
+            schedule: None,
+            related_products: None,
+            risk_assessment: None,
         })
         .await?;
     Ok(res)
 }
 
-pub async fn create_and_authorize_closed_loop_payment(
+/// Creates a closed-loop payment and authorizes it, without waiting for it to be executed.
+pub async fn authorize_closed_loop_payment(
     ctx: &TestContext,
-) -> anyhow::Result<Payment> {
+) -> anyhow::Result<CreatePaymentResponse> {
     let res = create_closed_loop_payment(ctx).await?;
 
     ctx.client
@@ -93,6 +101,14 @@ pub async fn create_and_authorize_closed_loop_payment(
     )
     .await?;
 
+    Ok(res)
+}
+
+pub async fn create_and_authorize_closed_loop_payment(
+    ctx: &TestContext,
+) -> anyhow::Result<Payment> {
+    let res = authorize_closed_loop_payment(ctx).await?;
+
     let payment = res
         .poll_until(
             &ctx.client,
@@ -0,0 +1,465 @@
+//! Tests relying on the lifecycle simulated by the local mock server, which cannot be reproduced
+//! deterministically against the sandbox.
+
+use std::time::Duration;
+
+use reqwest_retry::policies::ExponentialBackoff;
+#[cfg(feature = "merchant-accounts")]
+use truelayer_rust::apis::merchant_accounts::{SetupSweepingRequest, SweepingFrequency};
+#[cfg(any(feature = "payouts", feature = "mandates"))]
+use truelayer_rust::apis::payments::Currency;
+#[cfg(feature = "mandates")]
+use truelayer_rust::apis::{
+    mandates::{
+        Constraints, CreateMandateRequest, MandateBeneficiary, MandateDetail,
+        MandateProviderSelection, MandateStatus,
+    },
+    payments::{
+        ConsentSupported, CreatePaymentUserRequest, RedirectSupported,
+        StartAuthorizationFlowRequest,
+    },
+};
+#[cfg(feature = "payouts")]
+use truelayer_rust::apis::{
+    payments::AccountIdentifier,
+    payouts::{CreatePayoutRequest, PayoutBeneficiary, PayoutStatus},
+};
+#[cfg(feature = "payments")]
+use truelayer_rust::{
+    apis::payments::{
+        refunds::{CreateRefundRequest, RefundStatus},
+        PaymentStatus,
+    },
+    Error, Pollable,
+};
+use truelayer_rust::{pollable::PollOptions, PollableUntilTerminalState};
+
+use crate::common::test_context::TestContext;
+#[cfg(any(feature = "payments", feature = "payouts"))]
+use crate::common::{MockLifecycle, MOCK_FAILURE_REFERENCE};
+#[cfg(feature = "payments")]
+use crate::integration_tests::helpers;
+
+/// Delay long enough for resources to move forward only when the clock of the mock server is advanced.
+#[cfg(any(feature = "payments", feature = "payouts"))]
+const EXECUTION_DELAY: Duration = Duration::from_secs(60 * 60);
+
+fn poll_options() -> PollOptions<ExponentialBackoff> {
+    PollOptions::default().with_retry_policy(
+        ExponentialBackoff::builder()
+            .retry_bounds(Duration::from_millis(100), Duration::from_millis(500))
+            .build_with_total_retry_duration(Duration::from_secs(20)),
+    )
+}
+
+#[cfg(feature = "payments")]
+fn refund_request(amount_in_minor: Option<u64>, reference: &str) -> CreateRefundRequest {
+    CreateRefundRequest {
+        amount_in_minor,
+        reference: reference.to_string(),
+        metadata: None,
+    }
+}
+
+#[cfg(feature = "payments")]
+#[tokio::test]
+async fn payment_is_settled_after_execution() {
+    let ctx = TestContext::start_with_lifecycle(MockLifecycle {
+        settlement_delay: Duration::from_secs(2),
+        ..MockLifecycle::default()
+    })
+    .await;
+
+    let res = helpers::authorize_closed_loop_payment(&ctx).await.unwrap();
+
+    // The payment is executed, but not settled yet: it cannot be refunded
+    let payment = ctx
+        .client
+        .payments
+        .get_by_id(&res.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(payment.status, PaymentStatus::Executed { .. }));
+    let refund = ctx
+        .client
+        .payments
+        .create_refund(&res.id, &refund_request(None, "refund reference"))
+        .await;
+    assert!(matches!(refund, Err(Error::ApiError(e)) if e.status == 400));
+
+    // Eventually it settles
+    let payment = payment
+        .poll_until(&ctx.client, poll_options(), |p| {
+            matches!(p.status, PaymentStatus::Settled { .. })
+        })
+        .await
+        .unwrap();
+    assert!(matches!(payment.status, PaymentStatus::Settled { .. }));
+
+    // Settled payments cannot be cancelled
+    let res = ctx.client.payments.cancel(&payment.id).await;
+    assert!(matches!(res, Err(Error::ApiError(e)) if e.status == 400));
+}
+
+#[cfg(feature = "payments")]
+#[tokio::test]
+async fn refunds_are_limited_to_the_payment_amount() {
+    let ctx = TestContext::start().await;
+    let payment = helpers::create_and_authorize_closed_loop_payment(&ctx)
+        .await
+        .unwrap();
+
+    // A failed refund does not count towards the refunded amount
+    let failed = ctx
+        .client
+        .payments
+        .create_refund(&payment.id, &refund_request(None, MOCK_FAILURE_REFERENCE))
+        .await
+        .unwrap();
+    let failed = (payment.id.as_str(), failed)
+        .poll_until_terminal_state(&ctx.client, poll_options())
+        .await
+        .unwrap();
+    assert!(matches!(failed.status, RefundStatus::Failed { .. }));
+
+    let partial = ctx
+        .client
+        .payments
+        .create_refund(
+            &payment.id,
+            &refund_request(Some(payment.amount_in_minor - 1), "partial refund"),
+        )
+        .await
+        .unwrap();
+    let partial = (payment.id.as_str(), partial)
+        .poll_until_terminal_state(&ctx.client, poll_options())
+        .await
+        .unwrap();
+    assert!(matches!(partial.status, RefundStatus::Executed { .. }));
+
+    // Only 1 minor unit is left to refund
+    let res = ctx
+        .client
+        .payments
+        .create_refund(&payment.id, &refund_request(Some(2), "too much"))
+        .await;
+    assert!(matches!(res, Err(Error::ApiError(e)) if e.status == 400));
+
+    let refunds = ctx.client.payments.list_refunds(&payment.id).await.unwrap();
+    assert_eq!(refunds.len(), 2);
+
+    // Listing the refunds of an unknown payment fails
+    let res = ctx.client.payments.list_refunds("non-existent").await;
+    assert!(matches!(res, Err(Error::ApiError(e)) if e.status == 404));
+}
+
+#[cfg(feature = "payments")]
+#[tokio::test]
+async fn pending_refunds_can_be_cancelled() {
+    let ctx = TestContext::start_with_lifecycle(MockLifecycle {
+        execution_delay: EXECUTION_DELAY,
+        ..MockLifecycle::default()
+    })
+    .await;
+    let payment = helpers::create_and_authorize_closed_loop_payment(&ctx)
+        .await
+        .unwrap();
+    let payment = payment
+        .poll_until(&ctx.client, poll_options(), |p| {
+            matches!(p.status, PaymentStatus::Settled { .. })
+        })
+        .await
+        .unwrap();
+
+    let res = ctx
+        .client
+        .payments
+        .create_refund(&payment.id, &refund_request(None, "accidental refund"))
+        .await
+        .unwrap();
+    ctx.client
+        .payments
+        .cancel_refund(&payment.id, &res.id)
+        .await
+        .unwrap();
+
+    // The refund is never executed, and the payment can be refunded again
+    ctx.advance_clock(EXECUTION_DELAY);
+    let refund = ctx
+        .client
+        .payments
+        .get_refund_by_id(&payment.id, &res.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(refund.status, RefundStatus::Cancelled);
+
+    let res = ctx
+        .client
+        .payments
+        .create_refund(&payment.id, &refund_request(None, "refund reference"))
+        .await
+        .unwrap();
+    ctx.advance_clock(EXECUTION_DELAY);
+    let refund = ctx
+        .client
+        .payments
+        .get_refund_by_id(&payment.id, &res.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(refund.status, RefundStatus::Executed { .. }));
+
+    // Executed refunds cannot be cancelled
+    let res = ctx
+        .client
+        .payments
+        .cancel_refund(&payment.id, &refund.id)
+        .await;
+    assert!(matches!(res, Err(Error::ApiError(e)) if e.status == 400));
+}
+
+#[cfg(feature = "payouts")]
+#[tokio::test]
+async fn payout_is_pending_until_executed_or_failed() {
+    let ctx = TestContext::start_with_lifecycle(MockLifecycle {
+        execution_delay: EXECUTION_DELAY,
+        ..MockLifecycle::default()
+    })
+    .await;
+
+    for (reference, should_fail) in [("rust-sdk-test", false), (MOCK_FAILURE_REFERENCE, true)] {
+        let res = ctx
+            .client
+            .payouts
+            .create(&CreatePayoutRequest {
+                merchant_account_id: ctx.merchant_account_gbp_id.clone(),
+                amount_in_minor: 1,
+                currency: Currency::Gbp,
+                beneficiary: PayoutBeneficiary::business_account(reference),
+                metadata: None,
+            })
+            .await
+            .unwrap();
+
+        let payout = ctx
+            .client
+            .payouts
+            .get_by_id(&res.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payout.status, PayoutStatus::Pending);
+
+        ctx.advance_clock(EXECUTION_DELAY);
+        let payout = ctx
+            .client
+            .payouts
+            .get_by_id(&res.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            matches!(payout.status, PayoutStatus::Failed { .. }),
+            should_fail
+        );
+    }
+}
+
+#[cfg(feature = "payouts")]
+#[tokio::test]
+async fn payouts_are_executed_in_every_merchant_account_currency() {
+    let ctx = TestContext::start().await;
+
+    for (currency, account_identifier) in [
+        (
+            Currency::Eur,
+            AccountIdentifier::iban("DE89 3704 0044 0532 0130 00").unwrap(),
+        ),
+        (
+            Currency::Pln,
+            AccountIdentifier::nrb("61 1090 1014 0000 0712 1981 2874").unwrap(),
+        ),
+        (
+            Currency::Nok,
+            AccountIdentifier::bban("12345678901").unwrap(),
+        ),
+    ] {
+        let payout = ctx
+            .client
+            .payouts
+            .create(
+                &CreatePayoutRequest::builder()
+                    .merchant_account_id(ctx.merchant_account_id(currency.clone()))
+                    .amount_in_minor(1)
+                    .currency(currency.clone())
+                    .beneficiary(
+                        PayoutBeneficiary::external_account(
+                            "Mr. Holder",
+                            account_identifier,
+                            "rust-sdk-test",
+                        )
+                        .build()
+                        .unwrap(),
+                    )
+                    .build(),
+            )
+            .await
+            .unwrap()
+            .poll_until_terminal_state(&ctx.client, poll_options())
+            .await
+            .unwrap();
+
+        assert_eq!(payout.currency, currency);
+        assert!(matches!(payout.status, PayoutStatus::Executed { .. }));
+    }
+}
+
+#[cfg(feature = "mandates")]
+#[tokio::test]
+async fn mandate_lifecycle() {
+    let ctx = TestContext::start().await;
+
+    let res = ctx
+        .client
+        .mandates
+        .create(&CreateMandateRequest {
+            mandate: MandateDetail::Sweeping {
+                provider_selection: MandateProviderSelection::Preselected {
+                    provider_id: "mock-payments-gb-redirect".to_string(),
+                    remitter: None,
+                },
+                beneficiary: MandateBeneficiary::MerchantAccount {
+                    merchant_account_id: ctx.merchant_account_gbp_id.clone(),
+                    account_holder_name: None,
+                },
+                reference: Some("rust-sdk-test".to_string()),
+            },
+            currency: Currency::Gbp,
+            user: CreatePaymentUserRequest::NewUser {
+                name: Some("someone".to_string()),
+                email: Some("some.one@email.com".to_string()),
+                phone: None,
+                date_of_birth: None,
+                address: None,
+            },
+            constraints: Constraints {
+                valid_from: None,
+                valid_to: None,
+                maximum_individual_amount: 1000,
+                periodic_limits: None,
+            },
+            metadata: None,
+        })
+        .await
+        .unwrap();
+
+    // Mandates cannot be revoked before being authorized
+    assert!(ctx.client.mandates.revoke(&res.id).await.is_err());
+
+    ctx.client
+        .mandates
+        .start_authorization_flow(
+            &res.id,
+            &StartAuthorizationFlowRequest {
+                provider_selection: None,
+                redirect: Some(RedirectSupported {
+                    return_uri: "http://localhost:3000/callback".to_string(),
+                    direct_return_uri: None,
+                }),
+                consent: Some(ConsentSupported {}),
+                form: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    // Wait for the mandate to be authorized
+    let mandate = res
+        .poll_until_terminal_state(&ctx.client, poll_options())
+        .await
+        .unwrap();
+    assert!(matches!(mandate.status, MandateStatus::Authorized { .. }));
+
+    ctx.client.mandates.revoke(&res.id).await.unwrap();
+    let mandate = ctx
+        .client
+        .mandates
+        .get_by_id(&res.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(mandate.status, MandateStatus::Revoked { .. }));
+}
+
+#[cfg(feature = "merchant-accounts")]
+#[tokio::test]
+async fn sweeping_runs_once_per_period() {
+    let ctx = TestContext::start().await;
+
+    let settings = ctx
+        .client
+        .merchant_accounts
+        .setup_sweeping(
+            &ctx.merchant_account_gbp_id,
+            &SetupSweepingRequest {
+                max_amount_in_minor: 100,
+                currency: Currency::Gbp,
+                frequency: SweepingFrequency::Daily,
+                destination: None,
+            },
+        )
+        .await
+        .unwrap();
+    let first_run = settings.next_execution_at.unwrap();
+    assert!(ctx.sweeping_runs(&ctx.merchant_account_gbp_id).is_empty());
+
+    // Three days later, sweeping has run three times
+    ctx.advance_clock(Duration::from_secs(3 * 24 * 60 * 60));
+    assert_eq!(
+        ctx.sweeping_runs(&ctx.merchant_account_gbp_id),
+        vec![
+            first_run,
+            first_run + chrono::Duration::days(1),
+            first_run + chrono::Duration::days(2),
+        ]
+    );
+
+    let settings = ctx
+        .client
+        .merchant_accounts
+        .get_sweeping_settings(&ctx.merchant_account_gbp_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        settings.next_execution_at,
+        Some(first_run + chrono::Duration::days(3))
+    );
+}
+
+#[cfg(feature = "payments")]
+#[tokio::test]
+async fn mock_server_records_the_requests_sent_by_the_client() {
+    let ctx = TestContext::start().await;
+
+    let res = helpers::create_closed_loop_payment(&ctx).await.unwrap();
+    ctx.client.payments.get_by_id(&res.id).await.unwrap();
+
+    let requests = ctx.received_requests();
+    let create = requests
+        .iter()
+        .find(|r| r.method == "POST" && r.path == "/payments")
+        .unwrap();
+    assert!(create.is_signed());
+    assert!(create.idempotency_key().is_some());
+    assert_eq!(create.body_json().unwrap()["amount_in_minor"], 100);
+
+    let get = requests.last().unwrap();
+    assert_eq!(get.method, "GET");
+    assert_eq!(get.path, format!("/payments/{}", res.id));
+    assert_eq!(get.query, "");
+    assert!(!get.is_signed());
+    assert!(get.header("authorization").is_some());
+}
@@ -1,12 +1,17 @@
 use crate::common::test_context::TestContext;
 use chrono::{DateTime, Utc};
 use rand::Rng;
-use truelayer_rust::apis::{
-    merchant_accounts::{
-        ListPaymentSourcesRequest, ListTransactionsRequest, SetupSweepingRequest,
-        SweepingFrequency, SweepingSettings, TransactionType,
+use reqwest_retry::policies::ExponentialBackoff;
+use std::time::Duration;
+use truelayer_rust::{
+    apis::{
+        merchant_accounts::{
+            ListPaymentSourcesRequest, ListTransactionsRequest, SetupSweepingRequest,
+            SweepingFrequency, SweepingStatus, TransactionType,
+        },
+        payments::{AccountIdentifier, Currency},
     },
-    payments::{AccountIdentifier, Currency},
+    pollable::{PollError, PollOptions},
 };
 
 #[tokio::test]
@@ -59,6 +64,44 @@ async fn get_by_id_not_found() {
     assert_eq!(merchant_account, None);
 }
 
+#[tokio::test]
+async fn poll_until_available_balance() {
+    let ctx = TestContext::start().await;
+
+    let merchant_account = ctx
+        .client
+        .merchant_accounts
+        .get_by_id(&ctx.merchant_account_gbp_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // The current balance already satisfies the threshold
+    let polled = merchant_account
+        .poll_until_available_balance(
+            &ctx.client,
+            PollOptions::default(),
+            merchant_account.available_balance_in_minor,
+        )
+        .await
+        .unwrap();
+    assert_eq!(polled.id, merchant_account.id);
+
+    // An unreachable threshold times out
+    let res = merchant_account
+        .poll_until_available_balance(
+            &ctx.client,
+            PollOptions::default().with_retry_policy(
+                ExponentialBackoff::builder()
+                    .retry_bounds(Duration::from_secs(1), Duration::from_secs(1))
+                    .build_with_max_retries(1),
+            ),
+            i64::MAX,
+        )
+        .await;
+    assert!(matches!(res, Err(PollError::Timeout)));
+}
+
 #[tokio::test]
 async fn sweeping() {
     let ctx = TestContext::start().await;
@@ -68,7 +111,8 @@ async fn sweeping() {
     let max_amount_in_minor = 10_000_000_00 + rand::thread_rng().gen_range(0..999_999_99);
 
     // Setup sweeping
-    ctx.client
+    let stored_settings = ctx
+        .client
         .merchant_accounts
         .setup_sweeping(
             &ctx.merchant_account_gbp_id,
@@ -76,10 +120,21 @@ async fn sweeping() {
                 max_amount_in_minor,
                 currency: Currency::Gbp,
                 frequency: SweepingFrequency::Fortnightly,
+                destination: None,
             },
         )
         .await
         .unwrap();
+    assert_eq!(stored_settings.max_amount_in_minor, max_amount_in_minor);
+    assert_eq!(stored_settings.currency, Currency::Gbp);
+    assert_eq!(stored_settings.frequency, SweepingFrequency::Fortnightly);
+    assert_eq!(
+        stored_settings.destination,
+        AccountIdentifier::Iban {
+            iban: ctx.merchant_account_gbp_sweeping_iban
+        }
+    );
+    assert_eq!(stored_settings.status, SweepingStatus::Active);
 
     // Retrieve the settings
     let settings = ctx
@@ -88,17 +143,16 @@ async fn sweeping() {
         .get_sweeping_settings(&ctx.merchant_account_gbp_id)
         .await
         .unwrap();
-    assert_eq!(
-        settings,
-        Some(SweepingSettings {
-            max_amount_in_minor,
-            currency: Currency::Gbp,
-            frequency: SweepingFrequency::Fortnightly,
-            destination: AccountIdentifier::Iban {
-                iban: ctx.merchant_account_gbp_sweeping_iban
-            }
-        })
-    );
+    assert_eq!(settings.as_ref(), Some(&stored_settings));
+
+    // Retrieve the next execution
+    let next_execution = ctx
+        .client
+        .merchant_accounts
+        .get_next_sweeping_execution(&ctx.merchant_account_gbp_id)
+        .await
+        .unwrap();
+    assert_eq!(next_execution, stored_settings.next_execution_at);
 
     // Disable sweeping
     ctx.client
@@ -188,3 +242,54 @@ async fn list_payment_sources() {
 
     assert!(!payment_sources.is_empty());
 }
+
+#[tokio::test]
+async fn get_payment_source_by_id() {
+    let ctx = TestContext::start().await;
+
+    // Find an inbound transaction and extract its payment source
+    let payment_source = ctx
+        .client
+        .merchant_accounts
+        .list_transactions(
+            &ctx.merchant_account_gbp_id,
+            &ListTransactionsRequest {
+                from: DateTime::parse_from_rfc3339("2021-03-01T00:00:00.000Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+                to: DateTime::parse_from_rfc3339("2022-03-01T00:00:00.000Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+                r#type: None,
+            },
+        )
+        .await
+        .unwrap()
+        .into_iter()
+        .filter_map(|t| match t.r#type {
+            TransactionType::MerchantAccountPayment { payment_source, .. } => Some(payment_source),
+            _ => None,
+        })
+        .next()
+        .unwrap();
+
+    // Fetch the same payment source by id
+    let fetched = ctx
+        .client
+        .merchant_accounts
+        .get_payment_source_by_id(&ctx.merchant_account_gbp_id, &payment_source.id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(fetched.id, payment_source.id);
+
+    // Unknown payment sources are not found
+    assert!(ctx
+        .client
+        .merchant_accounts
+        .get_payment_source_by_id(&ctx.merchant_account_gbp_id, "non-existent")
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -1,7 +1,18 @@
 mod auth;
+#[cfg(feature = "payments")]
 mod helpers;
+#[cfg(all(
+    not(feature = "acceptance-tests"),
+    any(feature = "payments", feature = "payouts", feature = "mandates")
+))]
+mod lifecycle;
+#[cfg(feature = "merchant-accounts")]
 mod merchant_accounts;
+#[cfg(feature = "payments")]
 mod payments;
+#[cfg(feature = "payments")]
 mod payments_providers;
+#[cfg(feature = "merchant-accounts")]
 mod payouts;
+#[cfg(feature = "payments")]
 mod refunds;
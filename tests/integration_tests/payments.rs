@@ -10,22 +10,24 @@ use truelayer_rust::{
         AccountIdentifier, AdditionalInputType, AuthorizationFlow, AuthorizationFlowActions,
         AuthorizationFlowNextAction, AuthorizationFlowResponseStatus, Beneficiary,
         ConsentSupported, CreatePaymentRequest, CreatePaymentStatus, CreatePaymentUserRequest,
-        Currency, FailureStage, FormSupported, PaymentMethodRequest, PaymentStatus,
+        Currency, FailureReason, FailureStage, FormSupported, PaymentMethodRequest, PaymentStatus,
         ProviderSelectionRequest, ProviderSelectionSupported, RedirectSupported,
-        StartAuthorizationFlowRequest, StartAuthorizationFlowResponse, SubmitFormActionRequest,
+        RetryPaymentRequest, SchemeId, StartAuthorizationFlowRequest,
+        StartAuthorizationFlowResponse, SubmitFormActionRequest,
         SubmitProviderReturnParametersRequest, SubmitProviderReturnParametersResponseResource,
         SubmitProviderSelectionActionRequest,
     },
     pollable::PollOptions,
-    PollableUntilTerminalState,
+    sandbox::scenarios::MockProvider,
+    Error, PollableUntilTerminalState,
 };
 use url::Url;
 use uuid::Uuid;
 
-static MOCK_PROVIDER_GB_REDIRECT: &str = "mock-payments-gb-redirect";
-static MOCK_PROVIDER_PL_REDIRECT: &str = "mock-payments-pl-redirect";
-static MOCK_PROVIDER_NO_REDIRECT: &str = "mock-payments-no-redirect";
-static MOCK_PROVIDER_DE_ADDITIONAL_INPUTS: &str = "mock-payments-de-redirect-additional-input-text";
+static MOCK_PROVIDER_GB_REDIRECT: &str = MockProvider::GbRedirect.provider_id();
+static MOCK_PROVIDER_PL_REDIRECT: &str = MockProvider::PlRedirect.provider_id();
+static MOCK_PROVIDER_NO_REDIRECT: &str = MockProvider::NoRedirect.provider_id();
+static MOCK_PROVIDER_DE_ADDITIONAL_INPUTS: &str = MockProvider::DeAdditionalInputs.provider_id();
 static MOCK_RETURN_URI: &str = "http://localhost:3000/callback";
 
 #[tokio::test]
@@ -63,14 +65,20 @@ async fn hpp_link_returns_200() {
                     account_holder_name: None,
                     reference: None,
                     statement_reference: None,
+                    verification: None,
                 },
             },
             user: CreatePaymentUserRequest::NewUser {
                 name: Some("someone".to_string()),
                 email: Some("some.one@email.com".to_string()),
                 phone: None,
+                date_of_birth: None,
+                address: None,
             },
             metadata: None,
+            schedule: None,
+            related_products: None,
+            risk_assessment: None,
         })
         .await
         .unwrap();
@@ -109,7 +117,7 @@ enum ScenarioProviderSelection {
     },
     Preselected {
         provider_id: String,
-        scheme_id: String,
+        scheme_id: SchemeId,
     },
 }
 
@@ -126,7 +134,7 @@ enum ScenarioExpectedStatus {
     ExecutedOrSettled,
     Failed {
         failure_stage: FailureStage,
-        failure_reason: &'static str,
+        failure_reason: FailureReason,
     },
 }
 
@@ -197,6 +205,7 @@ impl CreatePaymentScenario {
                         account_holder_name: None,
                         reference: Some("Reference".to_string()),
                         statement_reference: Some("StReference".to_string()),
+                        verification: None,
                     },
                     ScenarioBeneficiary::OpenLoop {
                         ref account_identifier,
@@ -211,8 +220,13 @@ impl CreatePaymentScenario {
                 name: Some("someone".to_string()),
                 email: Some("some.one@email.com".to_string()),
                 phone: None,
+                date_of_birth: None,
+                address: None,
             },
-            metadata: Some(HashMap::from([("some".into(), "metadata".into())])),
+            metadata: Some(HashMap::from([("some".to_string(), "metadata".to_string())]).into()),
+            schedule: None,
+            related_products: None,
+            risk_assessment: None,
         };
         let res = ctx
             .client
@@ -222,7 +236,7 @@ impl CreatePaymentScenario {
             .unwrap();
 
         // Assert that we got sensible values back
-        assert!(!res.id.is_empty());
+        assert!(!res.id.as_str().is_empty());
         assert!(!res.resource_token.expose_secret().is_empty());
         assert!(!res.user.id.is_empty());
         assert_eq!(res.status, CreatePaymentStatus::AuthorizationRequired);
@@ -324,6 +338,7 @@ impl CreatePaymentScenario {
                     &res.id,
                     &SubmitProviderSelectionActionRequest {
                         provider_id: provider_id.to_string(),
+                        scheme_id: None,
                     },
                 )
                 .await
@@ -459,7 +474,7 @@ impl CreatePaymentScenario {
             assert_eq!(
                 submit_res.resource,
                 SubmitProviderReturnParametersResponseResource::Payment {
-                    payment_id: res.id.clone()
+                    payment_id: res.id.to_string()
                 }
             );
         } else {
@@ -585,7 +600,7 @@ impl CreatePaymentScenario {
         additional_inputs: None,
     },
     MockBankAction::RejectAuthorisation,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: "authorization_failed" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: FailureReason::AuthorizationFailed },
     RedirectFlow::Classic
     ; "user selected provider reject authorization"
 )]
@@ -599,7 +614,7 @@ impl CreatePaymentScenario {
         additional_inputs: None,
     },
     MockBankAction::RejectExecution,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorized, failure_reason: "provider_rejected" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorized, failure_reason: FailureReason::ProviderRejected },
     RedirectFlow::Classic
     ; "user selected provider reject execution"
 )]
@@ -613,7 +628,7 @@ impl CreatePaymentScenario {
         additional_inputs: None,
     },
     MockBankAction::Cancel,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: "not_authorized" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: FailureReason::NotAuthorized },
     RedirectFlow::Classic
     ; "user selected provider not authorized"
 )]
@@ -623,7 +638,7 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
@@ -638,7 +653,7 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_DE_ADDITIONAL_INPUTS.to_string(),
-            scheme_id:  "sepa_credit_transfer".to_string(),
+            scheme_id: SchemeId::SepaCreditTransfer,
         },
         additional_inputs: Some(HashMap::from([
             ("psu-branch-code".to_string(), "123".to_string()),
@@ -657,12 +672,12 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
     MockBankAction::RejectAuthorisation,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: "authorization_failed" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: FailureReason::AuthorizationFailed },
     RedirectFlow::Classic
     ; "preselected provider reject authorization"
 )]
@@ -672,12 +687,12 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
     MockBankAction::RejectExecution,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorized, failure_reason: "provider_rejected" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorized, failure_reason: FailureReason::ProviderRejected },
     RedirectFlow::Classic
     ; "preselected provider reject execution"
 )]
@@ -687,12 +702,12 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
     MockBankAction::Cancel,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: "not_authorized" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: FailureReason::NotAuthorized },
     RedirectFlow::Classic
     ; "preselected provider not authorized"
 )]
@@ -720,7 +735,7 @@ impl CreatePaymentScenario {
         additional_inputs: None,
     },
     MockBankAction::RejectAuthorisation,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: "authorization_failed" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: FailureReason::AuthorizationFailed },
     RedirectFlow::DirectReturn
     ; "user selected provider reject authorization direct return"
 )]
@@ -734,7 +749,7 @@ impl CreatePaymentScenario {
         additional_inputs: None,
     },
     MockBankAction::RejectExecution,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorized, failure_reason: "provider_rejected" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorized, failure_reason: FailureReason::ProviderRejected },
     RedirectFlow::DirectReturn
     ; "user selected provider reject execution direct return"
 )]
@@ -748,7 +763,7 @@ impl CreatePaymentScenario {
         additional_inputs: None,
     },
     MockBankAction::Cancel,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: "not_authorized" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: FailureReason::NotAuthorized },
     RedirectFlow::DirectReturn
     ; "user selected provider not authorized direct return"
 )]
@@ -758,7 +773,7 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
@@ -773,12 +788,12 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
     MockBankAction::RejectAuthorisation,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: "authorization_failed" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: FailureReason::AuthorizationFailed },
     RedirectFlow::DirectReturn
     ; "preselected provider reject authorization direct return"
 )]
@@ -788,12 +803,12 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
     MockBankAction::RejectExecution,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorized, failure_reason: "provider_rejected" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorized, failure_reason: FailureReason::ProviderRejected },
     RedirectFlow::DirectReturn
     ; "preselected provider reject execution direct return"
 )]
@@ -803,12 +818,12 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
     MockBankAction::Cancel,
-    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: "not_authorized" },
+    ScenarioExpectedStatus::Failed { failure_stage: FailureStage::Authorizing, failure_reason: FailureReason::NotAuthorized },
     RedirectFlow::DirectReturn
     ; "preselected provider not authorized direct return"
 )]
@@ -818,7 +833,7 @@ impl CreatePaymentScenario {
     ProviderScenario {
         provider_selection: ScenarioProviderSelection::Preselected {
             provider_id: MOCK_PROVIDER_GB_REDIRECT.to_string(),
-            scheme_id: "faster_payments_service".to_string(), 
+            scheme_id: SchemeId::FasterPaymentsService,
         },
         additional_inputs: None,
     },
@@ -857,7 +872,7 @@ async fn cancel_payment() {
 
     assert!(matches!(
         payment.status,
-        CreatePaymentStatus::AuthorizationRequired { .. }
+        CreatePaymentStatus::AuthorizationRequired
     ));
 
     ctx.client.payments.cancel(&payment.id).await.unwrap();
@@ -873,5 +888,40 @@ async fn cancel_payment() {
     assert!(matches!(
             payment.status,
             PaymentStatus::Failed { failure_reason, failure_stage, .. }
-            if failure_reason == *"canceled" && failure_stage == FailureStage::AuthorizationRequired));
+            if failure_reason == FailureReason::Canceled && failure_stage == FailureStage::AuthorizationRequired));
+}
+
+#[tokio::test]
+async fn retry_failed_payment() {
+    let ctx = TestContext::start().await;
+
+    // Create a closed-loop payment and make it fail by cancelling it
+    let payment = helpers::create_closed_loop_payment(&ctx).await.unwrap();
+    ctx.client.payments.cancel(&payment.id).await.unwrap();
+
+    let res = ctx
+        .client
+        .payments
+        .retry(&payment.id, &RetryPaymentRequest::default())
+        .await
+        .unwrap();
+    assert_eq!(res.id, payment.id);
+    assert_eq!(res.status, CreatePaymentStatus::AuthorizationRequired);
+
+    let payment = ctx
+        .client
+        .payments
+        .get_by_id(&payment.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(payment.status, PaymentStatus::AuthorizationRequired);
+
+    // Payments which have not failed cannot be retried
+    let res = ctx
+        .client
+        .payments
+        .retry(&payment.id, &RetryPaymentRequest::default())
+        .await;
+    assert!(matches!(res, Err(Error::ApiError(_))));
 }
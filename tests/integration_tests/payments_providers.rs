@@ -1,6 +1,6 @@
 use crate::common::test_context::TestContext;
 use truelayer_rust::apis::{
-    payments::{CountryCode, ReleaseChannel},
+    payments::{CountryCode, ReleaseChannel, SchemeId},
     payments_providers::{capabilities, Capabilities, PaymentScheme},
 };
 
@@ -32,10 +32,17 @@ async fn get_by_id_successful() {
                 bank_transfer: Some(capabilities::BankTransfer {
                     release_channel: ReleaseChannel::GeneralAvailability,
                     schemes: vec![PaymentScheme {
-                        id: "faster_payments_service".into()
+                        id: SchemeId::FasterPaymentsService
                     },]
                 })
-            }
+            },
+            mandates: Some(capabilities::Mandates {
+                vrp_sweeping: Some(capabilities::VrpMandate {
+                    release_channel: ReleaseChannel::GeneralAvailability,
+                    schemes: None
+                }),
+                vrp_commercial: None
+            })
         }
     );
 }
@@ -1,14 +1,14 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
-use crate::{
-    common::{retry, test_context::TestContext},
-    integration_tests::helpers,
-};
+use crate::common::test_context::TestContext;
+#[cfg(feature = "payments")]
+use crate::{common::retry, integration_tests::helpers};
 
 use reqwest_retry::policies::ExponentialBackoff;
+#[cfg(feature = "payments")]
+use truelayer_rust::apis::merchant_accounts::ListPaymentSourcesRequest;
 use truelayer_rust::{
     apis::{
-        merchant_accounts::ListPaymentSourcesRequest,
         payments::{AccountIdentifier, Currency},
         payouts::{CreatePayoutRequest, PayoutBeneficiary, PayoutStatus},
     },
@@ -16,6 +16,7 @@ use truelayer_rust::{
     PollableUntilTerminalState,
 };
 
+#[cfg(feature = "payments")]
 #[tokio::test]
 async fn closed_loop_payout() {
     let ctx = TestContext::start().await;
@@ -56,6 +57,7 @@ async fn closed_loop_payout() {
                 payment_source_id: payment_source.id,
                 reference: "rust-sdk-test".to_string(),
             },
+            metadata: None,
         })
         .await
         .unwrap();
@@ -108,12 +110,16 @@ async fn open_loop_payout() {
                 account_holder_name: merchant_account.account_holder_name.clone(),
                 account_identifier: account_identifier.clone(),
                 reference: "rust-sdk-test".to_string(),
+                verification: None,
+                date_of_birth: None,
+                address: None,
             },
+            metadata: Some(HashMap::from([("some".to_string(), "metadata".to_string())]).into()),
         })
         .await
         .unwrap();
 
-    assert!(!res.id.is_empty());
+    assert!(!res.id.as_str().is_empty());
 
     // Retrieve it again
     let payout = ctx
@@ -130,8 +136,29 @@ async fn open_loop_payout() {
     assert!(matches!(
         payout.beneficiary,
         PayoutBeneficiary::ExternalAccount {
-            reference,
+            ref reference,
             ..
         } if reference == "rust-sdk-test"
     ));
+    assert_eq!(
+        payout.metadata.as_ref().and_then(|m| m.get("some")),
+        Some(&"metadata".into())
+    );
+
+    // Wait until the payout is executed
+    let executed_payout = payout
+        .poll_until_terminal_state(
+            &ctx.client,
+            PollOptions::default().with_retry_policy(
+                ExponentialBackoff::builder()
+                    .build_with_total_retry_duration(Duration::from_secs(60)),
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(executed_payout.id, payout.id);
+    assert!(matches!(
+        executed_payout.status,
+        PayoutStatus::Executed { .. }
+    ));
 }
@@ -1,2 +1,11 @@
+// Parts of the mock server and of the test context serve only the tests of some API features.
+#[cfg_attr(
+    not(all(
+        feature = "payments",
+        feature = "merchant-accounts",
+        feature = "mandates"
+    )),
+    allow(dead_code, unused_imports)
+)]
 mod common;
 mod integration_tests;
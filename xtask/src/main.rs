@@ -0,0 +1,68 @@
+//! Development tasks of the SDK, run with `cargo xtask <task>`.
+//!
+//! Tasks:
+//!
+//! - `openapi-diff <spec>`: checks the models of the SDK against a TrueLayer OpenAPI spec
+//!   (JSON or YAML) and reports the fields and enum variants they are missing.
+//!   Exits with a failure if any is found.
+
+mod models;
+mod samples;
+
+use anyhow::{bail, Context};
+use std::{collections::BTreeSet, process::ExitCode};
+
+fn main() -> anyhow::Result<ExitCode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["openapi-diff", spec] => openapi_diff(spec),
+        _ => bail!("Usage: cargo xtask openapi-diff <path to OpenAPI spec>"),
+    }
+}
+
+fn openapi_diff(spec_path: &str) -> anyhow::Result<ExitCode> {
+    let content = std::fs::read_to_string(spec_path)
+        .with_context(|| format!("Cannot read spec {}", spec_path))?;
+    let spec = samples::Spec::parse(&content)?;
+
+    let mut drifted = false;
+    for (schema, model, check) in models::MODELS {
+        let samples = match spec.samples(schema) {
+            Ok(samples) => samples,
+            Err(e) => {
+                println!("? {} ({}): skipped, {:#}", schema, model, e);
+                continue;
+            }
+        };
+
+        // The same missing field is usually hit by many samples
+        let errors: BTreeSet<String> = samples
+            .into_iter()
+            .filter_map(|sample| check(sample).err())
+            .map(|e| e.to_string())
+            .collect();
+
+        if errors.is_empty() {
+            println!("✓ {} ({})", schema, model);
+        } else {
+            drifted = true;
+            println!("✗ {} ({})", schema, model);
+            for error in errors {
+                println!("    {}", error);
+            }
+        }
+    }
+
+    Ok(if drifted {
+        println!("\nThe models are out of sync with the spec.");
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
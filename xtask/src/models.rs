@@ -0,0 +1,84 @@
+//! Models of the SDK checked against the schemas of the OpenAPI spec.
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use truelayer_rust::apis::{
+    mandates::Mandate,
+    merchant_accounts::{MerchantAccount, Transaction},
+    payments::{
+        refunds::Refund, AdditionalInput, AuthorizationFlowNextAction, Beneficiary, CountryCode,
+        Currency, FailureStage, Payment, PaymentMethod, PaymentStatus, ProviderSelection,
+        SchemeSelection,
+    },
+    payments_providers::Provider,
+    payouts::Payout,
+};
+
+type Check = fn(Value) -> Result<(), serde_json::Error>;
+
+fn check<T: DeserializeOwned>(sample: Value) -> Result<(), serde_json::Error> {
+    serde_json::from_value::<T>(sample).map(|_| ())
+}
+
+/// Name of each schema in the spec, along with the model it must deserialize into.
+///
+/// Keep this list in sync when adding models or when schemas are renamed in the spec.
+pub const MODELS: &[(&str, &str, Check)] = &[
+    ("Payment", "payments::Payment", check::<Payment>),
+    (
+        "PaymentStatus",
+        "payments::PaymentStatus",
+        check::<PaymentStatus>,
+    ),
+    (
+        "PaymentMethod",
+        "payments::PaymentMethod",
+        check::<PaymentMethod>,
+    ),
+    ("Beneficiary", "payments::Beneficiary", check::<Beneficiary>),
+    (
+        "ProviderSelection",
+        "payments::ProviderSelection",
+        check::<ProviderSelection>,
+    ),
+    (
+        "SchemeSelection",
+        "payments::SchemeSelection",
+        check::<SchemeSelection>,
+    ),
+    (
+        "AuthorizationFlowNextAction",
+        "payments::AuthorizationFlowNextAction",
+        check::<AuthorizationFlowNextAction>,
+    ),
+    (
+        "AdditionalInput",
+        "payments::AdditionalInput",
+        check::<AdditionalInput>,
+    ),
+    (
+        "FailureStage",
+        "payments::FailureStage",
+        check::<FailureStage>,
+    ),
+    ("Currency", "payments::Currency", check::<Currency>),
+    ("CountryCode", "payments::CountryCode", check::<CountryCode>),
+    ("Refund", "payments::refunds::Refund", check::<Refund>),
+    ("Payout", "payouts::Payout", check::<Payout>),
+    ("Mandate", "mandates::Mandate", check::<Mandate>),
+    (
+        "MerchantAccount",
+        "merchant_accounts::MerchantAccount",
+        check::<MerchantAccount>,
+    ),
+    (
+        "Transaction",
+        "merchant_accounts::Transaction",
+        check::<Transaction>,
+    ),
+    (
+        "Provider",
+        "payments_providers::Provider",
+        check::<Provider>,
+    ),
+];
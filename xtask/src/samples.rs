@@ -0,0 +1,299 @@
+//! Generation of sample payloads from the schemas of an OpenAPI spec.
+//!
+//! Samples are as large as the schema allows: every property is set, including optional ones,
+//! and one sample is generated for each enum value and each `oneOf`/`anyOf` branch.
+//! Deserializing them into models with `strict-models` enabled therefore fails on any field
+//! or variant the models don't know about.
+
+use anyhow::{anyhow, Context};
+use serde_json::{Map, Value};
+
+/// Upper bound on the samples generated for a single schema, to keep combinations in check.
+const MAX_SAMPLES: usize = 256;
+
+/// Schemas nested deeper than this are not expanded, to cut recursive definitions.
+const MAX_DEPTH: usize = 16;
+
+/// An OpenAPI 3 spec, in JSON or YAML.
+pub struct Spec {
+    root: Value,
+}
+
+impl Spec {
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let root = match serde_json::from_str(content) {
+            Ok(root) => root,
+            Err(_) => serde_yaml::from_str(content).context("Spec is neither JSON nor YAML")?,
+        };
+
+        Ok(Self { root })
+    }
+
+    /// Returns the schema with the given name under `components.schemas`.
+    pub fn schema(&self, name: &str) -> Option<&Value> {
+        self.root.pointer(&format!(
+            "/components/schemas/{}",
+            name.replace('~', "~0").replace('/', "~1")
+        ))
+    }
+
+    /// Generates the samples of the schema with the given name.
+    pub fn samples(&self, name: &str) -> anyhow::Result<Vec<Value>> {
+        let schema = self
+            .schema(name)
+            .ok_or_else(|| anyhow!("Schema `{}` not found", name))?;
+
+        self.samples_of(schema, 0)
+    }
+
+    fn resolve<'a>(&'a self, schema: &'a Value) -> anyhow::Result<&'a Value> {
+        match schema.get("$ref").and_then(Value::as_str) {
+            Some(reference) => {
+                let pointer = reference
+                    .strip_prefix('#')
+                    .ok_or_else(|| anyhow!("Unsupported external reference `{}`", reference))?;
+                let target = self
+                    .root
+                    .pointer(pointer)
+                    .ok_or_else(|| anyhow!("Dangling reference `{}`", reference))?;
+                self.resolve(target)
+            }
+            None => Ok(schema),
+        }
+    }
+
+    fn samples_of(&self, schema: &Value, depth: usize) -> anyhow::Result<Vec<Value>> {
+        if depth > MAX_DEPTH {
+            return Ok(vec![Value::Null]);
+        }
+        let schema = self.resolve(schema)?;
+
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            return Ok(values.clone());
+        }
+        if let Some(value) = schema.get("const") {
+            return Ok(vec![value.clone()]);
+        }
+
+        for key in ["oneOf", "anyOf"] {
+            if let Some(branches) = schema.get(key).and_then(Value::as_array) {
+                let mut samples = Vec::new();
+                for branch in branches {
+                    samples.extend(self.samples_of(branch, depth + 1)?);
+                }
+                // Properties declared next to the branches apply to all of them
+                if schema.get("properties").is_some() {
+                    let shared = self.object_samples(schema, depth)?;
+                    samples = merge_variants(&samples, &shared);
+                }
+                return Ok(truncate(samples));
+            }
+        }
+
+        if let Some(parts) = schema.get("allOf").and_then(Value::as_array) {
+            let mut samples = vec![Value::Object(Map::new())];
+            for part in parts {
+                samples = merge_variants(&samples, &self.samples_of(part, depth + 1)?);
+            }
+            return Ok(truncate(samples));
+        }
+
+        let r#type = schema
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_else(|| {
+                if schema.get("properties").is_some() {
+                    "object"
+                } else {
+                    "string"
+                }
+            });
+
+        Ok(match r#type {
+            "object" => self.object_samples(schema, depth)?,
+            "array" => match schema.get("items") {
+                Some(items) => self
+                    .samples_of(items, depth + 1)?
+                    .into_iter()
+                    .map(|item| Value::Array(vec![item]))
+                    .collect(),
+                None => vec![Value::Array(Vec::new())],
+            },
+            _ => vec![scalar_sample(schema, r#type)],
+        })
+    }
+
+    /// Builds one object with every property set to its first sample,
+    /// plus one object for each other sample of each property.
+    fn object_samples(&self, schema: &Value, depth: usize) -> anyhow::Result<Vec<Value>> {
+        let properties = match schema.get("properties").and_then(Value::as_object) {
+            Some(properties) => properties,
+            None => return Ok(vec![Value::Object(Map::new())]),
+        };
+
+        let mut base = Map::new();
+        let mut variants = Vec::new();
+        for (name, property) in properties {
+            let mut samples = self.samples_of(property, depth + 1)?.into_iter();
+            base.insert(name.clone(), samples.next().unwrap_or(Value::Null));
+            variants.extend(samples.map(|sample| (name.clone(), sample)));
+        }
+
+        let mut samples = vec![Value::Object(base.clone())];
+        for (name, sample) in variants {
+            let mut object = base.clone();
+            object.insert(name, sample);
+            samples.push(Value::Object(object));
+        }
+
+        Ok(truncate(samples))
+    }
+}
+
+fn scalar_sample(schema: &Value, r#type: &str) -> Value {
+    if let Some(example) = schema.get("example") {
+        return example.clone();
+    }
+
+    match (r#type, schema.get("format").and_then(Value::as_str)) {
+        ("string", Some("date-time")) => Value::from("2022-01-01T00:00:00Z"),
+        ("string", Some("date")) => Value::from("2022-01-01"),
+        ("string", Some("uuid")) => Value::from("00000000-0000-0000-0000-000000000000"),
+        ("string", Some("uri")) => Value::from("https://example.com"),
+        ("integer", _) => Value::from(1),
+        ("number", _) => Value::from(1.0),
+        ("boolean", _) => Value::from(true),
+        _ => Value::from("string"),
+    }
+}
+
+/// Merges each sample of `variants` with the first sample of `others`,
+/// and the first sample of `variants` with each other sample of `others`.
+fn merge_variants(variants: &[Value], others: &[Value]) -> Vec<Value> {
+    let (first_variant, first_other) = match (variants.first(), others.first()) {
+        (Some(v), Some(o)) => (v, o),
+        _ => return variants.iter().chain(others).cloned().collect(),
+    };
+
+    variants
+        .iter()
+        .map(|variant| merge(variant, first_other))
+        .chain(
+            others
+                .iter()
+                .skip(1)
+                .map(|other| merge(first_variant, other)),
+        )
+        .collect()
+}
+
+fn merge(a: &Value, b: &Value) -> Value {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut merged = a.clone();
+            for (key, value) in b {
+                let value = match merged.get(key) {
+                    Some(existing) => merge(existing, value),
+                    None => value.clone(),
+                };
+                merged.insert(key.clone(), value);
+            }
+            Value::Object(merged)
+        }
+        (_, b) => b.clone(),
+    }
+}
+
+fn truncate(mut samples: Vec<Value>) -> Vec<Value> {
+    samples.truncate(MAX_SAMPLES);
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(schemas: Value) -> Spec {
+        Spec {
+            root: json!({ "components": { "schemas": schemas } }),
+        }
+    }
+
+    #[test]
+    fn one_sample_per_enum_value() {
+        let spec = spec(json!({
+            "Thing": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "status": { "type": "string", "enum": ["a", "b"] }
+                }
+            }
+        }));
+
+        assert_eq!(
+            spec.samples("Thing").unwrap(),
+            vec![
+                json!({ "id": "string", "status": "a" }),
+                json!({ "id": "string", "status": "b" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn one_sample_per_one_of_branch_with_refs_and_all_of() {
+        let spec = spec(json!({
+            "Base": {
+                "type": "object",
+                "properties": { "created_at": { "type": "string", "format": "date-time" } }
+            },
+            "Failed": {
+                "allOf": [
+                    { "$ref": "#/components/schemas/Base" },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "status": { "type": "string", "enum": ["failed"] },
+                            "reason": { "type": "string", "example": "expired" }
+                        }
+                    }
+                ]
+            },
+            "Status": {
+                "oneOf": [
+                    { "$ref": "#/components/schemas/Failed" },
+                    {
+                        "type": "object",
+                        "properties": { "status": { "type": "string", "enum": ["executed"] } }
+                    }
+                ]
+            }
+        }));
+
+        assert_eq!(
+            spec.samples("Status").unwrap(),
+            vec![
+                json!({ "created_at": "2022-01-01T00:00:00Z", "status": "failed", "reason": "expired" }),
+                json!({ "status": "executed" }),
+            ]
+        );
+    }
+
+    #[test]
+    fn recursive_schemas_are_cut() {
+        let spec = spec(json!({
+            "Node": {
+                "type": "object",
+                "properties": { "next": { "$ref": "#/components/schemas/Node" } }
+            }
+        }));
+
+        assert!(!spec.samples("Node").unwrap().is_empty());
+    }
+
+    #[test]
+    fn missing_schema_is_an_error() {
+        assert!(spec(json!({})).samples("Missing").is_err());
+    }
+}